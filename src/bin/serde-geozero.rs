@@ -0,0 +1,188 @@
+//! Command line tool for quick format conversions (requires the `cli` feature).
+//!
+//! ```text
+//! serde-geozero convert <input> <output> [--flatten] [--skip-null-geometry]
+//! serde-geozero inspect <input>
+//! serde-geozero validate <input> [--unique <column>]...
+//! ```
+//!
+//! Formats are chosen by file extension: `.fgb`, `.geojson`/`.json` and `.ndjson`/`.geojsonl`
+//! (output only).
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::Path,
+    process::ExitCode,
+};
+
+use anyhow::{bail, Context};
+use flatgeobuf::FgbReader;
+use serde_geozero::{
+    collector::RawCollector,
+    contract::fgb_schema,
+    error::Error,
+    geojson::{to_geojson_string, to_ndjson_string},
+    quality::{geometry_type_name, DuplicateKey, QualityChecker},
+    schema::DatasetInfo,
+    ser::{column_table, to_fgb, NestedProperties, NullGeometry},
+    DeserializeOptions, SerializeOptions,
+};
+
+const USAGE: &str = "usage:
+    serde-geozero convert <input> <output> [--flatten] [--skip-null-geometry]
+    serde-geozero inspect <input>
+    serde-geozero validate <input> [--unique <column>]...";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match run(&args) {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("error: {err:#}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: &[String]) -> anyhow::Result<ExitCode> {
+    match args {
+        [command, input, rest @ ..] if command == "inspect" && rest.is_empty() => {
+            inspect(Path::new(input))?;
+            Ok(ExitCode::SUCCESS)
+        }
+        [command, input, output, flags @ ..] if command == "convert" => {
+            let mut options = SerializeOptions::new();
+            for flag in flags {
+                options = match flag.as_str() {
+                    "--flatten" => options.nested_properties(NestedProperties::Flatten),
+                    "--skip-null-geometry" => options.null_geometry(NullGeometry::Skip),
+                    flag => bail!("unknown option {flag}\n{USAGE}"),
+                };
+            }
+            convert(Path::new(input), Path::new(output), &options)?;
+            Ok(ExitCode::SUCCESS)
+        }
+        [command, input, flags @ ..] if command == "validate" => {
+            let mut checker = QualityChecker::with_default_rules();
+            let mut flags = flags.iter();
+            while let Some(flag) = flags.next() {
+                match (flag.as_str(), flags.next()) {
+                    ("--unique", Some(column)) => {
+                        checker = checker.with_rule(DuplicateKey::new(column));
+                    }
+                    _ => bail!("unknown option {flag}\n{USAGE}"),
+                }
+            }
+            validate(Path::new(input), checker)
+        }
+        _ => {
+            eprintln!("{USAGE}");
+            Ok(ExitCode::FAILURE)
+        }
+    }
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase()
+}
+
+/// Reads all features of `path` into `collector`.
+fn read(path: &Path, collector: &mut RawCollector) -> anyhow::Result<()> {
+    let file = BufReader::new(File::open(path).with_context(|| path.display().to_string())?);
+    match extension(path).as_str() {
+        "fgb" => collector.process(&mut FgbReader::open(file)?.select_all()?)?,
+        "geojson" | "json" => collector.process(&mut geozero::geojson::GeoJsonReader(file))?,
+        _ => return Err(Error::UnsupportedFormat(path.display().to_string()).into()),
+    }
+    Ok(())
+}
+
+fn convert(input: &Path, output: &Path, options: &SerializeOptions) -> anyhow::Result<()> {
+    let mut collector = RawCollector::new();
+    read(input, &mut collector)?;
+    let features = collector.into_features();
+
+    match extension(output).as_str() {
+        "fgb" => {
+            let name = output
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or("features");
+            let options = options.clone().dataset(DatasetInfo {
+                name: Some(name.to_string()),
+                ..DatasetInfo::default()
+            });
+            to_fgb(&features, BufWriter::new(File::create(output)?), &options)?;
+        }
+        "geojson" | "json" => std::fs::write(output, to_geojson_string(&features, options)?)?,
+        "ndjson" | "geojsonl" => std::fs::write(output, to_ndjson_string(&features, options)?)?,
+        _ => return Err(Error::UnsupportedFormat(output.display().to_string()).into()),
+    }
+    println!("wrote {} features to {}", features.len(), output.display());
+    Ok(())
+}
+
+fn inspect(input: &Path) -> anyhow::Result<()> {
+    let mut collector = RawCollector::with_options(&DeserializeOptions::new().compute_bbox(true))?;
+    read(input, &mut collector)?;
+
+    println!("features: {}", collector.len());
+    if let Some(envelope) = collector.envelope() {
+        let (min, max) = (envelope.min(), envelope.max());
+        println!("extent: [{}, {}, {}, {}]", min.x, min.y, max.x, max.y);
+    }
+
+    let mut geometry_types = BTreeMap::new();
+    for feature in collector.features() {
+        *geometry_types
+            .entry(geometry_type_name(&feature.geometry))
+            .or_insert(0) += 1;
+    }
+    for (name, count) in geometry_types {
+        println!("geometry: {name} ({count})");
+    }
+
+    let mut columns = column_table(collector.features(), &SerializeOptions::new())?;
+    columns.sort_by(|a, b| a.name.cmp(&b.name));
+    for column in columns {
+        match column.column_type {
+            Some(column_type) => println!("column: {} {column_type:?}", column.name),
+            None => println!("column: {} (only nulls)", column.name),
+        }
+    }
+
+    if extension(input) == "fgb" {
+        let reader = FgbReader::open(BufReader::new(File::open(input)?))?;
+        if let Some(schema) = fgb_schema(&reader)? {
+            println!("schema: {} v{}", schema.name, schema.version);
+        }
+    }
+    Ok(())
+}
+
+fn validate(input: &Path, checker: QualityChecker) -> anyhow::Result<ExitCode> {
+    let mut collector = RawCollector::new().with_quality_checker(checker);
+    read(input, &mut collector)?;
+    let report = collector.take_quality_report().unwrap_or_default();
+
+    for issue in &report.issues {
+        match issue.feature {
+            Some(feature) => println!("{}: feature {feature}: {}", issue.rule, issue.message),
+            None => println!("{}: {}", issue.rule, issue.message),
+        }
+    }
+    println!(
+        "{} features, {} issues",
+        report.feature_count,
+        report.issues.len()
+    );
+    Ok(if report.is_clean() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
@@ -0,0 +1,176 @@
+//! Property name case conversion.
+//!
+//! Datasources often use `UPPERCASE` or `camelCase` column names while Rust structs use
+//! `snake_case`. Instead of renaming every field, convert the property names while reading with
+//! [`DeserializeOptions::property_case`](crate::DeserializeOptions::property_case) and while
+//! writing with [`SerializeOptions::property_case`](crate::SerializeOptions::property_case):
+//!
+//! ```
+//! use geo::Geometry;
+//! use serde::Deserialize;
+//! use serde_geozero::case::PropertyCase;
+//! use serde_geozero::{from_datasource_with_options, DeserializeOptions};
+//!
+//! #[derive(Deserialize)]
+//! struct Country {
+//!     geometry: Geometry,
+//!     pop_est: u64,
+//!     iso_code: String,
+//! }
+//!
+//! let geojson = r#"{"type": "Feature", "properties": {"POP_EST": 83000000, "isoCode": "DEU"},
+//!     "geometry": {"type": "Point", "coordinates": [10, 51]}}"#;
+//!
+//! let options = DeserializeOptions::new().property_case(PropertyCase::SnakeCase);
+//! let mut reader = geozero::geojson::GeoJsonReader(geojson.as_bytes());
+//! let countries: Vec<Country> = from_datasource_with_options(&mut reader, &options).unwrap();
+//! assert_eq!(countries[0].iso_code, "DEU");
+//! ```
+
+/// The case property names are converted to, named like serde's `rename_all` values.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PropertyCase {
+    /// Keep names as they are.
+    #[default]
+    Unchanged,
+    /// `lowercase`, only changes the case of letters.
+    LowerCase,
+    /// `UPPERCASE`, only changes the case of letters.
+    UpperCase,
+    /// `PascalCase`
+    PascalCase,
+    /// `camelCase`
+    CamelCase,
+    /// `snake_case`
+    SnakeCase,
+    /// `SCREAMING_SNAKE_CASE`
+    ScreamingSnakeCase,
+    /// `kebab-case`
+    KebabCase,
+    /// `SCREAMING-KEBAB-CASE`
+    ScreamingKebabCase,
+}
+
+impl PropertyCase {
+    /// Converts `name` to this case.
+    ///
+    /// Words are separated by `_`, `-`, spaces and changes from lower to upper case, so
+    /// `POP_EST`, `popEst` and `PopEst` all become `pop_est` in [`PropertyCase::SnakeCase`].
+    /// Dots are kept, each dotted segment is converted on its own.
+    #[must_use]
+    pub fn convert(self, name: &str) -> String {
+        match self {
+            PropertyCase::Unchanged => name.to_string(),
+            PropertyCase::LowerCase => name.to_lowercase(),
+            PropertyCase::UpperCase => name.to_uppercase(),
+            _ => name
+                .split('.')
+                .map(|segment| self.convert_words(&words(segment)))
+                .collect::<Vec<_>>()
+                .join("."),
+        }
+    }
+
+    fn convert_words(self, words: &[&str]) -> String {
+        let joined = |separator: &str, upper: bool| {
+            words
+                .iter()
+                .map(|word| {
+                    if upper {
+                        word.to_uppercase()
+                    } else {
+                        word.to_lowercase()
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(separator)
+        };
+        match self {
+            PropertyCase::SnakeCase => joined("_", false),
+            PropertyCase::ScreamingSnakeCase => joined("_", true),
+            PropertyCase::KebabCase => joined("-", false),
+            PropertyCase::ScreamingKebabCase => joined("-", true),
+            PropertyCase::PascalCase => words.iter().map(|word| capitalize(word)).collect(),
+            PropertyCase::CamelCase => words
+                .iter()
+                .enumerate()
+                .map(|(idx, word)| {
+                    if idx == 0 {
+                        word.to_lowercase()
+                    } else {
+                        capitalize(word)
+                    }
+                })
+                .collect(),
+            PropertyCase::Unchanged | PropertyCase::LowerCase | PropertyCase::UpperCase => {
+                words.concat()
+            }
+        }
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    chars.next().map_or_else(String::new, |first| {
+        first
+            .to_uppercase()
+            .chain(chars.flat_map(char::to_lowercase))
+            .collect()
+    })
+}
+
+/// Splits `name` into words at separators and case changes, keeping acronyms together.
+fn words(name: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    let chars: Vec<(usize, char)> = name.char_indices().collect();
+    let mut start = None;
+    for (pos, &(idx, c)) in chars.iter().enumerate() {
+        if matches!(c, '_' | '-' | ' ') {
+            if let Some(start) = start.take() {
+                words.push(&name[start..idx]);
+            }
+            continue;
+        }
+        let Some(word_start) = start else {
+            start = Some(idx);
+            continue;
+        };
+        let previous = chars[pos - 1].1;
+        let next_is_lower = chars.get(pos + 1).is_some_and(|(_, c)| c.is_lowercase());
+        let boundary = c.is_uppercase()
+            && (previous.is_lowercase()
+                || previous.is_ascii_digit()
+                || (previous.is_uppercase() && next_is_lower));
+        if boundary {
+            words.push(&name[word_start..idx]);
+            start = Some(idx);
+        }
+    }
+    if let Some(start) = start {
+        words.push(&name[start..]);
+    }
+    words
+}
+
+#[cfg(test)]
+mod test {
+    use super::PropertyCase;
+
+    #[test]
+    fn conversions() {
+        for name in ["POP_EST", "popEst", "PopEst", "pop-est", "pop_est"] {
+            assert_eq!(PropertyCase::SnakeCase.convert(name), "pop_est");
+        }
+        assert_eq!(
+            PropertyCase::CamelCase.convert("HTTPServer_id"),
+            "httpServerId"
+        );
+        assert_eq!(PropertyCase::PascalCase.convert("iso_code"), "IsoCode");
+        assert_eq!(
+            PropertyCase::ScreamingSnakeCase.convert("address.zipCode"),
+            "ADDRESS.ZIP_CODE"
+        );
+        assert_eq!(PropertyCase::KebabCase.convert("area2Km"), "area2-km");
+        assert_eq!(PropertyCase::UpperCase.convert("pop_est"), "POP_EST");
+    }
+}
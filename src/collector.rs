@@ -0,0 +1,2590 @@
+//! Collectors that receive geozero processing events and assemble features.
+//!
+//! [`GeozeroCollector`] is the `FeatureProcessor` behind [`crate::from_datasource`]: it buffers
+//! the geometry and properties of each feature in a [`GeozeroFeature`] and deserializes the
+//! finished feature into `T`. It is used in one of two modes:
+//!
+//! - [`RawCollector`] keeps every feature as a [`Feature`], its geometry and property map, e.g.
+//!   to inspect data whose schema isn't known.
+//! - [`TypedCollector`] deserializes every feature into your own type.
+//!
+//! ```
+//! use geo::Geometry;
+//! use serde::Deserialize;
+//! use serde_geozero::collector::{RawCollector, TypedCollector};
+//!
+//! #[derive(Deserialize)]
+//! struct City {
+//!     geometry: Geometry,
+//!     name: String,
+//! }
+//!
+//! let geojson = r#"{"type": "Feature", "geometry": {"type": "Point", "coordinates": [13.4, 52.5]},
+//!     "properties": {"name": "Berlin", "population": 3669495}}"#;
+//!
+//! let mut raw = RawCollector::new();
+//! raw.process(&mut geozero::geojson::GeoJsonReader(geojson.as_bytes())).unwrap();
+//! assert_eq!(raw.features()[0].get::<u32>("population").unwrap(), 3_669_495);
+//!
+//! let mut typed = TypedCollector::<City>::new();
+//! typed.process(&mut geozero::geojson::GeoJsonReader(geojson.as_bytes())).unwrap();
+//! assert_eq!(typed.len(), 1);
+//! let cities: Vec<City> = typed.into_features();
+//! assert_eq!(cities[0].name, "Berlin");
+//! ```
+#![allow(clippy::many_single_char_names)]
+use std::{
+    any::Any,
+    marker::PhantomData,
+    sync::{mpsc::SyncSender, Arc},
+};
+
+use geo::{coord, BoundingRect, Geometry, GeometryCollection, Rect, Validation};
+use geozero::{
+    error::GeozeroError, geo_types::GeoWriter, ColumnValue, FeatureAccess, FeatureProcessor,
+    GeomProcessor, GeozeroDatasource, PropertyProcessor,
+};
+use hashbrown::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "proj")]
+use crate::reproject::Reprojection;
+use crate::{
+    case::PropertyCase,
+    compare::ValueKey,
+    contract::struct_fields,
+    coord::Rounding,
+    de::{
+        Dedup, DeserializeOptions, Feature, FeatureLayout, FeatureRef, GeoJsonFeature,
+        ProgressCallback, BBOX_COL, GEOMETRY_COL, SRID_COL,
+    },
+    encoded::GeometryEncoding,
+    error::{Error, ErrorCallback, ErrorDecision, ErrorPolicy, FeatureError, Result},
+    finite::{self, NonFinite},
+    lookup::Lookup,
+    quality::{geometry_type_name, QualityChecker, QualityReport},
+    schema::{DatasetInfo, SchemaDrift, StrictMode},
+    value::Value,
+    zm::{ZmValues, M_COL, Z_COL},
+};
+#[cfg(feature = "rstar")]
+use rstar::{
+    primitives::{GeomWithData, Rectangle},
+    RTree,
+};
+
+/// An R-tree over feature bounding boxes. The data of every entry is the feature's index.
+#[cfg(feature = "rstar")]
+pub type FeatureIndex = RTree<GeomWithData<Rectangle<[f64; 2]>, usize>>;
+
+/// Collects every feature as a [`Feature`] with its geometry and property map.
+///
+/// The features are kept as they were read, with shared strings, the SRID and the other values
+/// serde doesn't see.
+pub type RawCollector = GeozeroCollector<'static, Feature>;
+
+/// Receives full chunks of features, see [`GeozeroCollector::with_chunks`].
+pub type ChunkCallback<'a, T> = Box<dyn FnMut(&mut Vec<T>) -> Result<()> + 'a>;
+
+/// Collects every feature deserialized into `T`.
+pub type TypedCollector<'de, T> = GeozeroCollector<'de, T>;
+
+#[allow(clippy::struct_excessive_bools)]
+pub struct GeozeroCollector<'de, T> {
+    pub features: Vec<T>,
+
+    current_feature: GeozeroFeature,
+    quality: Option<QualityChecker>,
+    envelope: Option<Rect>,
+    /// Computes the envelope from the geometries of features without a bounding box.
+    envelope_from_geometry: bool,
+    /// The geometry types of the collected features in the order they were first seen.
+    geometry_types: Vec<&'static str>,
+    on_error: Option<ErrorCallback>,
+    error_policy: ErrorPolicy,
+    validate_geometries: bool,
+    non_finite: NonFinite,
+    coord_rounding: Rounding,
+    lookups: Vec<Lookup>,
+    raw_json: bool,
+    coerce_types: bool,
+    compact: bool,
+    layout: FeatureLayout,
+    enum_tag: Option<Arc<str>>,
+    geometry_columns: Option<Arc<[(String, GeometryEncoding)]>>,
+    on_feature: Option<ProgressCallback>,
+    property_case: PropertyCase,
+    /// Converted property names, to convert every name only once.
+    property_names: HashMap<String, String>,
+    /// The only properties kept, `None` to keep all.
+    requested: Option<HashSet<String>>,
+    /// Whether `requested` are the [`DeserializeOptions::columns`] rather than the fields of `T`.
+    columns: bool,
+    max_properties: Option<usize>,
+    strict: StrictMode,
+    dataset: DatasetInfo,
+    /// The fields of `T`, `None` if they are unknown.
+    fields: Option<&'static [&'static str]>,
+    /// Fields of `T` some feature had a property for.
+    seen_fields: HashSet<&'static str>,
+    /// Unknown properties already reported.
+    unknown: HashSet<String>,
+    processed: usize,
+    skipped: usize,
+    dedup: Dedup,
+    /// The [`Feature::stable_hash`] of the features read so far, to detect duplicates with
+    /// [`Dedup::Exact`].
+    seen: HashSet<u64>,
+    /// The keys read so far, to detect duplicates with [`Dedup::ByKey`].
+    seen_keys: HashSet<ValueKey>,
+    duplicates: usize,
+    /// Stop reading once this many features were collected.
+    limit: Option<usize>,
+    limit_reached: bool,
+    /// Receives every feature instead of [`GeozeroCollector::features`].
+    sender: Option<SyncSender<Result<T>>>,
+    /// The chunk size and the callback the collected features are handed to.
+    chunks: Option<(usize, ChunkCallback<'de, T>)>,
+    /// The last error raised by the collector and the message it was passed on with.
+    error: Option<(Error, String)>,
+    stats: Option<CollectStats>,
+    #[cfg(feature = "proj")]
+    reprojection: Option<Reprojection>,
+    #[cfg(feature = "rstar")]
+    rtree: Option<FeatureIndex>,
+    /// Number of features collected, sent or passed on in chunks since the R-tree was emptied.
+    #[cfg(feature = "rstar")]
+    emitted: usize,
+    /// Turns the finished features into `T`.
+    convert: Box<dyn Convert<T> + 'de>,
+}
+
+impl<'de, T: Deserialize<'de> + 'static> GeozeroCollector<'de, T> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_converter(converter())
+    }
+
+    /// Creates a deserializer configured by `options`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the options cannot be applied (e.g. an unknown CRS).
+    pub fn with_options(options: &DeserializeOptions) -> Result<Self> {
+        Self::with_options_and_converter(options, struct_fields::<T>(), &[], converter())
+    }
+
+    /// Creates a collector with room for `capacity` features, for callers who know the count.
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut collector = Self::new();
+        collector.reserve(capacity);
+        collector
+    }
+}
+
+impl<'de, T> GeozeroCollector<'de, T> {
+    /// Creates a collector that turns the finished features into `T` with `convert`.
+    fn with_converter(convert: Box<dyn Convert<T> + 'de>) -> Self {
+        Self {
+            features: Vec::new(),
+            current_feature: GeozeroFeature::new(),
+            quality: None,
+            envelope: None,
+            envelope_from_geometry: false,
+            geometry_types: Vec::new(),
+            on_error: None,
+            error_policy: ErrorPolicy::default(),
+            validate_geometries: false,
+            non_finite: NonFinite::Allow,
+            coord_rounding: Rounding::default(),
+            lookups: Vec::new(),
+            raw_json: false,
+            coerce_types: false,
+            compact: false,
+            layout: FeatureLayout::default(),
+            enum_tag: None,
+            geometry_columns: None,
+            on_feature: None,
+            property_case: PropertyCase::default(),
+            property_names: HashMap::new(),
+            requested: None,
+            columns: false,
+            max_properties: None,
+            strict: StrictMode::default(),
+            dataset: DatasetInfo::default(),
+            fields: None,
+            seen_fields: HashSet::new(),
+            unknown: HashSet::new(),
+            processed: 0,
+            skipped: 0,
+            dedup: Dedup::Off,
+            seen: HashSet::new(),
+            seen_keys: HashSet::new(),
+            duplicates: 0,
+            limit: None,
+            limit_reached: false,
+            sender: None,
+            chunks: None,
+            error: None,
+            stats: None,
+            #[cfg(feature = "proj")]
+            reprojection: None,
+            #[cfg(feature = "rstar")]
+            rtree: None,
+            #[cfg(feature = "rstar")]
+            emitted: 0,
+            convert,
+        }
+    }
+
+    /// Creates a collector configured by `options` that turns the finished features into `T`
+    /// with `convert`.
+    ///
+    /// `fields` are those of the type `convert` deserializes the features into, and `keys` the
+    /// properties it reads besides them.
+    #[cfg_attr(not(feature = "proj"), allow(clippy::unnecessary_wraps))]
+    pub(crate) fn with_options_and_converter(
+        options: &DeserializeOptions,
+        fields: Option<&'static [&'static str]>,
+        keys: &[&str],
+        convert: Box<dyn Convert<T> + 'de>,
+    ) -> Result<Self> {
+        let mut deserializer = Self::with_converter(convert);
+        deserializer.current_feature.preserve_zm = options.preserve_zm;
+        deserializer.current_feature.compute_bbox = options.compute_bbox;
+        if options.intern_strings {
+            deserializer.current_feature.strings = Some(HashSet::new());
+        }
+        deserializer.on_error = options.on_error;
+        deserializer.error_policy = options.error_policy;
+        deserializer.validate_geometries = options.validate_geometries;
+        deserializer.non_finite = options.non_finite;
+        deserializer.coord_rounding = options.coord_rounding;
+        deserializer.lookups.clone_from(&options.lookups);
+        deserializer.raw_json = options.raw_json;
+        deserializer.coerce_types = options.coerce_types;
+        deserializer.compact = options.compact;
+        deserializer.property_case = options.property_case;
+        deserializer.max_properties = options.max_properties;
+        deserializer.strict = options.strict;
+        deserializer.layout = options.layout;
+        deserializer.enum_tag.clone_from(&options.enum_tag);
+        if !options.geometry_columns.is_empty() {
+            deserializer.geometry_columns = Some(options.geometry_columns.as_slice().into());
+        }
+        deserializer.on_feature.clone_from(&options.on_feature);
+        deserializer.dedup.clone_from(&options.dedup);
+        // the fields of nested properties are unknown
+        let flat = options.layout == FeatureLayout::Flat;
+        if flat && !matches!(options.strict, StrictMode::Off) {
+            deserializer.fields = fields;
+        }
+        // properties read for the collector itself or its converter
+        let internal = options
+            .lookups
+            .iter()
+            .map(|lookup| lookup.property().to_string())
+            .chain(match &options.dedup {
+                Dedup::ByKey(key) => Some(key.clone()),
+                Dedup::Off | Dedup::Exact => None,
+            })
+            .chain(keys.iter().map(ToString::to_string));
+        if flat && !options.keep_all_properties {
+            deserializer.requested = fields.map(|fields| {
+                fields
+                    .iter()
+                    .filter(|field| !SYNTHETIC_FIELDS.contains(field))
+                    .map(ToString::to_string)
+                    .chain(internal.clone())
+                    .collect()
+            });
+        }
+        if let Some(columns) = &options.columns {
+            deserializer.requested = Some(
+                columns
+                    .iter()
+                    .filter(|column| !SYNTHETIC_FIELDS.contains(&column.as_str()))
+                    .cloned()
+                    .chain(internal)
+                    .collect(),
+            );
+            deserializer.columns = true;
+        }
+        #[cfg(feature = "proj")]
+        if let Some((from, to)) = &options.reproject {
+            deserializer.reprojection = Some(Reprojection::new(from, to)?);
+        }
+        Ok(deserializer)
+    }
+
+    /// Reserves room for at least `additional` more features.
+    pub fn reserve(&mut self, additional: usize) {
+        self.features.reserve(additional);
+    }
+
+    /// The features collected so far.
+    #[must_use]
+    pub fn features(&self) -> &[T] {
+        &self.features
+    }
+
+    /// Consumes the collector and returns the collected features.
+    #[must_use]
+    pub fn into_features(self) -> Vec<T> {
+        self.features
+    }
+
+    /// Moves the collected features out and leaves the collector ready for more.
+    ///
+    /// The configuration and the counters like [`GeozeroCollector::skipped`] are kept. The
+    /// R-tree is emptied, as its entries point into the taken features.
+    pub fn take_features(&mut self) -> Vec<T> {
+        #[cfg(feature = "rstar")]
+        if let Some(rtree) = &mut self.rtree {
+            *rtree = FeatureIndex::new();
+        }
+        #[cfg(feature = "rstar")]
+        {
+            self.emitted = 0;
+        }
+        std::mem::take(&mut self.features)
+    }
+
+    /// Forgets everything collected, e.g. to reuse the collector for the next of many files.
+    ///
+    /// Drops the features, the envelope, the R-tree entries, the dataset info, the counters and
+    /// the schema drift seen so far, but keeps the configuration and the allocation of
+    /// [`GeozeroCollector::features`]. A quality checker keeps collecting into its report.
+    pub fn clear(&mut self) {
+        self.features.clear();
+        self.current_feature = GeozeroFeature {
+            current_properties: HashMap::new(),
+            current_order: Vec::new(),
+            current_geometry: GeoWriter::new(),
+            geometry: None,
+            srid: None,
+            zm: ZmValues::default(),
+            bbox: None,
+            ..std::mem::take(&mut self.current_feature)
+        };
+        self.envelope = None;
+        self.geometry_types.clear();
+        self.dataset = DatasetInfo::default();
+        self.seen_fields.clear();
+        self.unknown.clear();
+        self.processed = 0;
+        self.skipped = 0;
+        self.seen.clear();
+        self.seen_keys.clear();
+        self.duplicates = 0;
+        self.limit_reached = false;
+        self.error = None;
+        if let Some(stats) = &mut self.stats {
+            *stats = CollectStats::default();
+        }
+        #[cfg(feature = "rstar")]
+        if let Some(rtree) = &mut self.rtree {
+            *rtree = FeatureIndex::new();
+        }
+        #[cfg(feature = "rstar")]
+        {
+            self.emitted = 0;
+        }
+    }
+
+    /// The number of features collected so far.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.features.len()
+    }
+
+    /// Returns `true` if no feature was collected yet.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.features.is_empty()
+    }
+
+    /// Number of features left out because the error callback decided to skip them.
+    #[must_use]
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// Number of features dropped as duplicates, see [`DeserializeOptions::dedup`].
+    #[must_use]
+    pub fn duplicates(&self) -> usize {
+        self.duplicates
+    }
+
+    /// Processes the datasource into this collector.
+    ///
+    /// Errors raised by the collector itself have to pass the `GeozeroError` boundary of the
+    /// processor traits as plain strings. This method recovers the original, structured
+    /// [`Error`] so callers can match on it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the datasource fails or a feature cannot be deserialized into `T`.
+    pub fn process<S: GeozeroDatasource>(&mut self, source: &mut S) -> Result<()> {
+        self.error = None;
+        self.limit_reached = false;
+        let result = source.process(self);
+        self.finish(result)?;
+        self.flush_chunk()
+    }
+
+    /// Processes features that aren't read through a datasource, e.g. features kept from an
+    /// earlier read. They are numbered in iteration order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a feature fails or cannot be deserialized into `T`.
+    pub fn process_features<'a, F: FeatureAccess + 'a>(
+        &mut self,
+        features: impl IntoIterator<Item = &'a F>,
+    ) -> Result<()> {
+        self.error = None;
+        self.limit_reached = false;
+        let result = self.dataset_begin(None).and_then(|()| {
+            for (idx, feature) in (0u64..).zip(features) {
+                feature.process(self, idx)?;
+            }
+            self.dataset_end()
+        });
+        self.finish(result)?;
+        self.flush_chunk()
+    }
+
+    /// Processes the features of a streaming iterator, e.g. the selections of a `FlatGeobuf`
+    /// reader, see [`GeozeroCollector::process_features`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the iterator or a feature fails, or a feature cannot be deserialized
+    /// into `T`.
+    #[cfg(feature = "flatgeobuf")]
+    pub fn process_stream<I>(&mut self, features: &mut I) -> Result<()>
+    where
+        I: flatgeobuf::FallibleStreamingIterator,
+        I::Item: FeatureAccess + Sized,
+        Error: From<I::Error>,
+    {
+        self.error = None;
+        self.limit_reached = false;
+        let result = self.dataset_begin(None);
+        self.finish(result)?;
+        let mut idx = 0;
+        while let Some(feature) = features.next()? {
+            let result = feature.process(self, idx);
+            self.finish(result)?;
+            if self.limit_reached {
+                return self.flush_chunk();
+            }
+            idx += 1;
+        }
+        let result = self.dataset_end();
+        self.finish(result)?;
+        self.flush_chunk()
+    }
+
+    /// Maps the result of processing back to the structured error raised by this collector.
+    fn finish(&mut self, result: geozero::error::Result<()>) -> Result<()> {
+        match result {
+            Err(_) if self.limit_reached => Ok(()),
+            result => result.map_err(|err| self.take_error_for(err)),
+        }
+    }
+
+    /// Stops reading the datasource once `limit` features were collected.
+    pub(crate) fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Hands the collected features to `callback` whenever `chunk_size` of them are ready, and
+    /// the rest once the datasource is finished.
+    ///
+    /// The callback may move features out, e.g. with `chunk.drain(..)`. The chunk is cleared
+    /// afterwards and its allocation reused, so at most `chunk_size` features are held in
+    /// memory. An error returned by the callback stops reading.
+    #[must_use]
+    pub fn with_chunks(
+        mut self,
+        chunk_size: usize,
+        callback: impl FnMut(&mut Vec<T>) -> Result<()> + 'de,
+    ) -> Self {
+        let chunk_size = chunk_size.max(1);
+        self.features.reserve(chunk_size);
+        self.chunks = Some((chunk_size, Box::new(callback)));
+        self
+    }
+
+    /// Passes the features collected so far to the chunk callback, if there is one.
+    fn flush_chunk(&mut self) -> Result<()> {
+        let Some((_, callback)) = &mut self.chunks else {
+            return Ok(());
+        };
+        if self.features.is_empty() {
+            return Ok(());
+        }
+        let result = callback(&mut self.features);
+        self.features.clear();
+        result
+    }
+
+    /// Sends every feature to `sender` instead of collecting it, see [`crate::iter`].
+    pub(crate) fn with_sender(mut self, sender: SyncSender<Result<T>>) -> Self {
+        self.sender = Some(sender);
+        self
+    }
+
+    /// Extends the envelope and the R-tree by the bounding box of the feature about to be
+    /// emitted.
+    fn record_bbox(&mut self, bbox: Option<Rect>) {
+        if let Some(bbox) = bbox {
+            self.envelope = Some(merge_bbox(self.envelope, bbox));
+            #[cfg(feature = "rstar")]
+            if let Some(rtree) = &mut self.rtree {
+                rtree.insert(GeomWithData::new(
+                    Rectangle::from_corners(bbox.min().x_y().into(), bbox.max().x_y().into()),
+                    self.emitted,
+                ));
+            }
+        }
+        #[cfg(feature = "rstar")]
+        {
+            self.emitted += 1;
+        }
+    }
+
+    /// Takes the structured error behind the last `GeozeroError` raised by this collector.
+    pub fn take_error(&mut self) -> Option<Error> {
+        self.error.take().map(|(err, _)| err)
+    }
+
+    /// Whether the current feature had every requested property, so the datasource may skip
+    /// the rest. Strict mode has to see all of them.
+    fn has_requested_properties(&self) -> bool {
+        matches!(self.strict, StrictMode::Off)
+            && self.requested.as_ref().is_some_and(|requested| {
+                self.current_feature.current_properties.len() >= requested.len()
+            })
+    }
+
+    fn take_error_for(&mut self, err: GeozeroError) -> Error {
+        match self.error.take() {
+            Some((original, message)) if err.to_string() == message => original,
+            _ => Error::GeozeroError(err),
+        }
+    }
+
+    /// Remembers `err` and converts it for the processor traits.
+    fn raise(&mut self, err: Error) -> GeozeroError {
+        let converted = GeozeroError::Feature(err.to_string());
+        self.error = Some((err, converted.to_string()));
+        converted
+    }
+
+    /// The name and SRID the datasource reported, the SRID of the first feature that had one.
+    #[must_use]
+    pub fn dataset_info(&self) -> &DatasetInfo {
+        &self.dataset
+    }
+
+    /// The SRID of the collected features, the SRID of the target CRS when reprojecting.
+    pub(crate) fn output_srid(&self) -> Option<i32> {
+        #[cfg(feature = "proj")]
+        if let Some(reprojection) = &self.reprojection {
+            return reprojection.target_srid();
+        }
+        self.dataset.srid
+    }
+
+    /// The bounding box of all features processed so far.
+    ///
+    /// Only available if the collector was created with
+    /// [`DeserializeOptions::compute_bbox`] enabled or [`with_envelope`](Self::with_envelope).
+    #[must_use]
+    pub fn envelope(&self) -> Option<Rect> {
+        self.envelope
+    }
+
+    /// Computes the [`envelope`](Self::envelope) of the collected features without adding a
+    /// bounding box to every feature.
+    #[must_use]
+    pub fn with_envelope(mut self) -> Self {
+        self.envelope_from_geometry = true;
+        self
+    }
+
+    /// The geometry types of the features collected so far, e.g. `"MultiPolygon"`, in the order
+    /// they were first seen.
+    #[must_use]
+    pub fn geometry_types(&self) -> &[&'static str] {
+        &self.geometry_types
+    }
+
+    /// Runs the checker's rules against every feature before it is deserialized.
+    ///
+    /// Rules see all properties, including those `T` doesn't request, unless the collector was
+    /// created with [`DeserializeOptions::columns`]. Then they only see those columns.
+    #[must_use]
+    pub fn with_quality_checker(mut self, checker: QualityChecker) -> Self {
+        if !self.columns {
+            self.requested = None;
+        }
+        self.quality = Some(checker);
+        self
+    }
+
+    /// Finishes the quality checker and returns its report, if one was configured.
+    pub fn take_quality_report(&mut self) -> Option<QualityReport> {
+        self.quality.take().map(QualityChecker::finish)
+    }
+
+    /// Records [`CollectStats`] of the properties while collecting, e.g. to build a legend
+    /// without reading the features again.
+    ///
+    /// Only the properties kept for `T` are recorded. Features skipped later on, e.g. because
+    /// they failed to deserialize, are part of the statistics.
+    #[must_use]
+    pub fn with_stats(mut self) -> Self {
+        self.stats = Some(CollectStats::default());
+        self
+    }
+
+    /// The statistics recorded so far, if the collector was created
+    /// [`with_stats`](Self::with_stats).
+    #[must_use]
+    pub fn stats(&self) -> Option<&CollectStats> {
+        self.stats.as_ref()
+    }
+
+    /// Takes the recorded statistics and stops recording.
+    pub fn take_stats(&mut self) -> Option<CollectStats> {
+        self.stats.take()
+    }
+
+    /// Inserts the bounding box of every feature into an R-tree while collecting.
+    ///
+    /// The data of every tree entry is the running index of the feature, counting all features
+    /// collected so far, including the ones already handed to a
+    /// [`with_chunks`](Self::with_chunks) callback.
+    ///
+    /// Enables bounding box computation (see [`DeserializeOptions::compute_bbox`]).
+    #[cfg(feature = "rstar")]
+    #[must_use]
+    pub fn with_rtree(mut self) -> Self {
+        self.current_feature.compute_bbox = true;
+        self.rtree = Some(FeatureIndex::new());
+        self
+    }
+
+    /// The R-tree built so far, if the collector was created [`with_rtree`](Self::with_rtree).
+    #[cfg(feature = "rstar")]
+    #[must_use]
+    pub fn rtree(&self) -> Option<&FeatureIndex> {
+        self.rtree.as_ref()
+    }
+
+    /// Returns the collected features together with an R-tree over their bounding boxes.
+    ///
+    /// The data of every tree entry is the index of the feature in the returned `Vec`. Features
+    /// without coordinates are not part of the tree.
+    #[cfg(feature = "rstar")]
+    #[must_use]
+    pub fn into_rtree(self) -> (Vec<T>, FeatureIndex) {
+        (self.features, self.rtree.unwrap_or_default())
+    }
+}
+
+impl<'de, T: Deserialize<'de> + 'static> Default for GeozeroCollector<'de, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'de, T> PropertyProcessor for GeozeroCollector<'de, T> {
+    fn property(
+        &mut self,
+        idx: usize,
+        name: &str,
+        value: &ColumnValue,
+    ) -> geozero::error::Result<bool> {
+        let name = match self.property_case {
+            PropertyCase::Unchanged => name,
+            case => self
+                .property_names
+                .entry_ref(name)
+                .or_insert_with(|| case.convert(name)),
+        };
+        if let Some(fields) = self.fields {
+            if !fields.contains(&name)
+                && !self.lookups.iter().any(|lookup| lookup.property() == name)
+                && !self.unknown.contains(name)
+            {
+                self.unknown.insert(name.to_string());
+                let drift = SchemaDrift::UnknownProperty(name.to_string());
+                if let Some(err) = self.strict.report(&drift) {
+                    return Err(self.raise(err));
+                }
+            }
+        }
+        if self
+            .requested
+            .as_ref()
+            .is_some_and(|requested| !requested.contains(name))
+        {
+            return Ok(false);
+        }
+        if let Some(max) = self.max_properties {
+            if self.current_feature.current_properties.len() >= max {
+                return Err(self.raise(Error::TooManyProperties(max)));
+            }
+        }
+        if let Some(stats) = &mut self.stats {
+            // a property repeated within the feature, e.g. a renamed column, is counted once
+            if !self.current_feature.current_properties.contains_key(name) {
+                stats.record(name, value);
+            }
+        }
+        let finished = self.current_feature.property(idx, name, value)?;
+        Ok(finished || self.has_requested_properties())
+    }
+}
+
+impl<'de, T> GeomProcessor for GeozeroCollector<'de, T> {
+    fn dimensions(&self) -> geozero::CoordDimensions {
+        self.current_feature.dimensions()
+    }
+
+    fn multi_dim(&self) -> bool {
+        self.current_feature.multi_dim()
+    }
+
+    fn srid(&mut self, srid: Option<i32>) -> geozero::error::Result<()> {
+        if self.dataset.srid.is_none() {
+            self.dataset.srid = srid;
+        }
+        self.current_feature.srid(srid)
+    }
+
+    fn xy(&mut self, x: f64, y: f64, idx: usize) -> geozero::error::Result<()> {
+        self.current_feature.xy(x, y, idx)
+    }
+
+    fn coordinate(
+        &mut self,
+        x: f64,
+        y: f64,
+        z: Option<f64>,
+        m: Option<f64>,
+        t: Option<f64>,
+        tm: Option<u64>,
+        idx: usize,
+    ) -> geozero::error::Result<()> {
+        self.current_feature.coordinate(x, y, z, m, t, tm, idx)
+    }
+
+    fn empty_point(&mut self, idx: usize) -> geozero::error::Result<()> {
+        self.current_feature.empty_point(idx)
+    }
+
+    fn point_begin(&mut self, idx: usize) -> geozero::error::Result<()> {
+        self.current_feature.point_begin(idx)
+    }
+
+    fn point_end(&mut self, idx: usize) -> geozero::error::Result<()> {
+        self.current_feature.point_end(idx)
+    }
+
+    fn multipoint_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
+        self.current_feature.multipoint_begin(size, idx)
+    }
+
+    fn multipoint_end(&mut self, idx: usize) -> geozero::error::Result<()> {
+        self.current_feature.multipoint_end(idx)
+    }
+
+    fn linestring_begin(
+        &mut self,
+        tagged: bool,
+        size: usize,
+        idx: usize,
+    ) -> geozero::error::Result<()> {
+        self.current_feature.linestring_begin(tagged, size, idx)
+    }
+
+    fn linestring_end(&mut self, tagged: bool, idx: usize) -> geozero::error::Result<()> {
+        self.current_feature.linestring_end(tagged, idx)
+    }
+
+    fn multilinestring_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
+        self.current_feature.multilinestring_begin(size, idx)
+    }
+
+    fn multilinestring_end(&mut self, idx: usize) -> geozero::error::Result<()> {
+        self.current_feature.multilinestring_end(idx)
+    }
+
+    fn polygon_begin(
+        &mut self,
+        tagged: bool,
+        size: usize,
+        idx: usize,
+    ) -> geozero::error::Result<()> {
+        self.current_feature.polygon_begin(tagged, size, idx)
+    }
+
+    fn polygon_end(&mut self, tagged: bool, idx: usize) -> geozero::error::Result<()> {
+        self.current_feature.polygon_end(tagged, idx)
+    }
+
+    fn multipolygon_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
+        self.current_feature.multipolygon_begin(size, idx)
+    }
+
+    fn multipolygon_end(&mut self, idx: usize) -> geozero::error::Result<()> {
+        self.current_feature.multipolygon_end(idx)
+    }
+
+    fn geometrycollection_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
+        self.current_feature.geometrycollection_begin(size, idx)
+    }
+
+    fn geometrycollection_end(&mut self, idx: usize) -> geozero::error::Result<()> {
+        self.current_feature.geometrycollection_end(idx)
+    }
+
+    fn circularstring_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
+        self.current_feature.circularstring_begin(size, idx)
+    }
+
+    fn circularstring_end(&mut self, idx: usize) -> geozero::error::Result<()> {
+        self.current_feature.circularstring_end(idx)
+    }
+
+    fn compoundcurve_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
+        self.current_feature.compoundcurve_begin(size, idx)
+    }
+
+    fn compoundcurve_end(&mut self, idx: usize) -> geozero::error::Result<()> {
+        self.current_feature.compoundcurve_end(idx)
+    }
+
+    fn curvepolygon_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
+        self.current_feature.curvepolygon_begin(size, idx)
+    }
+
+    fn curvepolygon_end(&mut self, idx: usize) -> geozero::error::Result<()> {
+        self.current_feature.curvepolygon_end(idx)
+    }
+
+    fn multicurve_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
+        self.current_feature.multicurve_begin(size, idx)
+    }
+
+    fn multicurve_end(&mut self, idx: usize) -> geozero::error::Result<()> {
+        self.current_feature.multicurve_end(idx)
+    }
+
+    fn multisurface_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
+        self.current_feature.multisurface_begin(size, idx)
+    }
+
+    fn multisurface_end(&mut self, idx: usize) -> geozero::error::Result<()> {
+        self.current_feature.multisurface_end(idx)
+    }
+
+    fn triangle_begin(
+        &mut self,
+        tagged: bool,
+        size: usize,
+        idx: usize,
+    ) -> geozero::error::Result<()> {
+        self.current_feature.triangle_begin(tagged, size, idx)
+    }
+
+    fn triangle_end(&mut self, tagged: bool, idx: usize) -> geozero::error::Result<()> {
+        self.current_feature.triangle_end(tagged, idx)
+    }
+
+    fn polyhedralsurface_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
+        self.current_feature.polyhedralsurface_begin(size, idx)
+    }
+
+    fn polyhedralsurface_end(&mut self, idx: usize) -> geozero::error::Result<()> {
+        self.current_feature.polyhedralsurface_end(idx)
+    }
+
+    fn tin_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
+        self.current_feature.tin_begin(size, idx)
+    }
+
+    fn tin_end(&mut self, idx: usize) -> geozero::error::Result<()> {
+        self.current_feature.tin_end(idx)
+    }
+
+    fn pre_process_xy<F: Fn(&mut f64, &mut f64)>(
+        self,
+        transform_xy: F,
+    ) -> geozero::WrappedXYProcessor<Self, F>
+    where
+        Self: Sized,
+    {
+        geozero::WrappedXYProcessor::new(self, transform_xy)
+    }
+}
+
+impl<'de, T> FeatureProcessor for GeozeroCollector<'de, T> {
+    fn dataset_begin(&mut self, name: Option<&str>) -> geozero::error::Result<()> {
+        self.dataset.name = name.map(ToString::to_string);
+        Ok(())
+    }
+
+    fn feature_begin(&mut self, idx: u64) -> geozero::error::Result<()> {
+        if let Some(on_feature) = &self.on_feature {
+            if (on_feature.0)(self.processed).is_break() {
+                return Err(self.raise(Error::Cancelled(self.processed)));
+            }
+        }
+        if let Some(stats) = &mut self.stats {
+            stats.feature_count += 1;
+        }
+        self.current_feature.feature_begin(idx)
+    }
+
+    fn feature_end(&mut self, _idx: u64) -> geozero::error::Result<()> {
+        let geo_feature = if let Ok(feature) = Feature::try_from(&mut self.current_feature) {
+            feature
+        } else {
+            let Some(feature) = self.reject_missing_geometry()? else {
+                return Ok(());
+            };
+            feature
+        };
+        let Some(geo_feature) = self.validate(geo_feature)? else {
+            return Ok(());
+        };
+        let Some(geo_feature) = self.check_finite(geo_feature)? else {
+            return Ok(());
+        };
+        let mut geo_feature = geo_feature
+            .with_coord_rounding(self.coord_rounding)
+            .with_raw_json(self.raw_json)
+            .with_coerce_types(self.coerce_types)
+            .with_compact(self.compact)
+            .with_layout(self.layout)
+            .with_enum_tag(self.enum_tag.clone())
+            .with_geometry_columns(self.geometry_columns.clone());
+        #[cfg(feature = "proj")]
+        if let Some(reprojection) = &self.reprojection {
+            if let Err(err) = reprojection.apply(&mut geo_feature.geometry) {
+                return Err(self.raise(err));
+            }
+            // the bounding box was computed from the source coordinates
+            if geo_feature.bbox().is_some() {
+                let bbox = geo_feature.geometry.bounding_rect();
+                geo_feature = geo_feature.with_bbox(bbox);
+            }
+            // like the write side, report the SRID of the target CRS
+            geo_feature = geo_feature.with_srid(reprojection.target_srid());
+        }
+        if self.is_duplicate(&geo_feature) {
+            self.processed += 1;
+            self.duplicates += 1;
+            return Ok(());
+        }
+        for lookup in &self.lookups {
+            lookup.apply(&mut geo_feature);
+        }
+        if let Some(fields) = self.fields {
+            self.seen_fields.extend(
+                fields
+                    .iter()
+                    .filter(|field| geo_feature.properties.contains_key(**field)),
+            );
+        }
+        if let Some(checker) = &mut self.quality {
+            checker.check(&geo_feature);
+        }
+
+        let index = self.processed;
+        self.processed += 1;
+        let bbox = geo_feature.bbox().or_else(|| {
+            self.envelope_from_geometry
+                .then(|| geo_feature.geometry.bounding_rect())
+                .flatten()
+        });
+        let geometry_type = match &geo_feature.geometry {
+            // the placeholder of features read without geometry
+            Geometry::GeometryCollection(collection) if collection.0.is_empty() => None,
+            geometry => Some(geometry_type_name(geometry)),
+        };
+        let Some(feature) = self.deserialize_feature(index, geo_feature)? else {
+            return Ok(());
+        };
+
+        if let Some(geometry_type) = geometry_type {
+            if !self.geometry_types.contains(&geometry_type) {
+                self.geometry_types.push(geometry_type);
+            }
+        }
+        self.record_bbox(bbox);
+        if let Some(sender) = &self.sender {
+            // the receiver is gone once the iterator was dropped
+            if sender.send(Ok(feature)).is_err() {
+                self.limit_reached = true;
+                return Err(GeozeroError::Feature(
+                    "Feature iterator dropped".to_string(),
+                ));
+            }
+            return Ok(());
+        }
+        self.features.push(feature);
+        if self
+            .chunks
+            .as_ref()
+            .is_some_and(|(chunk_size, _)| self.features.len() >= *chunk_size)
+        {
+            if let Err(err) = self.flush_chunk() {
+                return Err(self.raise(err));
+            }
+        }
+
+        // datasources can only be stopped by returning an error
+        if self.limit == Some(self.features.len()) {
+            self.limit_reached = true;
+            return Err(GeozeroError::Feature("Feature limit reached".to_string()));
+        }
+        Ok(())
+    }
+
+    fn dataset_end(&mut self) -> geozero::error::Result<()> {
+        let Some(fields) = self.fields else {
+            return Ok(());
+        };
+        for field in fields {
+            if self.seen_fields.contains(field) || SYNTHETIC_FIELDS.contains(field) {
+                continue;
+            }
+            let drift = SchemaDrift::MissingColumn((*field).to_string());
+            if let Some(err) = self.strict.report(&drift) {
+                return Err(self.raise(err));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'de, T> GeozeroCollector<'de, T> {
+    /// Deserializes a feature, consulting the error callback on failure.
+    ///
+    /// Returns `None` if the feature is skipped or left out by the converter.
+    fn deserialize_feature(
+        &mut self,
+        index: usize,
+        mut feature: Feature,
+    ) -> geozero::error::Result<Option<T>> {
+        let mut retries = 0;
+        loop {
+            // the error callbacks get the feature back, so it is only lent to the deserializer
+            let keep_feature = self.on_error.is_some()
+                || matches!(self.error_policy, ErrorPolicy::SkipWithCallback(_));
+            let (err, kept) = if keep_feature {
+                match self.convert.try_convert(feature) {
+                    Ok(converted) => return Ok(converted),
+                    Err(failed) => {
+                        let (err, feature) = *failed;
+                        (err, Some(feature))
+                    }
+                }
+            } else {
+                match self.convert.convert(feature) {
+                    Ok(converted) => return Ok(converted),
+                    Err(err) => (err, None),
+                }
+            };
+            let err = err.with_feature_index(index);
+            let Some(kept) = kept else {
+                if matches!(self.error_policy, ErrorPolicy::Skip) {
+                    self.skipped += 1;
+                    return Ok(None);
+                }
+                return Err(self.raise_for_feature(index, err));
+            };
+
+            let mut feature_error = FeatureError {
+                index,
+                error: err,
+                feature: kept,
+            };
+            match self.decide(&mut feature_error) {
+                ErrorDecision::Skip => {
+                    self.skipped += 1;
+                    return Ok(None);
+                }
+                ErrorDecision::Retry if retries < MAX_RETRIES => {
+                    retries += 1;
+                    feature = feature_error.feature;
+                }
+                ErrorDecision::Retry | ErrorDecision::Abort => {
+                    return Err(self.raise_for_feature(index, feature_error.error));
+                }
+            }
+        }
+    }
+
+    /// Remembers `feature` and returns `true` if an equal one was read before.
+    fn is_duplicate(&mut self, feature: &Feature) -> bool {
+        match &self.dedup {
+            Dedup::Off => false,
+            Dedup::Exact => !self.seen.insert(feature.stable_hash()),
+            Dedup::ByKey(key) => match feature.value(key) {
+                None | Some(Value::Null) => false,
+                Some(value) => !self.seen_keys.insert(ValueKey(value.clone())),
+            },
+        }
+    }
+
+    /// Asks the error callback or policy how to continue after `feature_error`.
+    fn decide(&self, feature_error: &mut FeatureError) -> ErrorDecision {
+        match (self.on_error, self.error_policy) {
+            (Some(on_error), _) => on_error(feature_error),
+            (None, ErrorPolicy::Fail) => ErrorDecision::Abort,
+            (None, ErrorPolicy::Skip) => ErrorDecision::Skip,
+            (None, ErrorPolicy::SkipWithCallback(callback)) => {
+                callback(feature_error);
+                ErrorDecision::Skip
+            }
+        }
+    }
+
+    /// Handles a feature without geometry like a feature that failed to deserialize.
+    ///
+    /// Returns the feature to continue with if the error callback repaired it.
+    fn reject_missing_geometry(&mut self) -> geozero::error::Result<Option<Feature>> {
+        let index = self.processed;
+        let feature_error = FeatureError {
+            index,
+            error: Error::MissingGeometry(index),
+            feature: Feature::from_parts(
+                Geometry::GeometryCollection(GeometryCollection::default()),
+                std::mem::take(&mut self.current_feature.current_properties),
+                std::mem::take(&mut self.current_feature.current_order),
+            ),
+        };
+        self.reject(feature_error)
+    }
+
+    /// Checks the geometry of `feature` if [`DeserializeOptions::validate_geometries`] is set.
+    ///
+    /// Invalid geometries are handled like features that failed to deserialize, repaired
+    /// features are checked again.
+    fn validate(&mut self, mut feature: Feature) -> geozero::error::Result<Option<Feature>> {
+        if !self.validate_geometries {
+            return Ok(Some(feature));
+        }
+        let index = self.processed;
+        let mut retries = 0;
+        while let Err(invalid) = feature.geometry.check_validation() {
+            let error = Error::InvalidGeometry {
+                index,
+                reason: invalid.to_string(),
+            };
+            if retries == MAX_RETRIES {
+                return Err(self.raise(error));
+            }
+            retries += 1;
+            let Some(repaired) = self.reject(FeatureError {
+                index,
+                error,
+                feature,
+            })?
+            else {
+                return Ok(None);
+            };
+            feature = repaired;
+        }
+        Ok(Some(feature))
+    }
+
+    /// Applies [`DeserializeOptions::non_finite`] to `feature`.
+    ///
+    /// Like invalid geometries, non-finite values are handled by the error policy and repaired
+    /// features are checked again.
+    fn check_finite(&mut self, mut feature: Feature) -> geozero::error::Result<Option<Feature>> {
+        let index = self.processed;
+        match self.non_finite {
+            NonFinite::Allow => Ok(Some(feature)),
+            NonFinite::Error => {
+                let mut retries = 0;
+                while let Some(location) =
+                    finite::find(Some(&feature.geometry), &feature.properties)
+                {
+                    let error = Error::NonFinite { index, location };
+                    if retries == MAX_RETRIES {
+                        return Err(self.raise(error));
+                    }
+                    retries += 1;
+                    let Some(repaired) = self.reject(FeatureError {
+                        index,
+                        error,
+                        feature,
+                    })?
+                    else {
+                        return Ok(None);
+                    };
+                    feature = repaired;
+                }
+                Ok(Some(feature))
+            }
+            NonFinite::SkipFeature => {
+                if finite::find(Some(&feature.geometry), &feature.properties).is_some() {
+                    self.processed += 1;
+                    self.skipped += 1;
+                    return Ok(None);
+                }
+                Ok(Some(feature))
+            }
+            NonFinite::ClampOrNull => {
+                if finite::clamp(Some(&mut feature.geometry), &mut feature.properties) {
+                    return Ok(Some(feature));
+                }
+                feature.geometry = Geometry::GeometryCollection(GeometryCollection::default());
+                self.reject(FeatureError {
+                    index,
+                    error: Error::MissingGeometry(index),
+                    feature,
+                })
+            }
+        }
+    }
+
+    /// Handles a feature rejected before deserialization.
+    ///
+    /// Returns the feature to continue with if the error callback repaired it.
+    fn reject(
+        &mut self,
+        mut feature_error: FeatureError,
+    ) -> geozero::error::Result<Option<Feature>> {
+        match self.decide(&mut feature_error) {
+            ErrorDecision::Skip => {
+                self.processed += 1;
+                self.skipped += 1;
+                Ok(None)
+            }
+            ErrorDecision::Retry => Ok(Some(feature_error.feature)),
+            ErrorDecision::Abort => Err(self.raise(feature_error.error)),
+        }
+    }
+
+    /// Adds the index of the failed feature to `err` and raises it.
+    fn raise_for_feature(&mut self, index: usize, err: Error) -> GeozeroError {
+        self.raise(Error::Feature {
+            index,
+            source: Box::new(err),
+        })
+    }
+}
+
+/// How often a feature is retried after [`ErrorDecision::Retry`] before giving up.
+const MAX_RETRIES: usize = 3;
+
+/// Turns the features a [`GeozeroCollector`] finished into the values it collects.
+///
+/// A converter returns `None` for features it leaves out on purpose, which aren't counted as
+/// skipped.
+pub(crate) trait Convert<T> {
+    fn convert(&mut self, feature: Feature) -> Result<Option<T>>;
+
+    /// Like [`Convert::convert`], but hands the feature back with the error, e.g. for the error
+    /// callback.
+    fn try_convert(&mut self, feature: Feature) -> Converted<T>;
+}
+
+/// The result of [`Convert::try_convert`].
+pub(crate) type Converted<T> = std::result::Result<Option<T>, Box<(Error, Feature)>>;
+
+/// Deserializes every feature into `T`.
+struct Deserialized<'de, T>(PhantomData<fn(&'de ()) -> T>);
+
+impl<'de, T: Deserialize<'de>> Convert<T> for Deserialized<'de, T> {
+    fn convert(&mut self, feature: Feature) -> Result<Option<T>> {
+        T::deserialize(feature).map(Some)
+    }
+
+    fn try_convert(&mut self, feature: Feature) -> Converted<T> {
+        match T::deserialize(FeatureRef::new(&feature)) {
+            Ok(converted) => Ok(Some(converted)),
+            Err(err) => Err(Box::new((err, feature))),
+        }
+    }
+}
+
+/// Collects the features as they are, with their shared strings and the values serde doesn't
+/// see, like the SRID.
+struct Unchanged;
+
+impl Convert<Feature> for Unchanged {
+    fn convert(&mut self, feature: Feature) -> Result<Option<Feature>> {
+        Ok(Some(feature))
+    }
+
+    fn try_convert(&mut self, feature: Feature) -> Converted<Feature> {
+        Ok(Some(feature))
+    }
+}
+
+/// The converter for `T`: [`Unchanged`] if `T` is [`Feature`], [`Deserialized`] otherwise.
+fn converter<'de, T: Deserialize<'de> + 'static>() -> Box<dyn Convert<T> + 'de> {
+    let unchanged: Box<dyn Any> = Box::new(Box::new(Unchanged) as Box<dyn Convert<Feature>>);
+    if let Ok(unchanged) = unchanged.downcast::<Box<dyn Convert<T>>>() {
+        return *unchanged;
+    }
+    Box::new(Deserialized(PhantomData))
+}
+
+/// Statistics of a single property, see [`CollectStats`].
+#[derive(Debug, Clone, Default)]
+pub struct PropertyStats {
+    /// Number of features with a non-null value.
+    pub value_count: usize,
+    /// Number of explicit `null` values. Most readers leave out null properties, features
+    /// without the property are counted by [`CollectStats::missing_count`].
+    pub null_count: usize,
+    /// The smallest finite numeric value.
+    pub min: Option<f64>,
+    /// The largest finite numeric value.
+    pub max: Option<f64>,
+    sum: f64,
+    numeric_count: usize,
+    distinct: HashSet<String>,
+}
+
+impl PropertyStats {
+    /// The mean of the finite numeric values.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn mean(&self) -> Option<f64> {
+        (self.numeric_count > 0).then(|| self.sum / self.numeric_count as f64)
+    }
+
+    /// Number of different string values.
+    #[must_use]
+    pub fn distinct_count(&self) -> usize {
+        self.distinct.len()
+    }
+
+    /// Whether `value` is one of the string values seen.
+    #[must_use]
+    pub fn contains(&self, value: &str) -> bool {
+        self.distinct.contains(value)
+    }
+}
+
+/// Property statistics recorded while collecting, see [`GeozeroCollector::with_stats`].
+///
+/// ```
+/// use serde_geozero::collector::RawCollector;
+///
+/// let geojson = r#"{"type": "FeatureCollection", "features": [
+///     {"type": "Feature", "properties": {"kind": "city", "population": 3669495},
+///         "geometry": {"type": "Point", "coordinates": [13.4, 52.5]}},
+///     {"type": "Feature", "properties": {"kind": "town", "population": 1000},
+///         "geometry": {"type": "Point", "coordinates": [10.0, 53.6]}},
+///     {"type": "Feature", "properties": {"kind": "city"},
+///         "geometry": {"type": "Point", "coordinates": [11.6, 48.1]}}]}"#;
+///
+/// let mut collector = RawCollector::new().with_stats();
+/// collector.process(&mut geozero::geojson::GeoJsonReader(geojson.as_bytes())).unwrap();
+///
+/// let stats = collector.take_stats().unwrap();
+/// let population = stats.property("population").unwrap();
+/// assert_eq!((population.min, population.max), (Some(1000.), Some(3_669_495.)));
+/// assert_eq!(stats.missing_count("population"), 1);
+/// assert_eq!(stats.property("kind").unwrap().distinct_count(), 2);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CollectStats {
+    /// Number of features read.
+    pub feature_count: usize,
+    properties: HashMap<String, PropertyStats>,
+}
+
+impl CollectStats {
+    /// The statistics of the property `name`, `None` if no feature had it.
+    #[must_use]
+    pub fn property(&self, name: &str) -> Option<&PropertyStats> {
+        self.properties.get(name)
+    }
+
+    /// The names and statistics of all properties seen.
+    pub fn properties(&self) -> impl Iterator<Item = (&str, &PropertyStats)> {
+        self.properties
+            .iter()
+            .map(|(name, stats)| (name.as_str(), stats))
+    }
+
+    /// Number of features without a value for `name`, whether it was left out or `null`.
+    #[must_use]
+    pub fn missing_count(&self, name: &str) -> usize {
+        let value_count = self.property(name).map_or(0, |stats| stats.value_count);
+        self.feature_count.saturating_sub(value_count)
+    }
+
+    fn record(&mut self, name: &str, value: &ColumnValue) {
+        let stats = self.properties.entry_ref(name).or_default();
+        match value {
+            ColumnValue::Json("null") => {
+                stats.null_count += 1;
+                return;
+            }
+            ColumnValue::String(value) => {
+                if !stats.distinct.contains(*value) {
+                    stats.distinct.insert((*value).to_string());
+                }
+            }
+            ColumnValue::Json(_) | ColumnValue::DateTime(_) | ColumnValue::Binary(_) => {}
+            value => {
+                if let Some(number) = Value::from(value).as_f64().filter(|n| n.is_finite()) {
+                    stats.min = Some(stats.min.map_or(number, |min| min.min(number)));
+                    stats.max = Some(stats.max.map_or(number, |max| max.max(number)));
+                    stats.sum += number;
+                    stats.numeric_count += 1;
+                }
+            }
+        }
+        stats.value_count += 1;
+    }
+}
+
+/// Buffers the geometry and properties of a single feature.
+pub struct GeozeroFeature {
+    pub(crate) current_properties: HashMap<String, Value>,
+    /// The names of `current_properties` in the order they were read.
+    pub(crate) current_order: Vec<String>,
+    pub(crate) current_geometry: GeoWriter,
+    /// The geometry of the feature once it ended.
+    pub(crate) geometry: Option<Geometry>,
+    pub(crate) srid: Option<i32>,
+    pub(crate) zm: ZmValues,
+    pub(crate) bbox: Option<Rect>,
+    preserve_zm: bool,
+    compute_bbox: bool,
+    /// The most properties a feature had so far, to size the next property map.
+    properties_hint: usize,
+    /// Every string read so far, if strings are interned.
+    strings: Option<HashSet<Arc<str>>>,
+}
+
+impl GeozeroFeature {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            current_properties: HashMap::new(),
+            current_order: Vec::new(),
+            current_geometry: GeoWriter::new(),
+            geometry: None,
+            srid: None,
+            zm: ZmValues::default(),
+            bbox: None,
+            preserve_zm: false,
+            compute_bbox: false,
+            properties_hint: 0,
+            strings: None,
+        }
+    }
+}
+
+impl Default for GeozeroFeature {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PropertyProcessor for GeozeroFeature {
+    fn property(
+        &mut self,
+        _idx: usize,
+        name: &str,
+        value: &ColumnValue,
+    ) -> geozero::error::Result<bool> {
+        let value = match (value, &mut self.strings) {
+            (ColumnValue::String(value), Some(strings)) => {
+                Value::Shared(if let Some(shared) = strings.get(*value) {
+                    Arc::clone(shared)
+                } else {
+                    let shared: Arc<str> = Arc::from(*value);
+                    strings.insert(Arc::clone(&shared));
+                    shared
+                })
+            }
+            (value, _) => Value::from(value),
+        };
+        if self
+            .current_properties
+            .insert(name.to_string(), value)
+            .is_none()
+        {
+            self.current_order.push(name.to_string());
+        }
+        self.properties_hint = self.properties_hint.max(self.current_properties.len());
+        Ok(false)
+    }
+}
+
+impl GeomProcessor for GeozeroFeature {
+    fn dimensions(&self) -> geozero::CoordDimensions {
+        if self.preserve_zm {
+            geozero::CoordDimensions::xyzm()
+        } else {
+            self.current_geometry.dimensions()
+        }
+    }
+
+    fn multi_dim(&self) -> bool {
+        self.preserve_zm || self.current_geometry.multi_dim()
+    }
+
+    fn srid(&mut self, srid: Option<i32>) -> geozero::error::Result<()> {
+        self.srid = srid;
+        self.current_geometry.srid(srid)
+    }
+
+    fn xy(&mut self, x: f64, y: f64, idx: usize) -> geozero::error::Result<()> {
+        if self.preserve_zm {
+            self.zm.push(None, None);
+        }
+        if self.compute_bbox {
+            self.bbox = Some(expand_bbox(self.bbox, x, y));
+        }
+        self.current_geometry.xy(x, y, idx)
+    }
+
+    fn coordinate(
+        &mut self,
+        x: f64,
+        y: f64,
+        z: Option<f64>,
+        m: Option<f64>,
+        _t: Option<f64>,
+        _tm: Option<u64>,
+        idx: usize,
+    ) -> geozero::error::Result<()> {
+        if self.preserve_zm {
+            self.zm.push(z, m);
+        }
+        if self.compute_bbox {
+            self.bbox = Some(expand_bbox(self.bbox, x, y));
+        }
+        // GeoWriter only stores XY and ignores `coordinate` calls
+        self.current_geometry.xy(x, y, idx)
+    }
+
+    fn empty_point(&mut self, idx: usize) -> geozero::error::Result<()> {
+        self.current_geometry.empty_point(idx)
+    }
+
+    fn point_begin(&mut self, idx: usize) -> geozero::error::Result<()> {
+        self.current_geometry.point_begin(idx)
+    }
+
+    fn point_end(&mut self, idx: usize) -> geozero::error::Result<()> {
+        self.current_geometry.point_end(idx)
+    }
+
+    fn multipoint_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
+        self.current_geometry.multipoint_begin(size, idx)
+    }
+
+    fn multipoint_end(&mut self, idx: usize) -> geozero::error::Result<()> {
+        self.current_geometry.multipoint_end(idx)
+    }
+
+    fn linestring_begin(
+        &mut self,
+        tagged: bool,
+        size: usize,
+        idx: usize,
+    ) -> geozero::error::Result<()> {
+        self.current_geometry.linestring_begin(tagged, size, idx)
+    }
+
+    fn linestring_end(&mut self, tagged: bool, idx: usize) -> geozero::error::Result<()> {
+        self.current_geometry.linestring_end(tagged, idx)
+    }
+
+    fn multilinestring_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
+        self.current_geometry.multilinestring_begin(size, idx)
+    }
+
+    fn multilinestring_end(&mut self, idx: usize) -> geozero::error::Result<()> {
+        self.current_geometry.multilinestring_end(idx)
+    }
+
+    fn polygon_begin(
+        &mut self,
+        tagged: bool,
+        size: usize,
+        idx: usize,
+    ) -> geozero::error::Result<()> {
+        self.current_geometry.polygon_begin(tagged, size, idx)
+    }
+
+    fn polygon_end(&mut self, tagged: bool, idx: usize) -> geozero::error::Result<()> {
+        self.current_geometry.polygon_end(tagged, idx)
+    }
+
+    fn multipolygon_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
+        self.current_geometry.multipolygon_begin(size, idx)
+    }
+
+    fn multipolygon_end(&mut self, idx: usize) -> geozero::error::Result<()> {
+        self.current_geometry.multipolygon_end(idx)
+    }
+
+    fn geometrycollection_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
+        self.current_geometry.geometrycollection_begin(size, idx)
+    }
+
+    fn geometrycollection_end(&mut self, idx: usize) -> geozero::error::Result<()> {
+        self.current_geometry.geometrycollection_end(idx)
+    }
+
+    fn circularstring_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
+        self.current_geometry.circularstring_begin(size, idx)
+    }
+
+    fn circularstring_end(&mut self, idx: usize) -> geozero::error::Result<()> {
+        self.current_geometry.circularstring_end(idx)
+    }
+
+    fn compoundcurve_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
+        self.current_geometry.compoundcurve_begin(size, idx)
+    }
+
+    fn compoundcurve_end(&mut self, idx: usize) -> geozero::error::Result<()> {
+        self.current_geometry.compoundcurve_end(idx)
+    }
+
+    fn curvepolygon_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
+        self.current_geometry.curvepolygon_begin(size, idx)
+    }
+
+    fn curvepolygon_end(&mut self, idx: usize) -> geozero::error::Result<()> {
+        self.current_geometry.curvepolygon_end(idx)
+    }
+
+    fn multicurve_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
+        self.current_geometry.multicurve_begin(size, idx)
+    }
+
+    fn multicurve_end(&mut self, idx: usize) -> geozero::error::Result<()> {
+        self.current_geometry.multicurve_end(idx)
+    }
+
+    fn multisurface_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
+        self.current_geometry.multisurface_begin(size, idx)
+    }
+
+    fn multisurface_end(&mut self, idx: usize) -> geozero::error::Result<()> {
+        self.current_geometry.multisurface_end(idx)
+    }
+
+    fn triangle_begin(
+        &mut self,
+        tagged: bool,
+        size: usize,
+        idx: usize,
+    ) -> geozero::error::Result<()> {
+        self.current_geometry.triangle_begin(tagged, size, idx)
+    }
+
+    fn triangle_end(&mut self, tagged: bool, idx: usize) -> geozero::error::Result<()> {
+        self.current_geometry.triangle_end(tagged, idx)
+    }
+
+    fn polyhedralsurface_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
+        self.current_geometry.polyhedralsurface_begin(size, idx)
+    }
+
+    fn polyhedralsurface_end(&mut self, idx: usize) -> geozero::error::Result<()> {
+        self.current_geometry.polyhedralsurface_end(idx)
+    }
+
+    fn tin_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
+        self.current_geometry.tin_begin(size, idx)
+    }
+
+    fn tin_end(&mut self, idx: usize) -> geozero::error::Result<()> {
+        self.current_geometry.tin_end(idx)
+    }
+
+    fn pre_process_xy<F: Fn(&mut f64, &mut f64)>(
+        self,
+        transform_xy: F,
+    ) -> geozero::WrappedXYProcessor<Self, F>
+    where
+        Self: Sized,
+    {
+        geozero::WrappedXYProcessor::new(self, transform_xy)
+    }
+}
+
+impl FeatureProcessor for GeozeroFeature {
+    fn feature_begin(&mut self, _idx: u64) -> geozero::error::Result<()> {
+        self.current_geometry = GeoWriter::new();
+        self.geometry = None;
+        self.current_properties = HashMap::with_capacity(self.properties_hint);
+        self.current_order = Vec::with_capacity(self.properties_hint);
+        self.srid = None;
+        self.zm = ZmValues::default();
+        self.bbox = None;
+        Ok(())
+    }
+
+    fn feature_end(&mut self, _idx: u64) -> geozero::error::Result<()> {
+        self.geometry = self.current_geometry.take_geometry();
+        Ok(())
+    }
+}
+
+/// Writes a `GeoJSON` feature object like [`Feature`]. The geometry is `null` until the feature
+/// ended.
+impl Serialize for GeozeroFeature {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        GeoJsonFeature {
+            geometry: self.geometry.as_ref(),
+            properties: &self.current_properties,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Fields filled from the feature itself rather than from its properties.
+const SYNTHETIC_FIELDS: [&str; 5] = [GEOMETRY_COL, SRID_COL, BBOX_COL, Z_COL, M_COL];
+
+fn expand_bbox(bbox: Option<Rect>, x: f64, y: f64) -> Rect {
+    match bbox {
+        Some(bbox) => Rect::new(
+            coord! { x: bbox.min().x.min(x), y: bbox.min().y.min(y) },
+            coord! { x: bbox.max().x.max(x), y: bbox.max().y.max(y) },
+        ),
+        None => Rect::new(coord! { x: x, y: y }, coord! { x: x, y: y }),
+    }
+}
+
+fn merge_bbox(bbox: Option<Rect>, other: Rect) -> Rect {
+    let bbox = expand_bbox(bbox, other.min().x, other.min().y);
+    expand_bbox(Some(bbox), other.max().x, other.max().y)
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use geo::{coord, Geometry, Rect};
+    use geozero::{ColumnValue, FeatureProcessor, GeomProcessor, PropertyProcessor};
+    use serde::Deserialize;
+
+    use super::GeozeroCollector;
+    use crate::{
+        de::{Dedup, Feature},
+        error::{Error, ErrorDecision, ErrorPolicy},
+        finite::NonFinite,
+        lookup::Lookup,
+        schema::{SchemaDrift, StrictMode},
+        value::Value,
+        DeserializeOptions,
+    };
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn bounding_boxes() -> anyhow::Result<()> {
+        #[derive(Deserialize)]
+        struct Boxed {
+            #[allow(dead_code)]
+            geometry: Geometry,
+            bbox: [f64; 4],
+        }
+
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "properties": {},
+             "geometry": {"type": "LineString", "coordinates": [[1, 5], [3, 2]]}},
+            {"type": "Feature", "properties": {},
+             "geometry": {"type": "Point", "coordinates": [-1, 4]}}
+        ]}"#;
+
+        let mut collector =
+            GeozeroCollector::<Boxed>::with_options(&DeserializeOptions::new().compute_bbox(true))?;
+        geozero::GeozeroDatasource::process(
+            &mut geozero::geojson::GeoJsonReader(geojson.as_bytes()),
+            &mut collector,
+        )?;
+
+        assert_eq!(collector.features[0].bbox, [1., 2., 3., 5.]);
+        assert_eq!(collector.features[1].bbox, [-1., 4., -1., 4.]);
+        assert_eq!(
+            collector.envelope(),
+            Some(Rect::new(coord! { x: -1., y: 2. }, coord! { x: 3., y: 5. }))
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "proj")]
+    #[test]
+    fn reprojected_bounding_boxes() -> anyhow::Result<()> {
+        use geo::BoundingRect;
+
+        #[derive(Deserialize)]
+        struct Boxed {
+            geometry: Geometry,
+            bbox: [f64; 4],
+        }
+
+        let geojson = r#"{"type": "Feature", "properties": {},
+            "geometry": {"type": "LineString", "coordinates": [[0, 0], [10, 20]]}}"#;
+
+        let options = DeserializeOptions::new()
+            .compute_bbox(true)
+            .reproject("EPSG:4326", "EPSG:3857");
+        let mut collector = GeozeroCollector::<Boxed>::with_options(&options)?;
+        collector.process(&mut geozero::geojson::GeoJsonReader(geojson.as_bytes()))?;
+
+        let feature = &collector.features[0];
+        let expected = feature.geometry.bounding_rect().unwrap();
+        assert_eq!(
+            feature.bbox,
+            [
+                expected.min().x,
+                expected.min().y,
+                expected.max().x,
+                expected.max().y
+            ]
+        );
+        // 10° east in web mercator meters
+        assert!((feature.bbox[2] - 1_113_194.9).abs() < 1.);
+        Ok(())
+    }
+
+    #[test]
+    fn structured_errors_are_recovered() {
+        #[derive(Debug, Deserialize)]
+        struct Typed {
+            #[allow(dead_code)]
+            geometry: Geometry,
+            #[allow(dead_code)]
+            value: u8,
+        }
+
+        let geojson = r#"{"type": "Feature", "properties": {"value": "text"},
+            "geometry": {"type": "Point", "coordinates": [1, 1]}}"#;
+
+        let mut collector = GeozeroCollector::<Typed>::new();
+        let err = collector
+            .process(&mut geozero::geojson::GeoJsonReader(geojson.as_bytes()))
+            .unwrap_err();
+        assert_eq!(err.feature_index(), Some(0));
+        assert_eq!(err.field().as_deref(), Some("value"));
+        assert!(
+            matches!(err.root_cause(), Error::InvalidType { found, expected }
+                if found == "string \"text\"" && expected == "u8"),
+            "{err:?}"
+        );
+        assert_eq!(
+            err.to_string(),
+            "Feature 0: field \"value\": invalid type: string \"text\", expected u8"
+        );
+        assert!(collector.take_error().is_none());
+    }
+
+    #[test]
+    fn non_finite_values() -> anyhow::Result<()> {
+        #[derive(Debug, Deserialize)]
+        struct Sample {
+            geometry: Option<Geometry>,
+            value: Option<f64>,
+        }
+
+        let read = |options: &DeserializeOptions| -> crate::error::Result<Vec<Sample>> {
+            let mut collector = GeozeroCollector::<Sample>::with_options(options)?;
+            for (idx, (x, value)) in [(1., f64::INFINITY), (f64::NAN, 1.)]
+                .into_iter()
+                .enumerate()
+            {
+                collector.feature_begin(idx as u64)?;
+                collector.property(0, "value", &ColumnValue::Double(value))?;
+                collector.point_begin(0)?;
+                collector.xy(x, 2., 0)?;
+                collector.point_end(0)?;
+                if let Err(err) = collector.feature_end(idx as u64) {
+                    return Err(collector.take_error().unwrap_or(err.into()));
+                }
+            }
+            Ok(collector.into_features())
+        };
+        let options = |non_finite| DeserializeOptions::new().non_finite(non_finite);
+        assert_eq!(read(&options(NonFinite::Allow))?.len(), 2);
+        assert!(matches!(
+            read(&options(NonFinite::Error)),
+            Err(Error::NonFinite { index: 0, location }) if location == "value"
+        ));
+        assert!(read(&options(NonFinite::SkipFeature))?.is_empty());
+
+        let clamped = read(&options(NonFinite::ClampOrNull).error_policy(ErrorPolicy::Skip))?;
+        assert_eq!(clamped.len(), 1);
+        assert_eq!(clamped[0].value, Some(f64::MAX));
+        assert!(clamped[0].geometry.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn projected_properties() -> anyhow::Result<()> {
+        #[derive(Deserialize)]
+        struct Narrow {
+            #[allow(dead_code)]
+            #[serde(alias = "label")]
+            name: String,
+        }
+
+        let mut collector =
+            GeozeroCollector::<Narrow>::with_options(&DeserializeOptions::new().max_properties(1))?;
+        collector.feature_begin(0)?;
+        for idx in 0..100 {
+            collector.property(idx, &format!("column_{idx}"), &ColumnValue::Long(1))?;
+        }
+        collector.property(100, "label", &ColumnValue::String("a"))?;
+        assert_eq!(collector.current_feature.current_properties.len(), 1);
+        assert!(collector
+            .current_feature
+            .current_properties
+            .contains_key("label"));
+
+        let mut collector =
+            GeozeroCollector::<Feature>::with_options(&DeserializeOptions::new().columns(&["b"]))?;
+        collector.feature_begin(0)?;
+        assert!(!collector.property(0, "a", &ColumnValue::Long(1))?);
+        assert!(collector.property(1, "b", &ColumnValue::Long(2))?);
+        assert_eq!(collector.current_feature.current_properties.len(), 1);
+
+        // the keys read for lookups and dedup count once, even if they are selected columns
+        let options = DeserializeOptions::new()
+            .columns(&["geometry", "b", "code"])
+            .lookup(Lookup::new(
+                "code",
+                [(1, std::collections::HashMap::from([("label", "x")]))],
+            )?)
+            .dedup(Dedup::ByKey("id".to_string()));
+        let mut collector = GeozeroCollector::<Feature>::with_options(&options)?;
+        collector.feature_begin(0)?;
+        assert!(!collector.property(0, "a", &ColumnValue::Long(1))?);
+        assert!(!collector.property(1, "code", &ColumnValue::Long(1))?);
+        assert!(!collector.property(2, "b", &ColumnValue::Long(2))?);
+        assert!(collector.property(3, "id", &ColumnValue::Long(3))?);
+        assert_eq!(collector.current_feature.current_properties.len(), 3);
+
+        // the geometry and other fields not backed by properties don't keep the reader going
+        #[derive(Deserialize)]
+        #[allow(dead_code)]
+        struct Located {
+            geometry: Geometry,
+            srid: Option<i32>,
+            bbox: Option<[f64; 4]>,
+            name: String,
+        }
+        let mut collector = GeozeroCollector::<Located>::with_options(&DeserializeOptions::new())?;
+        collector.feature_begin(0)?;
+        assert!(!collector.property(0, "a", &ColumnValue::Long(1))?);
+        assert!(collector.property(1, "name", &ColumnValue::String("a"))?);
+
+        let reader = flatgeobuf::FgbReader::open(std::fs::File::open("test-data/countries.fgb")?)?;
+        let options = DeserializeOptions::new().columns(&["name"]);
+        let countries: Vec<Feature> =
+            crate::from_datasource_with_options(&mut reader.select_all()?, &options)?;
+        assert_eq!(countries[0].len(), 1);
+        assert_eq!(countries[0].get::<String>("name")?, "Antarctica");
+
+        let mut collector = GeozeroCollector::<Feature>::with_options(
+            &DeserializeOptions::new().max_properties(1),
+        )?;
+        collector.property(0, "a", &ColumnValue::Long(1))?;
+        assert!(collector.property(1, "b", &ColumnValue::Long(1)).is_err());
+        assert!(matches!(
+            collector.take_error(),
+            Some(Error::TooManyProperties(1))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn quality_checker_columns() -> anyhow::Result<()> {
+        use crate::quality::{DuplicateKey, QualityChecker};
+
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "properties": {"id": 1, "name": "a"},
+             "geometry": {"type": "Point", "coordinates": [1, 1]}},
+            {"type": "Feature", "properties": {"id": 1, "name": "a"},
+             "geometry": {"type": "Point", "coordinates": [2, 2]}}
+        ]}"#;
+        let checker = || {
+            QualityChecker::new()
+                .with_rule(DuplicateKey::new("id"))
+                .with_rule(DuplicateKey::new("name"))
+        };
+
+        let mut collector = GeozeroCollector::<Feature>::with_options(
+            &DeserializeOptions::new().columns(&["name"]),
+        )?
+        .with_quality_checker(checker());
+        geozero::GeozeroDatasource::process(
+            &mut geozero::geojson::GeoJsonReader(geojson.as_bytes()),
+            &mut collector,
+        )?;
+        let report = collector.take_quality_report().unwrap_or_default();
+        // the id column isn't read, so its duplicates go unnoticed
+        assert_eq!(report.issues.len(), 1);
+        assert!(report.issues[0].message.contains("\"name\""));
+        assert!(collector.features.iter().all(|feature| feature.len() == 1));
+        Ok(())
+    }
+
+    #[test]
+    fn strict_mode() -> anyhow::Result<()> {
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct City {
+            geometry: Geometry,
+            name: String,
+            population: Option<u64>,
+        }
+
+        let read = |geojson: &str, strict| {
+            crate::from_datasource_with_options::<City, _>(
+                &mut geozero::geojson::GeoJsonReader(geojson.as_bytes()),
+                &DeserializeOptions::new().strict(strict),
+            )
+        };
+        let unknown = r#"{"type": "Feature", "properties": {"name": "a", "population": 1, "x": 1},
+            "geometry": {"type": "Point", "coordinates": [1, 1]}}"#;
+        let missing = r#"{"type": "Feature", "properties": {"name": "a"},
+            "geometry": {"type": "Point", "coordinates": [1, 1]}}"#;
+
+        assert_eq!(read(unknown, StrictMode::Off)?.len(), 1);
+        assert_eq!(read(missing, StrictMode::Off)?.len(), 1);
+        assert!(matches!(
+            read(unknown, StrictMode::Error),
+            Err(Error::SchemaMismatch(message)) if message.contains("\"x\"")
+        ));
+        assert!(matches!(
+            read(missing, StrictMode::Error),
+            Err(Error::SchemaMismatch(message)) if message.contains("\"population\"")
+        ));
+        assert_eq!(
+            read(
+                missing,
+                StrictMode::Warn(|drift| assert_eq!(
+                    drift,
+                    &SchemaDrift::MissingColumn("population".to_string())
+                ))
+            )?
+            .len(),
+            1
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn error_decisions() -> anyhow::Result<()> {
+        #[derive(Debug, Deserialize)]
+        struct Typed {
+            #[allow(dead_code)]
+            geometry: Geometry,
+            value: u8,
+        }
+
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "properties": {"value": "text"},
+             "geometry": {"type": "Point", "coordinates": [1, 1]}},
+            {"type": "Feature", "properties": {"value": 2},
+             "geometry": {"type": "Point", "coordinates": [2, 2]}}
+        ]}"#;
+
+        let options = DeserializeOptions::new().on_error(|_| ErrorDecision::Skip);
+        let mut collector = GeozeroCollector::<Typed>::with_options(&options)?;
+        collector.process(&mut geozero::geojson::GeoJsonReader(geojson.as_bytes()))?;
+        assert_eq!(collector.skipped(), 1);
+        assert_eq!(collector.features.len(), 1);
+        assert_eq!(collector.features[0].value, 2);
+
+        // Retrying without repairing the feature gives up eventually.
+        let options = DeserializeOptions::new().on_error(|err| {
+            assert_eq!(err.index, 0);
+            ErrorDecision::Retry
+        });
+        let mut collector = GeozeroCollector::<Typed>::with_options(&options)?;
+        let err = collector
+            .process(&mut geozero::geojson::GeoJsonReader(geojson.as_bytes()))
+            .unwrap_err();
+        assert!(matches!(err, Error::Feature { index: 0, .. }), "{err:?}");
+
+        // The callback gets the whole feature back and may repair it.
+        let options = DeserializeOptions::new().on_error(|err| {
+            assert_eq!(err.feature.geometry, geo::point! { x: 1., y: 1. }.into());
+            assert_eq!(err.feature.get::<String>("value").unwrap(), "text");
+            err.feature.set("value", 1);
+            ErrorDecision::Retry
+        });
+        let mut collector = GeozeroCollector::<Typed>::with_options(&options)?;
+        collector.process(&mut geozero::geojson::GeoJsonReader(geojson.as_bytes()))?;
+        assert_eq!(collector.features[0].value, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn error_policies() -> anyhow::Result<()> {
+        #[derive(Debug, Deserialize)]
+        struct Typed {
+            #[allow(dead_code)]
+            geometry: Geometry,
+            value: u8,
+        }
+
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "properties": {"value": "text"},
+             "geometry": {"type": "Point", "coordinates": [1, 1]}},
+            {"type": "Feature", "properties": {"value": 2}, "geometry": null},
+            {"type": "Feature", "properties": {"value": 3},
+             "geometry": {"type": "Point", "coordinates": [2, 2]}}
+        ]}"#;
+        let read = |error_policy| {
+            let options = DeserializeOptions::new().error_policy(error_policy);
+            let mut collector = GeozeroCollector::<Typed>::with_options(&options)?;
+            collector
+                .process(&mut geozero::geojson::GeoJsonReader(geojson.as_bytes()))
+                .map(|()| collector)
+        };
+
+        let err = read(ErrorPolicy::Fail).err().unwrap();
+        assert_eq!(err.feature_index(), Some(0));
+
+        let collector = read(ErrorPolicy::Skip)?;
+        assert_eq!(collector.skipped(), 2);
+        assert_eq!(collector.features.len(), 1);
+        assert_eq!(collector.features[0].value, 3);
+
+        let collector = read(ErrorPolicy::SkipWithCallback(|err| {
+            // the feature that failed to deserialize is handed over whole
+            if err.index == 0 {
+                assert_eq!(err.feature.geometry, geo::point! { x: 1., y: 1. }.into());
+                assert_eq!(err.feature.get_str("value"), Some("text"));
+            }
+            if err.index == 1 {
+                assert!(matches!(err.error, Error::MissingGeometry(1)));
+                assert_eq!(
+                    err.feature.value("value"),
+                    Some(&crate::value::Value::Long(2))
+                );
+            }
+        }))?;
+        assert_eq!(collector.skipped(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn missing_geometry() -> anyhow::Result<()> {
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "properties": {"name": "nowhere"}, "geometry": null},
+            {"type": "Feature", "properties": {"name": "somewhere"},
+             "geometry": {"type": "Point", "coordinates": [1, 1]}}
+        ]}"#;
+        let read = |options: DeserializeOptions| {
+            let mut collector = GeozeroCollector::<Feature>::with_options(&options)?;
+            collector
+                .process(&mut geozero::geojson::GeoJsonReader(geojson.as_bytes()))
+                .map(|()| collector)
+        };
+
+        let err = read(DeserializeOptions::new()).err().unwrap();
+        assert!(matches!(err, Error::MissingGeometry(0)), "{err:?}");
+
+        let collector = read(DeserializeOptions::new().error_policy(ErrorPolicy::Skip))?;
+        assert_eq!(collector.skipped(), 1);
+        assert_eq!(collector.features[0].get_str("name"), Some("somewhere"));
+        Ok(())
+    }
+
+    #[test]
+    fn geometry_validation() -> anyhow::Result<()> {
+        // a bow tie and a valid square
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "properties": {"name": "bow tie"}, "geometry": {"type": "Polygon",
+             "coordinates": [[[0, 0], [1, 1], [1, 0], [0, 1], [0, 0]]]}},
+            {"type": "Feature", "properties": {"name": "square"}, "geometry": {"type": "Polygon",
+             "coordinates": [[[0, 0], [1, 0], [1, 1], [0, 1], [0, 0]]]}}
+        ]}"#;
+        let read = |options: DeserializeOptions| {
+            let mut collector = GeozeroCollector::<Feature>::with_options(&options)?;
+            collector
+                .process(&mut geozero::geojson::GeoJsonReader(geojson.as_bytes()))
+                .map(|()| collector)
+        };
+
+        assert_eq!(read(DeserializeOptions::new())?.len(), 2);
+
+        let err = read(DeserializeOptions::new().validate_geometries(true))
+            .err()
+            .unwrap();
+        assert!(
+            matches!(err, Error::InvalidGeometry { index: 0, .. }),
+            "{err:?}"
+        );
+
+        let collector = read(
+            DeserializeOptions::new()
+                .validate_geometries(true)
+                .error_policy(ErrorPolicy::Skip),
+        )?;
+        assert_eq!(collector.skipped(), 1);
+        assert_eq!(collector.features[0].get_str("name"), Some("square"));
+        Ok(())
+    }
+
+    #[test]
+    fn dedup() -> anyhow::Result<()> {
+        #[derive(Deserialize)]
+        struct Named {
+            name: String,
+        }
+
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "properties": {"id": 1, "name": "a"},
+             "geometry": {"type": "Point", "coordinates": [1, 1]}},
+            {"type": "Feature", "properties": {"id": 1, "name": "a"},
+             "geometry": {"type": "Point", "coordinates": [1, 1]}},
+            {"type": "Feature", "properties": {"id": 1, "name": "b"},
+             "geometry": {"type": "Point", "coordinates": [1, 1]}},
+            {"type": "Feature", "properties": {"name": "c"},
+             "geometry": {"type": "Point", "coordinates": [2, 2]}}
+        ]}"#;
+        let read = |dedup| {
+            let options = DeserializeOptions::new().dedup(dedup);
+            let mut collector = GeozeroCollector::<Named>::with_options(&options)?;
+            collector
+                .process(&mut geozero::geojson::GeoJsonReader(geojson.as_bytes()))
+                .map(|()| collector)
+        };
+
+        let names = |collector: &GeozeroCollector<Named>| {
+            collector
+                .features
+                .iter()
+                .map(|feature| feature.name.clone())
+                .collect::<Vec<_>>()
+        };
+        let all = read(Dedup::Off)?;
+        assert_eq!((all.len(), all.duplicates()), (4, 0));
+        let exact = read(Dedup::Exact)?;
+        assert_eq!(names(&exact), ["a", "b", "c"]);
+        assert_eq!(exact.duplicates(), 1);
+        // the key column is read although `Named` has no field for it
+        let by_key = read(Dedup::ByKey("id".to_string()))?;
+        assert_eq!(names(&by_key), ["a", "c"]);
+        assert_eq!(by_key.duplicates(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn dedup_by_typed_key() -> anyhow::Result<()> {
+        #[derive(Deserialize)]
+        struct Named {
+            name: String,
+        }
+
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "properties": {"id": 1, "name": "a"},
+             "geometry": {"type": "Point", "coordinates": [1, 1]}},
+            {"type": "Feature", "properties": {"id": 1.0, "name": "b"},
+             "geometry": {"type": "Point", "coordinates": [1, 1]}},
+            {"type": "Feature", "properties": {"id": "1", "name": "c"},
+             "geometry": {"type": "Point", "coordinates": [1, 1]}},
+            {"type": "Feature", "properties": {"id": 1, "name": "d"},
+             "geometry": {"type": "Point", "coordinates": [1, 1]}}
+        ]}"#;
+        let options = DeserializeOptions::new().dedup(Dedup::ByKey("id".to_string()));
+        let mut collector = GeozeroCollector::<Named>::with_options(&options)?;
+        collector.process(&mut geozero::geojson::GeoJsonReader(geojson.as_bytes()))?;
+        let names = collector
+            .features
+            .iter()
+            .map(|feature| feature.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["a", "b", "c"]);
+        assert_eq!(collector.duplicates(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn reuse() -> anyhow::Result<()> {
+        let geojson = |name: &str| {
+            format!(
+                r#"{{"type": "FeatureCollection", "features": [{{"type": "Feature",
+                "properties": {{"name": "{name}"}},
+                "geometry": {{"type": "Point", "coordinates": [1, 1]}}}}]}}"#
+            )
+        };
+        let options = DeserializeOptions::new().compute_bbox(true);
+        let mut collector = GeozeroCollector::<Feature>::with_options(&options)?;
+
+        collector.process(&mut geozero::geojson::GeoJsonReader(
+            geojson("a").as_bytes(),
+        ))?;
+        collector.process(&mut geozero::geojson::GeoJsonReader(
+            geojson("b").as_bytes(),
+        ))?;
+        let taken = collector.take_features();
+        assert_eq!(taken.len(), 2);
+        assert!(collector.is_empty());
+        assert!(collector.envelope().is_some());
+
+        collector.process(&mut geozero::geojson::GeoJsonReader(
+            geojson("c").as_bytes(),
+        ))?;
+        let capacity = collector.features.capacity();
+        collector.clear();
+        assert!(collector.is_empty());
+        assert_eq!(collector.features.capacity(), capacity);
+        assert!(collector.envelope().is_none());
+
+        collector.process(&mut geozero::geojson::GeoJsonReader(
+            geojson("d").as_bytes(),
+        ))?;
+        assert_eq!(collector.features[0].get_str("name"), Some("d"));
+        Ok(())
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn stats() -> anyhow::Result<()> {
+        #[derive(Deserialize)]
+        struct Named {
+            #[allow(dead_code)]
+            name: String,
+        }
+
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "properties": {"name": "a", "value": 1, "other": 1},
+             "geometry": {"type": "Point", "coordinates": [1, 1]}},
+            {"type": "Feature", "properties": {"name": "b", "value": 4.5},
+             "geometry": {"type": "Point", "coordinates": [1, 1]}},
+            {"type": "Feature", "properties": {"name": "a", "value": null},
+             "geometry": {"type": "Point", "coordinates": [1, 1]}}
+        ]}"#;
+
+        let mut collector = GeozeroCollector::<Feature>::new().with_stats();
+        collector.process(&mut geozero::geojson::GeoJsonReader(geojson.as_bytes()))?;
+        let stats = collector.stats().unwrap();
+        assert_eq!(stats.feature_count, 3);
+        let value = stats.property("value").unwrap();
+        assert_eq!(
+            (value.min, value.max, value.mean()),
+            (Some(1.), Some(4.5), Some(2.75))
+        );
+        // the reader leaves out the null value
+        assert_eq!((value.value_count, value.null_count), (2, 0));
+        assert_eq!(stats.missing_count("value"), 1);
+        assert_eq!(stats.missing_count("other"), 2);
+        let name = stats.property("name").unwrap();
+        assert_eq!(name.distinct_count(), 2);
+        assert!(name.contains("b"));
+        assert_eq!((name.min, name.mean()), (None, None));
+
+        collector.clear();
+        assert_eq!(collector.stats().unwrap().feature_count, 0);
+
+        // only the properties kept for the struct are recorded
+        let options = DeserializeOptions::new();
+        let mut collector = GeozeroCollector::<Named>::with_options(&options)?.with_stats();
+        collector.process(&mut geozero::geojson::GeoJsonReader(geojson.as_bytes()))?;
+        let stats = collector.take_stats().unwrap();
+        assert_eq!(stats.properties().count(), 1);
+        assert!(collector.stats().is_none());
+
+        // a property written twice to a feature, e.g. renamed onto an existing column
+        let mut collector = GeozeroCollector::<Feature>::new().with_stats();
+        collector.feature_begin(0)?;
+        collector.property(0, "value", &ColumnValue::Long(1))?;
+        collector.property(1, "value", &ColumnValue::Long(2))?;
+        let stats = collector.stats().unwrap();
+        assert_eq!(stats.property("value").unwrap().value_count, 1);
+        assert_eq!(stats.missing_count("value"), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn capacity_hints() -> anyhow::Result<()> {
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "properties": {"a": 1, "b": 2, "c": 3},
+             "geometry": {"type": "Point", "coordinates": [1, 1]}},
+            {"type": "Feature", "properties": {"a": 1},
+             "geometry": {"type": "Point", "coordinates": [10, 10]}}
+        ]}"#;
+
+        let mut collector = GeozeroCollector::<Feature>::with_capacity(16);
+        assert!(collector.features.capacity() >= 16);
+        collector.process(&mut geozero::geojson::GeoJsonReader(geojson.as_bytes()))?;
+        assert_eq!(collector.len(), 2);
+        assert_eq!(collector.current_feature.properties_hint, 3);
+        Ok(())
+    }
+
+    #[test]
+    fn interned_strings() -> anyhow::Result<()> {
+        #[derive(Deserialize)]
+        struct Typed {
+            #[allow(dead_code)]
+            geometry: Geometry,
+            kind: String,
+        }
+
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "properties": {"kind": "park"},
+             "geometry": {"type": "Point", "coordinates": [1, 1]}},
+            {"type": "Feature", "properties": {"kind": "park"},
+             "geometry": {"type": "Point", "coordinates": [2, 2]}}
+        ]}"#;
+        let options = DeserializeOptions::new().intern_strings(true);
+
+        let mut collector = GeozeroCollector::<Feature>::with_options(&options)?;
+        collector.process(&mut geozero::geojson::GeoJsonReader(geojson.as_bytes()))?;
+        let (Some(Value::Shared(first)), Some(Value::Shared(second))) = (
+            collector.features[0].value("kind"),
+            collector.features[1].value("kind"),
+        ) else {
+            panic!("strings are not interned");
+        };
+        assert!(Arc::ptr_eq(first, second));
+        assert_eq!(
+            collector.features[0].value("kind"),
+            Some(&Value::String("park".to_string()))
+        );
+
+        let mut collector = GeozeroCollector::<Typed>::with_options(&options)?;
+        collector.process(&mut geozero::geojson::GeoJsonReader(geojson.as_bytes()))?;
+        assert_eq!(collector.features[1].kind, "park");
+        Ok(())
+    }
+
+    #[cfg(feature = "rstar")]
+    #[test]
+    fn rtree() -> anyhow::Result<()> {
+        use rstar::AABB;
+
+        #[derive(Deserialize)]
+        struct Named {
+            #[allow(dead_code)]
+            geometry: Geometry,
+            name: String,
+        }
+
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "properties": {"name": "a"},
+             "geometry": {"type": "Point", "coordinates": [1, 1]}},
+            {"type": "Feature", "properties": {"name": "b"},
+             "geometry": {"type": "Point", "coordinates": [10, 10]}}
+        ]}"#;
+
+        let mut collector = GeozeroCollector::<Named>::new().with_rtree();
+        geozero::GeozeroDatasource::process(
+            &mut geozero::geojson::GeoJsonReader(geojson.as_bytes()),
+            &mut collector,
+        )?;
+        let (features, rtree) = collector.into_rtree();
+
+        let hits: Vec<_> = rtree
+            .locate_in_envelope_intersecting(&AABB::from_corners([0., 0.], [5., 5.]))
+            .map(|entry| features[entry.data].name.as_str())
+            .collect();
+        assert_eq!(hits, vec!["a"]);
+        Ok(())
+    }
+
+    #[cfg(feature = "rstar")]
+    #[test]
+    fn rtree_chunks() -> anyhow::Result<()> {
+        use rstar::AABB;
+
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "properties": {},
+             "geometry": {"type": "Point", "coordinates": [1, 1]}},
+            {"type": "Feature", "properties": {},
+             "geometry": {"type": "Point", "coordinates": [10, 10]}},
+            {"type": "Feature", "properties": {},
+             "geometry": {"type": "Point", "coordinates": [20, 20]}}
+        ]}"#;
+
+        let mut features = Vec::new();
+        let mut collector = GeozeroCollector::<Feature>::new()
+            .with_chunks(1, |chunk| {
+                features.append(chunk);
+                Ok(())
+            })
+            .with_rtree();
+        geozero::GeozeroDatasource::process(
+            &mut geozero::geojson::GeoJsonReader(geojson.as_bytes()),
+            &mut collector,
+        )?;
+        let rtree = collector.rtree().cloned().unwrap_or_default();
+        drop(collector);
+
+        let mut hits: Vec<_> = rtree
+            .locate_in_envelope_intersecting(&AABB::from_corners([5., 5.], [25., 25.]))
+            .map(|entry| entry.data)
+            .collect();
+        hits.sort_unstable();
+        assert_eq!(hits, [1, 2]);
+        assert_eq!(features.len(), 3);
+        Ok(())
+    }
+
+    #[cfg(all(feature = "rstar", feature = "proj"))]
+    #[test]
+    fn reprojected_rtree() -> anyhow::Result<()> {
+        use rstar::AABB;
+
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "properties": {},
+             "geometry": {"type": "Point", "coordinates": [1, 1]}},
+            {"type": "Feature", "properties": {},
+             "geometry": {"type": "Point", "coordinates": [10, 10]}}
+        ]}"#;
+
+        let options = DeserializeOptions::new().reproject("EPSG:4326", "EPSG:3857");
+        let mut collector = GeozeroCollector::<Feature>::with_options(&options)?.with_rtree();
+        collector.process(&mut geozero::geojson::GeoJsonReader(geojson.as_bytes()))?;
+
+        // the envelope and the tree are in web mercator meters, not degrees
+        let envelope = collector.envelope().unwrap();
+        assert!((envelope.max().x - 1_113_194.9).abs() < 1.);
+        let rtree = collector.rtree().unwrap();
+        assert_eq!(
+            rtree
+                .locate_in_envelope_intersecting(&AABB::from_corners([0., 0.], [20., 20.]))
+                .count(),
+            0
+        );
+        let hits: Vec<_> = rtree
+            .locate_in_envelope_intersecting(&AABB::from_corners(
+                [1_000_000., 1_000_000.],
+                [1_200_000., 1_200_000.],
+            ))
+            .map(|entry| entry.data)
+            .collect();
+        assert_eq!(hits, [1]);
+        Ok(())
+    }
+}
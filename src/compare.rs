@@ -0,0 +1,726 @@
+//! Tolerant equality and stable hashing for geometries and features.
+//!
+//! Hashes produced here only depend on the coordinates, the geometry structure and the
+//! property values, so they are identical across runs, platforms and crate builds. This makes
+//! them usable for deduplication, change detection and snapshot tests.
+use std::{
+    fmt::Debug,
+    hash::{Hash, Hasher},
+};
+
+use approx::AbsDiffEq;
+use geo::{Coord, Geometry, LineString, MapCoords, Polygon};
+use geozero::GeozeroDatasource;
+use hashbrown::{hash_map::Entry, HashMap};
+use serde::de::DeserializeOwned;
+
+use crate::{
+    de::{from_datasource_with_options, Both, DeserializeOptions, Feature, KeyCollision},
+    error::{Error, Result},
+    quality::geometry_type_name,
+    value::Value,
+};
+
+/// A 64 bit FNV-1a hasher.
+///
+/// Unlike [`std::collections::hash_map::DefaultHasher`] its output is guaranteed to be the same
+/// on every platform and Rust version.
+#[derive(Debug, Clone, Copy)]
+pub struct StableHasher(u64);
+
+impl StableHasher {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    #[must_use]
+    pub fn new() -> Self {
+        Self(Self::OFFSET_BASIS)
+    }
+}
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Writes integers as little endian bytes, where [`Hasher`] would use the native byte order.
+macro_rules! write_le {
+    ($($method:ident($ty:ty)),*) => {
+        $(
+            fn $method(&mut self, value: $ty) {
+                self.write(&value.to_le_bytes());
+            }
+        )*
+    };
+}
+
+impl Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    write_le!(
+        write_u8(u8),
+        write_u16(u16),
+        write_u32(u32),
+        write_u64(u64),
+        write_u128(u128),
+        write_i8(i8),
+        write_i16(i16),
+        write_i32(i32),
+        write_i64(i64),
+        write_i128(i128)
+    );
+
+    // pointer sized integers are hashed as 64 bits on every platform
+    fn write_usize(&mut self, value: usize) {
+        self.write_u64(value as u64);
+    }
+
+    fn write_isize(&mut self, value: isize) {
+        self.write_i64(value as i64);
+    }
+}
+
+/// Returns `true` if both geometries have the same type and structure and all coordinates
+/// differ by at most `epsilon`.
+#[must_use]
+pub fn geometry_approx_eq(a: &Geometry, b: &Geometry, epsilon: f64) -> bool {
+    a.abs_diff_eq(b, epsilon)
+}
+
+/// Feeds the geometry type, structure and coordinates into `state`.
+///
+/// `-0.0` and `0.0` as well as all NaN values hash identically.
+pub fn hash_geometry<H: Hasher>(geometry: &Geometry, state: &mut H) {
+    state.write(geometry_type_name(geometry).as_bytes());
+    match geometry {
+        Geometry::Point(point) => hash_coord(point.0, state),
+        Geometry::Line(line) => {
+            hash_coord(line.start, state);
+            hash_coord(line.end, state);
+        }
+        Geometry::LineString(line_string) => hash_line_string(line_string, state),
+        Geometry::Polygon(polygon) => hash_polygon(polygon, state),
+        Geometry::MultiPoint(points) => {
+            state.write_u64(points.0.len() as u64);
+            for point in points {
+                hash_coord(point.0, state);
+            }
+        }
+        Geometry::MultiLineString(line_strings) => {
+            state.write_u64(line_strings.0.len() as u64);
+            for line_string in line_strings {
+                hash_line_string(line_string, state);
+            }
+        }
+        Geometry::MultiPolygon(polygons) => {
+            state.write_u64(polygons.0.len() as u64);
+            for polygon in polygons {
+                hash_polygon(polygon, state);
+            }
+        }
+        Geometry::GeometryCollection(collection) => {
+            state.write_u64(collection.0.len() as u64);
+            for geometry in collection {
+                hash_geometry(geometry, state);
+            }
+        }
+        Geometry::Rect(rect) => {
+            hash_coord(rect.min(), state);
+            hash_coord(rect.max(), state);
+        }
+        Geometry::Triangle(triangle) => {
+            for coord in triangle.to_array() {
+                hash_coord(coord, state);
+            }
+        }
+    }
+}
+
+/// Stable hash of a geometry, see [`hash_geometry`].
+#[must_use]
+pub fn geometry_hash(geometry: &Geometry) -> u64 {
+    let mut hasher = StableHasher::new();
+    hash_geometry(geometry, &mut hasher);
+    hasher.finish()
+}
+
+/// The differences between an old and a new version of a dataset, see [`diff_datasources`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diff<K, T> {
+    /// Features only in the new dataset, in its order.
+    pub added: Vec<(K, T)>,
+    /// Features only in the old dataset, in its order.
+    pub removed: Vec<(K, T)>,
+    /// The old and the new version of features that differ, in the order of the new dataset.
+    pub changed: Vec<(K, T, T)>,
+    /// The number of features equal in both datasets.
+    pub unchanged: usize,
+}
+
+impl<K, T> Diff<K, T> {
+    /// Returns `true` if both datasets are equal.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Compares two versions of a dataset, matching features by the property `key_column`.
+///
+/// A feature has changed if any of its properties differs or a coordinate moved by more than
+/// `epsilon`, see [`Feature::approx_eq`]. All properties are compared, whether `T` has a field
+/// for them or not. Keys shared by several features of one dataset are handled as set by
+/// [`DeserializeOptions::key_collision`].
+///
+/// ```
+/// use geo::Geometry;
+/// use serde::Deserialize;
+/// use serde_geozero::compare::diff_datasources;
+/// use serde_geozero::DeserializeOptions;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Shop {
+///     geometry: Geometry,
+///     name: String,
+/// }
+///
+/// let last_week = r#"{"type": "FeatureCollection", "features": [
+///     {"type": "Feature", "properties": {"id": 1, "name": "Bakery"},
+///      "geometry": {"type": "Point", "coordinates": [1, 1]}},
+///     {"type": "Feature", "properties": {"id": 2, "name": "Florist"},
+///      "geometry": {"type": "Point", "coordinates": [2, 2]}}]}"#;
+/// let this_week = r#"{"type": "FeatureCollection", "features": [
+///     {"type": "Feature", "properties": {"id": 1, "name": "Bakery"},
+///      "geometry": {"type": "Point", "coordinates": [1.5, 1]}},
+///     {"type": "Feature", "properties": {"id": 3, "name": "Butcher"},
+///      "geometry": {"type": "Point", "coordinates": [3, 3]}}]}"#;
+///
+/// let diff = diff_datasources::<Shop, u32, _, _>(
+///     &mut geozero::geojson::GeoJsonReader(last_week.as_bytes()),
+///     &mut geozero::geojson::GeoJsonReader(this_week.as_bytes()),
+///     &DeserializeOptions::new(),
+///     "id",
+///     1e-6,
+/// )
+/// .unwrap();
+/// assert_eq!(diff.added[0].1.name, "Butcher");
+/// assert_eq!(diff.removed[0].1.name, "Florist");
+/// assert_eq!(diff.changed[0].0, 1);
+/// ```
+///
+/// # Errors
+///
+/// Returns the errors of reading either datasource, [`Error::MissingField`] or [`Error::Field`]
+/// if a feature has no valid key and [`Error::DuplicateKey`] as set by
+/// [`DeserializeOptions::key_collision`].
+pub fn diff_datasources<T, K, A, B>(
+    old: &mut A,
+    new: &mut B,
+    options: &DeserializeOptions,
+    key_column: &str,
+    epsilon: f64,
+) -> Result<Diff<K, T>>
+where
+    T: DeserializeOwned + 'static,
+    K: DeserializeOwned + Eq + Hash + Clone + Debug,
+    A: GeozeroDatasource,
+    B: GeozeroDatasource,
+{
+    let mut old = keyed::<T, K, _>(old, options, key_column)?;
+    let positions: HashMap<K, usize> = old
+        .iter()
+        .enumerate()
+        .filter_map(|(position, entry)| Some((entry.as_ref()?.0.clone(), position)))
+        .collect();
+
+    let mut diff = Diff {
+        added: Vec::new(),
+        removed: Vec::new(),
+        changed: Vec::new(),
+        unchanged: 0,
+    };
+    for (key, new, new_feature) in keyed::<T, K, _>(new, options, key_column)?
+        .into_iter()
+        .flatten()
+    {
+        match positions
+            .get(&key)
+            .and_then(|position| old[*position].take())
+        {
+            None => diff.added.push((key, new)),
+            Some((_, _, old_feature)) if old_feature.approx_eq(&new_feature, epsilon) => {
+                diff.unchanged += 1;
+            }
+            Some((_, old, _)) => diff.changed.push((key, old, new)),
+        }
+    }
+    diff.removed = old
+        .into_iter()
+        .flatten()
+        .map(|(key, old, _)| (key, old))
+        .collect();
+    Ok(diff)
+}
+
+/// Reads the features of `source` with their keys, wrapped in `Some` so matched features can be
+/// taken out.
+#[allow(clippy::type_complexity)]
+fn keyed<T, K, S>(
+    source: &mut S,
+    options: &DeserializeOptions,
+    key_column: &str,
+) -> Result<Vec<Option<(K, T, Feature)>>>
+where
+    T: DeserializeOwned + 'static,
+    K: DeserializeOwned + Eq + Hash + Clone + Debug,
+    S: GeozeroDatasource,
+{
+    let features: Vec<Both<T, Feature>> = from_datasource_with_options(source, options)?;
+    let mut positions = HashMap::with_capacity(features.len());
+    let mut keyed = Vec::with_capacity(features.len());
+    for Both(value, feature) in features {
+        let key: K = feature.get(key_column)?;
+        match positions.entry(key.clone()) {
+            Entry::Vacant(entry) => {
+                entry.insert(keyed.len());
+                keyed.push(Some((key, value, feature)));
+            }
+            Entry::Occupied(entry) => match options.key_collision {
+                KeyCollision::Error => return Err(Error::DuplicateKey(format!("{key:?}"))),
+                KeyCollision::KeepFirst => {}
+                KeyCollision::KeepLast => {
+                    // the last feature takes the place of the first one
+                    keyed[*entry.get()] = Some((key, value, feature));
+                }
+            },
+        }
+    }
+    Ok(keyed)
+}
+
+/// Records the bytes written to it, to compare geometries exactly as they are hashed.
+#[derive(Default)]
+struct Recorder(Vec<u8>);
+
+impl Hasher for Recorder {
+    fn finish(&self) -> u64 {
+        0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.extend_from_slice(bytes);
+    }
+}
+
+fn hashed_bytes(geometry: &Geometry) -> Vec<u8> {
+    let mut recorder = Recorder::default();
+    hash_geometry(geometry, &mut recorder);
+    recorder.0
+}
+
+fn hash_coord<H: Hasher>(coord: Coord, state: &mut H) {
+    state.write_u64(normalize(coord.x).to_bits());
+    state.write_u64(normalize(coord.y).to_bits());
+}
+
+fn hash_line_string<H: Hasher>(line_string: &LineString, state: &mut H) {
+    state.write_u64(line_string.0.len() as u64);
+    for coord in line_string {
+        hash_coord(*coord, state);
+    }
+}
+
+fn hash_polygon<H: Hasher>(polygon: &Polygon, state: &mut H) {
+    hash_line_string(polygon.exterior(), state);
+    state.write_u64(polygon.interiors().len() as u64);
+    for interior in polygon.interiors() {
+        hash_line_string(interior, state);
+    }
+}
+
+/// Feeds the length and the bytes into `state`, so consecutive byte strings can't run into
+/// each other.
+fn hash_bytes<H: Hasher>(bytes: &[u8], state: &mut H) {
+    state.write_u64(bytes.len() as u64);
+    state.write(bytes);
+}
+
+/// Feeds the type and the value of a property into `state`, consistent with how properties are
+/// compared: `String` and `Shared` values hash alike, floats by their bits with all NaN values
+/// hashing identically.
+pub(crate) fn hash_value<H: Hasher>(value: &Value, state: &mut H) {
+    match value {
+        Value::Null => state.write_u8(0),
+        Value::Byte(value) => {
+            state.write_u8(1);
+            state.write_i8(*value);
+        }
+        Value::UByte(value) => {
+            state.write_u8(2);
+            state.write_u8(*value);
+        }
+        Value::Bool(value) => {
+            state.write_u8(3);
+            state.write_u8(u8::from(*value));
+        }
+        Value::Short(value) => {
+            state.write_u8(4);
+            state.write_i16(*value);
+        }
+        Value::UShort(value) => {
+            state.write_u8(5);
+            state.write_u16(*value);
+        }
+        Value::Int(value) => {
+            state.write_u8(6);
+            state.write_i32(*value);
+        }
+        Value::UInt(value) => {
+            state.write_u8(7);
+            state.write_u32(*value);
+        }
+        Value::Long(value) => {
+            state.write_u8(8);
+            state.write_i64(*value);
+        }
+        Value::ULong(value) => {
+            state.write_u8(9);
+            state.write_u64(*value);
+        }
+        Value::Float(value) => {
+            state.write_u8(10);
+            let value = if value.is_nan() { f32::NAN } else { *value };
+            state.write_u32(value.to_bits());
+        }
+        Value::Double(value) => {
+            state.write_u8(11);
+            let value = if value.is_nan() { f64::NAN } else { *value };
+            state.write_u64(value.to_bits());
+        }
+        Value::String(_) | Value::Shared(_) => {
+            state.write_u8(12);
+            hash_bytes(value.as_str().unwrap_or_default().as_bytes(), state);
+        }
+        Value::Json(value) => {
+            state.write_u8(13);
+            hash_bytes(value.as_bytes(), state);
+        }
+        Value::DateTime(value) => {
+            state.write_u8(14);
+            hash_bytes(value.as_bytes(), state);
+        }
+        Value::Binary(value) => {
+            state.write_u8(15);
+            hash_bytes(value, state);
+        }
+    }
+}
+
+fn normalize(value: f64) -> f64 {
+    if value.is_nan() {
+        f64::NAN
+    } else if value == 0. {
+        0.
+    } else {
+        value
+    }
+}
+
+impl Feature {
+    /// Compares two features, allowing coordinates to differ by at most `epsilon`.
+    ///
+    /// Properties and SRID have to be equal.
+    #[must_use]
+    pub fn approx_eq(&self, other: &Feature, epsilon: f64) -> bool {
+        self.srid() == other.srid()
+            && self.properties == other.properties
+            && geometry_approx_eq(&self.geometry, &other.geometry, epsilon)
+    }
+
+    /// Stable hash over the geometry, the properties and the SRID of the feature.
+    ///
+    /// Properties are hashed in key order, so the hash does not depend on insertion order. Keys
+    /// and values are hashed with their length and values with their type, so e.g. `{"a1": 2}`
+    /// and `{"a": 12}` or `1` and `1.0` hash differently.
+    #[must_use]
+    pub fn stable_hash(&self) -> u64 {
+        let mut hasher = StableHasher::new();
+        hash_geometry(&self.geometry, &mut hasher);
+
+        let mut keys: Vec<_> = self.properties.keys().collect();
+        keys.sort();
+        for key in keys {
+            hash_bytes(key.as_bytes(), &mut hasher);
+            hash_value(&self.properties[key], &mut hasher);
+        }
+
+        if let Some(srid) = self.srid() {
+            hasher.write_i32(srid);
+        }
+        hasher.finish()
+    }
+
+    /// Stable hash of the geometry with coordinates rounded to `decimals` decimal places.
+    ///
+    /// Geometries that only differ below the precision, e.g. after a round trip through a
+    /// format storing fewer digits, get the same fingerprint. Coordinates close to a rounding
+    /// boundary may still round apart.
+    #[must_use]
+    pub fn geometry_fingerprint(&self, decimals: u32) -> u64 {
+        let scale = 10_f64.powi(i32::try_from(decimals).unwrap_or(i32::MAX));
+        let round = |value: f64| (value * scale).round() / scale;
+        geometry_hash(&self.geometry.map_coords(|coord| Coord {
+            x: round(coord.x),
+            y: round(coord.y),
+        }))
+    }
+}
+
+/// Features are equal if their SRIDs, properties and geometries are.
+///
+/// Coordinates are compared like they are hashed by [`hash_geometry`], so `-0.0` equals `0.0`
+/// and NaN equals NaN. Floating point properties are compared bitwise, Z and M values and the
+/// bounding box are ignored. This makes `Feature` usable as a `HashSet` entry.
+impl PartialEq for Feature {
+    fn eq(&self, other: &Self) -> bool {
+        self.srid() == other.srid()
+            && self.properties.len() == other.properties.len()
+            && self.properties.iter().all(|(key, value)| {
+                other
+                    .properties
+                    .get(key)
+                    .is_some_and(|other| value_eq(value, other))
+            })
+            && hashed_bytes(&self.geometry) == hashed_bytes(&other.geometry)
+    }
+}
+
+impl Eq for Feature {}
+
+impl Hash for Feature {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.stable_hash());
+    }
+}
+
+/// A property value usable as a hash set entry, compared and hashed like the properties of
+/// [`Feature`]s. Values of different types, e.g. `1` and `1.0`, are different.
+#[derive(Debug, Clone)]
+pub(crate) struct ValueKey(pub(crate) Value);
+
+impl PartialEq for ValueKey {
+    fn eq(&self, other: &Self) -> bool {
+        value_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for ValueKey {}
+
+impl Hash for ValueKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        hash_value(&self.0, state);
+    }
+}
+
+fn value_eq(a: &Value, b: &Value) -> bool {
+    let float_eq = |a: f64, b: f64| a.to_bits() == b.to_bits() || (a.is_nan() && b.is_nan());
+    match (a, b) {
+        (Value::Float(a), Value::Float(b)) => float_eq(f64::from(*a), f64::from(*b)),
+        (Value::Double(a), Value::Double(b)) => float_eq(*a, *b),
+        (a, b) => a == b,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::hash::Hasher;
+
+    use geo::{point, polygon, Geometry};
+    use hashbrown::{HashMap, HashSet};
+
+    use geozero::geojson::GeoJsonReader;
+
+    use super::{diff_datasources, geometry_approx_eq, geometry_hash, StableHasher};
+    use crate::{
+        de::{DeserializeOptions, Feature, KeyCollision},
+        error::Error,
+        value::Value,
+    };
+
+    #[test]
+    fn geometry_comparison() {
+        let a: Geometry = polygon![(x: 0., y: 0.), (x: 1., y: 0.), (x: 1., y: 1.)].into();
+        let b: Geometry = polygon![(x: 0., y: -0.), (x: 1.0001, y: 0.), (x: 1., y: 1.)].into();
+
+        assert!(geometry_approx_eq(&a, &b, 1e-3));
+        assert!(!geometry_approx_eq(&a, &b, 1e-6));
+        assert_ne!(geometry_hash(&a), geometry_hash(&b));
+        assert_eq!(
+            geometry_hash(&(point! { x: 0., y: 1. }).into()),
+            geometry_hash(&(point! { x: -0., y: 1. }).into())
+        );
+        // known value guards against accidental changes of the hash function
+        assert_eq!(
+            geometry_hash(&(point! { x: 1., y: 2. }).into()),
+            15_711_731_714_572_335_660
+        );
+    }
+
+    #[test]
+    fn integers_hash_in_little_endian() {
+        let hash = |write: fn(&mut StableHasher)| {
+            let mut hasher = StableHasher::new();
+            write(&mut hasher);
+            hasher.finish()
+        };
+
+        let bytes = hash(|hasher| hasher.write(&[1, 0, 0, 0]));
+        assert_eq!(hash(|hasher| hasher.write_u32(1)), bytes);
+        assert_eq!(hash(|hasher| hasher.write_i32(1)), bytes);
+        assert_eq!(
+            hash(|hasher| hasher.write_usize(7)),
+            hash(|hasher| hasher.write_u64(7))
+        );
+        assert_eq!(
+            hash(|hasher| hasher.write_isize(-7)),
+            hash(|hasher| hasher.write_i64(-7))
+        );
+    }
+
+    #[test]
+    fn feature_comparison() {
+        let props = |value: i32| {
+            HashMap::from_iter(vec![
+                ("a".to_string(), Value::from(value)),
+                ("b".to_string(), Value::from("text")),
+            ])
+        };
+        let a = Feature::new((point! { x: 1., y: 2. }).into(), props(1));
+        let b = Feature::new((point! { x: 1.000_001, y: 2. }).into(), props(1));
+        let c = Feature::new((point! { x: 1., y: 2. }).into(), props(2));
+
+        assert!(a.approx_eq(&b, 1e-3));
+        assert!(!a.approx_eq(&c, 1e-3));
+        assert_eq!(a.stable_hash(), a.clone().stable_hash());
+        assert_ne!(a.stable_hash(), c.stable_hash());
+        assert_ne!(
+            a.stable_hash(),
+            a.clone().with_srid(Some(4326)).stable_hash()
+        );
+
+        assert_eq!(a.geometry_fingerprint(3), b.geometry_fingerprint(3));
+        assert_ne!(a.geometry_fingerprint(9), b.geometry_fingerprint(9));
+    }
+
+    #[test]
+    fn diffs() -> anyhow::Result<()> {
+        let old = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "properties": {"id": "a", "v": 1},
+             "geometry": {"type": "Point", "coordinates": [1, 1]}},
+            {"type": "Feature", "properties": {"id": "b", "v": 1},
+             "geometry": {"type": "Point", "coordinates": [2, 2]}},
+            {"type": "Feature", "properties": {"id": "c", "v": 1},
+             "geometry": {"type": "Point", "coordinates": [3, 3]}}
+        ]}"#;
+        let new = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "properties": {"id": "a", "v": 1},
+             "geometry": {"type": "Point", "coordinates": [1.0000001, 1]}},
+            {"type": "Feature", "properties": {"id": "b", "v": 2},
+             "geometry": {"type": "Point", "coordinates": [2, 2]}},
+            {"type": "Feature", "properties": {"id": "d", "v": 1},
+             "geometry": {"type": "Point", "coordinates": [4, 4]}}
+        ]}"#;
+        let diff = |epsilon, options: &DeserializeOptions, new: &str| {
+            diff_datasources::<Feature, String, _, _>(
+                &mut GeoJsonReader(old.as_bytes()),
+                &mut GeoJsonReader(new.as_bytes()),
+                options,
+                "id",
+                epsilon,
+            )
+        };
+        let keys = |entries: &[(String, Feature)]| -> Vec<String> {
+            entries.iter().map(|(key, _)| key.clone()).collect()
+        };
+
+        let options = DeserializeOptions::new();
+        let result = diff(1e-3, &options, new)?;
+        assert_eq!(keys(&result.added), ["d"]);
+        assert_eq!(keys(&result.removed), ["c"]);
+        assert_eq!(result.changed.len(), 1);
+        assert_eq!(result.changed[0].0, "b");
+        assert_eq!(result.unchanged, 1);
+        // without tolerance the moved point changed as well
+        assert_eq!(diff(0., &options, new)?.changed.len(), 2);
+        assert!(diff(0., &options, old)?.is_empty());
+
+        let duplicated = old.replace(r#""id": "c""#, r#""id": "a""#);
+        assert!(matches!(
+            diff(0., &options, &duplicated),
+            Err(Error::DuplicateKey(_))
+        ));
+        let options = options.key_collision(KeyCollision::KeepFirst);
+        let result = diff(0., &options, &duplicated)?;
+        assert_eq!(
+            (keys(&result.removed), result.unchanged),
+            (vec!["c".to_string()], 2)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn stable_hash_separates_properties() {
+        let feature = |properties: &[(&str, Value)]| {
+            Feature::new(
+                (point! { x: 1., y: 2. }).into(),
+                properties
+                    .iter()
+                    .map(|(key, value)| ((*key).to_string(), value.clone()))
+                    .collect(),
+            )
+        };
+
+        let a1 = feature(&[("a1", Value::Long(2))]);
+        let a = feature(&[("a", Value::Long(12))]);
+        assert_ne!(a1.stable_hash(), a.stable_hash());
+        assert_ne!(
+            feature(&[("a", Value::Long(1))]).stable_hash(),
+            feature(&[("a", Value::Double(1.))]).stable_hash()
+        );
+        assert_ne!(
+            feature(&[("a", Value::from("1"))]).stable_hash(),
+            feature(&[("a", Value::Long(1))]).stable_hash()
+        );
+        assert_eq!(
+            feature(&[("a", Value::from("x"))]).stable_hash(),
+            feature(&[("a", Value::Shared("x".into()))]).stable_hash()
+        );
+    }
+
+    #[test]
+    fn feature_equality() {
+        let feature = |x: f64, value: f64| {
+            Feature::new(
+                (point! { x: x, y: 2. }).into(),
+                HashMap::from_iter([("value".to_string(), Value::from(value))]),
+            )
+        };
+
+        assert_eq!(feature(0., f64::NAN), feature(-0., f64::NAN));
+        assert_ne!(feature(1., 1.), feature(1., 2.));
+        assert_ne!(feature(1., 1.), feature(1., 1.).with_srid(Some(4326)));
+
+        let set: HashSet<Feature> = [feature(1., 1.), feature(1., 1.), feature(2., 1.)].into();
+        assert_eq!(set.len(), 2);
+    }
+}
@@ -0,0 +1,346 @@
+//! Cross-format conformance cases (requires the `conformance` feature).
+//!
+//! Every [`Case`] consists of a `GeoJSON` input and the typed values serde has to produce from
+//! it. Run them against your own datasources and processors to check they behave like the
+//! formats supported here, e.g. a round trip through a custom writer and reader:
+//!
+//! ```
+//! use serde_geozero::conformance::cases;
+//! use serde_geozero::geojson::to_geojson_string;
+//! use serde_geozero::{from_datasource, SerializeOptions};
+//!
+//! for case in cases() {
+//!     case.check_round_trip(|features| {
+//!         let geojson = to_geojson_string(features, &SerializeOptions::new())?;
+//!         from_datasource(&mut geozero::geojson::GeoJsonReader(geojson.as_bytes()))
+//!     })
+//!     .unwrap();
+//! }
+//! ```
+use std::fmt::Debug;
+
+use geo::{line_string, point, polygon, Geometry};
+use geozero::GeozeroDatasource;
+use serde::{de::DeserializeOwned, Deserialize};
+
+use crate::{
+    de::Feature,
+    error::{Error, Result},
+    from_datasource,
+};
+
+/// A conformance test case.
+pub struct Case {
+    /// Unique name of the case.
+    pub name: &'static str,
+    /// The input as a `GeoJSON` feature collection.
+    pub geojson: &'static str,
+    verify: fn(Vec<Feature>) -> Result<()>,
+}
+
+impl Case {
+    /// Reads the input into features, e.g. to write them with a processor under test.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the input can't be read, which is a bug in the case.
+    pub fn input(&self) -> Result<Vec<Feature>> {
+        from_datasource(&mut geozero::geojson::GeoJsonReader(
+            self.geojson.as_bytes(),
+        ))
+    }
+
+    /// Checks that `features` deserialize into the expected values.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Message`] describing the first difference.
+    pub fn verify(&self, features: Vec<Feature>) -> Result<()> {
+        (self.verify)(features).map_err(|err| Error::Message(format!("{}: {err}", self.name)))
+    }
+
+    /// Reads `source`, which has to hold the same data as [`Case::geojson`], and verifies it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `source` can't be read or doesn't match the expected values.
+    pub fn check_datasource(&self, source: &mut impl GeozeroDatasource) -> Result<()> {
+        self.verify(from_datasource(source)?)
+    }
+
+    /// Passes the input to `round_trip`, which writes and reads it back, and verifies the result.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of `round_trip` or a mismatch.
+    pub fn check_round_trip(
+        &self,
+        round_trip: impl FnOnce(&[Feature]) -> Result<Vec<Feature>>,
+    ) -> Result<()> {
+        self.verify(round_trip(&self.input()?)?)
+    }
+}
+
+/// Deserializes `features` into `T` and compares them with `expected`.
+fn expect<T: DeserializeOwned + PartialEq + Debug>(
+    features: Vec<Feature>,
+    expected: &[T],
+) -> Result<()> {
+    let actual = features
+        .into_iter()
+        .map(T::deserialize)
+        .collect::<Result<Vec<T>>>()?;
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(Error::Message(format!(
+            "expected {expected:?}, got {actual:?}"
+        )))
+    }
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Scalars {
+    geometry: Geometry,
+    name: String,
+    count: i64,
+    ratio: f64,
+    active: bool,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Optional {
+    geometry: Geometry,
+    label: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct GeometryOnly {
+    geometry: Geometry,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Address {
+    city: String,
+    zip: u32,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Nested {
+    geometry: Geometry,
+    address: Address,
+    tags: Vec<String>,
+}
+
+/// All conformance cases.
+#[must_use]
+pub fn cases() -> Vec<Case> {
+    vec![
+        scalars(),
+        optional_properties(),
+        line_strings(),
+        polygons(),
+        unicode_strings(),
+        nested_json(),
+    ]
+}
+
+/// Strings, integers, floats and booleans.
+fn scalars() -> Case {
+    Case {
+        name: "scalars",
+        geojson: r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "geometry": {"type": "Point", "coordinates": [13.4, 52.5]},
+             "properties": {"name": "Berlin", "count": 3669495, "ratio": 0.25, "active": true}}
+        ]}"#,
+        verify: |features| {
+            expect(
+                features,
+                &[Scalars {
+                    geometry: point! { x: 13.4, y: 52.5 }.into(),
+                    name: "Berlin".to_string(),
+                    count: 3_669_495,
+                    ratio: 0.25,
+                    active: true,
+                }],
+            )
+        },
+    }
+}
+
+/// `null` and missing properties read as `None`.
+fn optional_properties() -> Case {
+    Case {
+        name: "optional_properties",
+        geojson: r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "geometry": {"type": "Point", "coordinates": [1, 2]},
+             "properties": {"label": "a"}},
+            {"type": "Feature", "geometry": {"type": "Point", "coordinates": [3, 4]},
+             "properties": {"label": null}}
+        ]}"#,
+        verify: |features| {
+            expect(
+                features,
+                &[
+                    Optional {
+                        geometry: point! { x: 1., y: 2. }.into(),
+                        label: Some("a".to_string()),
+                    },
+                    Optional {
+                        geometry: point! { x: 3., y: 4. }.into(),
+                        label: None,
+                    },
+                ],
+            )
+        },
+    }
+}
+
+/// Line strings, with coordinates in all quadrants.
+fn line_strings() -> Case {
+    Case {
+        name: "line_strings",
+        geojson: r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "properties": {},
+             "geometry": {"type": "LineString", "coordinates": [[0, 0], [1, 1]]}},
+            {"type": "Feature", "properties": {},
+             "geometry": {"type": "LineString", "coordinates": [[-1.5, 2.5], [3, -4], [-5, -6]]}}
+        ]}"#,
+        verify: |features| {
+            expect(
+                features,
+                &[
+                    GeometryOnly {
+                        geometry: line_string![(x: 0., y: 0.), (x: 1., y: 1.)].into(),
+                    },
+                    GeometryOnly {
+                        geometry:
+                            line_string![(x: -1.5, y: 2.5), (x: 3., y: -4.), (x: -5., y: -6.)]
+                                .into(),
+                    },
+                ],
+            )
+        },
+    }
+}
+
+/// A polygon with a hole.
+fn polygons() -> Case {
+    Case {
+        name: "polygons",
+        geojson: r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "properties": {},
+             "geometry": {"type": "Polygon", "coordinates": [
+                 [[0, 0], [4, 0], [4, 4], [0, 0]], [[1, 1], [2, 1], [2, 2], [1, 1]]]}}
+        ]}"#,
+        verify: |features| {
+            expect(
+                features,
+                &[GeometryOnly {
+                    geometry: polygon!(
+                        exterior: [(x: 0., y: 0.), (x: 4., y: 0.), (x: 4., y: 4.)],
+                        interiors: [[(x: 1., y: 1.), (x: 2., y: 1.), (x: 2., y: 2.)]],
+                    )
+                    .into(),
+                }],
+            )
+        },
+    }
+}
+
+/// Strings with non-ASCII characters, quotes and line breaks.
+fn unicode_strings() -> Case {
+    Case {
+        name: "unicode_strings",
+        geojson: r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "geometry": {"type": "Point", "coordinates": [0, 1]},
+             "properties": {"label": "Zürich \"Hbf\"\n東京"}}
+        ]}"#,
+        verify: |features| {
+            expect(
+                features,
+                &[Optional {
+                    geometry: point! { x: 0., y: 1. }.into(),
+                    label: Some("Zürich \"Hbf\"\n東京".to_string()),
+                }],
+            )
+        },
+    }
+}
+
+/// Objects and arrays read into nested structs and `Vec`s.
+fn nested_json() -> Case {
+    Case {
+        name: "nested_json",
+        geojson: r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "geometry": {"type": "Point", "coordinates": [1, 1]},
+             "properties": {"address": {"city": "Berlin", "zip": 10115}, "tags": ["a", "b"]}}
+        ]}"#,
+        verify: |features| {
+            expect(
+                features,
+                &[Nested {
+                    geometry: point! { x: 1., y: 1. }.into(),
+                    address: Address {
+                        city: "Berlin".to_string(),
+                        zip: 10115,
+                    },
+                    tags: vec!["a".to_string(), "b".to_string()],
+                }],
+            )
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{from_datasource, geojson::to_geojson_string, SerializeOptions};
+
+    use super::cases;
+
+    #[test]
+    fn geojson() -> anyhow::Result<()> {
+        for case in cases() {
+            case.check_datasource(&mut geozero::geojson::GeoJsonReader(
+                case.geojson.as_bytes(),
+            ))?;
+            case.check_round_trip(|features| {
+                let geojson = to_geojson_string(features, &SerializeOptions::new())?;
+                from_datasource(&mut geozero::geojson::GeoJsonReader(geojson.as_bytes()))
+            })?;
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "flatgeobuf")]
+    #[test]
+    fn flatgeobuf() -> anyhow::Result<()> {
+        use flatgeobuf::{FgbReader, FgbWriter, FgbWriterOptions, GeometryType};
+        use geo::Geometry;
+
+        for case in cases() {
+            case.check_round_trip(|features| {
+                // without an index features keep their order
+                let geometry_type = match features.first().map(|feature| &feature.geometry) {
+                    Some(Geometry::Point(_)) => GeometryType::Point,
+                    Some(Geometry::LineString(_)) => GeometryType::LineString,
+                    Some(Geometry::Polygon(_)) => GeometryType::Polygon,
+                    _ => GeometryType::Unknown,
+                };
+                let mut writer = FgbWriter::create_with_options(
+                    case.name,
+                    geometry_type,
+                    FgbWriterOptions {
+                        write_index: false,
+                        ..Default::default()
+                    },
+                )?;
+                crate::to_geozero_datasource(features, &mut writer)?;
+                let mut buffer = Vec::new();
+                writer.write(&mut buffer)?;
+                from_datasource(&mut FgbReader::open(std::io::Cursor::new(buffer))?.select_all()?)
+            })?;
+        }
+        Ok(())
+    }
+}
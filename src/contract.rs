@@ -0,0 +1,608 @@
+//! Schema contracts embedded in dataset metadata.
+//!
+//! A [`StructSchema`] describes the fields of a struct and their Rust types. It is derived from
+//! the struct's `Deserialize` implementation, so no extra annotations are needed. Writers embed
+//! it into the dataset's metadata with [`StructSchema::to_metadata`] and readers verify it with
+//! [`StructSchema::verify`], so a file carries the contract it was written with.
+//!
+//! `FlatGeobuf` stores the metadata in its header, see `fgb_schema` (requires the `flatgeobuf`
+//! feature). Formats without a metadata field are not supported.
+//!
+//! ```
+//! use geo::Geometry;
+//! use serde::Deserialize;
+//! use serde_geozero::contract::StructSchema;
+//!
+//! #[derive(Deserialize)]
+//! struct Country {
+//!     geometry: Geometry,
+//!     name: String,
+//!     population: Option<u64>,
+//! }
+//!
+//! let schema = StructSchema::of::<Country>().unwrap().with_version(2);
+//! assert_eq!(schema.fields[2].rust_type, "Option<u64>");
+//!
+//! let metadata = schema.to_metadata();
+//! let embedded = StructSchema::from_metadata(&metadata).unwrap();
+//! assert!(schema.verify(embedded.as_ref()).is_ok());
+//! ```
+use serde::{
+    de::{self, value::StrDeserializer, DeserializeOwned, DeserializeSeed, Visitor},
+    forward_to_deserialize_any, Deserialize, Deserializer, Serialize,
+};
+
+use crate::error::{Error, Result};
+
+/// Key under which the schema is stored in the metadata JSON object.
+const METADATA_KEY: &str = "serde_geozero_schema";
+
+/// The fields of a struct and their Rust types.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StructSchema {
+    pub name: String,
+    /// Version of the contract, to be bumped by the user whenever the struct changes.
+    pub version: u32,
+    pub fields: Vec<FieldSchema>,
+}
+
+/// A single field of a [`StructSchema`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldSchema {
+    pub name: String,
+    /// The type as seen by serde, e.g. `u8`, `Option<String>` or `Geometry`.
+    pub rust_type: String,
+}
+
+impl StructSchema {
+    /// Describes the struct `T` by tracing its `Deserialize` implementation.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `T` is not a struct or uses `#[serde(flatten)]`, as the fields of such
+    /// types are only known at runtime.
+    pub fn of<T: DeserializeOwned>() -> Result<Self> {
+        let mut schema = StructSchema {
+            name: String::new(),
+            version: 0,
+            fields: Vec::new(),
+        };
+        T::deserialize(StructTracer {
+            schema: &mut schema,
+        })?;
+        Ok(schema)
+    }
+
+    #[must_use]
+    pub fn with_version(mut self, version: u32) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Encodes the schema as JSON, to be stored as dataset metadata.
+    #[must_use]
+    pub fn to_metadata(&self) -> String {
+        serde_json::json!({ METADATA_KEY: self }).to_string()
+    }
+
+    /// Extracts a schema written by [`StructSchema::to_metadata`].
+    ///
+    /// Returns `None` if the metadata doesn't contain a schema, e.g. because it was written by
+    /// another application.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the metadata contains a malformed schema.
+    pub fn from_metadata(metadata: &str) -> Result<Option<Self>> {
+        let Ok(serde_json::Value::Object(mut object)) = serde_json::from_str(metadata) else {
+            return Ok(None);
+        };
+        object
+            .remove(METADATA_KEY)
+            .map(serde_json::from_value)
+            .transpose()
+            .map_err(Error::SerdeError)
+    }
+
+    /// Verifies that the schema embedded in a dataset matches this one.
+    ///
+    /// Fields are compared by name and type regardless of their order, the struct name is not
+    /// compared so types can be renamed freely.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::SchemaMismatch`] listing all differences, or if no schema is embedded.
+    pub fn verify(&self, embedded: Option<&StructSchema>) -> Result<()> {
+        let Some(embedded) = embedded else {
+            return Err(Error::SchemaMismatch(
+                "the dataset has no embedded schema".to_string(),
+            ));
+        };
+
+        let mut differences = Vec::new();
+        if self.version != embedded.version {
+            differences.push(format!(
+                "expected version {}, found {}",
+                self.version, embedded.version
+            ));
+        }
+        for field in &self.fields {
+            match embedded
+                .fields
+                .iter()
+                .find(|other| other.name == field.name)
+            {
+                None => differences.push(format!("field {:?} is missing", field.name)),
+                Some(other) if other.rust_type != field.rust_type => differences.push(format!(
+                    "field {:?} is {}, expected {}",
+                    field.name, other.rust_type, field.rust_type
+                )),
+                Some(_) => {}
+            }
+        }
+        for field in &embedded.fields {
+            if !self.fields.iter().any(|other| other.name == field.name) {
+                differences.push(format!("unexpected field {:?}", field.name));
+            }
+        }
+
+        if differences.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::SchemaMismatch(differences.join(", ")))
+        }
+    }
+}
+
+/// Reads the schema embedded in the header of a `FlatGeobuf` file.
+///
+/// Write it by passing [`StructSchema::to_metadata`] as `metadata` in
+/// `flatgeobuf::FgbWriterOptions`.
+///
+/// # Errors
+///
+/// Returns an error if the header contains a malformed schema.
+#[cfg(feature = "flatgeobuf")]
+pub fn fgb_schema<R: std::io::Read>(
+    reader: &flatgeobuf::FgbReader<R>,
+) -> Result<Option<StructSchema>> {
+    reader
+        .header()
+        .metadata()
+        .map_or(Ok(None), StructSchema::from_metadata)
+}
+
+fn unsupported(what: &str) -> Error {
+    Error::Message(format!(
+        "Only structs without flattened fields can be described, found {what}"
+    ))
+}
+
+/// The field names of `T` if it is a struct without flattened fields, without tracing their
+/// types.
+pub(crate) fn struct_fields<'de, T: Deserialize<'de>>() -> Option<&'static [&'static str]> {
+    let mut fields = None;
+    // the field names are all we need, the error aborts the deserialization
+    let _ = T::deserialize(FieldNames {
+        fields: &mut fields,
+    });
+    fields
+}
+
+/// Captures the field names passed to `deserialize_struct`.
+struct FieldNames<'a> {
+    fields: &'a mut Option<&'static [&'static str]>,
+}
+
+impl<'de> Deserializer<'de> for FieldNames<'_> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value> {
+        Err(unsupported("a non struct type"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value> {
+        *self.fields = Some(fields);
+        Err(Error::Unknown)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map enum identifier ignored_any
+    }
+}
+
+/// Records the name and fields of the top level struct.
+struct StructTracer<'a> {
+    schema: &'a mut StructSchema,
+}
+
+impl<'de> Deserializer<'de> for StructTracer<'_> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value> {
+        Err(unsupported("a non struct type"))
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value> {
+        Err(unsupported("a map"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        name.clone_into(&mut self.schema.name);
+        visitor.visit_map(FieldTracer {
+            fields,
+            schema: Some(&mut self.schema.fields),
+        })
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct enum identifier ignored_any
+    }
+}
+
+/// Hands out every field of a struct once, recording their types if `schema` is set.
+struct FieldTracer<'a> {
+    fields: &'static [&'static str],
+    schema: Option<&'a mut Vec<FieldSchema>>,
+}
+
+impl<'de> de::MapAccess<'de> for FieldTracer<'_> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        let Some(field) = self.fields.first() else {
+            return Ok(None);
+        };
+        seed.deserialize(StrDeserializer::<Error>::new(field))
+            .map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        let (field, rest) = self
+            .fields
+            .split_first()
+            .ok_or_else(|| Error::Message("no field left".to_string()))?;
+        self.fields = rest;
+
+        let mut rust_type = String::new();
+        let value = seed.deserialize(TypeTracer {
+            rust_type: &mut rust_type,
+        })?;
+        if let Some(schema) = &mut self.schema {
+            schema.push(FieldSchema {
+                name: (*field).to_string(),
+                rust_type,
+            });
+        }
+        Ok(value)
+    }
+}
+
+/// Records the type requested by a `Deserialize` implementation and produces a default value.
+struct TypeTracer<'a> {
+    rust_type: &'a mut String,
+}
+
+impl TypeTracer<'_> {
+    fn record(&mut self, rust_type: &str) {
+        rust_type.clone_into(self.rust_type);
+    }
+}
+
+macro_rules! trace {
+    ($($method:ident => $visit:ident($value:expr)),*) => {
+        $(
+            fn $method<V: Visitor<'de>>(mut self, visitor: V) -> Result<V::Value> {
+                self.record(stringify!($method).trim_start_matches("deserialize_"));
+                visitor.$visit($value)
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for TypeTracer<'_> {
+    type Error = Error;
+
+    trace!(
+        deserialize_bool => visit_bool(false), deserialize_i8 => visit_i8(0),
+        deserialize_i16 => visit_i16(0), deserialize_i32 => visit_i32(0),
+        deserialize_i64 => visit_i64(0), deserialize_i128 => visit_i128(0),
+        deserialize_u8 => visit_u8(0), deserialize_u16 => visit_u16(0),
+        deserialize_u32 => visit_u32(0), deserialize_u64 => visit_u64(0),
+        deserialize_u128 => visit_u128(0), deserialize_f32 => visit_f32(0.),
+        deserialize_f64 => visit_f64(0.), deserialize_char => visit_char('\0')
+    );
+
+    fn deserialize_any<V: Visitor<'de>>(mut self, visitor: V) -> Result<V::Value> {
+        self.record("any");
+        visitor.visit_unit()
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(mut self, visitor: V) -> Result<V::Value> {
+        self.record("String");
+        visitor.visit_str("")
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(mut self, visitor: V) -> Result<V::Value> {
+        self.record("bytes");
+        visitor.visit_bytes(&[])
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(mut self, visitor: V) -> Result<V::Value> {
+        let mut inner = String::new();
+        let value = visitor.visit_some(TypeTracer {
+            rust_type: &mut inner,
+        })?;
+        self.record(&format!("Option<{inner}>"));
+        Ok(value)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(mut self, visitor: V) -> Result<V::Value> {
+        self.record("()");
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        mut self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.record(name);
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        mut self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.record(name);
+        visitor.visit_newtype_struct(TypeTracer {
+            rust_type: &mut String::new(),
+        })
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(mut self, visitor: V) -> Result<V::Value> {
+        self.record("seq");
+        visitor.visit_seq(SeqTracer(0))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(mut self, len: usize, visitor: V) -> Result<V::Value> {
+        self.record("tuple");
+        visitor.visit_seq(SeqTracer(len))
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        mut self,
+        name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.record(name);
+        visitor.visit_seq(SeqTracer(len))
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(mut self, visitor: V) -> Result<V::Value> {
+        self.record("map");
+        visitor.visit_map(FieldTracer {
+            fields: &[],
+            schema: None,
+        })
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        mut self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.record(name);
+        visitor.visit_map(FieldTracer {
+            fields,
+            schema: None,
+        })
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        mut self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.record(name);
+        let variant = variants
+            .first()
+            .ok_or_else(|| Error::Message(format!("{name} has no variants")))?;
+        visitor.visit_enum(VariantTracer(variant))
+    }
+
+    forward_to_deserialize_any! {
+        identifier ignored_any
+    }
+}
+
+/// A sequence of default values.
+struct SeqTracer(usize);
+
+impl<'de> de::SeqAccess<'de> for SeqTracer {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>> {
+        if self.0 == 0 {
+            return Ok(None);
+        }
+        self.0 -= 1;
+        seed.deserialize(TypeTracer {
+            rust_type: &mut String::new(),
+        })
+        .map(Some)
+    }
+}
+
+/// Selects the given variant of an enum.
+struct VariantTracer(&'static str);
+
+impl<'de> de::EnumAccess<'de> for VariantTracer {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self)> {
+        Ok((
+            seed.deserialize(StrDeserializer::<Error>::new(self.0))?,
+            self,
+        ))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for VariantTracer {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+        seed.deserialize(TypeTracer {
+            rust_type: &mut String::new(),
+        })
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value> {
+        visitor.visit_seq(SeqTracer(len))
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_map(FieldTracer {
+            fields,
+            schema: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use geo::Geometry;
+    use serde::Deserialize;
+
+    use super::{FieldSchema, StructSchema};
+    use crate::{de::Feature, error::Error};
+
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct Country {
+        geometry: Geometry,
+        name: String,
+        population: Option<u64>,
+        tags: Vec<String>,
+    }
+
+    #[test]
+    fn describe_struct() -> anyhow::Result<()> {
+        let schema = StructSchema::of::<Country>()?;
+        assert_eq!(schema.name, "Country");
+        let types: Vec<_> = schema
+            .fields
+            .iter()
+            .map(|field| (field.name.as_str(), field.rust_type.as_str()))
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                ("geometry", "Geometry"),
+                ("name", "String"),
+                ("population", "Option<u64>"),
+                ("tags", "seq"),
+            ]
+        );
+
+        assert!(StructSchema::of::<Feature>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn mismatch() -> anyhow::Result<()> {
+        let expected = StructSchema::of::<Country>()?.with_version(1);
+        let mut embedded = expected.clone();
+        embedded.fields[2].rust_type = "Option<u32>".to_string();
+        embedded.fields.pop();
+        embedded.fields.push(FieldSchema {
+            name: "area".to_string(),
+            rust_type: "f64".to_string(),
+        });
+
+        let err = expected.verify(Some(&embedded)).unwrap_err();
+        let Error::SchemaMismatch(message) = err else {
+            panic!("unexpected error {err:?}");
+        };
+        assert_eq!(
+            message,
+            "field \"population\" is Option<u32>, expected Option<u64>, field \"tags\" is \
+             missing, unexpected field \"area\""
+        );
+        assert!(expected.verify(None).is_err());
+        Ok(())
+    }
+
+    #[cfg(feature = "flatgeobuf")]
+    #[test]
+    fn fgb_round_trip() -> anyhow::Result<()> {
+        use flatgeobuf::{FgbReader, FgbWriter, FgbWriterOptions, GeometryType};
+        use std::io::Cursor;
+
+        use crate::to_geozero_datasource;
+
+        #[derive(serde::Serialize, Deserialize)]
+        struct Place {
+            geometry: Geometry,
+            name: String,
+        }
+
+        let schema = StructSchema::of::<Place>()?;
+        let metadata = schema.to_metadata();
+        let mut writer = FgbWriter::create_with_options(
+            "places",
+            GeometryType::Point,
+            FgbWriterOptions {
+                metadata: Some(&metadata),
+                ..Default::default()
+            },
+        )?;
+        let places = [Place {
+            geometry: Geometry::Point((1., 2.).into()),
+            name: "A".to_string(),
+        }];
+        to_geozero_datasource(places.as_slice(), &mut writer)?;
+        let mut buffer = Vec::new();
+        writer.write(&mut buffer)?;
+
+        let reader = FgbReader::open(Cursor::new(buffer))?;
+        schema.verify(super::fgb_schema(&reader)?.as_ref())?;
+        Ok(())
+    }
+}
@@ -0,0 +1,555 @@
+//! Coordinate types other than `f64`.
+//!
+//! Datasources always report `f64` coordinates. Geometry fields may nevertheless use any
+//! numeric coordinate type supported by `geo`, e.g. `Geometry<f32>` to halve the memory used by
+//! a rendering application. Coordinates are converted while deserializing, rounded according to
+//! [`Rounding`]:
+//!
+//! ```
+//! use geo::Geometry;
+//! use serde::Deserialize;
+//! use serde_geozero::coord::Rounding;
+//! use serde_geozero::{from_datasource_with_options, DeserializeOptions};
+//!
+//! #[derive(Deserialize)]
+//! struct Tile {
+//!     geometry: Geometry<i32>,
+//! }
+//!
+//! let geojson = r#"{"type": "Feature", "properties": {},
+//!     "geometry": {"type": "Point", "coordinates": [1.7, -1.2]}}"#;
+//!
+//! let options = DeserializeOptions::new().coord_rounding(Rounding::Floor);
+//! let mut reader = geozero::geojson::GeoJsonReader(geojson.as_bytes());
+//! let tiles: Vec<Tile> = from_datasource_with_options(&mut reader, &options).unwrap();
+//! assert_eq!(tiles[0].geometry, Geometry::Point((1, -2).into()));
+//! ```
+use serde::{
+    de::{self, value::StrDeserializer, DeserializeSeed, Visitor},
+    forward_to_deserialize_any, Deserializer,
+};
+use serde_json::{Map, Value};
+
+use crate::error::{Error, Result};
+
+/// How `f64` coordinates are rounded when converted to a smaller coordinate type.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Rounding {
+    /// Round to the nearest representable value.
+    #[default]
+    Nearest,
+    /// Round towards negative infinity.
+    Floor,
+    /// Round towards positive infinity.
+    Ceil,
+    /// Round towards zero.
+    TowardZero,
+}
+
+impl Rounding {
+    /// Converts `value` to the nearest `f32` in the rounding direction.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn to_f32(self, value: f64) -> f32 {
+        let nearest = value as f32;
+        let widened = f64::from(nearest);
+        match self {
+            Rounding::Floor if widened > value => nearest.next_down(),
+            Rounding::Ceil if widened < value => nearest.next_up(),
+            Rounding::TowardZero if widened.abs() > value.abs() => {
+                if value > 0. {
+                    nearest.next_down()
+                } else {
+                    nearest.next_up()
+                }
+            }
+            _ => nearest,
+        }
+    }
+
+    /// Rounds `value` to an integral value in the rounding direction.
+    #[must_use]
+    pub fn to_integral(self, value: f64) -> f64 {
+        match self {
+            Rounding::Nearest => value.round(),
+            Rounding::Floor => value.floor(),
+            Rounding::Ceil => value.ceil(),
+            Rounding::TowardZero => value.trunc(),
+        }
+    }
+}
+
+/// Deserializes a geometry encoded as JSON, rounding coordinates for non `f64` types.
+pub(crate) struct GeometryDeserializer {
+    value: Value,
+    rounding: Rounding,
+}
+
+impl GeometryDeserializer {
+    pub(crate) fn new(value: Value, rounding: Rounding) -> Self {
+        Self { value, rounding }
+    }
+
+    /// Unwraps the `{"Point": ...}` encoding of a geometry for fields of the single geometry
+    /// type `name`, e.g. `geo::Point`.
+    fn unwrap_geometry(self, name: &'static str) -> Result<Self> {
+        if !GEOMETRY_TYPES.contains(&name) {
+            return Ok(self);
+        }
+        match self.value {
+            Value::Object(map) if map.len() == 1 => {
+                let (found, value) = map.into_iter().next().expect("map has one entry");
+                if found == name {
+                    Ok(Self::new(value, self.rounding))
+                } else if GEOMETRY_TYPES.contains(&found.as_str()) {
+                    Err(Error::GeometryTypeMismatch {
+                        expected: name.to_string(),
+                        found,
+                        feature_idx: None,
+                    })
+                } else {
+                    Ok(Self::new(
+                        Value::Object(Map::from_iter([(found, value)])),
+                        self.rounding,
+                    ))
+                }
+            }
+            value => Ok(Self::new(value, self.rounding)),
+        }
+    }
+
+    fn float(&self) -> Option<f64> {
+        match &self.value {
+            Value::Number(number) => number.as_f64(),
+            _ => None,
+        }
+    }
+}
+
+macro_rules! deserialize_integer {
+    ($($method:ident => $visit:ident($ty:ty)),*) => {
+        $(
+            #[allow(
+                clippy::cast_possible_truncation,
+                clippy::cast_precision_loss,
+                clippy::cast_lossless
+            )]
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+                let Some(value) = self.float() else {
+                    return self.deserialize_any(visitor);
+                };
+                let rounded = self.rounding.to_integral(value);
+                if rounded < <$ty>::MIN as f64 || rounded > <$ty>::MAX as f64 {
+                    return Err(de::Error::custom(format!(
+                        "coordinate {value} does not fit into {}",
+                        stringify!($ty)
+                    )));
+                }
+                visitor.$visit(rounded as $ty)
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for GeometryDeserializer {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value {
+            Value::Array(values) => visitor.visit_seq(SeqAccess {
+                values: values.into_iter(),
+                rounding: self.rounding,
+            }),
+            Value::Object(map) => visitor.visit_map(MapAccess {
+                entries: map.into_iter(),
+                value: None,
+                rounding: self.rounding,
+            }),
+            value => value.deserialize_any(visitor).map_err(Error::SerdeError),
+        }
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.float() {
+            Some(value) => visitor.visit_f32(self.rounding.to_f32(value)),
+            None if self.value.is_null() => visitor.visit_f32(f32::NAN),
+            None => self.deserialize_any(visitor),
+        }
+    }
+
+    // JSON writes NaN and infinite coordinates as null, read them back as NaN
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value {
+            Value::Null => visitor.visit_f64(f64::NAN),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    deserialize_integer!(
+        deserialize_i16 => visit_i16(i16), deserialize_i32 => visit_i32(i32),
+        deserialize_i64 => visit_i64(i64)
+    );
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value {
+            Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self.unwrap_geometry(name)?)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.unwrap_geometry(name)?.deserialize_any(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.unwrap_geometry(name)?.deserialize_any(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        match self.value {
+            Value::String(variant) => visitor.visit_enum(EnumAccess {
+                variant,
+                value: None,
+                rounding: self.rounding,
+            }),
+            Value::Object(map) if map.len() == 1 => {
+                let (variant, value) = map.into_iter().next().expect("map has one entry");
+                visitor.visit_enum(EnumAccess {
+                    variant,
+                    value: Some(value),
+                    rounding: self.rounding,
+                })
+            }
+            _ => Err(de::Error::custom("expected an enum")),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i128 u8 u16 u32 u64 u128 char str string
+        bytes byte_buf unit unit_struct seq tuple
+        map identifier ignored_any
+    }
+}
+
+/// Deserializes the geometry of a feature.
+///
+/// The geometry is encoded as JSON for [`GeometryDeserializer`] only once the field asks for
+/// it. A [`crate::with::geos_geometry`] field gets a GEOS geometry built from it directly.
+pub(crate) struct FeatureGeometry<'a> {
+    geometry: &'a geo::Geometry,
+    rounding: Rounding,
+}
+
+impl<'a> FeatureGeometry<'a> {
+    pub(crate) fn new(geometry: &'a geo::Geometry, rounding: Rounding) -> Self {
+        Self { geometry, rounding }
+    }
+
+    fn into_json(self) -> Result<GeometryDeserializer> {
+        Ok(GeometryDeserializer::new(
+            serde_json::to_value(self.geometry)?,
+            self.rounding,
+        ))
+    }
+}
+
+macro_rules! forward_to_json {
+    ($($method:ident),*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+                self.into_json()?.$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for FeatureGeometry<'_> {
+    type Error = Error;
+
+    forward_to_json!(
+        deserialize_any,
+        deserialize_bool,
+        deserialize_i8,
+        deserialize_i16,
+        deserialize_i32,
+        deserialize_i64,
+        deserialize_i128,
+        deserialize_u8,
+        deserialize_u16,
+        deserialize_u32,
+        deserialize_u64,
+        deserialize_u128,
+        deserialize_f32,
+        deserialize_f64,
+        deserialize_char,
+        deserialize_str,
+        deserialize_string,
+        deserialize_bytes,
+        deserialize_byte_buf,
+        deserialize_option,
+        deserialize_unit,
+        deserialize_seq,
+        deserialize_map,
+        deserialize_identifier,
+        deserialize_ignored_any
+    );
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        #[cfg(feature = "geos")]
+        if name == crate::with::geos_geometry::GEOS_NAME {
+            crate::with::geos_geometry::hand_over(self.geometry)?;
+            return visitor.visit_unit();
+        }
+        self.into_json()?.deserialize_newtype_struct(name, visitor)
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.into_json()?.deserialize_unit_struct(name, visitor)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value> {
+        self.into_json()?.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.into_json()?
+            .deserialize_tuple_struct(name, len, visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.into_json()?.deserialize_struct(name, fields, visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.into_json()?.deserialize_enum(name, variants, visitor)
+    }
+}
+
+/// The variants of `geo::Geometry`, which are also the names of the geometry types.
+pub(crate) const GEOMETRY_TYPES: &[&str] = &[
+    "Point",
+    "Line",
+    "LineString",
+    "Polygon",
+    "MultiPoint",
+    "MultiLineString",
+    "MultiPolygon",
+    "GeometryCollection",
+    "Rect",
+    "Triangle",
+];
+
+struct SeqAccess {
+    values: std::vec::IntoIter<Value>,
+    rounding: Rounding,
+}
+
+impl<'de> de::SeqAccess<'de> for SeqAccess {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>> {
+        self.values
+            .next()
+            .map(|value| seed.deserialize(GeometryDeserializer::new(value, self.rounding)))
+            .transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.values.len())
+    }
+}
+
+struct MapAccess {
+    entries: <Map<String, Value> as IntoIterator>::IntoIter,
+    value: Option<Value>,
+    rounding: Rounding,
+}
+
+impl<'de> de::MapAccess<'de> for MapAccess {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        let Some((key, value)) = self.entries.next() else {
+            return Ok(None);
+        };
+        self.value = Some(value);
+        seed.deserialize(StrDeserializer::<Error>::new(&key))
+            .map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        let value = self
+            .value
+            .take()
+            .ok_or_else(|| Error::Message("value requested before key".to_string()))?;
+        seed.deserialize(GeometryDeserializer::new(value, self.rounding))
+    }
+}
+
+struct EnumAccess {
+    variant: String,
+    value: Option<Value>,
+    rounding: Rounding,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumAccess {
+    type Error = Error;
+    type Variant = GeometryDeserializer;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self::Variant)> {
+        let variant = seed.deserialize(StrDeserializer::<Error>::new(&self.variant))?;
+        Ok((
+            variant,
+            GeometryDeserializer::new(self.value.unwrap_or(Value::Null), self.rounding),
+        ))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for GeometryDeserializer {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        self.deserialize_any(visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_any(visitor)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use geo::{point, Geometry};
+    use serde::Deserialize;
+
+    use super::{GeometryDeserializer, Rounding};
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn directed_rounding() {
+        let value = 0.1_f64;
+        assert!(f64::from(Rounding::Floor.to_f32(value)) <= value);
+        assert!(f64::from(Rounding::Ceil.to_f32(value)) >= value);
+        assert!(f64::from(Rounding::TowardZero.to_f32(-value)) >= -value);
+        assert_eq!(Rounding::Nearest.to_f32(value), 0.1_f32);
+        assert_eq!(Rounding::TowardZero.to_integral(-1.7), -1.);
+    }
+
+    #[test]
+    fn geometry_types() -> anyhow::Result<()> {
+        let json = serde_json::to_value(Geometry::Point(point! { x: 1.5, y: -2.5 }))?;
+
+        let geometry =
+            Geometry::<f32>::deserialize(GeometryDeserializer::new(json.clone(), Rounding::Floor))?;
+        assert_eq!(geometry, Geometry::Point(point! { x: 1.5_f32, y: -2.5 }));
+
+        let geometry =
+            Geometry::<i64>::deserialize(GeometryDeserializer::new(json.clone(), Rounding::Ceil))?;
+        assert_eq!(geometry, Geometry::Point(point! { x: 2, y: -2 }));
+
+        let geometry =
+            Geometry::<f64>::deserialize(GeometryDeserializer::new(json, Rounding::Ceil))?;
+        assert_eq!(geometry, Geometry::Point(point! { x: 1.5, y: -2.5 }));
+        Ok(())
+    }
+
+    #[test]
+    fn single_geometry_types() -> anyhow::Result<()> {
+        use geo::{Point, Polygon};
+
+        use crate::{error::Error, from_datasource};
+
+        #[derive(Debug, Deserialize)]
+        struct Station {
+            #[allow(dead_code)]
+            geometry: Point<f32>,
+        }
+
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "properties": {},
+             "geometry": {"type": "Point", "coordinates": [1, 2]}},
+            {"type": "Feature", "properties": {}, "geometry": {"type": "Polygon",
+             "coordinates": [[[0, 0], [1, 0], [1, 1], [0, 0]]]}}
+        ]}"#;
+        let mut reader = geozero::geojson::GeoJsonReader(geojson.as_bytes());
+        let err = from_datasource::<Station, _>(&mut reader).unwrap_err();
+        assert!(matches!(
+            err.root_cause(),
+            Error::GeometryTypeMismatch { expected, found, feature_idx: Some(1) }
+                if expected == "Point" && found == "Polygon"
+        ));
+        assert_eq!(
+            err.root_cause().to_string(),
+            "Expected a Point geometry but found a Polygon in feature 1"
+        );
+
+        let json = serde_json::to_value(Geometry::Point(point! { x: 1., y: 2. }))?;
+        let station =
+            Point::<f64>::deserialize(GeometryDeserializer::new(json, Rounding::Nearest))?;
+        assert_eq!(station, point! { x: 1., y: 2. });
+        assert!(Polygon::<f64>::deserialize(GeometryDeserializer::new(
+            serde_json::to_value(Geometry::Point(station))?,
+            Rounding::Nearest
+        ))
+        .is_err());
+        Ok(())
+    }
+}
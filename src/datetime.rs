@@ -0,0 +1,91 @@
+//! Date and time properties (requires the `chrono` feature).
+//!
+//! Reading: `DateTime` columns are handed to serde as strings, so fields typed
+//! `chrono::DateTime<Utc>`, `DateTime<FixedOffset>`, `NaiveDateTime` or `NaiveDate` deserialize
+//! directly as long as the stored text matches the field's format. Fields that need a different
+//! precision can use the [`date`] adapter to read only the date part of a timestamp.
+//!
+//! Writing: chrono types serialize to strings. Strings in one of the formats accepted by
+//! [`is_datetime`] are written as `ColumnValue::DateTime` instead of `ColumnValue::String`, so
+//! formats with a native date time type store them as such. This can be turned off with
+//! [`SerializeOptions::detect_datetimes`](crate::SerializeOptions::detect_datetimes).
+//!
+//! ```
+//! use chrono::{DateTime, NaiveDate, Utc};
+//! use geo::Geometry;
+//! use serde::Deserialize;
+//! use serde_geozero::from_datasource;
+//!
+//! #[derive(Deserialize)]
+//! struct Event {
+//!     geometry: Geometry,
+//!     start: DateTime<Utc>,
+//!     #[serde(with = "serde_geozero::datetime::date")]
+//!     day: NaiveDate,
+//! }
+//!
+//! let geojson = r#"{"type": "Feature", "geometry": {"type": "Point", "coordinates": [1, 1]},
+//!     "properties": {"start": "2024-05-01T12:00:00Z", "day": "2024-05-01T12:00:00Z"}}"#;
+//!
+//! let mut reader = geozero::geojson::GeoJsonReader(geojson.as_bytes());
+//! let events: Vec<Event> = from_datasource(&mut reader).unwrap();
+//! assert_eq!(events[0].day, NaiveDate::from_ymd_opt(2024, 5, 1).unwrap());
+//! ```
+use std::str::FromStr;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+
+/// Returns `true` if `value` is an RFC 3339 timestamp, a timestamp without offset
+/// (`2024-05-01T12:00:00`) or a date (`2024-05-01`).
+#[must_use]
+pub fn is_datetime(value: &str) -> bool {
+    // cheap pre-check, most strings aren't dates
+    let bytes = value.as_bytes();
+    if bytes.len() < 10 || bytes[4] != b'-' || bytes[7] != b'-' {
+        return false;
+    }
+    DateTime::parse_from_rfc3339(value).is_ok()
+        || NaiveDateTime::from_str(value).is_ok()
+        || NaiveDate::from_str(value).is_ok()
+}
+
+/// Reads a `NaiveDate` from either a date or any timestamp accepted by [`is_datetime`], and
+/// writes it as a date. Use with `#[serde(with = "serde_geozero::datetime::date")]`.
+pub mod date {
+    use std::str::FromStr;
+
+    use chrono::{DateTime, NaiveDate, NaiveDateTime};
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    /// # Errors
+    ///
+    /// Returns an error if the serializer fails.
+    pub fn serialize<S: Serializer>(date: &NaiveDate, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(date)
+    }
+
+    /// # Errors
+    ///
+    /// Returns an error if the value is neither a date nor a timestamp.
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<NaiveDate, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        NaiveDate::from_str(&value)
+            .or_else(|_| DateTime::parse_from_rfc3339(&value).map(|value| value.date_naive()))
+            .or_else(|_| NaiveDateTime::from_str(&value).map(|value| value.date()))
+            .map_err(|_| de::Error::custom(format!("{value:?} is not a date")))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::is_datetime;
+
+    #[test]
+    fn detection() {
+        assert!(is_datetime("2024-05-01"));
+        assert!(is_datetime("2024-05-01T12:00:00"));
+        assert!(is_datetime("2024-05-01T12:00:00.5+02:00"));
+        assert!(!is_datetime("2024-05-01 and more"));
+        assert!(!is_datetime("Berlin"));
+    }
+}
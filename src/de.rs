@@ -1,21 +1,37 @@
 #![allow(clippy::many_single_char_names)]
-use std::marker::PhantomData;
+use std::{
+    borrow::Cow, collections::BTreeMap, fmt::Debug, hash::Hash, marker::PhantomData,
+    ops::ControlFlow, sync::Arc,
+};
 
-use geo::Geometry;
+use geo::{Geometry, GeometryCollection, Rect};
 use geozero::{
-    error::GeozeroError, geo_types::GeoWriter, ColumnValue, FeatureAccess, FeatureProcessor,
-    GeomProcessor, GeozeroDatasource, PropertyProcessor,
+    error::GeozeroError, FeatureAccess, FeatureProperties, GeomProcessor, GeozeroDatasource,
+    GeozeroGeometry, PropertyProcessor,
 };
-use hashbrown::HashMap;
+use hashbrown::{hash_map::Entry, HashMap};
 use serde::{
-    de::{value::StringDeserializer, MapAccess},
+    de::{
+        value::{SeqDeserializer, StrDeserializer, StringDeserializer},
+        EnumAccess, MapAccess, SeqAccess, VariantAccess, Visitor,
+    },
+    ser::SerializeStruct,
     Deserialize, Serialize,
 };
-use serde_json::Value;
 
+pub use crate::collector::GeozeroCollector;
 use crate::{
-    error::{Error, Result},
-    ser::ColumnValueSerializer,
+    case::PropertyCase,
+    collector::{Convert, Converted},
+    contract::struct_fields,
+    coord::{FeatureGeometry, GeometryDeserializer, Rounding},
+    encoded::{decode_json, to_geojson, GeometryEncoding},
+    error::{Error, ErrorCallback, ErrorPolicy, Result},
+    finite::NonFinite,
+    lookup::Lookup,
+    schema::StrictMode,
+    value::{Coerced, Compact, Value},
+    zm::{ZmValues, M_COL, Z_COL},
 };
 
 /// Deserializes data from a `GeozeroDatasource` into a type that implements Deserialize.
@@ -76,6 +92,35 @@ use crate::{
 /// let countries: Vec<Country> = from_datasource(&mut reader.select_all().unwrap()).unwrap();
 /// ```
 ///
+/// Properties without a field of their own can be collected in a `#[serde(flatten)]` map, which
+/// serializes back into separate properties:
+/// ```
+/// use std::collections::BTreeMap;
+///
+/// use serde::Deserialize;
+/// use geo::Geometry;
+/// use serde_geozero::{from_datasource, value::Value};
+///
+/// #[derive(Deserialize)]
+/// struct City {
+///     geometry: Geometry,
+///     name: String,
+///     #[serde(flatten)]
+///     extra: BTreeMap<String, Value>,
+/// }
+///
+/// let geojson = r#"{"type": "Feature", "geometry": {"type": "Point", "coordinates": [13.4, 52.5]},
+///     "properties": {"name": "Berlin", "population": 3669495, "capital": true}}"#;
+///
+/// let mut reader = geozero::geojson::GeoJsonReader(geojson.as_bytes());
+/// let cities: Vec<City> = from_datasource(&mut reader).unwrap();
+/// assert_eq!(cities[0].extra["population"], Value::Long(3669495));
+/// ```
+///
+/// Synthetic fields like `srid` or `bbox` land in the map as well unless the struct declares
+/// them. Structs with flattened fields read every property, so
+/// [`DeserializeOptions::project_properties`] and [`DeserializeOptions::strict`] have no effect.
+///
 /// # Errors
 ///
 /// Returns an error if:
@@ -83,585 +128,2381 @@ use crate::{
 /// - The collected features cannot be serialized to JSON
 /// - The JSON cannot be deserialized into the target type
 ///
-pub fn from_datasource<'de, T: Deserialize<'de>, S: GeozeroDatasource>(
+pub fn from_datasource<'de, T: Deserialize<'de> + 'static, S: GeozeroDatasource>(
     processor: &mut S,
 ) -> Result<Vec<T>> {
-    let mut collector = DataSourceDeserializer::new();
-    processor.process(&mut collector)?;
-
-    Ok(collector.features)
+    from_datasource_with_options(processor, &DeserializeOptions::default())
 }
 
-pub struct DataSourceDeserializer<'de, T: Deserialize<'de>> {
-    pub features: Vec<T>,
+/// Where a struct keeps the properties of a feature.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FeatureLayout {
+    /// Properties are fields next to `geometry`, e.g. `{ geometry, name, population }`.
+    #[default]
+    Flat,
+    /// Properties are fields of a nested struct or map in a `properties` field, like in
+    /// `GeoJSON`, e.g. `{ geometry, properties: CityProperties }`. Synthetic fields like `srid`
+    /// or `bbox` stay next to `geometry`.
+    Nested,
+}
 
-    current_feature: GeozeroFeature,
-    _phantom: &'de PhantomData<()>,
+/// Options for [`from_datasource_with_options`].
+#[derive(Debug, Clone, Default)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct DeserializeOptions {
+    pub(crate) intern_strings: bool,
+    #[cfg(feature = "proj")]
+    pub(crate) reproject: Option<(String, String)>,
+    pub(crate) preserve_zm: bool,
+    pub(crate) compute_bbox: bool,
+    pub(crate) validate_geometries: bool,
+    pub(crate) non_finite: NonFinite,
+    pub(crate) on_error: Option<ErrorCallback>,
+    pub(crate) error_policy: ErrorPolicy,
+    pub(crate) coord_rounding: Rounding,
+    pub(crate) lookups: Vec<Lookup>,
+    pub(crate) raw_json: bool,
+    pub(crate) coerce_types: bool,
+    pub(crate) compact: bool,
+    pub(crate) property_case: PropertyCase,
+    pub(crate) keep_all_properties: bool,
+    pub(crate) columns: Option<Vec<String>>,
+    pub(crate) max_properties: Option<usize>,
+    pub(crate) strict: StrictMode,
+    pub(crate) layout: FeatureLayout,
+    pub(crate) key_collision: KeyCollision,
+    pub(crate) dedup: Dedup,
+    pub(crate) enum_tag: Option<Arc<str>>,
+    pub(crate) geometry_columns: Vec<(String, GeometryEncoding)>,
+    pub(crate) on_feature: Option<ProgressCallback>,
 }
 
-impl<'de, T: Deserialize<'de>> DataSourceDeserializer<'de, T> {
-    #[must_use]
-    pub fn new() -> Self {
-        Self {
-            features: Vec::new(),
-            current_feature: GeozeroFeature::new(),
-            _phantom: &PhantomData,
-        }
+/// The callback set with [`DeserializeOptions::on_feature`].
+#[derive(Clone)]
+pub(crate) struct ProgressCallback(pub(crate) Arc<dyn Fn(usize) -> ControlFlow<()> + Send + Sync>);
+
+impl Debug for ProgressCallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProgressCallback").finish_non_exhaustive()
     }
 }
 
-impl<'de, T: Deserialize<'de>> Default for DataSourceDeserializer<'de, T> {
-    fn default() -> Self {
-        Self::new()
-    }
+/// What [`from_datasource_keyed`] does with features sharing a key.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KeyCollision {
+    /// Fail with [`Error::DuplicateKey`].
+    #[default]
+    Error,
+    /// Keep the first feature with the key.
+    KeepFirst,
+    /// Keep the last feature with the key.
+    KeepLast,
 }
 
-impl<'de, T: Deserialize<'de>> PropertyProcessor for DataSourceDeserializer<'de, T> {
-    fn property(
-        &mut self,
-        idx: usize,
-        name: &str,
-        value: &ColumnValue,
-    ) -> geozero::error::Result<bool> {
-        self.current_feature.property(idx, name, value)
-    }
+/// Which features are dropped as duplicates while reading, see [`DeserializeOptions::dedup`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Dedup {
+    /// Keep every feature.
+    #[default]
+    Off,
+    /// Drop features equal to an earlier one, compared like [`Feature`]s over the geometry, the
+    /// SRID and the properties read. Only the [`Feature::stable_hash`] of every distinct
+    /// feature is kept, so in the rare case of a hash collision a distinct feature is dropped
+    /// too.
+    Exact,
+    /// Drop features whose property is equal to that of an earlier feature. Values of
+    /// different types, e.g. `1` and `1.0`, are different keys. Features without the property
+    /// or with a null value are kept.
+    ByKey(String),
 }
 
-impl<'de, T: Deserialize<'de>> GeomProcessor for DataSourceDeserializer<'de, T> {
-    fn dimensions(&self) -> geozero::CoordDimensions {
-        self.current_feature.dimensions()
-    }
+/// Which features [`from_datasource_joined`] keeps.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Join {
+    /// Only features with a matching record.
+    #[default]
+    Inner,
+    /// Every feature, with `None` if there is no matching record.
+    Left,
+}
 
-    fn multi_dim(&self) -> bool {
-        self.current_feature.multi_dim()
+impl DeserializeOptions {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
     }
 
-    fn srid(&mut self, srid: Option<i32>) -> geozero::error::Result<()> {
-        self.current_feature.srid(srid)
+    /// Requests Z and M values from the datasource and offers them as the companion fields
+    /// described in [`crate::zm`].
+    #[must_use]
+    pub fn preserve_zm(mut self, preserve_zm: bool) -> Self {
+        self.preserve_zm = preserve_zm;
+        self
     }
 
-    fn xy(&mut self, x: f64, y: f64, idx: usize) -> geozero::error::Result<()> {
-        self.current_feature.xy(x, y, idx)
+    /// Computes the bounding box of every feature while its coordinates are processed.
+    ///
+    /// The box is available via [`Feature::bbox`], as a synthetic `bbox: [f64; 4]` field
+    /// (`[min_x, min_y, max_x, max_y]`) and, merged over all features, via
+    /// [`GeozeroCollector::envelope`].
+    #[must_use]
+    pub fn compute_bbox(mut self, compute_bbox: bool) -> Self {
+        self.compute_bbox = compute_bbox;
+        self
     }
 
-    fn coordinate(
-        &mut self,
-        x: f64,
-        y: f64,
-        z: Option<f64>,
-        m: Option<f64>,
-        t: Option<f64>,
-        tm: Option<u64>,
-        idx: usize,
-    ) -> geozero::error::Result<()> {
-        self.current_feature.coordinate(x, y, z, m, t, tm, idx)
+    /// Checks every geometry against geo's validity rules (e.g. minimum point counts, closed and
+    /// non self-intersecting rings, holes inside their exterior) before it is deserialized.
+    ///
+    /// Invalid geometries raise [`Error::InvalidGeometry`], which is handled like any other
+    /// feature that can't be read, see [`DeserializeOptions::error_policy`]. Ring orientation
+    /// isn't part of these rules.
+    #[must_use]
+    pub fn validate_geometries(mut self, validate_geometries: bool) -> Self {
+        self.validate_geometries = validate_geometries;
+        self
     }
 
-    fn empty_point(&mut self, idx: usize) -> geozero::error::Result<()> {
-        self.current_feature.empty_point(idx)
+    /// Selects how NaN and infinite coordinates and property values are handled, see
+    /// [`crate::finite`].
+    #[must_use]
+    pub fn non_finite(mut self, non_finite: NonFinite) -> Self {
+        self.non_finite = non_finite;
+        self
     }
 
-    fn point_begin(&mut self, idx: usize) -> geozero::error::Result<()> {
-        self.current_feature.point_begin(idx)
+    /// Sets how coordinates are rounded for geometry fields that don't use `f64`.
+    ///
+    /// See [`crate::coord`] for details.
+    #[must_use]
+    pub fn coord_rounding(mut self, coord_rounding: Rounding) -> Self {
+        self.coord_rounding = coord_rounding;
+        self
     }
 
-    fn point_end(&mut self, idx: usize) -> geozero::error::Result<()> {
-        self.current_feature.point_end(idx)
+    /// Converts property names to `property_case` before they are matched with struct fields.
+    ///
+    /// See [`crate::case`]. Lookups refer to the converted names.
+    #[must_use]
+    pub fn property_case(mut self, property_case: PropertyCase) -> Self {
+        self.property_case = property_case;
+        self
+    }
+
+    /// Only reads the properties the target struct has fields for, enabled by default.
+    ///
+    /// The field names are taken from the struct's `Deserialize` implementation, so wide
+    /// attribute tables with thousands of columns only cost memory for the columns that are
+    /// used. Types the field names can't be determined for, e.g. [`Feature`], maps or structs
+    /// with `#[serde(flatten)]` fields, always receive all properties, as do the rules of an
+    /// attached [`crate::quality::QualityChecker`] unless [`DeserializeOptions::columns`] are
+    /// selected. Error callbacks only see the kept properties.
+    ///
+    /// Dropped properties never reach serde, so `#[serde(deny_unknown_fields)]` has no effect.
+    /// Use [`DeserializeOptions::strict`] or disable the projection instead.
+    #[must_use]
+    pub fn project_properties(mut self, project_properties: bool) -> Self {
+        self.keep_all_properties = !project_properties;
+        self
+    }
+
+    /// Only reads the properties in `columns`, also for targets whose fields can't be
+    /// determined, like [`Feature`]s, maps or nested properties.
+    ///
+    /// Replaces the projection of [`DeserializeOptions::project_properties`], names are matched
+    /// after applying [`DeserializeOptions::property_case`]. Once a feature had all of them, the
+    /// datasource is asked to skip its remaining properties, which e.g. `FlatGeobuf` supports,
+    /// unless [`DeserializeOptions::strict`] has to see every property. The keys of lookups and
+    /// [`Dedup::ByKey`] are read as well, names of fields not backed by properties, like
+    /// `geometry` or `bbox`, are ignored.
+    #[must_use]
+    pub fn columns(mut self, columns: &[&str]) -> Self {
+        self.columns = Some(columns.iter().map(ToString::to_string).collect());
+        self
+    }
+
+    /// Selects where the target struct keeps the properties.
+    ///
+    /// With [`FeatureLayout::Nested`] the nested fields aren't known in advance, so
+    /// [`DeserializeOptions::project_properties`] and [`DeserializeOptions::strict`] have no
+    /// effect.
+    ///
+    /// ```
+    /// use geo::Geometry;
+    /// use serde::Deserialize;
+    /// use serde_geozero::de::FeatureLayout;
+    /// use serde_geozero::{from_datasource_with_options, DeserializeOptions};
+    ///
+    /// #[derive(Deserialize)]
+    /// struct City {
+    ///     geometry: Geometry,
+    ///     properties: CityProperties,
+    /// }
+    ///
+    /// #[derive(Deserialize)]
+    /// struct CityProperties {
+    ///     name: String,
+    /// }
+    ///
+    /// let geojson = r#"{"type": "Feature", "geometry": {"type": "Point", "coordinates": [13.4, 52.5]},
+    ///     "properties": {"name": "Berlin"}}"#;
+    ///
+    /// let options = DeserializeOptions::new().layout(FeatureLayout::Nested);
+    /// let mut reader = geozero::geojson::GeoJsonReader(geojson.as_bytes());
+    /// let cities: Vec<City> = from_datasource_with_options(&mut reader, &options).unwrap();
+    /// assert_eq!(cities[0].properties.name, "Berlin");
+    /// ```
+    #[must_use]
+    pub fn layout(mut self, layout: FeatureLayout) -> Self {
+        self.layout = layout;
+        self
     }
 
-    fn multipoint_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
-        self.current_feature.multipoint_begin(size, idx)
+    /// Selects what [`from_datasource_keyed`] does with features sharing a key.
+    #[must_use]
+    pub fn key_collision(mut self, key_collision: KeyCollision) -> Self {
+        self.key_collision = key_collision;
+        self
     }
 
-    fn multipoint_end(&mut self, idx: usize) -> geozero::error::Result<()> {
-        self.current_feature.multipoint_end(idx)
+    /// Reports properties the target struct has no field for and fields without a column.
+    ///
+    /// See [`StrictMode`] for details.
+    #[must_use]
+    pub fn strict(mut self, strict: StrictMode) -> Self {
+        self.strict = strict;
+        self
     }
 
-    fn linestring_begin(
-        &mut self,
-        tagged: bool,
-        size: usize,
-        idx: usize,
-    ) -> geozero::error::Result<()> {
-        self.current_feature.linestring_begin(tagged, size, idx)
+    /// Drops duplicate features while reading, e.g. where merged tiles overlap at their seams.
+    ///
+    /// The first feature is kept, the number of dropped ones is reported by
+    /// [`GeozeroCollector::duplicates`]. Features are compared before they are deserialized,
+    /// after reprojection and before lookups.
+    #[must_use]
+    pub fn dedup(mut self, dedup: Dedup) -> Self {
+        self.dedup = dedup;
+        self
     }
 
-    fn linestring_end(&mut self, tagged: bool, idx: usize) -> geozero::error::Result<()> {
-        self.current_feature.linestring_end(tagged, idx)
+    /// Fails with [`Error::TooManyProperties`] instead of reading features with more than
+    /// `max_properties` properties, after projecting them to the requested fields.
+    ///
+    /// Guards against datasets that would otherwise exhaust memory. There is no limit by default.
+    #[must_use]
+    pub fn max_properties(mut self, max_properties: usize) -> Self {
+        self.max_properties = Some(max_properties);
+        self
+    }
+
+    /// Selects the variant of externally tagged enums by the value of the property `tag`.
+    ///
+    /// The property is removed and the remaining feature is deserialized into the variant, so
+    /// `enum Layer { Road(Road), Building(Building) }` reads features with `"kind": "Road"`
+    /// into `Layer::Road`. Internally tagged enums (`#[serde(tag = "kind")]`) work without this
+    /// option.
+    ///
+    /// ```
+    /// use geo::Geometry;
+    /// use serde::Deserialize;
+    /// use serde_geozero::{from_datasource_with_options, DeserializeOptions};
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Road {
+    ///     geometry: Geometry,
+    ///     lanes: u8,
+    /// }
+    ///
+    /// #[derive(Deserialize)]
+    /// enum Layer {
+    ///     Road(Road),
+    ///     Poi { geometry: Geometry, name: String },
+    /// }
+    ///
+    /// let geojson = r#"{"type": "FeatureCollection", "features": [
+    ///     {"type": "Feature", "properties": {"kind": "Road", "lanes": 2},
+    ///      "geometry": {"type": "Point", "coordinates": [1, 1]}},
+    ///     {"type": "Feature", "properties": {"kind": "Poi", "name": "Zoo"},
+    ///      "geometry": {"type": "Point", "coordinates": [2, 2]}}
+    /// ]}"#;
+    ///
+    /// let options = DeserializeOptions::new().enum_tag("kind");
+    /// let mut reader = geozero::geojson::GeoJsonReader(geojson.as_bytes());
+    /// let layers: Vec<Layer> = from_datasource_with_options(&mut reader, &options).unwrap();
+    /// assert!(matches!(&layers[1], Layer::Poi { name, .. } if name == "Zoo"));
+    /// ```
+    #[must_use]
+    pub fn enum_tag(mut self, tag: &str) -> Self {
+        self.enum_tag = Some(tag.into());
+        self
     }
 
-    fn multilinestring_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
-        self.current_feature.multilinestring_begin(size, idx)
+    /// Decodes the property `column` as a geometry encoded with `encoding`, see
+    /// [`crate::encoded`].
+    #[must_use]
+    pub fn geometry_column(mut self, column: &str, encoding: GeometryEncoding) -> Self {
+        self.geometry_columns.push((column.to_string(), encoding));
+        self
     }
 
-    fn multilinestring_end(&mut self, idx: usize) -> geozero::error::Result<()> {
-        self.current_feature.multilinestring_end(idx)
+    /// Parses JSON columns for fields that aren't strings, enabled by default.
+    ///
+    /// When disabled, JSON columns are handed to serde as raw strings.
+    #[must_use]
+    pub fn parse_json(mut self, parse_json: bool) -> Self {
+        self.raw_json = !parse_json;
+        self
+    }
+
+    /// Converts property values to the type of the field they are read into where that is
+    /// lossless, disabled by default.
+    ///
+    /// Numeric strings go into number fields, numbers and booleans into `String` fields and `0`,
+    /// `1`, `"true"` or `"false"` into `bool` fields, which helps with stringly-typed sources
+    /// like DBF tables or CSV files.
+    ///
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_geozero::{from_datasource_with_options, DeserializeOptions};
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Parcel {
+    ///     id: i64,
+    ///     zone: String,
+    ///     built: bool,
+    /// }
+    ///
+    /// let geojson = r#"{"type": "Feature", "geometry": {"type": "Point", "coordinates": [1, 2]},
+    ///     "properties": {"id": "42", "zone": 7, "built": 1}}"#;
+    /// let options = DeserializeOptions::new().coerce_types(true);
+    /// let mut reader = geozero::geojson::GeoJsonReader(geojson.as_bytes());
+    /// let parcels: Vec<Parcel> = from_datasource_with_options(&mut reader, &options).unwrap();
+    /// assert_eq!((parcels[0].id, parcels[0].zone.as_str(), parcels[0].built), (42, "7", true));
+    /// ```
+    #[must_use]
+    pub fn coerce_types(mut self, coerce_types: bool) -> Self {
+        self.coerce_types = coerce_types;
+        self
+    }
+
+    /// Whether property values are read as human readable, the default.
+    ///
+    /// Types like `Ipv4Addr` or `uuid::Uuid` check
+    /// [`Deserializer::is_human_readable`](serde::Deserializer::is_human_readable) to parse text
+    /// or bytes. Disable it to read values written with
+    /// [`SerializeOptions::human_readable`](crate::SerializeOptions::human_readable) disabled,
+    /// e.g. from `FlatGeobuf` binary columns.
+    #[must_use]
+    pub fn human_readable(mut self, human_readable: bool) -> Self {
+        self.compact = !human_readable;
+        self
     }
 
-    fn polygon_begin(
-        &mut self,
-        tagged: bool,
-        size: usize,
-        idx: usize,
-    ) -> geozero::error::Result<()> {
-        self.current_feature.polygon_begin(tagged, size, idx)
+    /// Shares one allocation between all string properties with the same text.
+    ///
+    /// Strings are kept as [`Value::Shared`], which saves memory for categorical columns when
+    /// features are collected as [`Feature`]s or into `Value` fields. Fields of type `String`
+    /// still get their own copy.
+    #[must_use]
+    pub fn intern_strings(mut self, intern_strings: bool) -> Self {
+        self.intern_strings = intern_strings;
+        self
     }
 
-    fn polygon_end(&mut self, tagged: bool, idx: usize) -> geozero::error::Result<()> {
-        self.current_feature.polygon_end(tagged, idx)
+    /// Merges the properties found in `lookup` into every feature before it is deserialized.
+    ///
+    /// Lookups are applied in the order they were added, see [`crate::lookup`].
+    #[must_use]
+    pub fn lookup(mut self, lookup: Lookup) -> Self {
+        self.lookups.push(lookup);
+        self
+    }
+
+    /// Selects what happens with features that can't be read.
+    ///
+    /// Covers features without a geometry and features that can't be deserialized into the
+    /// target type. [`DeserializeOptions::on_error`] takes precedence if both are set.
+    ///
+    /// ```
+    /// use geo::Geometry;
+    /// use serde::Deserialize;
+    /// use serde_geozero::error::ErrorPolicy;
+    /// use serde_geozero::{from_datasource_with_options, DeserializeOptions};
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Place {
+    ///     geometry: Geometry,
+    ///     population: u32,
+    /// }
+    ///
+    /// let geojson = r#"{"type": "FeatureCollection", "features": [
+    ///     {"type": "Feature", "properties": {"population": "unknown"},
+    ///      "geometry": {"type": "Point", "coordinates": [1, 1]}},
+    ///     {"type": "Feature", "properties": {"population": 10}, "geometry": null},
+    ///     {"type": "Feature", "properties": {"population": 20},
+    ///      "geometry": {"type": "Point", "coordinates": [2, 2]}}
+    /// ]}"#;
+    ///
+    /// let options = DeserializeOptions::new()
+    ///     .error_policy(ErrorPolicy::SkipWithCallback(|err| eprintln!("skipped: {}", err.error)));
+    /// let mut reader = geozero::geojson::GeoJsonReader(geojson.as_bytes());
+    /// let places: Vec<Place> = from_datasource_with_options(&mut reader, &options).unwrap();
+    /// assert_eq!(places.len(), 1);
+    /// ```
+    #[must_use]
+    pub fn error_policy(mut self, error_policy: ErrorPolicy) -> Self {
+        self.error_policy = error_policy;
+        self
+    }
+
+    /// Calls `on_feature` with the index of every feature before it is read, e.g. to show
+    /// progress. Returning [`ControlFlow::Break`] stops reading with [`Error::Cancelled`]:
+    ///
+    /// ```
+    /// use std::ops::ControlFlow;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// use serde_geozero::de::Feature;
+    /// use serde_geozero::error::Error;
+    /// use serde_geozero::{from_datasource_with_options, DeserializeOptions};
+    ///
+    /// let file = std::fs::File::open("test-data/countries.fgb").unwrap();
+    /// let mut features = flatgeobuf::FgbReader::open(file).unwrap().select_all().unwrap();
+    ///
+    /// let progress = Arc::new(AtomicUsize::new(0));
+    /// let seen = Arc::clone(&progress);
+    /// let options = DeserializeOptions::new().on_feature(move |idx| {
+    ///     seen.store(idx, Ordering::Relaxed);
+    ///     if idx == 10 {
+    ///         ControlFlow::Break(())
+    ///     } else {
+    ///         ControlFlow::Continue(())
+    ///     }
+    /// });
+    /// let result = from_datasource_with_options::<Feature, _>(&mut features, &options);
+    /// assert!(matches!(result, Err(Error::Cancelled(10))));
+    /// assert_eq!(progress.load(Ordering::Relaxed), 10);
+    /// ```
+    ///
+    /// Features collected before the cancellation stay in the
+    /// [`GeozeroCollector`] if one is used directly.
+    #[must_use]
+    pub fn on_feature(
+        mut self,
+        on_feature: impl Fn(usize) -> ControlFlow<()> + Send + Sync + 'static,
+    ) -> Self {
+        self.on_feature = Some(ProgressCallback(Arc::new(on_feature)));
+        self
+    }
+
+    /// Lets `on_error` decide how to continue when a feature cannot be read.
+    ///
+    /// The callback receives the error together with the raw [`Feature`]. It may modify the
+    /// feature before answering [`ErrorDecision::Retry`], e.g. to repair a property. Features
+    /// without a geometry are passed with [`Error::MissingGeometry`] and an empty geometry
+    /// collection.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geo::Geometry;
+    /// use serde::Deserialize;
+    /// use serde_geozero::error::{ErrorDecision, FeatureError};
+    /// use serde_geozero::value::Value;
+    /// use serde_geozero::{from_datasource_with_options, DeserializeOptions};
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Place {
+    ///     geometry: Geometry,
+    ///     population: u32,
+    /// }
+    ///
+    /// fn repair(err: &mut FeatureError) -> ErrorDecision {
+    ///     match err.feature.properties.get("population") {
+    ///         Some(Value::String(_)) => {
+    ///             err.feature.properties.insert("population".to_string(), 0.into());
+    ///             ErrorDecision::Retry
+    ///         }
+    ///         _ => ErrorDecision::Skip,
+    ///     }
+    /// }
+    ///
+    /// let geojson = r#"{"type": "FeatureCollection", "features": [
+    ///     {"type": "Feature", "properties": {"population": "unknown"},
+    ///      "geometry": {"type": "Point", "coordinates": [1, 1]}},
+    ///     {"type": "Feature", "properties": {},
+    ///      "geometry": {"type": "Point", "coordinates": [2, 2]}}
+    /// ]}"#;
+    ///
+    /// let mut reader = geozero::geojson::GeoJsonReader(geojson.as_bytes());
+    /// let options = DeserializeOptions::new().on_error(repair);
+    /// let places: Vec<Place> = from_datasource_with_options(&mut reader, &options).unwrap();
+    /// assert_eq!(places.len(), 1);
+    /// assert_eq!(places[0].population, 0);
+    /// ```
+    #[must_use]
+    pub fn on_error(mut self, on_error: ErrorCallback) -> Self {
+        self.on_error = Some(on_error);
+        self
     }
 
-    fn multipolygon_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
-        self.current_feature.multipolygon_begin(size, idx)
+    /// Transforms all geometries from the `from` to the `to` CRS while reading,
+    /// e.g. `reproject("EPSG:3857", "EPSG:4326")`.
+    ///
+    /// Bounding boxes are computed from the transformed geometries. The SRID of every feature is
+    /// the code of `to` if it is given as `EPSG:<code>`, `None` otherwise.
+    #[cfg(feature = "proj")]
+    #[must_use]
+    pub fn reproject(mut self, from: &str, to: &str) -> Self {
+        self.reproject = Some((from.to_string(), to.to_string()));
+        self
     }
+}
 
-    fn multipolygon_end(&mut self, idx: usize) -> geozero::error::Result<()> {
-        self.current_feature.multipolygon_end(idx)
-    }
+/// Deserializes data from a `GeozeroDatasource` like [`from_datasource`], applying the given
+/// options.
+///
+/// # Errors
+///
+/// Returns the same errors as [`from_datasource`], and an error if the options cannot be
+/// applied (e.g. an unknown CRS).
+pub fn from_datasource_with_options<'de, T: Deserialize<'de> + 'static, S: GeozeroDatasource>(
+    processor: &mut S,
+    options: &DeserializeOptions,
+) -> Result<Vec<T>> {
+    let mut collector = DataSourceDeserializer::with_options(options)?;
+    collector.process(processor)?;
 
-    fn geometrycollection_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
-        self.current_feature.geometrycollection_begin(size, idx)
-    }
+    Ok(collector.features)
+}
 
-    fn geometrycollection_end(&mut self, idx: usize) -> geozero::error::Result<()> {
-        self.current_feature.geometrycollection_end(idx)
-    }
+/// Facts about a dataset gathered while deserializing it, see [`from_datasource_with_summary`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProcessSummary {
+    /// Number of features deserialized.
+    pub feature_count: usize,
+    /// The bounding box of all deserialized features, `None` if none had coordinates.
+    pub bbox: Option<Rect>,
+    /// The geometry types of the features, e.g. `"MultiPolygon"`, in the order they were first
+    /// seen. Features read without a geometry are left out.
+    pub geometry_types_seen: Vec<&'static str>,
+    /// The SRID of the first feature that had one, or the SRID of the target CRS when
+    /// reprojecting.
+    pub srid: Option<i32>,
+}
 
-    fn circularstring_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
-        self.current_feature.circularstring_begin(size, idx)
-    }
+/// Deserializes data from a `GeozeroDatasource` like [`from_datasource_with_options`] and
+/// returns a [`ProcessSummary`] of the features read, e.g. the extent a writer needs up front.
+///
+/// The envelope is computed from the geometries without enabling
+/// [`DeserializeOptions::compute_bbox`], after they were reprojected.
+///
+/// ```
+/// use geo::Geometry;
+/// use serde::Deserialize;
+/// use serde_geozero::de::from_datasource_with_summary;
+/// use serde_geozero::DeserializeOptions;
+///
+/// #[derive(Deserialize)]
+/// struct City {
+///     geometry: Geometry,
+///     name: String,
+/// }
+///
+/// let geojson = r#"{"type": "FeatureCollection", "features": [
+///     {"type": "Feature", "properties": {"name": "Berlin"},
+///         "geometry": {"type": "Point", "coordinates": [13.4, 52.5]}},
+///     {"type": "Feature", "properties": {"name": "Hamburg"},
+///         "geometry": {"type": "Point", "coordinates": [10.0, 53.6]}}]}"#;
+/// let mut reader = geozero::geojson::GeoJsonReader(geojson.as_bytes());
+/// let (cities, summary) =
+///     from_datasource_with_summary::<City, _>(&mut reader, &DeserializeOptions::new()).unwrap();
+/// assert_eq!(summary.feature_count, cities.len());
+/// assert_eq!(summary.bbox.unwrap().min().x, 10.0);
+/// assert_eq!(summary.geometry_types_seen, ["Point"]);
+/// ```
+///
+/// # Errors
+///
+/// Returns the same errors as [`from_datasource_with_options`].
+pub fn from_datasource_with_summary<'de, T: Deserialize<'de> + 'static, S: GeozeroDatasource>(
+    processor: &mut S,
+    options: &DeserializeOptions,
+) -> Result<(Vec<T>, ProcessSummary)> {
+    let mut collector = DataSourceDeserializer::with_options(options)?.with_envelope();
+    collector.process(processor)?;
+
+    let summary = ProcessSummary {
+        feature_count: collector.len(),
+        bbox: collector.envelope(),
+        geometry_types_seen: collector.geometry_types().to_vec(),
+        srid: collector.output_srid(),
+    };
+    Ok((collector.into_features(), summary))
+}
 
-    fn circularstring_end(&mut self, idx: usize) -> geozero::error::Result<()> {
-        self.current_feature.circularstring_end(idx)
-    }
+/// Deserializes the features of all `sources` into a single `Vec`, in the order of the sources.
+///
+/// Use a [`MergeSource`](crate::merge::MergeSource) to rename columns per source or to tag
+/// every feature with the index of its source.
+///
+/// # Errors
+///
+/// Returns the same errors as [`from_datasource_with_options`], for the first source that
+/// fails. Feature indices in errors count across all sources.
+pub fn from_datasources<'de, T: Deserialize<'de> + 'static, S: GeozeroDatasource>(
+    sources: &mut [S],
+    options: &DeserializeOptions,
+) -> Result<Vec<T>> {
+    from_datasource_with_options(&mut crate::merge::MergeSource::new(sources), options)
+}
 
-    fn compoundcurve_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
-        self.current_feature.compoundcurve_begin(size, idx)
-    }
+/// Deserializes the features of `processor` in chunks of `chunk_size`, handing each chunk to
+/// `callback` before the next one is read.
+///
+/// The buffer is reused between chunks, so memory stays bounded whatever the size of the
+/// dataset, e.g. to insert every chunk into a database. The callback may move the features out
+/// with `chunk.drain(..)`, the last chunk may be smaller. See
+/// [`GeozeroCollector::with_chunks`](crate::collector::GeozeroCollector::with_chunks).
+///
+/// ```
+/// use serde::Deserialize;
+/// use serde_geozero::de::from_datasource_chunks;
+/// use serde_geozero::DeserializeOptions;
+///
+/// #[derive(Deserialize)]
+/// struct Country {
+///     name: String,
+/// }
+///
+/// # #[cfg(feature = "flatgeobuf")]
+/// # {
+/// let file = std::fs::File::open("test-data/countries.fgb").unwrap();
+/// let mut features = flatgeobuf::FgbReader::open(file).unwrap().select_all().unwrap();
+/// let mut names = Vec::new();
+/// from_datasource_chunks(&mut features, 100, &DeserializeOptions::new(), |chunk: &mut Vec<Country>| {
+///     assert!(chunk.len() <= 100);
+///     names.extend(chunk.drain(..).map(|country| country.name));
+///     Ok(())
+/// })
+/// .unwrap();
+/// assert_eq!(names.len(), 179);
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// Returns the same errors as [`from_datasource_with_options`] and the first error returned by
+/// `callback`, which stops reading.
+pub fn from_datasource_chunks<'de, T, S, F>(
+    processor: &mut S,
+    chunk_size: usize,
+    options: &DeserializeOptions,
+    callback: F,
+) -> Result<()>
+where
+    T: Deserialize<'de> + 'static,
+    S: GeozeroDatasource,
+    F: FnMut(&mut Vec<T>) -> Result<()> + 'de,
+{
+    DataSourceDeserializer::with_options(options)?
+        .with_chunks(chunk_size, callback)
+        .process(processor)
+}
 
-    fn compoundcurve_end(&mut self, idx: usize) -> geozero::error::Result<()> {
-        self.current_feature.compoundcurve_end(idx)
-    }
+/// Deserializes the features of `processor` with a [`DeserializeSeed`](serde::de::DeserializeSeed)
+/// created by `make_seed` for every feature.
+///
+/// Seeds carry state into deserialization, e.g. a string interner, an arena or a schema only
+/// known at runtime. Every feature is deserialized as soon as it was read. Errors of a seed are
+/// handled by [`DeserializeOptions::error_policy`] and [`DeserializeOptions::on_error`] like
+/// those of any other feature, and a retried feature gets a new seed.
+///
+/// ```
+/// use std::fmt;
+///
+/// use serde::de::{DeserializeSeed, Deserializer, MapAccess, Visitor};
+/// use serde_geozero::de::from_datasource_seed;
+/// use serde_geozero::value::Value;
+/// use serde_geozero::DeserializeOptions;
+///
+/// /// Reads the columns named at runtime, in their order.
+/// struct Columns<'a>(&'a [&'a str]);
+///
+/// impl<'de> DeserializeSeed<'de> for Columns<'_> {
+///     type Value = Vec<Value>;
+///
+///     fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+///         deserializer.deserialize_map(self)
+///     }
+/// }
+///
+/// impl<'de> Visitor<'de> for Columns<'_> {
+///     type Value = Vec<Value>;
+///
+///     fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+///         formatter.write_str("a feature")
+///     }
+///
+///     fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+///         let mut row = vec![Value::Null; self.0.len()];
+///         while let Some(key) = map.next_key::<String>()? {
+///             match self.0.iter().position(|column| *column == key) {
+///                 Some(position) => row[position] = map.next_value()?,
+///                 None => map.next_value::<serde::de::IgnoredAny>().map(|_| ())?,
+///             }
+///         }
+///         Ok(row)
+///     }
+/// }
+///
+/// let geojson = r#"{"type": "Feature", "properties": {"name": "Berlin", "population": 3669495},
+///     "geometry": {"type": "Point", "coordinates": [13.4, 52.5]}}"#;
+/// let columns = ["population", "name"];
+/// let mut reader = geozero::geojson::GeoJsonReader(geojson.as_bytes());
+/// let rows =
+///     from_datasource_seed(&mut reader, &DeserializeOptions::new(), || Columns(&columns)).unwrap();
+/// assert_eq!(rows[0], [Value::Long(3_669_495), Value::from("Berlin")]);
+/// ```
+///
+/// # Errors
+///
+/// Returns the same errors as [`from_datasource_with_options`], including [`Error::Feature`]
+/// with the error of a seed that fails.
+pub fn from_datasource_seed<'de, D, F, S>(
+    processor: &mut S,
+    options: &DeserializeOptions,
+    make_seed: F,
+) -> Result<Vec<D::Value>>
+where
+    D: serde::de::DeserializeSeed<'de>,
+    F: FnMut() -> D,
+    S: GeozeroDatasource,
+{
+    let seeded = Seeded(make_seed, PhantomData);
+    let mut collector =
+        GeozeroCollector::with_options_and_converter(options, None, &[], Box::new(seeded))?;
+    collector.process(processor)?;
+    Ok(collector.features)
+}
 
-    fn curvepolygon_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
-        self.current_feature.curvepolygon_begin(size, idx)
-    }
+/// Deserializes every feature with a new seed made by `F`.
+struct Seeded<'de, F, D>(F, PhantomData<fn(&'de ()) -> D>);
 
-    fn curvepolygon_end(&mut self, idx: usize) -> geozero::error::Result<()> {
-        self.current_feature.curvepolygon_end(idx)
+impl<'de, F, D> Convert<D::Value> for Seeded<'de, F, D>
+where
+    D: serde::de::DeserializeSeed<'de>,
+    F: FnMut() -> D,
+{
+    fn convert(&mut self, feature: Feature) -> Result<Option<D::Value>> {
+        (self.0)().deserialize(feature).map(Some)
     }
 
-    fn multicurve_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
-        self.current_feature.multicurve_begin(size, idx)
+    fn try_convert(&mut self, feature: Feature) -> Converted<D::Value> {
+        match (self.0)().deserialize(FeatureRef::new(&feature)) {
+            Ok(value) => Ok(Some(value)),
+            Err(err) => Err(Box::new((err, feature))),
+        }
     }
+}
 
-    fn multicurve_end(&mut self, idx: usize) -> geozero::error::Result<()> {
-        self.current_feature.multicurve_end(idx)
-    }
+/// Deserializes every feature of `processor` into both `A` and `B` in a single pass.
+///
+/// The datasource is parsed once and each feature is shared by both targets, e.g. to read a
+/// light index record next to the full one. Use [`Both`] with a [`GeozeroCollector`] for more
+/// control or nest it for more than two targets.
+///
+/// ```
+/// use geo::Geometry;
+/// use serde::Deserialize;
+/// use serde_geozero::{de::from_datasource_split, DeserializeOptions};
+///
+/// #[derive(Deserialize)]
+/// struct Summary {
+///     name: String,
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct Detail {
+///     geometry: Geometry,
+///     name: String,
+///     population: i64,
+/// }
+///
+/// let geojson = r#"{"type": "Feature", "geometry": {"type": "Point", "coordinates": [13.4, 52.5]},
+///     "properties": {"name": "Berlin", "population": 3669495}}"#;
+///
+/// let mut reader = geozero::geojson::GeoJsonReader(geojson.as_bytes());
+/// let (summaries, details): (Vec<Summary>, Vec<Detail>) =
+///     from_datasource_split(&mut reader, &DeserializeOptions::new()).unwrap();
+/// assert_eq!(summaries[0].name, details[0].name);
+/// ```
+///
+/// # Errors
+///
+/// Returns the same errors as [`from_datasource_with_options`], for whichever target fails first.
+pub fn from_datasource_split<'de, A, B, S>(
+    processor: &mut S,
+    options: &DeserializeOptions,
+) -> Result<(Vec<A>, Vec<B>)>
+where
+    A: Deserialize<'de> + 'static,
+    B: Deserialize<'de> + 'static,
+    S: GeozeroDatasource,
+{
+    let both: Vec<Both<A, B>> = from_datasource_with_options(processor, options)?;
+    Ok(both.into_iter().map(|Both(a, b)| (a, b)).unzip())
+}
 
-    fn multisurface_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
-        self.current_feature.multisurface_begin(size, idx)
-    }
+/// Two values deserialized from the same feature, see [`from_datasource_split`].
+///
+/// Only [`Feature`] can be deserialized into `Both`. Since the target types differ, all
+/// properties are read, [`DeserializeOptions::project_properties`] and
+/// [`DeserializeOptions::strict`] have no effect.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Both<A, B>(pub A, pub B);
 
-    fn multisurface_end(&mut self, idx: usize) -> geozero::error::Result<()> {
-        self.current_feature.multisurface_end(idx)
-    }
+/// The name [`Feature`] recognizes [`Both`] by.
+const BOTH_NAME: &str = "serde_geozero::Both";
 
-    fn triangle_begin(
-        &mut self,
-        tagged: bool,
-        size: usize,
-        idx: usize,
-    ) -> geozero::error::Result<()> {
-        self.current_feature.triangle_begin(tagged, size, idx)
+impl<'de, A: Deserialize<'de>, B: Deserialize<'de>> Deserialize<'de> for Both<A, B> {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_tuple_struct(BOTH_NAME, 2, BothVisitor(PhantomData))
     }
+}
 
-    fn triangle_end(&mut self, tagged: bool, idx: usize) -> geozero::error::Result<()> {
-        self.current_feature.triangle_end(tagged, idx)
-    }
+struct BothVisitor<A, B>(PhantomData<(A, B)>);
 
-    fn polyhedralsurface_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
-        self.current_feature.polyhedralsurface_begin(size, idx)
-    }
+impl<'de, A: Deserialize<'de>, B: Deserialize<'de>> Visitor<'de> for BothVisitor<A, B> {
+    type Value = Both<A, B>;
 
-    fn polyhedralsurface_end(&mut self, idx: usize) -> geozero::error::Result<()> {
-        self.current_feature.polyhedralsurface_end(idx)
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a feature")
     }
 
-    fn tin_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
-        self.current_feature.tin_begin(size, idx)
+    fn visit_seq<S: SeqAccess<'de>>(
+        self,
+        mut seq: S,
+    ) -> std::result::Result<Self::Value, S::Error> {
+        let a = seq
+            .next_element()?
+            .ok_or_else(|| serde::de::Error::invalid_length(0, &self))?;
+        let b = seq
+            .next_element()?
+            .ok_or_else(|| serde::de::Error::invalid_length(1, &self))?;
+        Ok(Both(a, b))
     }
+}
 
-    fn tin_end(&mut self, idx: usize) -> geozero::error::Result<()> {
-        self.current_feature.tin_end(idx)
-    }
+/// A feature read into the enum variant named by its tag property.
+struct FeatureVariant<F> {
+    variant: String,
+    feature: F,
+}
 
-    fn pre_process_xy<F: Fn(&mut f64, &mut f64)>(
-        self,
-        transform_xy: F,
-    ) -> geozero::WrappedXYProcessor<Self, F>
+impl<'de, F: VariantAccess<'de, Error = Error>> EnumAccess<'de> for FeatureVariant<F> {
+    type Error = Error;
+    type Variant = F;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, F)>
     where
-        Self: Sized,
+        V: serde::de::DeserializeSeed<'de>,
     {
-        geozero::WrappedXYProcessor::new(self, transform_xy)
+        let variant = seed.deserialize(StringDeserializer::<Error>::new(self.variant))?;
+        Ok((variant, self.feature))
     }
 }
 
-impl<'de, T: Deserialize<'de>> FeatureProcessor for DataSourceDeserializer<'de, T> {
-    fn feature_begin(&mut self, _idx: u64) -> geozero::error::Result<()> {
-        self.current_feature = GeozeroFeature::new();
-        Ok(())
+/// The variant named by the tag property `tag`, whose value is `value`.
+fn variant_name(tag: &str, value: Option<&Value>) -> Result<String> {
+    match value {
+        Some(Value::String(variant)) => Ok(variant.clone()),
+        Some(Value::Shared(variant)) => Ok(variant.to_string()),
+        Some(Value::Null) | None => Err(Error::MissingDiscriminator(tag.to_string())),
+        Some(value) => Ok(value.to_string()),
     }
+}
 
-    fn feature_end(&mut self, _idx: u64) -> geozero::error::Result<()> {
-        let geo_feature: Feature = Feature::try_from(&mut self.current_feature)?;
-        self.features.push(
-            T::deserialize(geo_feature).map_err(|err| GeozeroError::Feature(err.to_string()))?,
-        );
+fn missing_enum_tag(name: &str) -> Error {
+    Error::Message(format!(
+        "set DeserializeOptions::enum_tag to read features into enum {name}"
+    ))
+}
+
+fn unsupported_tuple_variant() -> Error {
+    Error::Message("features can't be read into tuple variants".to_string())
+}
+
+impl<'de> VariantAccess<'de> for Feature {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
         Ok(())
     }
-}
 
-#[derive(Serialize, Deserialize)]
-pub struct Feature {
-    pub geometry: Geometry,
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
+    where
+        T: serde::de::DeserializeSeed<'de>,
+    {
+        seed.deserialize(self)
+    }
 
-    #[serde(flatten)]
-    pub properties: HashMap<String, Value>,
+    fn tuple_variant<V>(self, _len: usize, _visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        Err(unsupported_tuple_variant())
+    }
 
-    // fields necessary for serde
-    #[serde(skip)]
-    map_keys: Vec<String>,
+    fn struct_variant<V>(self, fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.visit_entries(Some(fields), visitor)
+    }
+}
 
-    #[serde(skip)]
-    current_col: Option<String>,
+/// Hands out a feature `remaining` times, cloning all but the last copy.
+struct FeatureCopies<F> {
+    feature: Option<F>,
+    remaining: usize,
 }
 
-impl Feature {
-    #[must_use]
-    pub fn new(geometry: Geometry, properties: HashMap<String, Value>) -> Self {
-        let map_keys = properties.keys().cloned().collect();
+impl<'de, F> SeqAccess<'de> for FeatureCopies<F>
+where
+    F: serde::de::Deserializer<'de, Error = Error> + Clone,
+{
+    type Error = Error;
 
-        Self {
-            geometry,
-            properties,
-            current_col: None,
-            map_keys,
-        }
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: serde::de::DeserializeSeed<'de>,
+    {
+        self.remaining = self.remaining.saturating_sub(1);
+        let feature = match (self.remaining, &self.feature) {
+            (_, None) => return Ok(None),
+            (0, Some(_)) => self.feature.take(),
+            (_, Some(feature)) => Some(feature.clone()),
+        };
+        feature.map(|feature| seed.deserialize(feature)).transpose()
     }
 }
 
-pub struct GeozeroFeature {
-    current_properties: HashMap<String, Value>,
-    current_geometry: GeoWriter,
+/// Reads the geometries of a datasource, ignoring any properties.
+///
+/// Works for bare geometry streams like WKT or WKB readers as well as for feature datasources.
+/// `G` is [`Geometry`] or one of the concrete `geo` types:
+///
+/// ```
+/// use geo::Point;
+/// use serde_geozero::de::geometry_from_datasource;
+///
+/// let mut wkt = geozero::wkt::WktStr("POINT(13.4 52.5)");
+/// let points: Vec<Point> = geometry_from_datasource(&mut wkt).unwrap();
+/// assert_eq!(points[0].x(), 13.4);
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if the datasource fails, or [`Error::Feature`] if a geometry can't be
+/// converted into `G`.
+pub fn geometry_from_datasource<G, S>(processor: &mut S) -> Result<Vec<G>>
+where
+    G: TryFrom<Geometry>,
+    G::Error: std::fmt::Display,
+    S: GeozeroDatasource,
+{
+    let mut collector = GeometryStream::default();
+    // `GeozeroDatasource::process_geom` drops geometry collection events
+    processor.process(&mut collector)?;
+    collector
+        .geometries
+        .into_iter()
+        .enumerate()
+        .map(|(index, geometry)| {
+            G::try_from(geometry).map_err(|err| Error::Feature {
+                index,
+                source: Box::new(Error::Message(err.to_string())),
+            })
+        })
+        .collect()
+}
+
+/// Splits the geometry events of a datasource into one geometry per top level geometry.
+#[derive(Default)]
+struct GeometryStream {
+    writer: geozero::geo_types::GeoWriter,
+    depth: usize,
+    geometries: Vec<Geometry>,
 }
 
-impl GeozeroFeature {
-    #[must_use]
-    pub fn new() -> Self {
-        Self {
-            current_properties: HashMap::new(),
-            current_geometry: GeoWriter::new(),
+impl GeometryStream {
+    fn begin(&mut self) {
+        self.depth += 1;
+    }
+
+    fn end(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
+        if self.depth == 0 {
+            self.geometries.extend(self.writer.take_geometry());
         }
     }
 }
 
-impl Default for GeozeroFeature {
-    fn default() -> Self {
-        Self::new()
-    }
+macro_rules! delegate_geometry {
+    ($($begin:ident($($arg:ident: $ty:ty),*) / $end:ident($($end_arg:ident: $end_ty:ty),*);)*) => {
+        $(
+            fn $begin(&mut self, $($arg: $ty),*) -> geozero::error::Result<()> {
+                self.begin();
+                self.writer.$begin($($arg),*)
+            }
+
+            fn $end(&mut self, $($end_arg: $end_ty),*) -> geozero::error::Result<()> {
+                self.writer.$end($($end_arg),*)?;
+                self.end();
+                Ok(())
+            }
+        )*
+    };
 }
 
-impl TryFrom<&mut GeozeroFeature> for Feature {
-    type Error = GeozeroError;
+impl GeomProcessor for GeometryStream {
+    fn xy(&mut self, x: f64, y: f64, idx: usize) -> geozero::error::Result<()> {
+        self.writer.xy(x, y, idx)
+    }
 
-    fn try_from(value: &mut GeozeroFeature) -> std::result::Result<Self, Self::Error> {
-        Ok(Feature::new(
-            value
-                .current_geometry
-                .take_geometry()
-                .ok_or(GeozeroError::Geometry(
-                    "Could not fetch geometry for feature".to_string(),
-                ))?,
-            std::mem::take(&mut value.current_properties),
-        ))
+    delegate_geometry! {
+        point_begin(idx: usize) / point_end(idx: usize);
+        multipoint_begin(size: usize, idx: usize) / multipoint_end(idx: usize);
+        linestring_begin(tagged: bool, size: usize, idx: usize)
+            / linestring_end(tagged: bool, idx: usize);
+        multilinestring_begin(size: usize, idx: usize) / multilinestring_end(idx: usize);
+        polygon_begin(tagged: bool, size: usize, idx: usize)
+            / polygon_end(tagged: bool, idx: usize);
+        multipolygon_begin(size: usize, idx: usize) / multipolygon_end(idx: usize);
+        geometrycollection_begin(size: usize, idx: usize) / geometrycollection_end(idx: usize);
     }
 }
 
-impl<'de> serde::de::Deserializer<'de> for Feature {
-    type Error = Error;
+impl PropertyProcessor for GeometryStream {}
 
-    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
-    where
-        V: serde::de::Visitor<'de>,
-    {
-        visitor.visit_map(self)
-    }
+impl geozero::FeatureProcessor for GeometryStream {}
 
-    // Forward all other methods to Value's deserializer
-    serde::forward_to_deserialize_any! {
-        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
-        bytes byte_buf option unit unit_struct newtype_struct seq tuple
-        tuple_struct map struct enum identifier ignored_any
+/// Deserializes the properties of every feature into `T`, next to its geometry.
+///
+/// `T` is a plain properties struct without a `geometry` field, so existing non-geospatial types
+/// can be reused. Synthetic fields like `srid` or `bbox` are offered to `T`.
+///
+/// ```
+/// use geo::Geometry;
+/// use serde::Deserialize;
+/// use serde_geozero::{de::from_datasource_pairs, DeserializeOptions};
+///
+/// #[derive(Deserialize)]
+/// struct City {
+///     name: String,
+/// }
+///
+/// let geojson = r#"{"type": "Feature", "geometry": {"type": "Point", "coordinates": [13.4, 52.5]},
+///     "properties": {"name": "Berlin"}}"#;
+///
+/// let mut reader = geozero::geojson::GeoJsonReader(geojson.as_bytes());
+/// let cities: Vec<(Geometry, City)> =
+///     from_datasource_pairs(&mut reader, &DeserializeOptions::new()).unwrap();
+/// assert_eq!(cities[0].1.name, "Berlin");
+/// ```
+///
+/// # Errors
+///
+/// Returns the same errors as [`from_datasource_with_options`].
+pub fn from_datasource_pairs<'de, T: Deserialize<'de> + 'static, S: GeozeroDatasource>(
+    processor: &mut S,
+    options: &DeserializeOptions,
+) -> Result<Vec<(Geometry, T)>> {
+    let pairs: Vec<Pair<T>> = from_datasource_with_options(processor, options)?;
+    Ok(pairs
+        .into_iter()
+        .map(|Pair(geometry, t)| (geometry, t))
+        .collect())
+}
+
+/// Deserializes the features of `processor` into a map keyed by `key`.
+///
+/// Features sharing a key are handled as set by [`DeserializeOptions::key_collision`].
+///
+/// ```
+/// use geo::Geometry;
+/// use serde::Deserialize;
+/// use serde_geozero::{de::from_datasource_keyed, DeserializeOptions};
+///
+/// #[derive(Deserialize)]
+/// struct Country {
+///     geometry: Geometry,
+///     id: String,
+///     name: String,
+/// }
+///
+/// let file = std::fs::File::open("test-data/countries.fgb").unwrap();
+/// let mut features = flatgeobuf::FgbReader::open(file).unwrap().select_all().unwrap();
+/// let countries =
+///     from_datasource_keyed(&mut features, &DeserializeOptions::new(), |country: &Country| {
+///         country.id.clone()
+///     })
+///     .unwrap();
+/// assert_eq!(countries["DEU"].name, "Germany");
+/// ```
+///
+/// # Errors
+///
+/// Returns the same errors as [`from_datasource_with_options`] and [`Error::DuplicateKey`] for
+/// the first shared key with [`KeyCollision::Error`].
+pub fn from_datasource_keyed<'de, K, T, S>(
+    processor: &mut S,
+    options: &DeserializeOptions,
+    key: impl Fn(&T) -> K,
+) -> Result<HashMap<K, T>>
+where
+    K: Eq + Hash + Debug,
+    T: Deserialize<'de> + 'static,
+    S: GeozeroDatasource,
+{
+    let features: Vec<T> = from_datasource_with_options(processor, options)?;
+    let mut keyed = HashMap::with_capacity(features.len());
+    for feature in features {
+        match keyed.entry(key(&feature)) {
+            Entry::Vacant(entry) => {
+                entry.insert(feature);
+            }
+            Entry::Occupied(mut entry) => match options.key_collision {
+                KeyCollision::Error => {
+                    return Err(Error::DuplicateKey(format!("{:?}", entry.key())))
+                }
+                KeyCollision::KeepFirst => {}
+                KeyCollision::KeepLast => {
+                    entry.insert(feature);
+                }
+            },
+        }
     }
+    Ok(keyed)
 }
 
-const GEOMETRY_COL: &str = "geometry";
+/// Deserializes the features of `processor` and joins them with the records of `table` whose
+/// key equals the property `key_column`.
+///
+/// This is the classic join of a shapefile with a CSV table: the key column is read from every
+/// feature, whether `T` has a field for it or not. Features without the column, with a null key
+/// or without a matching record are dropped by [`Join::Inner`] and kept with `None` by
+/// [`Join::Left`].
+///
+/// ```
+/// use geo::Geometry;
+/// use hashbrown::HashMap;
+/// use serde::Deserialize;
+/// use serde_geozero::de::{from_datasource_joined, Join};
+/// use serde_geozero::DeserializeOptions;
+///
+/// #[derive(Deserialize)]
+/// struct District {
+///     geometry: Geometry,
+///     name: String,
+/// }
+///
+/// struct Census {
+///     population: u32,
+/// }
+///
+/// let census = HashMap::from([(7, Census { population: 120_000 })]);
+/// let geojson = r#"{"type": "FeatureCollection", "features": [
+///     {"type": "Feature", "properties": {"name": "Mitte", "district_id": 7},
+///      "geometry": {"type": "Point", "coordinates": [13.4, 52.5]}},
+///     {"type": "Feature", "properties": {"name": "Spandau", "district_id": 5},
+///      "geometry": {"type": "Point", "coordinates": [13.2, 52.5]}}]}"#;
+/// let mut reader = geozero::geojson::GeoJsonReader(geojson.as_bytes());
+/// let joined: Vec<(District, Option<&Census>)> = from_datasource_joined(
+///     &mut reader,
+///     &DeserializeOptions::new(),
+///     "district_id",
+///     &census,
+///     Join::Left,
+/// )
+/// .unwrap();
+/// assert_eq!(joined[0].1.map(|census| census.population), Some(120_000));
+/// assert!(joined[1].1.is_none());
+/// ```
+///
+/// # Errors
+///
+/// Returns the same errors as [`from_datasource_with_options`], and [`Error::Feature`] wrapping
+/// [`Error::Field`] if the key column of a feature can't be deserialized into `K`. Key errors
+/// are handled by [`DeserializeOptions::error_policy`] like other errors of the feature.
+pub fn from_datasource_joined<'de, 't, T, K, U, S>(
+    processor: &mut S,
+    options: &DeserializeOptions,
+    key_column: &str,
+    table: &'t HashMap<K, U>,
+    join: Join,
+) -> Result<Vec<(T, Option<&'t U>)>>
+where
+    T: Deserialize<'de> + 'static,
+    K: serde::de::DeserializeOwned + Eq + Hash,
+    S: GeozeroDatasource,
+{
+    let joined = Joined {
+        key_column,
+        table,
+        join,
+        _phantom: PhantomData,
+    };
+    let mut collector = GeozeroCollector::with_options_and_converter(
+        options,
+        struct_fields::<T>(),
+        &[key_column],
+        Box::new(joined),
+    )?;
+    collector.process(processor)?;
+    Ok(collector.features)
+}
 
-impl<'de> MapAccess<'de> for Feature {
-    type Error = Error;
+/// Looks up the record of every feature and deserializes only the features
+/// [`from_datasource_joined`] keeps.
+struct Joined<'k, 't, 'de, T, K, U> {
+    key_column: &'k str,
+    table: &'t HashMap<K, U>,
+    join: Join,
+    _phantom: PhantomData<fn(&'de ()) -> T>,
+}
 
-    fn next_key_seed<K>(&mut self, seed: K) -> std::result::Result<Option<K::Value>, Self::Error>
+impl<'t, K, U, T> Joined<'_, 't, '_, T, K, U>
+where
+    K: serde::de::DeserializeOwned + Eq + Hash,
+{
+    /// The record of `feature`, `None` if the feature is left out.
+    fn record(&self, feature: &Feature) -> Result<Option<Option<&'t U>>> {
+        let record = feature
+            .get::<Option<K>>(self.key_column)?
+            .and_then(|key| self.table.get(&key));
+        Ok((record.is_some() || self.join == Join::Left).then_some(record))
+    }
+}
+
+impl<'de, 't, T, K, U> Convert<(T, Option<&'t U>)> for Joined<'_, 't, 'de, T, K, U>
+where
+    T: Deserialize<'de>,
+    K: serde::de::DeserializeOwned + Eq + Hash,
+{
+    fn convert(&mut self, feature: Feature) -> Result<Option<(T, Option<&'t U>)>> {
+        let Some(record) = self.record(&feature)? else {
+            return Ok(None);
+        };
+        T::deserialize(feature).map(|t| Some((t, record)))
+    }
+
+    fn try_convert(&mut self, feature: Feature) -> Converted<(T, Option<&'t U>)> {
+        let deserialized = self.record(&feature).and_then(|record| {
+            record
+                .map(|record| T::deserialize(FeatureRef::new(&feature)).map(|t| (t, record)))
+                .transpose()
+        });
+        deserialized.map_err(|err| Box::new((err, feature)))
+    }
+}
+
+/// The geometry of a feature and its properties deserialized into `T`.
+struct Pair<T>(Geometry, T);
+
+/// The name [`Feature`] recognizes [`Pair`] by.
+const PAIR_NAME: &str = "serde_geozero::Pair";
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Pair<T> {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
     where
-        K: serde::de::DeserializeSeed<'de>,
+        D: serde::Deserializer<'de>,
     {
-        // First return geometry field
-        if self.current_col.is_none() {
-            self.current_col = Some(GEOMETRY_COL.to_string());
-        } else {
-            self.current_col = self.map_keys.pop();
-        }
+        deserializer.deserialize_tuple_struct(PAIR_NAME, 2, PairVisitor(PhantomData))
+    }
+}
 
-        if let Some(col) = &self.current_col {
-            return seed
-                .deserialize(StringDeserializer::new(col.clone()))
-                .map(Some);
-        }
-        Ok(None)
+struct PairVisitor<T>(PhantomData<T>);
+
+impl<'de, T: Deserialize<'de>> Visitor<'de> for PairVisitor<T> {
+    type Value = Pair<T>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a feature")
     }
 
-    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    fn visit_seq<S: SeqAccess<'de>>(
+        self,
+        mut seq: S,
+    ) -> std::result::Result<Self::Value, S::Error> {
+        let geometry = seq
+            .next_element()?
+            .ok_or_else(|| serde::de::Error::invalid_length(0, &self))?;
+        let t = seq
+            .next_element()?
+            .ok_or_else(|| serde::de::Error::invalid_length(1, &self))?;
+        Ok(Pair(geometry, t))
+    }
+}
+
+/// Hands out the geometry of a feature and then the feature without it.
+struct GeometryThenProperties<'g, F> {
+    geometry: Option<(Cow<'g, Geometry>, Rounding)>,
+    properties: Option<F>,
+}
+
+impl<'de, F> SeqAccess<'de> for GeometryThenProperties<'_, F>
+where
+    F: serde::de::Deserializer<'de, Error = Error>,
+{
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
     where
-        V: serde::de::DeserializeSeed<'de>,
+        T: serde::de::DeserializeSeed<'de>,
     {
-        if self.current_col == Some(GEOMETRY_COL.to_string()) {
-            // Return geometry value
+        if let Some((geometry, rounding)) = self.geometry.take() {
             return seed
-                .deserialize(serde_json::to_value(&self.geometry).map_err(Error::SerdeError)?)
-                .map_err(Error::SerdeError);
+                .deserialize(FeatureGeometry::new(&geometry, rounding))
+                .map(Some);
         }
+        self.properties
+            .take()
+            .map(|properties| seed.deserialize(properties))
+            .transpose()
+    }
+}
 
-        if let Some(col) = &self.current_col {
-            if let Some(value) = self.properties.get(col) {
-                return seed.deserialize(value.clone()).map_err(Error::SerdeError);
-            }
+pub type DataSourceDeserializer<'de, T> = GeozeroCollector<'de, T>;
+pub use crate::collector::GeozeroFeature;
+
+#[derive(Clone, Debug)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct Feature {
+    pub geometry: Geometry,
+    pub properties: HashMap<String, Value>,
+    /// The property names in the order the datasource reported them, see [`Feature::iter`].
+    order: Vec<String>,
+    srid: Option<i32>,
+    zm: ZmValues,
+    bbox: Option<Rect>,
+    coord_rounding: Rounding,
+    raw_json: bool,
+    coerce_types: bool,
+    compact: bool,
+    layout: FeatureLayout,
+    enum_tag: Option<Arc<str>>,
+
+    /// Properties decoded as geometries, see [`DeserializeOptions::geometry_column`].
+    geometry_columns: Option<Arc<[(String, GeometryEncoding)]>>,
+
+    /// Set for the nested `properties` of [`FeatureLayout::Nested`], which have no geometry.
+    properties_only: bool,
+
+    // fields necessary for serde
+    map_entries: Vec<(Cow<'static, str>, Option<Value>)>,
+    pending_key: Option<Cow<'static, str>>,
+    pending_value: Option<FieldValue<'static>>,
+}
+
+/// Names the newtype struct a [`Feature`] asks its deserializer for.
+pub(crate) const FEATURE_NAME: &str = "serde_geozero::Feature";
+
+/// The fields any other deserializer is read into, the flat layout `Feature` derived before or
+/// the `GeoJSON` feature it serializes to.
+#[derive(Deserialize)]
+struct FeatureFields {
+    geometry: serde_json::Value,
+    #[serde(flatten)]
+    properties: HashMap<String, Value>,
+}
+
+impl FeatureFields {
+    fn into_feature<E: serde::de::Error>(mut self) -> std::result::Result<Feature, E> {
+        let geometry = decode_json(self.geometry)
+            .map_err(E::custom)?
+            .ok_or_else(|| E::custom("a feature without geometry"))?;
+        if matches!(self.properties.get("type"), Some(Value::String(kind)) if kind == "Feature") {
+            self.properties = match self.properties.remove(PROPERTIES_COL) {
+                Some(Value::Json(json)) => serde_json::from_str(&json).map_err(E::custom)?,
+                _ => HashMap::new(),
+            };
         }
+        Ok(Feature::new(geometry, self.properties))
+    }
+}
 
-        Err(Error::SerdeError(serde::de::Error::custom(
-            "no value found",
-        )))
+/// Writes a `GeoJSON` feature object with the properties sorted by name, e.g. for a
+/// `serde_json` based HTTP response. The SRID, Z/M values and bbox are not written.
+impl Serialize for Feature {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        GeoJsonFeature {
+            geometry: Some(&self.geometry),
+            properties: &self.properties,
+        }
+        .serialize(serializer)
     }
 }
 
-impl PropertyProcessor for GeozeroFeature {
-    fn property(
-        &mut self,
-        _idx: usize,
-        name: &str,
-        value: &ColumnValue,
-    ) -> geozero::error::Result<bool> {
-        self.current_properties.insert(
-            name.to_string(),
-            serde_json::to_value(ColumnValueSerializer(value))
-                .map_err(|err| GeozeroError::Property(err.to_string()))?,
-        );
-        Ok(false)
+/// The `GeoJSON` feature object [`Feature`] and [`GeozeroFeature`] serialize to.
+///
+/// The struct is named like the newtype [`Feature`] deserializes from, so the crate's own
+/// serializer can tell it from a struct with a `type` field.
+pub(crate) struct GeoJsonFeature<'a> {
+    pub(crate) geometry: Option<&'a Geometry>,
+    pub(crate) properties: &'a HashMap<String, Value>,
+}
+
+impl Serialize for GeoJsonFeature<'_> {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        let properties: BTreeMap<&String, &Value> = self.properties.iter().collect();
+        let mut feature = serializer.serialize_struct(FEATURE_NAME, 3)?;
+        feature.serialize_field("type", "Feature")?;
+        feature.serialize_field(GEOMETRY_COL, &self.geometry.map(to_geojson))?;
+        feature.serialize_field(PROPERTIES_COL, &properties)?;
+        feature.end()
     }
 }
 
-impl GeomProcessor for GeozeroFeature {
-    fn dimensions(&self) -> geozero::CoordDimensions {
-        self.current_geometry.dimensions()
+impl<'de> Deserialize<'de> for Feature {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(FEATURE_NAME, FeatureVisitor)
     }
+}
+
+struct FeatureVisitor;
 
-    fn multi_dim(&self) -> bool {
-        self.current_geometry.multi_dim()
+impl<'de> Visitor<'de> for FeatureVisitor {
+    type Value = Feature;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a feature")
     }
 
-    fn srid(&mut self, srid: Option<i32>) -> geozero::error::Result<()> {
-        self.current_geometry.srid(srid)
+    fn visit_newtype_struct<D>(self, deserializer: D) -> std::result::Result<Feature, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        FeatureFields::deserialize(deserializer)?.into_feature()
     }
 
-    fn xy(&mut self, x: f64, y: f64, idx: usize) -> geozero::error::Result<()> {
-        self.current_geometry.xy(x, y, idx)
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> std::result::Result<Feature, A::Error> {
+        FeatureFields::deserialize(serde::de::value::MapAccessDeserializer::new(map))?
+            .into_feature()
     }
+}
 
-    fn coordinate(
-        &mut self,
-        x: f64,
-        y: f64,
-        z: Option<f64>,
-        m: Option<f64>,
-        t: Option<f64>,
-        tm: Option<u64>,
-        idx: usize,
-    ) -> geozero::error::Result<()> {
-        self.current_geometry.coordinate(x, y, z, m, t, tm, idx)
+impl Feature {
+    /// Creates a feature, its properties are ordered by name, see [`Feature::iter`].
+    #[must_use]
+    pub fn new(geometry: Geometry, properties: HashMap<String, Value>) -> Self {
+        let mut order: Vec<String> = properties.keys().cloned().collect();
+        order.sort_unstable();
+        Self::from_parts(geometry, properties, order)
     }
 
-    fn empty_point(&mut self, idx: usize) -> geozero::error::Result<()> {
-        self.current_geometry.empty_point(idx)
+    /// Creates a feature whose properties are in `order`, which names every property once.
+    pub(crate) fn from_parts(
+        geometry: Geometry,
+        properties: HashMap<String, Value>,
+        order: Vec<String>,
+    ) -> Self {
+        Self {
+            geometry,
+            properties,
+            order,
+            srid: None,
+            zm: ZmValues::default(),
+            bbox: None,
+            coord_rounding: Rounding::default(),
+            raw_json: false,
+            coerce_types: false,
+            compact: false,
+            layout: FeatureLayout::default(),
+            enum_tag: None,
+            geometry_columns: None,
+            properties_only: false,
+            map_entries: Vec::new(),
+            pending_key: None,
+            pending_value: None,
+        }
     }
 
-    fn point_begin(&mut self, idx: usize) -> geozero::error::Result<()> {
-        self.current_geometry.point_begin(idx)
+    pub(crate) fn with_zm(mut self, zm: ZmValues) -> Self {
+        self.zm = zm;
+        self
     }
 
-    fn point_end(&mut self, idx: usize) -> geozero::error::Result<()> {
-        self.current_geometry.point_end(idx)
+    pub(crate) fn with_coord_rounding(mut self, coord_rounding: Rounding) -> Self {
+        self.coord_rounding = coord_rounding;
+        self
     }
 
-    fn multipoint_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
-        self.current_geometry.multipoint_begin(size, idx)
+    pub(crate) fn with_enum_tag(mut self, enum_tag: Option<Arc<str>>) -> Self {
+        self.enum_tag = enum_tag;
+        self
     }
 
-    fn multipoint_end(&mut self, idx: usize) -> geozero::error::Result<()> {
-        self.current_geometry.multipoint_end(idx)
+    pub(crate) fn with_geometry_columns(
+        mut self,
+        geometry_columns: Option<Arc<[(String, GeometryEncoding)]>>,
+    ) -> Self {
+        self.geometry_columns = geometry_columns;
+        self
     }
 
-    fn linestring_begin(
-        &mut self,
-        tagged: bool,
-        size: usize,
-        idx: usize,
-    ) -> geozero::error::Result<()> {
-        self.current_geometry.linestring_begin(tagged, size, idx)
+    pub(crate) fn with_raw_json(mut self, raw_json: bool) -> Self {
+        self.raw_json = raw_json;
+        self
     }
 
-    fn linestring_end(&mut self, tagged: bool, idx: usize) -> geozero::error::Result<()> {
-        self.current_geometry.linestring_end(tagged, idx)
+    pub(crate) fn with_coerce_types(mut self, coerce_types: bool) -> Self {
+        self.coerce_types = coerce_types;
+        self
     }
 
-    fn multilinestring_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
-        self.current_geometry.multilinestring_begin(size, idx)
+    pub(crate) fn with_compact(mut self, compact: bool) -> Self {
+        self.compact = compact;
+        self
     }
 
-    fn multilinestring_end(&mut self, idx: usize) -> geozero::error::Result<()> {
-        self.current_geometry.multilinestring_end(idx)
+    /// Sets where the struct this feature is deserialized into keeps the properties.
+    #[must_use]
+    pub fn with_layout(mut self, layout: FeatureLayout) -> Self {
+        self.layout = layout;
+        self
     }
 
-    fn polygon_begin(
-        &mut self,
-        tagged: bool,
-        size: usize,
-        idx: usize,
-    ) -> geozero::error::Result<()> {
-        self.current_geometry.polygon_begin(tagged, size, idx)
+    pub(crate) fn with_bbox(mut self, bbox: Option<Rect>) -> Self {
+        self.bbox = bbox;
+        self
     }
 
-    fn polygon_end(&mut self, tagged: bool, idx: usize) -> geozero::error::Result<()> {
-        self.current_geometry.polygon_end(tagged, idx)
+    /// Iterates over the properties in the order the datasource reported them.
+    ///
+    /// The properties of features built by [`Feature::new`] are ordered by name, and those added
+    /// with [`Feature::set`] follow in the order they were set. Properties inserted into
+    /// [`Feature::properties`] directly, e.g. by a [`Lookup`](crate::lookup::Lookup), come last
+    /// sorted by name. Synthetic fields like `srid` or `bbox` are not part of the properties.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Value)> {
+        ordered_properties(&self.properties, &self.order)
+            .map(|(name, value)| (name.as_str(), value))
     }
 
-    fn multipolygon_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
-        self.current_geometry.multipolygon_begin(size, idx)
+    /// The number of properties.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.properties.len()
     }
 
-    fn multipolygon_end(&mut self, idx: usize) -> geozero::error::Result<()> {
-        self.current_geometry.multipolygon_end(idx)
+    /// Returns `true` if the feature has no properties.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.properties.is_empty()
     }
 
-    fn geometrycollection_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
-        self.current_geometry.geometrycollection_begin(size, idx)
+    /// Returns `true` if the feature has the property `name`.
+    #[must_use]
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.properties.contains_key(name)
     }
 
-    fn geometrycollection_end(&mut self, idx: usize) -> geozero::error::Result<()> {
-        self.current_geometry.geometrycollection_end(idx)
+    /// The value of the property `name`.
+    #[must_use]
+    pub fn value(&self, name: &str) -> Option<&Value> {
+        self.properties.get(name)
+    }
+
+    /// Deserializes the property `name` into `T`.
+    ///
+    /// A missing property reads like `null`, so `Option<T>` gives `None`:
+    ///
+    /// ```
+    /// use serde_geozero::de::FeatureBuilder;
+    ///
+    /// let feature = FeatureBuilder::new(geo::point! { x: 1., y: 2. })
+    ///     .property("population", 3_669_495)
+    ///     .build();
+    /// assert_eq!(feature.get::<u32>("population").unwrap(), 3_669_495);
+    /// assert_eq!(feature.get::<Option<String>>("name").unwrap(), None);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::MissingField`] if the property is missing and `T` isn't optional, or
+    /// [`Error::Field`] if its value doesn't fit `T`.
+    pub fn get<T: serde::de::DeserializeOwned>(&self, name: &str) -> Result<T> {
+        match self.properties.get(name) {
+            Some(value) => T::deserialize(value.clone()).map_err(|err| Error::Field {
+                field: name.to_string(),
+                source: Box::new(err),
+            }),
+            None => T::deserialize(Value::Null).map_err(|_| Error::MissingField(name.to_string())),
+        }
     }
 
-    fn circularstring_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
-        self.current_geometry.circularstring_begin(size, idx)
+    /// The property `name` if it is a string.
+    #[must_use]
+    pub fn get_str(&self, name: &str) -> Option<&str> {
+        self.properties.get(name).and_then(Value::as_str)
     }
 
-    fn circularstring_end(&mut self, idx: usize) -> geozero::error::Result<()> {
-        self.current_geometry.circularstring_end(idx)
+    /// The property `name` if it is a number, integers are converted.
+    #[must_use]
+    pub fn get_f64(&self, name: &str) -> Option<f64> {
+        self.properties.get(name).and_then(Value::as_f64)
     }
 
-    fn compoundcurve_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
-        self.current_geometry.compoundcurve_begin(size, idx)
+    /// Sets the property `name`, returning its previous value. New properties are iterated
+    /// after the existing ones.
+    pub fn set(&mut self, name: impl Into<String>, value: impl Into<Value>) -> Option<Value> {
+        let name = name.into();
+        if !self.properties.contains_key(&name) && !self.order.contains(&name) {
+            self.order.push(name.clone());
+        }
+        self.properties.insert(name, value.into())
     }
 
-    fn compoundcurve_end(&mut self, idx: usize) -> geozero::error::Result<()> {
-        self.current_geometry.compoundcurve_end(idx)
+    /// The bounding box of the geometry, if it was computed while reading.
+    ///
+    /// See [`DeserializeOptions::compute_bbox`].
+    #[must_use]
+    pub fn bbox(&self) -> Option<Rect> {
+        self.bbox
     }
 
-    fn curvepolygon_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
-        self.current_geometry.curvepolygon_begin(size, idx)
+    /// Sets the SRID of the feature's geometry.
+    #[must_use]
+    pub fn with_srid(mut self, srid: Option<i32>) -> Self {
+        self.srid = srid;
+        self
     }
 
-    fn curvepolygon_end(&mut self, idx: usize) -> geozero::error::Result<()> {
-        self.current_geometry.curvepolygon_end(idx)
+    /// The SRID reported by the datasource for this feature's geometry, if any.
+    ///
+    /// When deserializing, the SRID is also offered as a synthetic `srid` field, so structs can
+    /// capture it with a `srid: Option<i32>` member. Structs without one never see it, so
+    /// `#[serde(deny_unknown_fields)]` keeps working.
+    #[must_use]
+    pub fn srid(&self) -> Option<i32> {
+        self.srid
     }
 
-    fn multicurve_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
-        self.current_geometry.multicurve_begin(size, idx)
+    /// Z values of the geometry's coordinates, if they were preserved while reading.
+    ///
+    /// See [`crate::zm`] for details.
+    #[must_use]
+    pub fn z(&self) -> Option<&[f64]> {
+        self.zm.z.as_deref()
     }
 
-    fn multicurve_end(&mut self, idx: usize) -> geozero::error::Result<()> {
-        self.current_geometry.multicurve_end(idx)
+    /// M values of the geometry's coordinates, if they were preserved while reading.
+    ///
+    /// See [`crate::zm`] for details.
+    #[must_use]
+    pub fn m(&self) -> Option<&[f64]> {
+        self.zm.m.as_deref()
     }
+}
 
-    fn multisurface_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
-        self.current_geometry.multisurface_begin(size, idx)
+/// Builds a [`Feature`] by hand, e.g. for tests or to serialize it:
+///
+/// ```
+/// use serde_geozero::de::FeatureBuilder;
+///
+/// let feature = FeatureBuilder::new(geo::point! { x: 13.4, y: 52.5 })
+///     .property("name", "Berlin")
+///     .property("population", 3_669_495)
+///     .property("tags", serde_json::json!(["capital"]))
+///     .srid(4326)
+///     .build();
+/// assert_eq!(feature.get_str("name"), Some("Berlin"));
+/// assert_eq!(feature.srid(), Some(4326));
+/// ```
+#[derive(Debug, Clone)]
+pub struct FeatureBuilder {
+    feature: Feature,
+}
+
+impl FeatureBuilder {
+    /// Starts a feature with `geometry` and no properties.
+    #[must_use]
+    pub fn new(geometry: impl Into<Geometry>) -> Self {
+        Self {
+            feature: Feature::new(geometry.into(), HashMap::new()),
+        }
     }
 
-    fn multisurface_end(&mut self, idx: usize) -> geozero::error::Result<()> {
-        self.current_geometry.multisurface_end(idx)
+    /// Sets the property `name`, arrays and objects can be passed as `serde_json::Value`.
+    #[must_use]
+    pub fn property(mut self, name: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.feature.set(name, value);
+        self
     }
 
-    fn triangle_begin(
-        &mut self,
-        tagged: bool,
-        size: usize,
-        idx: usize,
-    ) -> geozero::error::Result<()> {
-        self.current_geometry.triangle_begin(tagged, size, idx)
+    /// Sets the SRID of the geometry.
+    #[must_use]
+    pub fn srid(mut self, srid: i32) -> Self {
+        self.feature.srid = Some(srid);
+        self
     }
 
-    fn triangle_end(&mut self, tagged: bool, idx: usize) -> geozero::error::Result<()> {
-        self.current_geometry.triangle_end(tagged, idx)
+    /// Finishes the feature.
+    #[must_use]
+    pub fn build(self) -> Feature {
+        self.feature
     }
+}
+
+impl TryFrom<&mut GeozeroFeature> for Feature {
+    type Error = GeozeroError;
 
-    fn polyhedralsurface_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
-        self.current_geometry.polyhedralsurface_begin(size, idx)
+    fn try_from(value: &mut GeozeroFeature) -> std::result::Result<Self, Self::Error> {
+        Ok(Feature::from_parts(
+            value
+                .geometry
+                .take()
+                .or_else(|| value.current_geometry.take_geometry())
+                .ok_or(GeozeroError::Geometry(
+                    "Could not fetch geometry for feature".to_string(),
+                ))?,
+            std::mem::take(&mut value.current_properties),
+            std::mem::take(&mut value.current_order),
+        )
+        .with_srid(value.srid.take())
+        .with_zm(std::mem::take(&mut value.zm))
+        .with_bbox(value.bbox.take()))
     }
+}
 
-    fn polyhedralsurface_end(&mut self, idx: usize) -> geozero::error::Result<()> {
-        self.current_geometry.polyhedralsurface_end(idx)
+/// Feeds the feature back into any `geozero` processor, e.g. `ToWkt` or a `GeoJsonWriter`.
+impl GeozeroGeometry for Feature {
+    fn process_geom<P: GeomProcessor>(&self, processor: &mut P) -> geozero::error::Result<()> {
+        self.geometry.process_geom(processor)
     }
 
-    fn tin_begin(&mut self, size: usize, idx: usize) -> geozero::error::Result<()> {
-        self.current_geometry.tin_begin(size, idx)
+    fn srid(&self) -> Option<i32> {
+        self.srid
     }
+}
 
-    fn tin_end(&mut self, idx: usize) -> geozero::error::Result<()> {
-        self.current_geometry.tin_end(idx)
+impl FeatureProperties for Feature {
+    /// Properties are written sorted by name with their native column types, `null` values are
+    /// omitted.
+    fn process_properties<P: PropertyProcessor>(
+        &self,
+        processor: &mut P,
+    ) -> geozero::error::Result<bool> {
+        crate::ser::process_sorted_properties(&self.properties, processor)?;
+        Ok(false)
     }
+}
+
+impl FeatureAccess for Feature {}
+
+impl FeatureProperties for GeozeroFeature {
+    fn process_properties<P: PropertyProcessor>(
+        &self,
+        processor: &mut P,
+    ) -> geozero::error::Result<bool> {
+        crate::ser::process_sorted_properties(&self.current_properties, processor)?;
+        Ok(false)
+    }
+}
+
+/// Deserializes the feature as a map of its geometry, synthetic fields and properties.
+///
+/// The properties are moved into the visitor rather than copied: owned string values become the
+/// `String`s of the target, names are lent to it. This isn't zero-copy, reading a feature from a
+/// datasource allocates every name and value once, see [`GeozeroFeature`]. Interned strings, see
+/// [`DeserializeOptions::intern_strings`], are copied into owned targets.
+impl<'de> serde::de::Deserializer<'de> for Feature {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        self.visit_entries(None, visitor)
+    }
+
+    /// Structs only get the geometry and synthetic fields they have, so
+    /// `#[serde(deny_unknown_fields)]` doesn't reject e.g. the `srid`.
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        self.visit_entries(Some(fields), visitor)
+    }
+
+    fn deserialize_enum<V>(
+        mut self,
+        name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        let Some(tag) = self.enum_tag.take() else {
+            return Err(missing_enum_tag(name));
+        };
+        let variant = variant_name(&tag, self.properties.remove(&*tag).as_ref())?;
+        visitor.visit_enum(FeatureVariant {
+            variant,
+            feature: self,
+        })
+    }
+
+    fn deserialize_tuple_struct<V>(
+        mut self,
+        name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        if name == BOTH_NAME {
+            visitor.visit_seq(FeatureCopies {
+                feature: Some(self),
+                remaining: len,
+            })
+        } else if name == PAIR_NAME {
+            let geometry = std::mem::replace(
+                &mut self.geometry,
+                Geometry::GeometryCollection(GeometryCollection::default()),
+            );
+            let rounding = self.coord_rounding;
+            self.properties_only = true;
+            visitor.visit_seq(GeometryThenProperties {
+                geometry: Some((Cow::Owned(geometry), rounding)),
+                properties: Some(self),
+            })
+        } else {
+            self.deserialize_any(visitor)
+        }
+    }
+
+    // Forward all other methods to Value's deserializer
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        map identifier ignored_any
+    }
+
+    fn is_human_readable(&self) -> bool {
+        !self.compact
+    }
+}
+
+/// The `properties` in `order`, which names every property at most once, followed by those it
+/// doesn't name sorted by name.
+pub(crate) fn ordered_properties<'a>(
+    properties: &'a HashMap<String, Value>,
+    order: &'a [String],
+) -> impl Iterator<Item = (&'a String, &'a Value)> {
+    let mut rest = Vec::new();
+    let complete =
+        order.len() == properties.len() && order.iter().all(|name| properties.contains_key(name));
+    if !complete {
+        let ordered: hashbrown::HashSet<&str> = order.iter().map(String::as_str).collect();
+        rest.extend(
+            properties
+                .iter()
+                .filter(|(name, _)| !ordered.contains(name.as_str())),
+        );
+        rest.sort_unstable_by_key(|(name, _)| *name);
+    }
+    order
+        .iter()
+        .filter_map(|name| properties.get_key_value(name))
+        .chain(rest)
+}
+
+/// Whether `col` is one of the `fields` of a struct, always `true` for other targets.
+fn requested(fields: Option<&[&str]>, col: &str) -> bool {
+    fields.is_none_or(|fields| fields.contains(&col))
+}
+
+impl Feature {
+    /// Visits the feature as a map of its synthetic fields and properties. With the `fields` of
+    /// a struct, synthetic fields the struct doesn't have are left out.
+    fn visit_entries<'de, V>(self, fields: Option<&[&str]>, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        // properties are public and may have been changed since construction, so the entries are
+        // collected here. They are popped from the back, hence the geometry comes first.
+        let mut feature = self;
+        let mut map_entries = Vec::with_capacity(feature.properties.len() + 5);
+        map_entries.extend(
+            feature
+                .synthetic_fields(fields)
+                .map(|col| (Cow::Borrowed(col), None)),
+        );
+        if feature.layout == FeatureLayout::Nested {
+            map_entries.push((Cow::Borrowed(PROPERTIES_COL), None));
+            map_entries.push((Cow::Borrowed(GEOMETRY_COL), None));
+            feature.map_entries = map_entries;
+            feature.pending_value = None;
+            return visitor.visit_map(feature);
+        }
+        // properties are moved instead of cloned and sorted by name, so maps filled from the
+        // properties (e.g. a `#[serde(flatten)]` field) see them in the same order every time
+        let synthetic = map_entries.len();
+        map_entries.extend(
+            feature
+                .properties
+                .drain()
+                .map(|(key, value)| (Cow::Owned(key), Some(value))),
+        );
+        map_entries[synthetic..].sort_unstable_by(|(a, _), (b, _)| b.cmp(a));
+        if !feature.properties_only && requested(fields, GEOMETRY_COL) {
+            map_entries.push((Cow::Borrowed(GEOMETRY_COL), None));
+        }
+        feature.map_entries = map_entries;
+        feature.pending_value = None;
+        visitor.visit_map(feature)
+    }
+
+    /// The synthetic fields the feature has values for, leaving out those the `fields` of a
+    /// struct don't name and those a property shadows.
+    fn synthetic_fields<'a>(
+        &'a self,
+        fields: Option<&'a [&'a str]>,
+    ) -> impl Iterator<Item = &'static str> + 'a {
+        [
+            (SRID_COL, self.srid.is_some()),
+            (Z_COL, self.zm.z.is_some()),
+            (M_COL, self.zm.m.is_some()),
+            (BBOX_COL, self.bbox.is_some()),
+        ]
+        .into_iter()
+        .filter(move |(col, present)| {
+            *present && requested(fields, col) && !self.properties.contains_key(*col)
+        })
+        .map(|(col, _)| col)
+    }
+}
+
+pub(crate) const GEOMETRY_COL: &str = "geometry";
+pub(crate) const PROPERTIES_COL: &str = "properties";
+pub(crate) const SRID_COL: &str = "srid";
+pub(crate) const BBOX_COL: &str = "bbox";
+
+impl<'de> MapAccess<'de> for Feature {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> std::result::Result<Option<K::Value>, Self::Error>
+    where
+        K: serde::de::DeserializeSeed<'de>,
+    {
+        let Some((col, value)) = self.map_entries.pop() else {
+            return Ok(None);
+        };
+
+        // Values are moved out of the feature, so every key and value is handed over exactly once.
+        self.pending_value = if value.is_none() && col == PROPERTIES_COL {
+            Some(FieldValue::Properties(Box::new(self.take_properties())))
+        } else {
+            self.field_value(&col, value.map(Cow::Owned))?
+        };
+        // the key is kept to name the field in errors
+        let key = seed.deserialize(StrDeserializer::<Error>::new(&col))?;
+        self.pending_key = Some(col);
+        Ok(Some(key))
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: serde::de::DeserializeSeed<'de>,
+    {
+        let col = self.pending_key.take().unwrap_or_default();
+        let value = self.pending_value.take();
+        self.deserialize_field(&col, value, seed)
+    }
+}
+
+impl Feature {
+    /// The encoding of `col` if it is a geometry column, see
+    /// [`DeserializeOptions::geometry_column`].
+    fn geometry_encoding(&self, col: &str) -> Option<GeometryEncoding> {
+        self.geometry_columns
+            .as_ref()?
+            .iter()
+            .find_map(|(column, encoding)| (column == col).then_some(*encoding))
+    }
+
+    /// Prepares the field `col`, given the value of the property of the same name.
+    ///
+    /// The geometry and the Z/M values are only marked here and read from the feature once the
+    /// field asks for them.
+    fn field_value(
+        &self,
+        col: &str,
+        value: Option<Cow<'_, Value>>,
+    ) -> Result<Option<FieldValue<'static>>> {
+        Ok(match (value, self.geometry_encoding(col)) {
+            (Some(value), Some(encoding)) if *value != Value::Null => {
+                Some(decode_geometry(col, encoding, &value)?)
+            }
+            (Some(value), _) => Some(FieldValue::Property(match value.into_owned() {
+                Value::Json(json) if self.raw_json => Value::String(json),
+                value => value,
+            })),
+            (None, _) => match col {
+                GEOMETRY_COL => Some(FieldValue::FeatureGeometry),
+                SRID_COL => self.srid.map(|srid| FieldValue::Property(srid.into())),
+                BBOX_COL => self.bbox.map(|bbox| {
+                    FieldValue::Floats(vec![bbox.min().x, bbox.min().y, bbox.max().x, bbox.max().y])
+                }),
+                Z_COL if self.zm.z.is_some() => Some(FieldValue::Ordinates),
+                M_COL if self.zm.m.is_some() => Some(FieldValue::Ordinates),
+                _ => None,
+            },
+        })
+    }
+
+    /// Deserializes the field `col` prepared by [`Feature::field_value`].
+    fn deserialize_field<'de, V>(
+        &self,
+        col: &str,
+        value: Option<FieldValue<'_>>,
+        seed: V,
+    ) -> Result<V::Value>
+    where
+        V: serde::de::DeserializeSeed<'de>,
+    {
+        let result = match value {
+            Some(FieldValue::Property(value)) => match (self.coerce_types, self.compact) {
+                (false, false) => seed.deserialize(value),
+                (true, false) => seed.deserialize(Coerced(value)),
+                (false, true) => seed.deserialize(Compact(value)),
+                (true, true) => seed.deserialize(Compact(Coerced(value))),
+            },
+            Some(FieldValue::Geometry(value)) => {
+                seed.deserialize(GeometryDeserializer::new(value, self.coord_rounding))
+            }
+            Some(FieldValue::FeatureGeometry) => {
+                seed.deserialize(FeatureGeometry::new(&self.geometry, self.coord_rounding))
+            }
+            Some(FieldValue::Floats(values)) => {
+                seed.deserialize(SeqDeserializer::<_, Error>::new(values.into_iter()))
+            }
+            Some(FieldValue::Ordinates) => {
+                let ordinates = if col == Z_COL { &self.zm.z } else { &self.zm.m };
+                seed.deserialize(SeqDeserializer::<_, Error>::new(
+                    ordinates.iter().flatten().copied(),
+                ))
+            }
+            Some(FieldValue::Properties(properties)) => seed.deserialize(*properties),
+            Some(FieldValue::PropertiesRef(properties)) => seed.deserialize(properties),
+            None => Err(Error::Message("no value found".to_string())),
+        };
+        result.map_err(|err| Error::Field {
+            field: col.to_string(),
+            source: Box::new(err),
+        })
+    }
+
+    /// Moves the properties into a feature of their own, the nested `properties` field.
+    fn take_properties(&mut self) -> Feature {
+        let mut properties = Feature::from_parts(
+            Geometry::GeometryCollection(GeometryCollection::default()),
+            std::mem::take(&mut self.properties),
+            std::mem::take(&mut self.order),
+        )
+        .with_raw_json(self.raw_json)
+        .with_coerce_types(self.coerce_types)
+        .with_compact(self.compact)
+        .with_geometry_columns(self.geometry_columns.clone());
+        properties.properties_only = true;
+        properties
+    }
+}
 
-    fn pre_process_xy<F: Fn(&mut f64, &mut f64)>(
+/// Decodes the geometry column `col` for the field of the same name.
+fn decode_geometry(
+    col: &str,
+    encoding: GeometryEncoding,
+    value: &Value,
+) -> Result<FieldValue<'static>> {
+    encoding
+        .decode(value)
+        .and_then(|geometry| Ok(FieldValue::Geometry(serde_json::to_value(geometry)?)))
+        .map_err(|err| Error::Field {
+            field: col.to_string(),
+            source: Box::new(err),
+        })
+}
+
+/// A value handed out by the feature's `MapAccess`.
+#[derive(Clone, Debug)]
+enum FieldValue<'f> {
+    Property(Value),
+    Geometry(serde_json::Value),
+    /// The geometry of the feature, see [`FeatureGeometry`].
+    FeatureGeometry,
+    Floats(Vec<f64>),
+    /// The Z or M values of the feature, whichever the field is named after.
+    Ordinates,
+    Properties(Box<Feature>),
+    PropertiesRef(FeatureRef<'f>),
+}
+
+/// Deserializes a feature without consuming it, e.g. to keep it for an error callback.
+///
+/// Reads the same map as [`Feature`]'s own deserializer, but lends the geometry to the field
+/// and copies property values one at a time as the fields ask for them.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct FeatureRef<'f> {
+    feature: &'f Feature,
+    part: Part,
+    /// The property holding the enum variant, which the variant doesn't see.
+    tag: Option<&'f str>,
+}
+
+/// The entries of a [`FeatureRef`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Part {
+    Whole,
+    /// Everything but the geometry, the second half of a [`Pair`].
+    WithoutGeometry,
+    /// Only the properties, the nested `properties` field.
+    Properties,
+}
+
+impl<'f> FeatureRef<'f> {
+    pub(crate) fn new(feature: &'f Feature) -> Self {
+        Self {
+            feature,
+            part: Part::Whole,
+            tag: None,
+        }
+    }
+
+    fn part(self, part: Part) -> Self {
+        Self { part, ..self }
+    }
+
+    /// The entries of the feature's map, popped from the back like those of [`Feature`].
+    fn entries(self, fields: Option<&[&str]>) -> FeatureEntries<'f> {
+        let feature = self.feature;
+        let mut entries = Vec::with_capacity(feature.properties.len() + 5);
+        if self.part != Part::Properties {
+            entries.extend(feature.synthetic_fields(fields).map(|col| (col, None)));
+            if feature.layout == FeatureLayout::Nested {
+                entries.push((PROPERTIES_COL, None));
+                if self.part == Part::Whole {
+                    entries.push((GEOMETRY_COL, None));
+                }
+                return FeatureEntries::new(self, entries);
+            }
+        }
+        let synthetic = entries.len();
+        entries.extend(
+            feature
+                .properties
+                .iter()
+                .filter(|(key, _)| Some(key.as_str()) != self.tag)
+                .map(|(key, value)| (key.as_str(), Some(value))),
+        );
+        entries[synthetic..].sort_unstable_by(|(a, _), (b, _)| b.cmp(a));
+        if self.part == Part::Whole && requested(fields, GEOMETRY_COL) {
+            entries.push((GEOMETRY_COL, None));
+        }
+        FeatureEntries::new(self, entries)
+    }
+}
+
+impl<'de> serde::de::Deserializer<'de> for FeatureRef<'_> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        visitor.visit_map(self.entries(None))
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        visitor.visit_map(self.entries(Some(fields)))
+    }
+
+    fn deserialize_enum<V>(
         self,
-        transform_xy: F,
-    ) -> geozero::WrappedXYProcessor<Self, F>
+        name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        // like the owned feature, the tag is only read once
+        let feature = self.feature;
+        let Some(tag) = feature.enum_tag.as_deref().filter(|_| self.tag.is_none()) else {
+            return Err(missing_enum_tag(name));
+        };
+        visitor.visit_enum(FeatureVariant {
+            variant: variant_name(tag, feature.properties.get(tag))?,
+            feature: Self {
+                tag: Some(tag),
+                ..self
+            },
+        })
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        if name == BOTH_NAME {
+            visitor.visit_seq(FeatureCopies {
+                feature: Some(self),
+                remaining: len,
+            })
+        } else if name == PAIR_NAME {
+            let feature = self.feature;
+            visitor.visit_seq(GeometryThenProperties {
+                geometry: Some((Cow::Borrowed(&feature.geometry), feature.coord_rounding)),
+                properties: Some(self.part(Part::WithoutGeometry)),
+            })
+        } else {
+            self.deserialize_any(visitor)
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        map identifier ignored_any
+    }
+
+    fn is_human_readable(&self) -> bool {
+        !self.feature.compact
+    }
+}
+
+impl<'de> VariantAccess<'de> for FeatureRef<'_> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
+    where
+        T: serde::de::DeserializeSeed<'de>,
+    {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V>(self, _len: usize, _visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        Err(unsupported_tuple_variant())
+    }
+
+    fn struct_variant<V>(self, fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_map(self.entries(Some(fields)))
+    }
+}
+
+/// The map of a [`FeatureRef`].
+struct FeatureEntries<'f> {
+    feature: FeatureRef<'f>,
+    entries: Vec<(&'f str, Option<&'f Value>)>,
+    pending_key: Option<&'f str>,
+    pending_value: Option<FieldValue<'f>>,
+}
+
+impl<'f> FeatureEntries<'f> {
+    fn new(feature: FeatureRef<'f>, entries: Vec<(&'f str, Option<&'f Value>)>) -> Self {
+        Self {
+            feature,
+            entries,
+            pending_key: None,
+            pending_value: None,
+        }
+    }
+}
+
+impl<'de> MapAccess<'de> for FeatureEntries<'_> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: serde::de::DeserializeSeed<'de>,
+    {
+        let Some((col, value)) = self.entries.pop() else {
+            return Ok(None);
+        };
+        self.pending_value = if value.is_none() && col == PROPERTIES_COL {
+            Some(FieldValue::PropertiesRef(
+                self.feature.part(Part::Properties),
+            ))
+        } else {
+            self.feature
+                .feature
+                .field_value(col, value.map(Cow::Borrowed))?
+        };
+        let key = seed.deserialize(StrDeserializer::<Error>::new(col))?;
+        self.pending_key = Some(col);
+        Ok(Some(key))
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
     where
-        Self: Sized,
+        V: serde::de::DeserializeSeed<'de>,
     {
-        geozero::WrappedXYProcessor::new(self, transform_xy)
-    }
-}
-
-impl FeatureProcessor for GeozeroFeature {
-    fn feature_begin(&mut self, _idx: u64) -> geozero::error::Result<()> {
-        self.current_geometry = GeoWriter::new();
-        self.current_properties = HashMap::new();
-        Ok(())
+        let col = self.pending_key.take().unwrap_or_default();
+        let value = self.pending_value.take();
+        self.feature.feature.deserialize_field(col, value, seed)
     }
 }
 
-/// .
+/// Deserializes a single feature, e.g. one item of a `FlatGeobuf` selection.
 ///
 /// # Errors
 ///
-/// This function will return an error if .
+/// Returns an error if the feature fails to process or cannot be deserialized into `T`.
 pub fn feature_to_struct<'de, S: FeatureAccess, T: Deserialize<'de>>(feature: &S) -> Result<T> {
     let mut geozero_feature = GeozeroFeature::new();
     feature.process(&mut geozero_feature, 0)?;
@@ -671,15 +2512,95 @@ pub fn feature_to_struct<'de, S: FeatureAccess, T: Deserialize<'de>>(feature: &S
     T::deserialize(feature)
 }
 
+/// Deserializes features that aren't read through a datasource, like [`feature_to_struct`] for
+/// many features.
+///
+/// ```
+/// use serde::Deserialize;
+/// use serde_geozero::de::{from_feature_iter, Feature};
+///
+/// #[derive(Deserialize)]
+/// struct City {
+///     name: String,
+/// }
+///
+/// let geojson = r#"{"type": "FeatureCollection", "features": [
+///     {"type": "Feature", "properties": {"name": "Berlin", "capital": true},
+///         "geometry": {"type": "Point", "coordinates": [13.4, 52.5]}},
+///     {"type": "Feature", "properties": {"name": "Hamburg", "capital": false},
+///         "geometry": {"type": "Point", "coordinates": [10.0, 53.6]}}]}"#;
+/// let mut reader = geozero::geojson::GeoJsonReader(geojson.as_bytes());
+/// let features: Vec<Feature> = serde_geozero::from_datasource(&mut reader).unwrap();
+///
+/// let capitals = features.iter().filter(|f| f.get::<bool>("capital").unwrap());
+/// let cities: Vec<City> = from_feature_iter(capitals).unwrap();
+/// assert_eq!(cities[0].name, "Berlin");
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if a feature fails to process or cannot be deserialized into `T`.
+pub fn from_feature_iter<'a, 'de, T: Deserialize<'de> + 'static, F: FeatureAccess + 'a>(
+    features: impl IntoIterator<Item = &'a F>,
+) -> Result<Vec<T>> {
+    from_feature_iter_with_options(features, &DeserializeOptions::default())
+}
+
+/// Deserializes features that aren't read through a datasource, configured by `options`.
+///
+/// # Errors
+///
+/// Returns an error if a feature fails to process or cannot be deserialized into `T`.
+pub fn from_feature_iter_with_options<
+    'a,
+    'de,
+    T: Deserialize<'de> + 'static,
+    F: FeatureAccess + 'a,
+>(
+    features: impl IntoIterator<Item = &'a F>,
+    options: &DeserializeOptions,
+) -> Result<Vec<T>> {
+    let mut collector = DataSourceDeserializer::with_options(options)?;
+    collector.process_features(features)?;
+
+    Ok(collector.features)
+}
+
+/// Deserializes the features of a streaming iterator, e.g. a `FlatGeobuf` selection, configured
+/// by `options`.
+///
+/// # Errors
+///
+/// Returns an error if the iterator or a feature fails, or a feature cannot be deserialized
+/// into `T`.
+#[cfg(feature = "flatgeobuf")]
+pub fn from_feature_stream<'de, T: Deserialize<'de> + 'static, I>(
+    features: &mut I,
+    options: &DeserializeOptions,
+) -> Result<Vec<T>>
+where
+    I: flatgeobuf::FallibleStreamingIterator,
+    I::Item: FeatureAccess + Sized,
+    Error: From<I::Error>,
+{
+    let mut collector = DataSourceDeserializer::with_options(options)?;
+    collector.process_stream(features)?;
+
+    Ok(collector.features)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use approx::assert_relative_eq;
     use flatgeobuf::{FallibleStreamingIterator, FgbReader};
     use geo::Geometry;
+    use geozero::{FeatureProcessor, GeomProcessor};
     use serde::{Deserialize, Serialize};
     use std::fs::File;
 
+    use crate::error::ErrorDecision;
+
     #[test]
     fn test_flatgeobuf_feature() -> anyhow::Result<()> {
         #[derive(Debug, Deserialize)]
@@ -702,6 +2623,229 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn feature_iter() -> anyhow::Result<()> {
+        #[derive(Debug, Deserialize)]
+        struct Country {
+            name: String,
+        }
+
+        let reader = FgbReader::open(File::open("test-data/countries.fgb")?)?;
+        let features: Vec<Feature> = from_datasource(&mut reader.select_all()?)?;
+        let kept: Vec<&Feature> = features
+            .iter()
+            .filter(|feature| feature.get::<String>("name").unwrap().starts_with('A'))
+            .collect();
+
+        let countries: Vec<Country> = from_feature_iter(kept.iter().copied())?;
+        assert_eq!(countries.len(), kept.len());
+        assert_eq!(countries[0].name, "Antarctica");
+
+        let mut collector = DataSourceDeserializer::<Country>::new().with_limit(2);
+        collector.process_features(kept.iter().copied())?;
+        assert_eq!(collector.len(), 2);
+
+        #[cfg(feature = "flatgeobuf")]
+        {
+            let reader = FgbReader::open(File::open("test-data/countries.fgb")?)?;
+            let options = DeserializeOptions::new();
+            let countries: Vec<Country> = from_feature_stream(&mut reader.select_all()?, &options)?;
+            assert_eq!(countries.len(), features.len());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn chunks() -> anyhow::Result<()> {
+        #[derive(Debug, Deserialize)]
+        struct Country {
+            name: String,
+        }
+
+        let options = DeserializeOptions::new();
+        let mut sizes = Vec::new();
+        let mut names = Vec::new();
+        let reader = FgbReader::open(File::open("test-data/countries.fgb")?)?;
+        from_datasource_chunks(
+            &mut reader.select_all()?,
+            50,
+            &options,
+            |chunk: &mut Vec<Country>| {
+                sizes.push((chunk.len(), chunk.capacity()));
+                names.extend(chunk.drain(..).map(|country| country.name));
+                Ok(())
+            },
+        )?;
+        assert_eq!(names.len(), 179);
+        assert_eq!(names[0], "Antarctica");
+        assert_eq!(
+            sizes.iter().map(|(len, _)| *len).collect::<Vec<_>>(),
+            [50, 50, 50, 29]
+        );
+        // the buffer is reused instead of growing
+        assert!(sizes.iter().all(|(_, capacity)| *capacity == sizes[0].1));
+
+        let mut calls = 0;
+        let reader = FgbReader::open(File::open("test-data/countries.fgb")?)?;
+        let err = from_datasource_chunks(
+            &mut reader.select_all()?,
+            50,
+            &options,
+            |_: &mut Vec<Country>| {
+                calls += 1;
+                Err(Error::Message("database is down".to_string()))
+            },
+        )
+        .unwrap_err();
+        assert_eq!(calls, 1);
+        assert!(matches!(err, Error::Message(message) if message == "database is down"));
+        Ok(())
+    }
+
+    #[test]
+    fn seeds() -> anyhow::Result<()> {
+        #[derive(Debug, Deserialize)]
+        struct Country {
+            name: String,
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct Numbered {
+            #[allow(dead_code)]
+            name: u32,
+        }
+
+        let mut seeds = 0;
+        let reader = FgbReader::open(File::open("test-data/countries.fgb")?)?;
+        let countries = from_datasource_seed(
+            &mut reader.select_all()?,
+            &DeserializeOptions::new(),
+            || {
+                seeds += 1;
+                PhantomData::<Country>
+            },
+        )?;
+        assert_eq!((countries.len(), seeds), (179, 179));
+        assert_eq!(countries[0].name, "Antarctica");
+
+        let reader = FgbReader::open(File::open("test-data/countries.fgb")?)?;
+        let err = from_datasource_seed(
+            &mut reader.select_all()?,
+            &DeserializeOptions::new(),
+            PhantomData::<Numbered>::default,
+        )
+        .unwrap_err();
+        assert_eq!(err.feature_index(), Some(0));
+
+        // seed errors are handled like other errors of the feature
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "properties": {"name": 1},
+             "geometry": {"type": "Point", "coordinates": [1, 1]}},
+            {"type": "Feature", "properties": {"name": "b"},
+             "geometry": {"type": "Point", "coordinates": [2, 2]}},
+            {"type": "Feature", "properties": {"name": 2},
+             "geometry": {"type": "Point", "coordinates": [3, 3]}}
+        ]}"#;
+        let read = |options: &DeserializeOptions| {
+            from_datasource_seed(
+                &mut geozero::geojson::GeoJsonReader(geojson.as_bytes()),
+                options,
+                PhantomData::<Country>::default,
+            )
+        };
+        let countries = read(&DeserializeOptions::new().error_policy(ErrorPolicy::Skip))?;
+        assert_eq!(countries.len(), 1);
+        assert_eq!(countries[0].name, "b");
+        let err = read(&DeserializeOptions::new().on_error(|err| {
+            if err.index == 0 {
+                ErrorDecision::Skip
+            } else {
+                ErrorDecision::Abort
+            }
+        }))
+        .unwrap_err();
+        assert_eq!(err.feature_index(), Some(2));
+        Ok(())
+    }
+
+    #[test]
+    fn joins() -> anyhow::Result<()> {
+        #[derive(Debug, Deserialize)]
+        struct Country {
+            name: String,
+        }
+
+        let capitals = HashMap::from([
+            ("DEU".to_string(), "Berlin"),
+            ("FRA".to_string(), "Paris"),
+            ("XXX".to_string(), "Nowhere"),
+        ]);
+        let join = |join| -> anyhow::Result<Vec<(Country, Option<&&str>)>> {
+            let reader = FgbReader::open(File::open("test-data/countries.fgb")?)?;
+            from_datasource_joined(
+                &mut reader.select_all()?,
+                &DeserializeOptions::new(),
+                "id",
+                &capitals,
+                join,
+            )
+            .map_err(Into::into)
+        };
+
+        let inner = join(Join::Inner)?;
+        assert_eq!(inner.len(), 2);
+        assert!(inner
+            .iter()
+            .any(|(country, capital)| country.name == "Germany" && capital == &Some(&"Berlin")));
+        let left = join(Join::Left)?;
+        assert_eq!(left.len(), 179);
+        assert_eq!(
+            left.iter().filter(|(_, capital)| capital.is_some()).count(),
+            2
+        );
+
+        let numbers = HashMap::from([(1, ())]);
+        let reader = FgbReader::open(File::open("test-data/countries.fgb")?)?;
+        let err = from_datasource_joined::<Country, i64, _, _>(
+            &mut reader.select_all()?,
+            &DeserializeOptions::new(),
+            "id",
+            &numbers,
+            Join::Inner,
+        )
+        .unwrap_err();
+        assert_eq!(err.feature_index(), Some(0));
+        assert_eq!(err.field().as_deref(), Some("id"));
+
+        // features without a record are left out before they are deserialized
+        #[derive(Debug, Deserialize)]
+        struct District {
+            population: u32,
+        }
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "properties": {"id": 1, "population": "unknown"},
+             "geometry": {"type": "Point", "coordinates": [1, 1]}},
+            {"type": "Feature", "properties": {"id": 2, "population": 42},
+             "geometry": {"type": "Point", "coordinates": [2, 2]}}
+        ]}"#;
+        let table = HashMap::from([(2, "Mitte")]);
+        let join = |join| {
+            from_datasource_joined::<District, i64, _, _>(
+                &mut geozero::geojson::GeoJsonReader(geojson.as_bytes()),
+                &DeserializeOptions::new(),
+                "id",
+                &table,
+                join,
+            )
+        };
+        let inner = join(Join::Inner)?;
+        assert_eq!(inner.len(), 1);
+        assert_eq!((inner[0].0.population, inner[0].1), (42, Some(&"Mitte")));
+        assert_eq!(join(Join::Left).unwrap_err().feature_index(), Some(0));
+        Ok(())
+    }
+
     #[test]
     fn test_flatgeobuf() -> anyhow::Result<()> {
         #[derive(Debug, Deserialize)]
@@ -781,6 +2925,53 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn srid_is_captured() -> anyhow::Result<()> {
+        #[derive(Debug, Deserialize)]
+        struct Located {
+            geometry: Geometry,
+            srid: Option<i32>,
+        }
+
+        let mut collector = DataSourceDeserializer::<Located>::new();
+        for (idx, srid) in [Some(4326), None].into_iter().enumerate() {
+            collector.feature_begin(idx as u64)?;
+            collector.srid(srid)?;
+            collector.point_begin(0)?;
+            collector.xy(1., 2., 0)?;
+            collector.point_end(0)?;
+            collector.feature_end(idx as u64)?;
+        }
+
+        assert_eq!(collector.features[0].srid, Some(4326));
+        assert_eq!(collector.features[1].srid, None);
+        assert!(matches!(collector.features[1].geometry, Geometry::Point(_)));
+        Ok(())
+    }
+
+    #[cfg(feature = "proj")]
+    #[test]
+    fn reprojection() -> anyhow::Result<()> {
+        let geojson = r#"{"type": "Feature", "properties": {},
+            "geometry": {"type": "Point", "coordinates": [10, 0]}}"#;
+
+        let options = DeserializeOptions::new()
+            .compute_bbox(true)
+            .reproject("EPSG:4326", "EPSG:3857");
+        let mut reader = geozero::geojson::GeoJsonReader(geojson.as_bytes());
+        let features: Vec<Feature> = from_datasource_with_options(&mut reader, &options)?;
+
+        let Geometry::Point(point) = features[0].geometry else {
+            panic!("expected a point");
+        };
+        assert_relative_eq!(point.x(), 1_113_194.907_932_7, epsilon = 1e-3);
+        assert_relative_eq!(point.y(), 0., epsilon = 1e-3);
+        assert_eq!(features[0].srid(), Some(3857));
+        let bbox = features[0].bbox().unwrap();
+        assert_eq!((bbox.min(), bbox.max()), (point.0, point.0));
+        Ok(())
+    }
+
     #[test]
     fn test_geojson() -> Result<()> {
         #[derive(Serialize, Deserialize, Debug, PartialEq)]
@@ -848,4 +3039,551 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn json_columns() -> Result<()> {
+        #[derive(Deserialize)]
+        struct Address {
+            city: String,
+        }
+
+        #[derive(Deserialize)]
+        struct Shop {
+            geometry: Geometry,
+            address: Address,
+            tags: Vec<String>,
+            raw: String,
+        }
+
+        #[derive(Deserialize)]
+        struct RawShop {
+            geometry: Geometry,
+            tags: serde_json::Value,
+        }
+
+        let geojson = r#"{"type": "Feature", "geometry": {"type": "Point", "coordinates": [1, 2]},
+            "properties": {"address": {"city": "Berlin"}, "tags": ["a", "b"], "raw": [1]}}"#;
+
+        let shops: Vec<Shop> =
+            from_datasource(&mut geozero::geojson::GeoJsonReader(geojson.as_bytes()))?;
+        assert_eq!(shops[0].address.city, "Berlin");
+        assert_eq!(shops[0].tags, ["a", "b"]);
+        assert_eq!(shops[0].raw, "[1]");
+        assert!(matches!(shops[0].geometry, Geometry::Point(_)));
+
+        let shops: Vec<RawShop> = from_datasource_with_options(
+            &mut geozero::geojson::GeoJsonReader(geojson.as_bytes()),
+            &DeserializeOptions::new().parse_json(false),
+        )?;
+        assert_eq!(shops[0].tags, serde_json::json!(r#"["a","b"]"#));
+        assert!(matches!(shops[0].geometry, Geometry::Point(_)));
+        Ok(())
+    }
+
+    #[test]
+    fn extra_properties() -> anyhow::Result<()> {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Shop {
+            geometry: Geometry,
+            name: String,
+            #[serde(flatten)]
+            extra: std::collections::BTreeMap<String, Value>,
+        }
+
+        let geojson = r#"{"type": "Feature", "geometry": {"type": "Point", "coordinates": [1, 2]},
+            "properties": {"name": "a", "floor": 2, "open": true, "tags": ["x"], "note": null}}"#;
+        let shops: Vec<Shop> =
+            from_datasource(&mut geozero::geojson::GeoJsonReader(geojson.as_bytes()))?;
+        assert_eq!(shops[0].name, "a");
+        assert_eq!(
+            shops[0].extra.keys().collect::<Vec<_>>(),
+            ["floor", "open", "tags"]
+        );
+        assert_eq!(shops[0].extra["tags"], Value::Json(r#"["x"]"#.to_string()));
+
+        let written = crate::geojson::to_geojson_string(&shops, &crate::SerializeOptions::new())?;
+        let read: Vec<Shop> =
+            from_datasource(&mut geozero::geojson::GeoJsonReader(written.as_bytes()))?;
+        assert_eq!(read, shops);
+
+        let f = File::open("test-data/countries.fgb")?;
+        let countries: Vec<Shop> = from_datasource(&mut FgbReader::open(f)?.select_all()?)?;
+        assert_eq!(countries[0].extra.keys().collect::<Vec<_>>(), ["id"]);
+        Ok(())
+    }
+
+    #[test]
+    fn synthetic_fields_of_strict_structs() -> anyhow::Result<()> {
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct Strict {
+            #[allow(dead_code)]
+            geometry: Geometry,
+            name: String,
+        }
+
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct Located {
+            name: String,
+            srid: Option<i32>,
+        }
+
+        let feature = Feature::new(
+            geo::point! { x: 1., y: 2. }.into(),
+            HashMap::from_iter([("name".to_string(), Value::String("a".to_string()))]),
+        )
+        .with_srid(Some(4326))
+        .with_bbox(Some(geo::Rect::new((1., 2.), (1., 2.))));
+        assert_eq!(Strict::deserialize(feature.clone())?.name, "a");
+        let located = Located::deserialize(feature.clone())?;
+        assert_eq!((located.name.as_str(), located.srid), ("a", Some(4326)));
+        // maps still see every synthetic field
+        let map = HashMap::<String, serde_json::Value>::deserialize(feature)?;
+        assert!(map.contains_key("srid") && map.contains_key("bbox"));
+        Ok(())
+    }
+
+    #[test]
+    fn moves_property_strings() -> anyhow::Result<()> {
+        #[derive(Deserialize)]
+        struct Named {
+            name: String,
+        }
+
+        let name = "a".repeat(64);
+        let allocation = name.as_ptr();
+        let feature = Feature::new(
+            geo::point! { x: 1., y: 2. }.into(),
+            HashMap::from_iter([("name".to_string(), Value::String(name))]),
+        );
+        // the string is handed over, not copied
+        assert_eq!(Named::deserialize(feature)?.name.as_ptr(), allocation);
+        Ok(())
+    }
+
+    #[test]
+    fn deserialize_by_reference() -> anyhow::Result<()> {
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Station {
+            geometry: Geometry,
+            srid: Option<i32>,
+            name: String,
+        }
+        #[derive(Debug, PartialEq, Deserialize)]
+        #[serde(rename_all = "lowercase")]
+        enum Stop {
+            Station { name: String, lines: u8 },
+            Halt(HashMap<String, Value>),
+        }
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Nested {
+            geometry: Geometry,
+            properties: HashMap<String, Value>,
+        }
+
+        let feature = Feature::new(
+            geo::point! { x: 1., y: 2. }.into(),
+            HashMap::from_iter([
+                ("name".to_string(), Value::from("Zoo")),
+                ("kind".to_string(), Value::from("station")),
+                ("lines".to_string(), Value::from(3)),
+            ]),
+        )
+        .with_srid(Some(4326))
+        .with_enum_tag(Some("kind".into()));
+
+        // the borrowed feature reads like the owned one and is left as it was
+        let station = Station::deserialize(FeatureRef::new(&feature))?;
+        assert_eq!(station, Station::deserialize(feature.clone())?);
+        assert_eq!(station.srid, Some(4326));
+        assert_eq!(
+            HashMap::<String, Value>::deserialize(FeatureRef::new(&feature))?,
+            HashMap::<String, Value>::deserialize(feature.clone())?
+        );
+        let stop = Stop::deserialize(FeatureRef::new(&feature))?;
+        assert_eq!(stop, Stop::deserialize(feature.clone())?);
+        assert!(matches!(stop, Stop::Station { lines: 3, .. }));
+        let Both(station, copy) = Both::<Station, Feature>::deserialize(FeatureRef::new(&feature))?;
+        assert_eq!(
+            (copy.geometry, station.name),
+            (feature.geometry.clone(), "Zoo".to_string())
+        );
+        let Pair(geometry, properties) =
+            Pair::<HashMap<String, Value>>::deserialize(FeatureRef::new(&feature))?;
+        assert_eq!(geometry, feature.geometry);
+        assert!(!properties.contains_key(GEOMETRY_COL));
+        let nested = feature.clone().with_layout(FeatureLayout::Nested);
+        assert_eq!(
+            Nested::deserialize(FeatureRef::new(&nested))?,
+            Nested::deserialize(nested.clone())?
+        );
+        assert_eq!(feature.value("kind"), Some(&Value::from("station")));
+        Ok(())
+    }
+
+    #[test]
+    fn feature_from_feature() -> anyhow::Result<()> {
+        let feature = Feature::new(
+            geo::point! { x: 1., y: 2. }.into(),
+            HashMap::from_iter([("name".to_string(), Value::String("Zoo".to_string()))]),
+        );
+        assert_eq!(Feature::deserialize(feature.clone())?, feature);
+        Ok(())
+    }
+
+    #[test]
+    fn property_access() {
+        let mut feature = Feature::new(
+            geo::point! { x: 1., y: 2. }.into(),
+            HashMap::from_iter([
+                ("b".to_string(), Value::Long(2)),
+                ("a".to_string(), Value::String("x".to_string())),
+            ]),
+        )
+        .with_srid(Some(4326));
+        assert_eq!(feature.len(), 2);
+        assert!(!feature.is_empty());
+        assert!(feature.contains_key("a"));
+        assert!(!feature.contains_key("srid"));
+        assert_eq!(feature.value("b"), Some(&Value::Long(2)));
+        assert_eq!(
+            feature.iter().map(|(name, _)| name).collect::<Vec<_>>(),
+            ["a", "b"]
+        );
+
+        // properties set later follow in the order they were set
+        feature.set("d", 4);
+        feature.set("c", 3);
+        feature.set("a", "y");
+        assert_eq!(
+            feature.iter().map(|(name, _)| name).collect::<Vec<_>>(),
+            ["a", "b", "d", "c"]
+        );
+        let built = FeatureBuilder::new(geo::point! { x: 1., y: 2. })
+            .property("zone", 1)
+            .property("area", 2)
+            .build();
+        assert_eq!(
+            built.iter().map(|(name, _)| name).collect::<Vec<_>>(),
+            ["zone", "area"]
+        );
+    }
+
+    #[test]
+    fn property_order() -> anyhow::Result<()> {
+        use crate::collector::GeozeroFeature;
+        use geozero::PropertyProcessor;
+
+        let mut source = GeozeroFeature::new();
+        source.feature_begin(0)?;
+        for (idx, name) in ["zone", "area", "name", "area"].into_iter().enumerate() {
+            source.property(idx, name, &geozero::ColumnValue::Long(1))?;
+        }
+        source.point_begin(0)?;
+        source.xy(1., 2., 0)?;
+        source.point_end(0)?;
+        source.feature_end(0)?;
+        let mut feature = Feature::try_from(&mut source)?;
+        let names = |feature: &Feature| {
+            feature
+                .iter()
+                .map(|(name, _)| name.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&feature), ["zone", "area", "name"]);
+
+        // properties added later follow the source order
+        let properties = &mut feature.properties;
+        properties.insert("code".to_string(), Value::Long(2));
+        properties.insert("beta".to_string(), Value::Long(3));
+        properties.remove("area");
+        assert_eq!(names(&feature), ["zone", "name", "beta", "code"]);
+        Ok(())
+    }
+
+    #[test]
+    fn geometry_only() -> anyhow::Result<()> {
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "properties": {"name": "a"},
+             "geometry": {"type": "Polygon", "coordinates": [[[0, 0], [1, 0], [1, 1], [0, 0]]]}},
+            {"type": "Feature", "properties": {},
+             "geometry": {"type": "GeometryCollection", "geometries": [
+                 {"type": "Point", "coordinates": [1, 2]},
+                 {"type": "LineString", "coordinates": [[0, 0], [1, 1]]}]}}
+        ]}"#;
+        let geometries: Vec<Geometry> =
+            geometry_from_datasource(&mut geozero::geojson::GeoJsonReader(geojson.as_bytes()))?;
+        assert_eq!(geometries.len(), 2);
+        assert!(matches!(geometries[0], Geometry::Polygon(_)));
+        assert!(matches!(&geometries[1], Geometry::GeometryCollection(gc) if gc.0.len() == 2));
+
+        let points = geometry_from_datasource::<geo::Point, _>(
+            &mut geozero::geojson::GeoJsonReader(geojson.as_bytes()),
+        );
+        assert!(matches!(points, Err(Error::Feature { index: 0, .. })));
+        Ok(())
+    }
+
+    #[test]
+    fn typed_accessors() -> anyhow::Result<()> {
+        let mut feature = FeatureBuilder::new(geo::point! { x: 1., y: 2. })
+            .property("name", "A")
+            .property("value", 42)
+            .property("tags", serde_json::json!(["a", "b"]))
+            .build();
+        assert_eq!(feature.get::<u8>("value")?, 42);
+        assert_eq!(feature.get::<Vec<String>>("tags")?, ["a", "b"]);
+        assert_eq!(feature.get::<Option<u8>>("missing")?, None);
+        assert!(matches!(
+            feature.get::<u8>("missing"),
+            Err(Error::MissingField(name)) if name == "missing"
+        ));
+        assert!(matches!(
+            feature.get::<u8>("name"),
+            Err(Error::Field { field, .. }) if field == "name"
+        ));
+        assert_eq!(feature.get_str("name"), Some("A"));
+        assert_eq!(feature.get_f64("value"), Some(42.));
+        assert_eq!(feature.get_f64("name"), None);
+
+        assert_eq!(feature.set("value", 43), Some(Value::Int(42)));
+        assert_eq!(feature.get::<u8>("value")?, 43);
+        Ok(())
+    }
+
+    #[test]
+    fn feature_access() -> anyhow::Result<()> {
+        use geozero::{geojson::GeoJsonWriter, FeatureProperties, ToJson};
+
+        let feature = Feature::new(
+            geo::point! { x: 1., y: 2. }.into(),
+            HashMap::from_iter([
+                ("name".to_string(), Value::String("A".to_string())),
+                ("value".to_string(), Value::Int(42)),
+            ]),
+        );
+        assert_eq!(feature.property::<String>("name")?, "A");
+        assert_eq!(feature.property_n::<i32>(1)?, 42);
+        assert_eq!(
+            feature.to_json()?,
+            r#"{"type": "Point", "coordinates": [1,2]}"#
+        );
+
+        let mut output = Vec::new();
+        feature.process(&mut GeoJsonWriter::new(&mut output), 0)?;
+        let written: serde_json::Value = serde_json::from_slice(&output)?;
+        assert_eq!(written["properties"]["value"], 42);
+        Ok(())
+    }
+
+    #[test]
+    fn geojson_shape() -> anyhow::Result<()> {
+        use crate::collector::GeozeroFeature;
+        use geozero::{geojson::GeoJsonReader, GeozeroDatasource};
+
+        let feature = Feature::new(
+            geo::point! { x: 1., y: 2. }.into(),
+            HashMap::from_iter([
+                ("name".to_string(), Value::String("A".to_string())),
+                ("value".to_string(), Value::Int(42)),
+            ]),
+        );
+        let expected = serde_json::json!({
+            "type": "Feature",
+            "geometry": {"type": "Point", "coordinates": [1., 2.]},
+            "properties": {"name": "A", "value": 42},
+        });
+        let json = serde_json::to_value(&feature)?;
+        assert_eq!(json, expected);
+        let read: Feature = serde_json::from_value(json)?;
+        assert_eq!(read.geometry, feature.geometry);
+        assert_eq!(read.properties["value"], Value::ULong(42));
+
+        let mut buffered = GeozeroFeature::new();
+        GeoJsonReader(expected.to_string().as_bytes()).process(&mut buffered)?;
+        assert_eq!(serde_json::to_value(&buffered)?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn geometry_pairs() -> anyhow::Result<()> {
+        #[derive(Debug, Deserialize)]
+        struct Country {
+            name: String,
+            id: String,
+        }
+
+        let f = File::open("test-data/countries.fgb")?;
+        let countries: Vec<(Geometry, Country)> = from_datasource_pairs(
+            &mut FgbReader::open(f)?.select_all()?,
+            &DeserializeOptions::new(),
+        )?;
+        assert!(matches!(countries[0].0, Geometry::MultiPolygon(_)));
+        assert_eq!(countries[0].1.name, "Antarctica");
+        assert_eq!(countries[0].1.id, "ATA");
+        Ok(())
+    }
+
+    #[test]
+    fn keyed_features() -> anyhow::Result<()> {
+        #[derive(Debug, Deserialize)]
+        struct Record {
+            code: u8,
+            name: String,
+        }
+
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "properties": {"code": 1, "name": "a"},
+             "geometry": {"type": "Point", "coordinates": [1, 1]}},
+            {"type": "Feature", "properties": {"code": 1, "name": "b"},
+             "geometry": {"type": "Point", "coordinates": [2, 2]}}
+        ]}"#;
+        let read = |key_collision| {
+            from_datasource_keyed(
+                &mut geozero::geojson::GeoJsonReader(geojson.as_bytes()),
+                &DeserializeOptions::new().key_collision(key_collision),
+                |record: &Record| record.code,
+            )
+        };
+        assert!(matches!(
+            read(KeyCollision::Error),
+            Err(Error::DuplicateKey(key)) if key == "1"
+        ));
+        assert_eq!(read(KeyCollision::KeepFirst)?[&1].name, "a");
+        assert_eq!(read(KeyCollision::KeepLast)?[&1].name, "b");
+        Ok(())
+    }
+
+    #[test]
+    fn tagged_enums() -> anyhow::Result<()> {
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Road {
+            geometry: Geometry,
+            lanes: u8,
+        }
+
+        #[derive(Debug, PartialEq, Deserialize)]
+        enum Layer {
+            Road(Road),
+            Water,
+        }
+
+        #[derive(Debug, PartialEq, Deserialize)]
+        #[serde(tag = "kind")]
+        enum Internal {
+            Road(Road),
+            Water { geometry: Geometry },
+        }
+
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "properties": {"kind": "Road", "lanes": 2},
+             "geometry": {"type": "Point", "coordinates": [1, 1]}},
+            {"type": "Feature", "properties": {"kind": "Water"},
+             "geometry": {"type": "Point", "coordinates": [2, 2]}}
+        ]}"#;
+        let road = Road {
+            geometry: geo::point! { x: 1., y: 1. }.into(),
+            lanes: 2,
+        };
+
+        let layers: Vec<Layer> = from_datasource_with_options(
+            &mut geozero::geojson::GeoJsonReader(geojson.as_bytes()),
+            &DeserializeOptions::new().enum_tag("kind"),
+        )?;
+        assert_eq!(layers, [Layer::Road(road), Layer::Water]);
+
+        let internal: Vec<Internal> =
+            from_datasource(&mut geozero::geojson::GeoJsonReader(geojson.as_bytes()))?;
+        assert!(matches!(internal[0], Internal::Road(Road { lanes: 2, .. })));
+        assert!(matches!(internal[1], Internal::Water { .. }));
+
+        let missing: Result<Vec<Layer>> = from_datasource_with_options(
+            &mut geozero::geojson::GeoJsonReader(geojson.as_bytes()),
+            &DeserializeOptions::new().enum_tag("layer"),
+        );
+        assert!(matches!(
+            missing.as_ref().map_err(Error::root_cause),
+            Err(Error::MissingDiscriminator(tag)) if tag == "layer"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn split_targets() -> anyhow::Result<()> {
+        #[derive(Debug, Deserialize)]
+        struct Summary {
+            id: String,
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct Country {
+            geometry: Geometry,
+            name: String,
+        }
+
+        let f = File::open("test-data/countries.fgb")?;
+        let (summaries, countries): (Vec<Summary>, Vec<Country>) = from_datasource_split(
+            &mut FgbReader::open(f)?.select_all()?,
+            &DeserializeOptions::new(),
+        )?;
+        assert_eq!(summaries.len(), countries.len());
+        assert_eq!(summaries[0].id, "ATA");
+        assert_eq!(countries[0].name, "Antarctica");
+        assert!(matches!(countries[0].geometry, Geometry::MultiPolygon(_)));
+
+        let feature = Feature::new(geo::point! { x: 1., y: 2. }.into(), HashMap::new());
+        let nested = Both::<Both<Feature, Feature>, Feature>::deserialize(feature)?;
+        assert_eq!(nested.0 .1.geometry, geo::point! { x: 1., y: 2. }.into());
+        Ok(())
+    }
+
+    #[test]
+    fn summary() -> anyhow::Result<()> {
+        #[derive(Debug, Deserialize)]
+        struct Country {
+            name: String,
+            #[serde(flatten)]
+            extra: HashMap<String, Value>,
+        }
+
+        let reader = FgbReader::open(File::open("test-data/countries.fgb")?)?;
+        let envelope: Vec<f64> = reader.header().envelope().unwrap().iter().collect();
+        let (countries, summary) = from_datasource_with_summary::<Country, _>(
+            &mut reader.select_all()?,
+            &DeserializeOptions::new(),
+        )?;
+        assert_eq!(summary.feature_count, countries.len());
+        assert_eq!(countries[0].name, "Antarctica");
+        // the envelope is computed without adding a bbox to the features
+        assert!(!countries[0].extra.contains_key(BBOX_COL));
+        let bbox = summary.bbox.unwrap();
+        assert_relative_eq!(bbox.min().x, envelope[0]);
+        assert_relative_eq!(bbox.max().y, envelope[3]);
+        assert!(summary.geometry_types_seen.contains(&"MultiPolygon"));
+        Ok(())
+    }
+
+    #[cfg(feature = "proj")]
+    #[test]
+    fn reprojected_summary() -> anyhow::Result<()> {
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "properties": {},
+             "geometry": {"type": "Point", "coordinates": [0, 0]}},
+            {"type": "Feature", "properties": {},
+             "geometry": {"type": "Point", "coordinates": [10, 0]}}
+        ]}"#;
+
+        let options = DeserializeOptions::new().reproject("EPSG:4326", "EPSG:3857");
+        let (features, summary) = from_datasource_with_summary::<Feature, _>(
+            &mut geozero::geojson::GeoJsonReader(geojson.as_bytes()),
+            &options,
+        )?;
+        assert_eq!(summary.feature_count, features.len());
+        let bbox = summary.bbox.unwrap();
+        assert_relative_eq!(bbox.min().x, 0., epsilon = 1e-3);
+        assert_relative_eq!(bbox.max().x, 1_113_194.907_932_7, epsilon = 1e-3);
+        assert_eq!(summary.srid, Some(3857));
+        Ok(())
+    }
 }
@@ -0,0 +1,122 @@
+//! Properties derived from the geometry while writing.
+//!
+//! Label points, areas or extents are often needed next to the geometry, e.g. to place labels
+//! in map tiles. Instead of computing them in a separate pass, add them as properties with
+//! [`SerializeOptions::derived`](crate::SerializeOptions::derived):
+//!
+//! ```
+//! use geo::{polygon, Geometry};
+//! use serde::Serialize;
+//! use serde_geozero::derived::Derived;
+//! use serde_geozero::geojson::to_geojson_string;
+//! use serde_geozero::SerializeOptions;
+//!
+//! #[derive(Serialize)]
+//! struct Park {
+//!     geometry: Geometry,
+//!     name: String,
+//! }
+//!
+//! let parks = [Park {
+//!     geometry: polygon![(x: 0., y: 0.), (x: 2., y: 0.), (x: 2., y: 2.), (x: 0., y: 2.)].into(),
+//!     name: "Tiergarten".to_string(),
+//! }];
+//! let options = SerializeOptions::new()
+//!     .derived("label_x", Derived::CentroidX)
+//!     .derived("label_y", Derived::CentroidY)
+//!     .derived("area", Derived::Area);
+//! let geojson = to_geojson_string(&parks, &options).unwrap();
+//! assert!(geojson.contains(r#""label_x": 1"#));
+//! assert!(geojson.contains(r#""area": 4"#));
+//! ```
+//!
+//! Values are computed from the written geometry, after reprojecting and simplifying, in the
+//! units of its coordinates. Features without a geometry get `null`.
+use geo::{Area, BoundingRect, Centroid, Euclidean, Geometry, Length, Point, Polygon};
+
+use crate::value::Value;
+
+/// A value derived from the geometry of a feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Derived {
+    /// The x coordinate of the centroid.
+    CentroidX,
+    /// The y coordinate of the centroid.
+    CentroidY,
+    /// The planar area, 0 for points and lines.
+    Area,
+    /// The planar length of lines and the perimeter of polygons, 0 for points.
+    Length,
+    /// The bounding box as a JSON array `[min_x, min_y, max_x, max_y]`.
+    Bbox,
+}
+
+impl Derived {
+    /// Computes the value for `geometry`, `null` if there is none or it is empty.
+    pub(crate) fn compute(self, geometry: Option<&Geometry>) -> Value {
+        let Some(geometry) = geometry else {
+            return Value::Null;
+        };
+        match self {
+            Derived::CentroidX => geometry.centroid().map(Point::x).into(),
+            Derived::CentroidY => geometry.centroid().map(Point::y).into(),
+            Derived::Area => geometry.unsigned_area().into(),
+            Derived::Length => length(geometry).into(),
+            Derived::Bbox => geometry.bounding_rect().map_or(Value::Null, |bbox| {
+                serde_json::json!([bbox.min().x, bbox.min().y, bbox.max().x, bbox.max().y]).into()
+            }),
+        }
+    }
+}
+
+fn length(geometry: &Geometry) -> f64 {
+    let rings = |polygon: &Polygon| {
+        Euclidean.length(polygon.exterior())
+            + polygon
+                .interiors()
+                .iter()
+                .map(|ring| Euclidean.length(ring))
+                .sum::<f64>()
+    };
+    match geometry {
+        Geometry::Point(_) | Geometry::MultiPoint(_) => 0.,
+        Geometry::Line(line) => Euclidean.length(line),
+        Geometry::LineString(line) => Euclidean.length(line),
+        Geometry::MultiLineString(lines) => Euclidean.length(lines),
+        Geometry::Polygon(polygon) => rings(polygon),
+        Geometry::MultiPolygon(polygons) => polygons.iter().map(rings).sum(),
+        Geometry::Rect(rect) => rings(&rect.to_polygon()),
+        Geometry::Triangle(triangle) => rings(&triangle.to_polygon()),
+        Geometry::GeometryCollection(collection) => collection.iter().map(length).sum(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use geo::{line_string, point, polygon, Geometry};
+
+    use super::Derived;
+    use crate::value::Value;
+
+    #[test]
+    fn derived_values() {
+        let square: Geometry =
+            polygon![(x: 0., y: 0.), (x: 2., y: 0.), (x: 2., y: 2.), (x: 0., y: 2.)].into();
+        assert_eq!(Derived::Area.compute(Some(&square)), Value::Double(4.));
+        assert_eq!(Derived::Length.compute(Some(&square)), Value::Double(8.));
+        assert_eq!(Derived::CentroidY.compute(Some(&square)), Value::Double(1.));
+        assert_eq!(
+            Derived::Bbox.compute(Some(&square)),
+            Value::Json("[0.0,0.0,2.0,2.0]".to_string())
+        );
+
+        let line: Geometry = line_string![(x: 0., y: 0.), (x: 3., y: 4.)].into();
+        assert_eq!(Derived::Length.compute(Some(&line)), Value::Double(5.));
+        assert_eq!(Derived::Area.compute(Some(&line)), Value::Double(0.));
+        assert_eq!(
+            Derived::CentroidX.compute(Some(&point! { x: 7., y: 1. }.into())),
+            Value::Double(7.)
+        );
+        assert_eq!(Derived::Area.compute(None), Value::Null);
+    }
+}
@@ -0,0 +1,289 @@
+//! Features with a schema defined at runtime instead of a struct.
+//!
+//! A [`DynamicSchema`] lists the expected fields and their types, e.g. from user
+//! configuration in a plugin system or a query engine. Reading a datasource with it checks
+//! every feature against the schema and converts its values to the expected types:
+//!
+//! ```
+//! use serde_geozero::dynamic::DynamicSchema;
+//! use serde_geozero::schema::ColumnType;
+//! use serde_geozero::value::Value;
+//! use serde_geozero::DeserializeOptions;
+//!
+//! let schema = DynamicSchema::new()
+//!     .field("name", ColumnType::String)
+//!     .field("population", ColumnType::Double)
+//!     .optional("capital", ColumnType::Bool);
+//!
+//! let geojson = r#"{"type": "Feature", "properties": {"name": "Berlin", "population": 3669495},
+//!     "geometry": {"type": "Point", "coordinates": [13.4, 52.5]}}"#;
+//! let mut reader = geozero::geojson::GeoJsonReader(geojson.as_bytes());
+//! let cities = schema.read(&mut reader, &DeserializeOptions::new()).unwrap();
+//! assert_eq!(cities[0].get("population"), Some(&Value::Double(3_669_495.)));
+//! assert_eq!(cities[0].get("capital"), Some(&Value::Null));
+//! ```
+use std::sync::Arc;
+
+use geo::Geometry;
+use geozero::GeozeroDatasource;
+use serde::{ser::SerializeMap, Deserialize, Serialize, Serializer};
+
+use crate::{
+    de::{from_datasource_chunks, DeserializeOptions, Feature},
+    error::{Error, Result},
+    schema::ColumnType,
+    value::Value,
+};
+
+/// A field of a [`DynamicSchema`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DynamicField {
+    pub name: String,
+    pub column_type: ColumnType,
+    /// Whether features without a value for the field are rejected.
+    pub required: bool,
+}
+
+/// The fields expected in every feature, see the [module documentation](self).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DynamicSchema {
+    fields: Arc<Vec<DynamicField>>,
+}
+
+impl DynamicSchema {
+    /// A schema without fields, reading only the geometry.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a field every feature has to have a value of `column_type` for.
+    #[must_use]
+    pub fn field(self, name: &str, column_type: ColumnType) -> Self {
+        self.push(name, column_type, true)
+    }
+
+    /// Adds a field that may be missing or null.
+    #[must_use]
+    pub fn optional(self, name: &str, column_type: ColumnType) -> Self {
+        self.push(name, column_type, false)
+    }
+
+    fn push(mut self, name: &str, column_type: ColumnType, required: bool) -> Self {
+        Arc::make_mut(&mut self.fields).push(DynamicField {
+            name: name.to_string(),
+            column_type,
+            required,
+        });
+        self
+    }
+
+    /// The fields in the order they were added.
+    #[must_use]
+    pub fn fields(&self) -> &[DynamicField] {
+        &self.fields
+    }
+
+    /// The position of the field `name`.
+    #[must_use]
+    pub fn position(&self, name: &str) -> Option<usize> {
+        self.fields.iter().position(|field| field.name == name)
+    }
+
+    /// Reads the features of `source`, only requesting the fields of the schema.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of reading the datasource and [`Error::Feature`] with the error of
+    /// [`DynamicSchema::feature`] for the first feature not matching the schema.
+    pub fn read<S: GeozeroDatasource>(
+        &self,
+        source: &mut S,
+        options: &DeserializeOptions,
+    ) -> Result<Vec<DynamicFeature>> {
+        let names: Vec<&str> = self
+            .fields
+            .iter()
+            .map(|field| field.name.as_str())
+            .collect();
+        let mut features = Vec::new();
+        from_datasource_chunks(
+            source,
+            256,
+            &options.clone().columns(&names),
+            |chunk: &mut Vec<Feature>| {
+                for feature in chunk.drain(..) {
+                    let index = features.len();
+                    let feature = self.feature(feature).map_err(|err| Error::Feature {
+                        index,
+                        source: Box::new(err),
+                    })?;
+                    features.push(feature);
+                }
+                Ok(())
+            },
+        )?;
+        Ok(features)
+    }
+
+    /// Checks `feature` against the schema and converts its values to the field types.
+    ///
+    /// Integers are converted to wider integer and floating point types, strings are accepted
+    /// as date times. Properties without a field are dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::MissingField`] for a required field without value and [`Error::Field`]
+    /// with the cause for a value that can't be converted.
+    pub fn feature(&self, mut feature: Feature) -> Result<DynamicFeature> {
+        let values = self
+            .fields
+            .iter()
+            .map(|field| match feature.properties.remove(&field.name) {
+                None | Some(Value::Null) if field.required => {
+                    Err(Error::MissingField(field.name.clone()))
+                }
+                None | Some(Value::Null) => Ok(Value::Null),
+                Some(value) => convert(value, field.column_type).map_err(|err| Error::Field {
+                    field: field.name.clone(),
+                    source: Box::new(err),
+                }),
+            })
+            .collect::<Result<_>>()?;
+        Ok(DynamicFeature {
+            geometry: feature.geometry,
+            values,
+            schema: self.clone(),
+        })
+    }
+}
+
+fn convert(value: Value, column_type: ColumnType) -> Result<Value> {
+    let invalid = |value: &Value, expected: &str| Error::InvalidType {
+        found: format!("{value:?}"),
+        expected: expected.to_string(),
+    };
+    Ok(match column_type {
+        ColumnType::Byte => Value::Byte(i8::deserialize(value)?),
+        ColumnType::UByte => Value::UByte(u8::deserialize(value)?),
+        ColumnType::Bool => Value::Bool(bool::deserialize(value)?),
+        ColumnType::Short => Value::Short(i16::deserialize(value)?),
+        ColumnType::UShort => Value::UShort(u16::deserialize(value)?),
+        ColumnType::Int => Value::Int(i32::deserialize(value)?),
+        ColumnType::UInt => Value::UInt(u32::deserialize(value)?),
+        ColumnType::Long => Value::Long(i64::deserialize(value)?),
+        ColumnType::ULong => Value::ULong(u64::deserialize(value)?),
+        ColumnType::Float => Value::Float(f32::deserialize(value)?),
+        ColumnType::Double => Value::Double(f64::deserialize(value)?),
+        ColumnType::String => match value {
+            Value::String(_) | Value::Shared(_) => value,
+            value => return Err(invalid(&value, "a string")),
+        },
+        ColumnType::Json => match value {
+            Value::Json(_) => value,
+            value => return Err(invalid(&value, "JSON")),
+        },
+        ColumnType::DateTime => match value {
+            Value::DateTime(text) | Value::String(text) => Value::DateTime(text),
+            Value::Shared(text) => Value::DateTime(text.to_string()),
+            value => return Err(invalid(&value, "a date time")),
+        },
+        ColumnType::Binary => match value {
+            Value::Binary(_) => value,
+            value => return Err(invalid(&value, "binary data")),
+        },
+    })
+}
+
+/// A feature read with a [`DynamicSchema`], holding a value for every field.
+///
+/// Serializes like a struct with a `geometry` field followed by the fields of the schema, so it
+/// can be written with [`to_geozero_datasource`](crate::ser::to_geozero_datasource).
+#[derive(Debug, Clone, PartialEq)]
+pub struct DynamicFeature {
+    pub geometry: Geometry,
+    values: Vec<Value>,
+    schema: DynamicSchema,
+}
+
+impl DynamicFeature {
+    /// The value of the field `name`, `None` if the schema has no such field.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.schema
+            .position(name)
+            .map(|position| &self.values[position])
+    }
+
+    /// The values in the order of the schema's fields.
+    #[must_use]
+    pub fn values(&self) -> &[Value] {
+        &self.values
+    }
+
+    /// The schema the feature was read with.
+    #[must_use]
+    pub fn schema(&self) -> &DynamicSchema {
+        &self.schema
+    }
+}
+
+impl Serialize for DynamicFeature {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.values.len() + 1))?;
+        map.serialize_entry("geometry", &self.geometry)?;
+        for (field, value) in self.schema.fields.iter().zip(&self.values) {
+            map.serialize_entry(&field.name, value)?;
+        }
+        map.end()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use geozero::geojson::GeoJsonReader;
+
+    use super::DynamicSchema;
+    use crate::{
+        error::Error, geojson::to_geojson_string, schema::ColumnType, value::Value,
+        DeserializeOptions, SerializeOptions,
+    };
+
+    #[test]
+    fn dynamic_features() -> anyhow::Result<()> {
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "properties": {"id": 1, "name": "a", "extra": true},
+             "geometry": {"type": "Point", "coordinates": [1, 1]}},
+            {"type": "Feature", "properties": {"id": 300, "name": null},
+             "geometry": {"type": "Point", "coordinates": [2, 2]}}
+        ]}"#;
+        let read = |schema: &DynamicSchema| {
+            schema.read(
+                &mut GeoJsonReader(geojson.as_bytes()),
+                &DeserializeOptions::new(),
+            )
+        };
+
+        let schema = DynamicSchema::new()
+            .field("id", ColumnType::Long)
+            .optional("name", ColumnType::String);
+        let features = read(&schema)?;
+        assert_eq!(features[0].values(), [Value::Long(1), Value::from("a")]);
+        assert_eq!(features[1].get("name"), Some(&Value::Null));
+        assert_eq!(features[0].get("extra"), None);
+        let written = to_geojson_string(&features, &SerializeOptions::new())?;
+        assert!(written.contains(r#""properties": {"id": 1, "name": "a"}"#));
+
+        let err = read(&schema.clone().field("missing", ColumnType::Bool)).unwrap_err();
+        assert_eq!(err.feature_index(), Some(0));
+        assert!(matches!(err.root_cause(), Error::MissingField(field) if field == "missing"));
+
+        let err = read(&DynamicSchema::new().field("id", ColumnType::UByte)).unwrap_err();
+        assert_eq!(err.feature_index(), Some(1));
+        assert_eq!(err.field().as_deref(), Some("id"));
+
+        let err = read(&DynamicSchema::new().field("name", ColumnType::Double)).unwrap_err();
+        assert_eq!(err.field().as_deref(), Some("name"));
+        Ok(())
+    }
+}
@@ -0,0 +1,322 @@
+//! Copy-on-write editing of collected features.
+//!
+//! An [`EditSession`] borrows a slice of [`Feature`]s and records every property or geometry
+//! change as an [`Edit`]. Features are only cloned when they are first modified, so opening a
+//! session over a large dataset is cheap. The session can either produce the full edited dataset
+//! or just the list of changes, and every change can be undone.
+use geo::Geometry;
+use hashbrown::HashMap;
+use serde::Deserialize;
+
+use crate::{
+    de::Feature,
+    error::{Error, Result},
+    value::Value,
+};
+
+/// A single recorded change to a feature.
+///
+/// Every variant carries the previous state so the change can be reverted.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Edit {
+    /// A property was added or overwritten. `old` is `None` if the property did not exist.
+    SetProperty {
+        feature: usize,
+        name: String,
+        old: Option<Value>,
+        new: Value,
+    },
+    /// A property was removed.
+    RemoveProperty {
+        feature: usize,
+        name: String,
+        old: Value,
+    },
+    /// The geometry of a feature was replaced.
+    SetGeometry {
+        feature: usize,
+        old: Geometry,
+        new: Geometry,
+    },
+}
+
+impl Edit {
+    /// Index of the feature this edit applies to.
+    #[must_use]
+    pub fn feature(&self) -> usize {
+        match self {
+            Edit::SetProperty { feature, .. }
+            | Edit::RemoveProperty { feature, .. }
+            | Edit::SetGeometry { feature, .. } => *feature,
+        }
+    }
+}
+
+/// Records edits on top of a borrowed slice of features.
+///
+/// # Examples
+///
+/// ```
+/// use geo::point;
+/// use hashbrown::HashMap;
+/// use serde_geozero::de::Feature;
+/// use serde_geozero::edit::EditSession;
+/// use serde_geozero::value::Value;
+///
+/// let features = vec![Feature::new(
+///     (point! { x: 1.0, y: 2.0 }).into(),
+///     HashMap::from_iter(vec![("name".to_string(), Value::from("A"))]),
+/// )];
+///
+/// let mut session = EditSession::new(&features);
+/// session.set_property(0, "name", Value::from("B")).unwrap();
+/// assert_eq!(session.changelog().len(), 1);
+///
+/// session.undo();
+/// assert!(session.changelog().is_empty());
+/// assert_eq!(session.feature(0).unwrap().properties["name"], "A");
+/// ```
+pub struct EditSession<'a> {
+    original: &'a [Feature],
+    edited: HashMap<usize, Feature>,
+    changelog: Vec<Edit>,
+}
+
+impl<'a> EditSession<'a> {
+    #[must_use]
+    pub fn new(features: &'a [Feature]) -> Self {
+        Self {
+            original: features,
+            edited: HashMap::new(),
+            changelog: Vec::new(),
+        }
+    }
+
+    /// Number of features in the session.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.original.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.original.is_empty()
+    }
+
+    /// Returns the current state of a feature, including all edits applied so far.
+    #[must_use]
+    pub fn feature(&self, idx: usize) -> Option<&Feature> {
+        self.edited.get(&idx).or_else(|| self.original.get(idx))
+    }
+
+    /// Returns `true` if at least one edit has been recorded.
+    #[must_use]
+    pub fn is_dirty(&self) -> bool {
+        !self.changelog.is_empty()
+    }
+
+    /// All edits in the order they were applied.
+    #[must_use]
+    pub fn changelog(&self) -> &[Edit] {
+        &self.changelog
+    }
+
+    /// Sets a property on the feature at `idx`, adding it if it does not exist yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::FeatureIndexOutOfBounds`] if there is no feature at `idx`.
+    pub fn set_property(&mut self, idx: usize, name: &str, value: Value) -> Result<()> {
+        let feature = self.feature_mut(idx)?;
+        let old = feature.properties.insert(name.to_string(), value.clone());
+        self.changelog.push(Edit::SetProperty {
+            feature: idx,
+            name: name.to_string(),
+            old,
+            new: value,
+        });
+        Ok(())
+    }
+
+    /// Removes a property from the feature at `idx` and returns its value.
+    ///
+    /// Removing a property that does not exist is not recorded as an edit.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::FeatureIndexOutOfBounds`] if there is no feature at `idx`.
+    pub fn remove_property(&mut self, idx: usize, name: &str) -> Result<Option<Value>> {
+        if !self
+            .feature(idx)
+            .ok_or(Error::FeatureIndexOutOfBounds(idx))?
+            .properties
+            .contains_key(name)
+        {
+            return Ok(None);
+        }
+
+        let old = self.feature_mut(idx)?.properties.remove(name);
+        if let Some(old) = &old {
+            self.changelog.push(Edit::RemoveProperty {
+                feature: idx,
+                name: name.to_string(),
+                old: old.clone(),
+            });
+        }
+        Ok(old)
+    }
+
+    /// Replaces the geometry of the feature at `idx`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::FeatureIndexOutOfBounds`] if there is no feature at `idx`.
+    pub fn set_geometry(&mut self, idx: usize, geometry: Geometry) -> Result<()> {
+        let feature = self.feature_mut(idx)?;
+        let old = std::mem::replace(&mut feature.geometry, geometry.clone());
+        self.changelog.push(Edit::SetGeometry {
+            feature: idx,
+            old,
+            new: geometry,
+        });
+        Ok(())
+    }
+
+    /// Reverts the most recent edit and returns it, or `None` if there is nothing to undo.
+    pub fn undo(&mut self) -> Option<Edit> {
+        let edit = self.changelog.pop()?;
+        // features are cloned into `edited` before any edit on them is recorded
+        if let Some(feature) = self.edited.get_mut(&edit.feature()) {
+            match &edit {
+                Edit::SetProperty {
+                    name, old: None, ..
+                } => {
+                    feature.properties.remove(name);
+                }
+                Edit::SetProperty {
+                    name,
+                    old: Some(old),
+                    ..
+                }
+                | Edit::RemoveProperty { name, old, .. } => {
+                    feature.properties.insert(name.clone(), old.clone());
+                }
+                Edit::SetGeometry { old, .. } => feature.geometry = old.clone(),
+            }
+        }
+
+        if !self.changelog.iter().any(|e| e.feature() == edit.feature()) {
+            self.edited.remove(&edit.feature());
+        }
+
+        Some(edit)
+    }
+
+    /// Consumes the session and returns the full dataset with all edits applied.
+    #[must_use]
+    pub fn into_features(mut self) -> Vec<Feature> {
+        self.original
+            .iter()
+            .enumerate()
+            .map(|(idx, feature)| self.edited.remove(&idx).unwrap_or_else(|| feature.clone()))
+            .collect()
+    }
+
+    /// Consumes the session and deserializes the edited dataset into `T`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a feature cannot be deserialized into `T`.
+    pub fn into_typed<'de, T: Deserialize<'de>>(self) -> Result<Vec<T>> {
+        self.into_features()
+            .into_iter()
+            .map(T::deserialize)
+            .collect()
+    }
+
+    /// Consumes the session and returns only the recorded edits.
+    #[must_use]
+    pub fn into_changelog(self) -> Vec<Edit> {
+        self.changelog
+    }
+
+    fn feature_mut(&mut self, idx: usize) -> Result<&mut Feature> {
+        let original = self
+            .original
+            .get(idx)
+            .ok_or(Error::FeatureIndexOutOfBounds(idx))?;
+        Ok(self.edited.entry(idx).or_insert_with(|| original.clone()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use geo::{point, Geometry};
+    use hashbrown::HashMap;
+    use serde::Deserialize;
+
+    use super::{Edit, EditSession};
+    use crate::{de::Feature, value::Value};
+
+    fn features() -> Vec<Feature> {
+        vec![
+            Feature::new(
+                (point! { x: 1.0, y: 2.0 }).into(),
+                HashMap::from_iter(vec![("name".to_string(), Value::from("A"))]),
+            ),
+            Feature::new(
+                (point! { x: 3.0, y: 4.0 }).into(),
+                HashMap::from_iter(vec![("name".to_string(), Value::from("B"))]),
+            ),
+        ]
+    }
+
+    #[test]
+    fn edits_are_recorded_and_applied() -> anyhow::Result<()> {
+        #[derive(Deserialize)]
+        struct Named {
+            geometry: Geometry,
+            name: String,
+        }
+
+        let features = features();
+        let mut session = EditSession::new(&features);
+        session.set_property(1, "name", Value::from("C"))?;
+        session.set_geometry(0, (point! { x: 5.0, y: 6.0 }).into())?;
+        assert!(session.set_property(2, "name", Value::from("D")).is_err());
+
+        assert_eq!(session.changelog().len(), 2);
+        assert!(matches!(
+            session.changelog()[0],
+            Edit::SetProperty { feature: 1, .. }
+        ));
+        // the source is left untouched
+        assert_eq!(features[1].properties["name"], Value::from("B"));
+
+        let named: Vec<Named> = session.into_typed()?;
+        assert_eq!(named[0].geometry, (point! { x: 5.0, y: 6.0 }).into());
+        assert_eq!(named[1].name, "C");
+        Ok(())
+    }
+
+    #[test]
+    fn undo_restores_previous_state() -> anyhow::Result<()> {
+        let features = features();
+        let mut session = EditSession::new(&features);
+        session.set_property(0, "extra", Value::from(1))?;
+        assert_eq!(session.remove_property(0, "name")?, Some(Value::from("A")));
+        assert_eq!(session.remove_property(0, "missing")?, None);
+        assert_eq!(session.changelog().len(), 2);
+
+        session.undo();
+        assert_eq!(
+            session.feature(0).unwrap().properties["name"],
+            Value::from("A")
+        );
+        session.undo();
+        assert!(!session.feature(0).unwrap().properties.contains_key("extra"));
+        assert!(!session.is_dirty());
+        assert!(session.undo().is_none());
+        Ok(())
+    }
+}
@@ -0,0 +1,425 @@
+//! Geometry fields read from WKT or WKB encoded properties.
+//!
+//! CSV files and side tables often keep a second geometry in a regular column. Name the column
+//! with [`DeserializeOptions::geometry_column`](crate::DeserializeOptions::geometry_column) to
+//! decode it for struct fields of type `geo::Geometry`:
+//!
+//! ```
+//! use geo::Geometry;
+//! use serde::Deserialize;
+//! use serde_geozero::encoded::GeometryEncoding;
+//! use serde_geozero::{from_datasource_with_options, DeserializeOptions};
+//!
+//! #[derive(Deserialize)]
+//! struct Parcel {
+//!     geometry: Geometry,
+//!     entrance: Option<Geometry>,
+//! }
+//!
+//! let geojson = r#"{"type": "Feature", "properties": {"entrance": "POINT(1 2)"},
+//!     "geometry": {"type": "Point", "coordinates": [1, 1]}}"#;
+//!
+//! let options = DeserializeOptions::new().geometry_column("entrance", GeometryEncoding::Wkt);
+//! let mut reader = geozero::geojson::GeoJsonReader(geojson.as_bytes());
+//! let parcels: Vec<Parcel> = from_datasource_with_options(&mut reader, &options).unwrap();
+//! assert_eq!(parcels[0].entrance, Some(geo::point! { x: 1., y: 2. }.into()));
+//! ```
+//!
+//! The properties themselves keep the encoded value, e.g. when reading into
+//! [`Feature`](crate::de::Feature)s. Z and M values are dropped. The adapters of
+//! [`crate::with`] write and read the same encodings for single struct fields.
+use std::fmt::Write;
+
+use geo::{Coord, Geometry, LineString, MultiLineString, MultiPoint};
+use geo::{MultiPolygon, Point, Polygon};
+use geozero::{
+    error::Result as GeozeroResult,
+    geo_types::GeoWriter,
+    wkb::{Ewkb, Wkb},
+    wkt::Wkt,
+    CoordDimensions, GeomProcessor, GeozeroGeometry, ToGeo, ToWkb, ToWkt,
+};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{
+    coord::{GeometryDeserializer, Rounding},
+    error::{Error, Result},
+    value::Value,
+};
+
+/// How a geometry is encoded in a property.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GeometryEncoding {
+    /// Well-known text, e.g. `POINT(1 2)`.
+    Wkt,
+    /// Well-known binary in a binary column or as a hex string, with the ISO type codes for Z
+    /// and M values or the Z, M and SRID flags of `PostGIS`' EWKB.
+    Wkb,
+    /// A `GeoJSON` geometry object, e.g. `{"type": "Point", "coordinates": [1, 2]}`.
+    GeoJson,
+}
+
+impl GeometryEncoding {
+    /// Decodes the geometry held by `value`.
+    pub(crate) fn decode(self, value: &Value) -> Result<Geometry> {
+        match (self, value) {
+            (GeometryEncoding::Wkt, value) => {
+                let text = value
+                    .as_str()
+                    .ok_or_else(|| expected("a WKT string", value))?;
+                Ok(Wkt(text).to_geo()?)
+            }
+            (GeometryEncoding::Wkb, Value::Binary(bytes)) => read_wkb(bytes),
+            (GeometryEncoding::Wkb, value) => {
+                let hex = value.as_str().ok_or_else(|| expected("WKB", value))?;
+                read_wkb(&decode_hex(hex)?)
+            }
+            (GeometryEncoding::GeoJson, Value::Json(json)) => {
+                from_geojson(serde_json::from_str(json)?)
+            }
+            (GeometryEncoding::GeoJson, value) => {
+                let text = value
+                    .as_str()
+                    .ok_or_else(|| expected("a GeoJSON geometry", value))?;
+                from_geojson(serde_json::from_str(text)?)
+            }
+        }
+    }
+}
+
+/// Reads the geometry of a serialized field: `null`, a `geo` geometry, a `GeoJSON` geometry
+/// object, or a WKT or hex WKB string.
+pub(crate) fn decode_json(value: serde_json::Value) -> Result<Option<Geometry>> {
+    match value {
+        serde_json::Value::Null => Ok(None),
+        serde_json::Value::String(text) => {
+            let encoding = if !text.is_empty() && text.bytes().all(|b| b.is_ascii_hexdigit()) {
+                GeometryEncoding::Wkb
+            } else {
+                GeometryEncoding::Wkt
+            };
+            encoding.decode(&Value::String(text)).map(Some)
+        }
+        serde_json::Value::Object(object) if object.contains_key("type") => {
+            from_geojson(serde_json::Value::Object(object)).map(Some)
+        }
+        value => Ok(Some(Geometry::deserialize(GeometryDeserializer::new(
+            value,
+            Rounding::default(),
+        ))?)),
+    }
+}
+
+pub(crate) fn to_wkt(geometry: &Geometry) -> Result<String> {
+    Ok(geometry.to_wkt()?)
+}
+
+/// Writes little endian OGC WKB, lines become `LineString`s and rectangles and triangles
+/// `Polygon`s.
+pub(crate) fn to_wkb_hex(geometry: &Geometry) -> Result<String> {
+    let bytes = geometry.to_wkb(CoordDimensions::xy())?;
+    let mut hex = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        let _ = write!(hex, "{byte:02X}");
+    }
+    Ok(hex)
+}
+
+/// Writes a `GeoJSON` geometry object, lines become `LineString`s and rectangles and triangles
+/// `Polygon`s.
+pub(crate) fn to_geojson(geometry: &Geometry) -> serde_json::Value {
+    let position = |coord: &Coord| json!([coord.x, coord.y]);
+    let line = |line: &LineString| line.coords().map(position).collect::<Vec<_>>();
+    let rings = |polygon: &Polygon| {
+        std::iter::once(polygon.exterior())
+            .chain(polygon.interiors())
+            .map(line)
+            .collect::<Vec<_>>()
+    };
+    match geometry {
+        Geometry::Point(point) => json!({"type": "Point", "coordinates": position(&point.0)}),
+        Geometry::Line(segment) => json!({
+            "type": "LineString",
+            "coordinates": [position(&segment.start), position(&segment.end)],
+        }),
+        Geometry::LineString(line_string) => {
+            json!({"type": "LineString", "coordinates": line(line_string)})
+        }
+        Geometry::Polygon(polygon) => json!({"type": "Polygon", "coordinates": rings(polygon)}),
+        Geometry::MultiPoint(points) => json!({
+            "type": "MultiPoint",
+            "coordinates": points.iter().map(|point| position(&point.0)).collect::<Vec<_>>(),
+        }),
+        Geometry::MultiLineString(lines) => json!({
+            "type": "MultiLineString",
+            "coordinates": lines.iter().map(line).collect::<Vec<_>>(),
+        }),
+        Geometry::MultiPolygon(polygons) => json!({
+            "type": "MultiPolygon",
+            "coordinates": polygons.iter().map(rings).collect::<Vec<_>>(),
+        }),
+        Geometry::GeometryCollection(collection) => json!({
+            "type": "GeometryCollection",
+            "geometries": collection.iter().map(to_geojson).collect::<Vec<_>>(),
+        }),
+        Geometry::Rect(rect) => to_geojson(&rect.to_polygon().into()),
+        Geometry::Triangle(triangle) => to_geojson(&triangle.to_polygon().into()),
+    }
+}
+
+type Positions = Vec<Vec<f64>>;
+
+pub(crate) fn from_geojson(mut value: serde_json::Value) -> Result<Geometry> {
+    fn position(position: &[f64]) -> Result<Coord> {
+        match position {
+            [x, y, ..] => Ok(Coord { x: *x, y: *y }),
+            _ => Err(Error::Message(format!(
+                "invalid GeoJSON position {position:?}"
+            ))),
+        }
+    }
+    fn line(positions: &[Vec<f64>]) -> Result<LineString> {
+        positions.iter().map(|p| position(p)).collect()
+    }
+    fn polygon(rings: &[Positions]) -> Result<Polygon> {
+        let mut rings = rings.iter().map(|ring| line(ring));
+        let exterior = rings
+            .next()
+            .transpose()?
+            .unwrap_or_else(|| LineString::new(Vec::new()));
+        Ok(Polygon::new(exterior, rings.collect::<Result<_>>()?))
+    }
+
+    let kind = value["type"].as_str().unwrap_or_default().to_string();
+    if kind == "GeometryCollection" {
+        let geometries = match value["geometries"].take() {
+            serde_json::Value::Array(geometries) => geometries,
+            _ => Vec::new(),
+        };
+        return Ok(Geometry::GeometryCollection(
+            geometries
+                .into_iter()
+                .map(from_geojson)
+                .collect::<Result<_>>()?,
+        ));
+    }
+    let coordinates = value["coordinates"].take();
+    Ok(match kind.as_str() {
+        "Point" => Point::from(position(&Vec::deserialize(coordinates)?)?).into(),
+        "LineString" => line(&Positions::deserialize(coordinates)?)?.into(),
+        "Polygon" => polygon(&Vec::deserialize(coordinates)?)?.into(),
+        "MultiPoint" => MultiPoint::new(
+            Positions::deserialize(coordinates)?
+                .iter()
+                .map(|p| position(p).map(Point::from))
+                .collect::<Result<_>>()?,
+        )
+        .into(),
+        "MultiLineString" => MultiLineString::new(
+            Vec::<Positions>::deserialize(coordinates)?
+                .iter()
+                .map(|positions| line(positions))
+                .collect::<Result<_>>()?,
+        )
+        .into(),
+        "MultiPolygon" => MultiPolygon::new(
+            Vec::<Vec<Positions>>::deserialize(coordinates)?
+                .iter()
+                .map(|rings| polygon(rings))
+                .collect::<Result<_>>()?,
+        )
+        .into(),
+        kind => {
+            return Err(Error::Message(format!(
+                "unsupported GeoJSON geometry type {kind:?}"
+            )))
+        }
+    })
+}
+
+fn expected(expected: &str, value: &Value) -> Error {
+    Error::InvalidType {
+        found: value.to_string(),
+        expected: expected.to_string(),
+    }
+}
+
+fn decode_hex(hex: &str) -> Result<Vec<u8>> {
+    let invalid = || Error::Message(format!("invalid hex WKB {hex:?}"));
+    if !hex.len().is_multiple_of(2) {
+        return Err(invalid());
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|start| {
+            hex.get(start..start + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .ok_or_else(invalid)
+        })
+        .collect()
+}
+
+/// The Z, M and SRID flags of `PostGIS`' EWKB type codes.
+const EWKB_FLAGS: u32 = 0xe000_0000;
+
+/// Reads OGC WKB, including the ISO type codes with Z and M values, or EWKB, told apart by the
+/// EWKB flags of the type code.
+fn read_wkb(bytes: &[u8]) -> Result<Geometry> {
+    let code = match bytes {
+        [order, a, b, c, d, ..] if *order == 0 => u32::from_be_bytes([*a, *b, *c, *d]),
+        [_, a, b, c, d, ..] => u32::from_le_bytes([*a, *b, *c, *d]),
+        _ => 0,
+    };
+    let mut writer = BoundedWriter {
+        writer: GeoWriter::new(),
+        max_size: bytes.len(),
+    };
+    if code & EWKB_FLAGS == 0 {
+        Wkb(bytes).process_geom(&mut writer)?;
+    } else {
+        Ewkb(bytes).process_geom(&mut writer)?;
+    }
+    writer
+        .writer
+        .take_geometry()
+        .ok_or_else(|| Error::Message("empty WKB geometry".to_string()))
+}
+
+/// A `GeoWriter` that reserves space for at most `max_size` elements.
+///
+/// The sizes passed to the processor are element counts read from the WKB, a bogus count would
+/// reserve gigabytes before the reader runs out of bytes. Every element takes at least one byte.
+struct BoundedWriter {
+    writer: GeoWriter,
+    max_size: usize,
+}
+
+impl GeomProcessor for BoundedWriter {
+    fn xy(&mut self, x: f64, y: f64, idx: usize) -> GeozeroResult<()> {
+        self.writer.xy(x, y, idx)
+    }
+
+    fn point_begin(&mut self, idx: usize) -> GeozeroResult<()> {
+        self.writer.point_begin(idx)
+    }
+
+    fn point_end(&mut self, idx: usize) -> GeozeroResult<()> {
+        self.writer.point_end(idx)
+    }
+
+    fn multipoint_begin(&mut self, size: usize, idx: usize) -> GeozeroResult<()> {
+        self.writer.multipoint_begin(size.min(self.max_size), idx)
+    }
+
+    fn multipoint_end(&mut self, idx: usize) -> GeozeroResult<()> {
+        self.writer.multipoint_end(idx)
+    }
+
+    fn linestring_begin(&mut self, tagged: bool, size: usize, idx: usize) -> GeozeroResult<()> {
+        self.writer
+            .linestring_begin(tagged, size.min(self.max_size), idx)
+    }
+
+    fn linestring_end(&mut self, tagged: bool, idx: usize) -> GeozeroResult<()> {
+        self.writer.linestring_end(tagged, idx)
+    }
+
+    fn multilinestring_begin(&mut self, size: usize, idx: usize) -> GeozeroResult<()> {
+        self.writer
+            .multilinestring_begin(size.min(self.max_size), idx)
+    }
+
+    fn multilinestring_end(&mut self, idx: usize) -> GeozeroResult<()> {
+        self.writer.multilinestring_end(idx)
+    }
+
+    fn polygon_begin(&mut self, tagged: bool, size: usize, idx: usize) -> GeozeroResult<()> {
+        self.writer
+            .polygon_begin(tagged, size.min(self.max_size), idx)
+    }
+
+    fn polygon_end(&mut self, tagged: bool, idx: usize) -> GeozeroResult<()> {
+        self.writer.polygon_end(tagged, idx)
+    }
+
+    fn multipolygon_begin(&mut self, size: usize, idx: usize) -> GeozeroResult<()> {
+        self.writer.multipolygon_begin(size.min(self.max_size), idx)
+    }
+
+    fn multipolygon_end(&mut self, idx: usize) -> GeozeroResult<()> {
+        self.writer.multipolygon_end(idx)
+    }
+
+    fn geometrycollection_begin(&mut self, size: usize, idx: usize) -> GeozeroResult<()> {
+        self.writer
+            .geometrycollection_begin(size.min(self.max_size), idx)
+    }
+
+    fn geometrycollection_end(&mut self, idx: usize) -> GeozeroResult<()> {
+        self.writer.geometrycollection_end(idx)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use geo::{line_string, point, polygon, Geometry, MultiPoint};
+
+    use super::{to_wkb_hex, GeometryEncoding};
+    use crate::value::Value;
+
+    #[test]
+    fn wkb() -> anyhow::Result<()> {
+        use geozero::{CoordDimensions, ToWkb};
+
+        let point = Geometry::from(point! { x: 1., y: 2. });
+        let hex = to_wkb_hex(&point)?;
+        assert_eq!(hex, "0101000000000000000000F03F0000000000000040");
+        assert_eq!(
+            GeometryEncoding::Wkb.decode(&Value::from(hex.as_str()))?,
+            point
+        );
+
+        let line = Geometry::from(line_string![(x: 0., y: 0.), (x: 1., y: 1.)]);
+        for bytes in [
+            line.to_ewkb(CoordDimensions::xyz(), Some(4326))?,
+            line.to_wkb(CoordDimensions::xyzm())?,
+        ] {
+            assert_eq!(GeometryEncoding::Wkb.decode(&Value::Binary(bytes))?, line);
+        }
+        let multi = Geometry::from(MultiPoint::from(vec![point! { x: 1., y: 2. }]));
+        assert_eq!(
+            GeometryEncoding::Wkb.decode(&Value::from(to_wkb_hex(&multi)?.as_str()))?,
+            multi
+        );
+
+        // a line string claiming 4294967295 coordinates
+        let bogus = "0102000000FFFFFFFF";
+        assert!(GeometryEncoding::Wkb.decode(&Value::from(bogus)).is_err());
+        assert!(GeometryEncoding::Wkb.decode(&Value::from("0101")).is_err());
+        assert!(GeometryEncoding::Wkb.decode(&Value::from(1)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn wkt() -> anyhow::Result<()> {
+        assert_eq!(
+            GeometryEncoding::Wkt.decode(&Value::from("LINESTRING(0 0, 1 1)"))?,
+            Geometry::from(line_string![(x: 0., y: 0.), (x: 1., y: 1.)])
+        );
+        assert_eq!(
+            GeometryEncoding::Wkt.decode(&Value::from("POLYGON((0 0, 1 0, 1 1, 0 0))"))?,
+            Geometry::from(polygon![(x: 0., y: 0.), (x: 1., y: 0.), (x: 1., y: 1.)])
+        );
+        assert!(GeometryEncoding::Wkt
+            .decode(&Value::from("POINT("))
+            .is_err());
+        assert_eq!(
+            GeometryEncoding::GeoJson.decode(&Value::Json(
+                r#"{"type": "LineString", "coordinates": [[0, 0], [1, 1]]}"#.to_string()
+            ))?,
+            Geometry::from(line_string![(x: 0., y: 0.), (x: 1., y: 1.)])
+        );
+        Ok(())
+    }
+}
@@ -1,5 +1,9 @@
 use std::fmt::Display;
 
+use geozero::error::GeozeroError;
+
+use crate::de::Feature;
+
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     #[error("Error while processing the geozero source: {}.", .0.to_string())]
@@ -11,12 +15,195 @@ pub enum Error {
     #[error("An error happend: {:?}.", .0)]
     Message(String),
 
+    #[cfg(feature = "proj")]
+    #[error("Error while setting up the reprojection: {}", .0.to_string())]
+    ProjCreateError(#[from] proj::ProjCreateError),
+
+    #[cfg(feature = "proj")]
+    #[error("Error while reprojecting: {}", .0.to_string())]
+    ProjError(#[from] proj::ProjError),
+
+    #[cfg(feature = "flatgeobuf")]
+    #[error("Error while reading FlatGeobuf: {}", .0.to_string())]
+    FlatGeobufError(#[from] flatgeobuf::Error),
+
+    #[error("Unsupported file format: {0}.")]
+    UnsupportedFormat(String),
+
+    #[error("Feature {0} has no geometry.")]
+    MissingGeometry(usize),
+
+    #[error("Feature {index} has an invalid geometry: {reason}.")]
+    InvalidGeometry { index: usize, reason: String },
+
+    #[error(
+        "Expected a {expected} geometry but found a {found}{}",
+        feature_idx.map(|idx| format!(" in feature {idx}")).unwrap_or_default()
+    )]
+    GeometryTypeMismatch {
+        expected: String,
+        found: String,
+        /// The index of the feature, set once the error left the geometry field.
+        feature_idx: Option<usize>,
+    },
+
+    #[error("Feature {index} has a non-finite value in {location}.")]
+    NonFinite { index: usize, location: String },
+
+    #[error("Property {name:?} of feature {index} is null.")]
+    NullProperty { name: String, index: usize },
+
+    #[error("Feature {0} has no non-negative integer fid property.")]
+    InvalidFid(usize),
+
+    #[error("Feature index {0} is out of bounds.")]
+    FeatureIndexOutOfBounds(usize),
+
+    #[error("Feature has no discriminator property {0:?}.")]
+    MissingDiscriminator(String),
+
+    #[error("No type registered for discriminator {0:?}.")]
+    UnregisteredType(String),
+
+    #[error("Schema mismatch: {0}.")]
+    SchemaMismatch(String),
+
+    #[error(
+        "Feature has more than {0} properties, deserialize into a struct without flattened fields \
+         to only read its fields or raise DeserializeOptions::max_properties."
+    )]
+    TooManyProperties(usize),
+
+    #[error("Cancelled before feature {0}.")]
+    Cancelled(usize),
+
+    #[error("Feature {index}: {source}")]
+    Feature { index: usize, source: Box<Error> },
+
+    #[error("field {field:?}: {source}")]
+    Field { field: String, source: Box<Error> },
+
+    #[error("invalid type: {found}, expected {expected}")]
+    InvalidType { found: String, expected: String },
+
+    #[error("missing field {0:?}")]
+    MissingField(String),
+
+    #[error("Key {0} is used by more than one feature.")]
+    DuplicateKey(String),
+
+    #[error("Error while sending: {0}")]
+    Sink(Box<dyn std::error::Error + Send + Sync>),
+
     #[error("Unknown error")]
     Unknown,
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+impl Error {
+    /// The index of the feature that failed to deserialize, if known.
+    #[must_use]
+    pub fn feature_index(&self) -> Option<usize> {
+        match self {
+            Error::Feature { index, .. } => Some(*index),
+            _ => None,
+        }
+    }
+
+    /// The dotted path of the field that failed to deserialize, if known.
+    #[must_use]
+    pub fn field(&self) -> Option<String> {
+        let mut path = Vec::new();
+        let mut err = self;
+        loop {
+            match err {
+                Error::Feature { source, .. } => err = source,
+                Error::Field { field, source } => {
+                    path.push(field.as_str());
+                    err = source;
+                }
+                _ => break,
+            }
+        }
+        (!path.is_empty()).then(|| path.join("."))
+    }
+
+    /// The error without the feature and field context.
+    #[must_use]
+    pub fn root_cause(&self) -> &Error {
+        match self {
+            Error::Feature { source, .. } | Error::Field { source, .. } => source.root_cause(),
+            err => err,
+        }
+    }
+
+    /// Records the index of the feature `self` was raised for where the error has a field for
+    /// it.
+    pub(crate) fn with_feature_index(mut self, index: usize) -> Self {
+        let mut err = &mut self;
+        while let Error::Feature { source, .. } | Error::Field { source, .. } = err {
+            err = source;
+        }
+        if let Error::GeometryTypeMismatch { feature_idx, .. } = err {
+            *feature_idx = Some(index);
+        }
+        self
+    }
+}
+
+/// A feature that could not be deserialized.
+#[derive(Debug)]
+pub struct FeatureError {
+    /// Position of the feature in the datasource.
+    pub index: usize,
+    pub error: Error,
+    /// The raw feature. Changes made to it are used when the feature is retried.
+    pub feature: Feature,
+}
+
+/// How to continue after a [`FeatureError`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorDecision {
+    /// Leave the feature out and continue with the next one.
+    Skip,
+    /// Stop processing and return the error.
+    Abort,
+    /// Deserialize the (possibly modified) feature again. Features are retried at most three
+    /// times before processing is aborted.
+    Retry,
+}
+
+/// Decides how to continue after a feature failed to deserialize.
+pub type ErrorCallback = fn(&mut FeatureError) -> ErrorDecision;
+
+/// What happens with features that can't be read, see
+/// [`DeserializeOptions::error_policy`](crate::DeserializeOptions::error_policy).
+#[derive(Debug, Clone, Copy, Default)]
+pub enum ErrorPolicy {
+    /// Stop reading and return the error.
+    #[default]
+    Fail,
+    /// Leave the feature out and continue with the next one.
+    Skip,
+    /// Pass the error and the raw feature to the callback, e.g. for logging, and leave the
+    /// feature out.
+    SkipWithCallback(fn(&FeatureError)),
+}
+
+/// Converts a crate error for use inside geozero's processor traits.
+///
+/// Geozero errors are passed through unchanged, all other errors become
+/// [`GeozeroError::Feature`] carrying the error message.
+impl From<Error> for GeozeroError {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::GeozeroError(err) => err,
+            err => GeozeroError::Feature(err.to_string()),
+        }
+    }
+}
+
 impl serde::de::Error for Error {
     fn custom<T>(msg: T) -> Self
     where
@@ -24,6 +211,17 @@ impl serde::de::Error for Error {
     {
         Error::Message(msg.to_string())
     }
+
+    fn invalid_type(unexp: serde::de::Unexpected, exp: &dyn serde::de::Expected) -> Self {
+        Error::InvalidType {
+            found: unexp.to_string(),
+            expected: exp.to_string(),
+        }
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        Error::MissingField(field.to_string())
+    }
 }
 
 impl serde::ser::Error for Error {
@@ -0,0 +1,146 @@
+//! Handling of NaN and infinite values in coordinates and properties.
+//!
+//! `GeoJSON` has no representation for them, so writers produce output other parsers reject,
+//! and computations on read geometries silently turn into NaN. Select a [`NonFinite`] policy
+//! with [`SerializeOptions::non_finite`](crate::SerializeOptions::non_finite) or
+//! [`DeserializeOptions::non_finite`](crate::DeserializeOptions::non_finite):
+//!
+//! ```
+//! use geo::{point, Geometry};
+//! use serde::Serialize;
+//! use serde_geozero::finite::NonFinite;
+//! use serde_geozero::geojson::to_geojson_string;
+//! use serde_geozero::SerializeOptions;
+//!
+//! #[derive(Serialize)]
+//! struct Sample {
+//!     geometry: Geometry,
+//!     value: f64,
+//! }
+//!
+//! let samples = [
+//!     Sample { geometry: point! { x: 1., y: 2. }.into(), value: 1. },
+//!     Sample { geometry: point! { x: f64::NAN, y: 2. }.into(), value: 2. },
+//! ];
+//! let options = SerializeOptions::new().non_finite(NonFinite::SkipFeature);
+//! let geojson = to_geojson_string(&samples, &options).unwrap();
+//! assert!(!geojson.contains("NaN"));
+//! ```
+//!
+//! Geometry fields pass through serde as JSON, which has no infinite numbers either, so
+//! infinite coordinates of serialized structs arrive as NaN.
+use geo::{CoordsIter, Geometry, MapCoordsInPlace};
+use hashbrown::HashMap;
+
+use crate::value::Value;
+
+/// How NaN and infinite coordinates and property values are handled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NonFinite {
+    /// Pass coordinates on unchecked, the writers of [`crate::geojson`] fail on them. Serialized
+    /// properties are left out, like JSON numbers that can't be represented.
+    #[default]
+    Allow,
+    /// Fail with [`Error::NonFinite`](crate::error::Error::NonFinite). While deserializing,
+    /// the error policy decides, like for any feature that failed.
+    Error,
+    /// Leave the feature out.
+    SkipFeature,
+    /// Clamp infinities to the largest finite value and replace NaN properties by null.
+    ///
+    /// A geometry with NaN coordinates is dropped, so the feature is handled like one without
+    /// a geometry.
+    ClampOrNull,
+}
+
+/// Where the first non-finite value of a feature is, `"geometry"` or the property name.
+pub(crate) fn find(
+    geometry: Option<&Geometry>,
+    properties: &HashMap<String, Value>,
+) -> Option<String> {
+    if geometry.is_some_and(|geometry| {
+        geometry
+            .coords_iter()
+            .any(|coord| !coord.x.is_finite() || !coord.y.is_finite())
+    }) {
+        return Some("geometry".to_string());
+    }
+    let mut names: Vec<&String> = properties
+        .iter()
+        .filter(|(_, value)| !is_finite(value))
+        .map(|(name, _)| name)
+        .collect();
+    names.sort_unstable();
+    names.first().map(|name| (*name).clone())
+}
+
+fn is_finite(value: &Value) -> bool {
+    match value {
+        Value::Float(value) => value.is_finite(),
+        Value::Double(value) => value.is_finite(),
+        _ => true,
+    }
+}
+
+/// Applies [`NonFinite::ClampOrNull`], returns `false` if the geometry has NaN coordinates.
+pub(crate) fn clamp(
+    geometry: Option<&mut Geometry>,
+    properties: &mut HashMap<String, Value>,
+) -> bool {
+    for value in properties.values_mut() {
+        match value {
+            Value::Float(float) if float.is_nan() => *value = Value::Null,
+            Value::Float(float) => *float = float.clamp(f32::MIN, f32::MAX),
+            Value::Double(double) if double.is_nan() => *value = Value::Null,
+            Value::Double(double) => *double = double.clamp(f64::MIN, f64::MAX),
+            _ => {}
+        }
+    }
+    let Some(geometry) = geometry else {
+        return true;
+    };
+    if geometry
+        .coords_iter()
+        .any(|coord| coord.x.is_nan() || coord.y.is_nan())
+    {
+        return false;
+    }
+    geometry.map_coords_in_place(|mut coord| {
+        coord.x = coord.x.clamp(f64::MIN, f64::MAX);
+        coord.y = coord.y.clamp(f64::MIN, f64::MAX);
+        coord
+    });
+    true
+}
+
+#[cfg(test)]
+mod test {
+    use geo::{point, Geometry};
+    use hashbrown::HashMap;
+
+    use super::{clamp, find};
+    use crate::value::Value;
+
+    #[test]
+    fn non_finite_values() {
+        let mut geometry: Geometry = point! { x: f64::INFINITY, y: 1. }.into();
+        let mut properties = HashMap::from([
+            ("a".to_string(), Value::Double(f64::NAN)),
+            ("b".to_string(), Value::Float(f32::NEG_INFINITY)),
+        ]);
+        assert_eq!(
+            find(Some(&geometry), &properties).as_deref(),
+            Some("geometry")
+        );
+        assert_eq!(find(None, &properties).as_deref(), Some("a"));
+
+        assert!(clamp(Some(&mut geometry), &mut properties));
+        assert_eq!(geometry, point! { x: f64::MAX, y: 1. }.into());
+        assert_eq!(properties["a"], Value::Null);
+        assert_eq!(properties["b"], Value::Float(f32::MIN));
+        assert_eq!(find(Some(&geometry), &properties), None);
+
+        let mut nan: Geometry = point! { x: f64::NAN, y: 1. }.into();
+        assert!(!clamp(Some(&mut nan), &mut properties));
+    }
+}
@@ -0,0 +1,259 @@
+//! Reading files without knowing their format up front.
+//!
+//! [`from_reader`] sniffs the first bytes of the input to pick the matching `geozero` reader,
+//! [`from_reader_with_format`] skips the detection:
+//!
+//! ```
+//! use geo::Geometry;
+//! use serde::Deserialize;
+//! use serde_geozero::format::{from_reader, Format};
+//!
+//! #[derive(Deserialize)]
+//! struct Country {
+//!     geometry: Geometry,
+//!     name: String,
+//! }
+//!
+//! let mut file = std::io::BufReader::new(std::fs::File::open("test-data/countries.fgb").unwrap());
+//! assert_eq!(Format::detect(&mut file).unwrap(), Format::FlatGeobuf);
+//!
+//! # #[cfg(feature = "flatgeobuf")]
+//! # {
+//! let countries: Vec<Country> = from_reader(file).unwrap();
+//! assert_eq!(countries[0].name, "Antarctica");
+//! # }
+//! ```
+//!
+//! `FlatGeobuf` requires the `flatgeobuf` feature, `GeoJSON` the `geojson` feature. Shapefiles
+//! and `GeoPackage`s are detected, but can't be read yet.
+use std::{
+    fmt::{self, Display},
+    io::{Read, Seek, SeekFrom},
+};
+
+use serde::de::DeserializeOwned;
+
+use crate::{
+    de::DeserializeOptions,
+    error::{Error, Result},
+};
+
+const FGB_MAGIC: &[u8] = b"fgb\x03";
+const SHP_MAGIC: &[u8] = &[0x00, 0x00, 0x27, 0x0a];
+const SQLITE_MAGIC: &[u8] = b"SQLite format 3\0";
+const UTF8_BOM: &[u8] = &[0xef, 0xbb, 0xbf];
+
+/// The most features room is reserved for up front when a file reports its feature count.
+#[cfg(feature = "flatgeobuf")]
+const MAX_PREALLOCATED_FEATURES: usize = 1 << 20;
+
+/// A file format [`from_reader_with_format`] can be asked to read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// `FlatGeobuf`, detected by its magic bytes.
+    FlatGeobuf,
+    /// A `GeoJSON` document, detected by a leading `{`.
+    GeoJson,
+    /// Newline delimited `GeoJSON` features, never detected as the first line looks like
+    /// [`Format::GeoJson`].
+    GeoJsonLines,
+    /// An ESRI Shapefile (`.shp`), detected by its file code.
+    Shapefile,
+    /// A `GeoPackage`, detected by the `SQLite` header.
+    GeoPackage,
+}
+
+impl Format {
+    /// Sniffs the format from the first bytes of `reader`, which is rewound afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnsupportedFormat`] if no known format matches, or an error if
+    /// `reader` can't be read.
+    pub fn detect<R: Read + Seek>(reader: &mut R) -> Result<Format> {
+        let io_error = |err: std::io::Error| Error::Message(err.to_string());
+        let start = reader.stream_position().map_err(io_error)?;
+        let mut head = Vec::with_capacity(64);
+        reader
+            .by_ref()
+            .take(64)
+            .read_to_end(&mut head)
+            .map_err(io_error)?;
+        reader.seek(SeekFrom::Start(start)).map_err(io_error)?;
+        Format::from_bytes(&head).ok_or_else(|| {
+            Error::UnsupportedFormat("no known format matches the first bytes".to_string())
+        })
+    }
+
+    /// Picks the format for a file extension like `fgb` or `geojson`, ignoring case.
+    #[must_use]
+    pub fn from_extension(extension: &str) -> Option<Format> {
+        match extension.to_ascii_lowercase().as_str() {
+            "fgb" => Some(Format::FlatGeobuf),
+            "geojson" | "json" => Some(Format::GeoJson),
+            "ndjson" | "geojsonl" | "geojsons" => Some(Format::GeoJsonLines),
+            "shp" => Some(Format::Shapefile),
+            "gpkg" => Some(Format::GeoPackage),
+            _ => None,
+        }
+    }
+
+    fn from_bytes(head: &[u8]) -> Option<Format> {
+        if head.starts_with(FGB_MAGIC) {
+            return Some(Format::FlatGeobuf);
+        }
+        if head.starts_with(SHP_MAGIC) {
+            return Some(Format::Shapefile);
+        }
+        if head.starts_with(SQLITE_MAGIC) {
+            return Some(Format::GeoPackage);
+        }
+        let text = head.strip_prefix(UTF8_BOM).unwrap_or(head);
+        match text.iter().find(|byte| !byte.is_ascii_whitespace()) {
+            Some(b'{') => Some(Format::GeoJson),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Format::FlatGeobuf => "FlatGeobuf",
+            Format::GeoJson => "GeoJSON",
+            Format::GeoJsonLines => "GeoJSON lines",
+            Format::Shapefile => "Shapefile",
+            Format::GeoPackage => "GeoPackage",
+        })
+    }
+}
+
+/// Detects the format of `reader` and deserializes all of its features into `T`.
+///
+/// Wrap files in a [`std::io::BufReader`], the readers don't buffer on their own.
+///
+/// # Errors
+///
+/// Returns the errors of [`Format::detect`] and [`from_reader_with_format`].
+pub fn from_reader<T: DeserializeOwned + 'static, R: Read + Seek>(mut reader: R) -> Result<Vec<T>> {
+    let format = Format::detect(&mut reader)?;
+    from_reader_with_format(reader, format, &DeserializeOptions::new())
+}
+
+/// Deserializes all features of `reader`, which is read as `format`, into `T`.
+///
+/// # Errors
+///
+/// Returns [`Error::UnsupportedFormat`] if `format` can't be read, e.g. because the feature it
+/// requires is disabled, and the same errors as
+/// [`from_datasource_with_options`](crate::from_datasource_with_options) otherwise.
+#[allow(unused_variables, clippy::needless_pass_by_value)]
+pub fn from_reader_with_format<T: DeserializeOwned + 'static, R: Read + Seek>(
+    reader: R,
+    format: Format,
+    options: &DeserializeOptions,
+) -> Result<Vec<T>> {
+    match format {
+        #[cfg(feature = "flatgeobuf")]
+        Format::FlatGeobuf => {
+            let mut features = flatgeobuf::FgbReader::open(reader)?.select_all()?;
+            let mut collector = crate::collector::TypedCollector::with_options(options)?;
+            // the count comes from the file, don't trust it with more than a hint
+            collector.reserve(
+                features
+                    .features_count()
+                    .unwrap_or_default()
+                    .min(MAX_PREALLOCATED_FEATURES),
+            );
+            collector.process(&mut features)?;
+            Ok(collector.into_features())
+        }
+        #[cfg(feature = "geojson")]
+        Format::GeoJson => crate::from_datasource_with_options(
+            &mut geozero::geojson::GeoJsonReader(reader),
+            options,
+        ),
+        #[cfg(feature = "geojson")]
+        Format::GeoJsonLines => crate::from_datasource_with_options(
+            &mut geozero::geojson::GeoJsonLineReader::new(reader),
+            options,
+        ),
+        format => Err(Error::UnsupportedFormat(format.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{Cursor, Seek};
+
+    use super::Format;
+
+    #[test]
+    fn detection() -> anyhow::Result<()> {
+        let mut file = std::fs::File::open("test-data/countries.fgb")?;
+        assert_eq!(Format::detect(&mut file)?, Format::FlatGeobuf);
+        assert_eq!(file.stream_position()?, 0);
+
+        let detect = |bytes: &[u8]| Format::detect(&mut Cursor::new(bytes.to_vec())).ok();
+        assert_eq!(
+            detect(b"\xef\xbb\xbf\n  {\"type\": \"FeatureCollection\"}"),
+            Some(Format::GeoJson)
+        );
+        assert_eq!(detect(b"\x00\x00\x27\x0a\x00\x00"), Some(Format::Shapefile));
+        assert_eq!(
+            detect(b"SQLite format 3\0\x10\x00"),
+            Some(Format::GeoPackage)
+        );
+        assert_eq!(detect(b"POINT(1 2)"), None);
+        assert_eq!(Format::from_extension("GeoJSON"), Some(Format::GeoJson));
+        Ok(())
+    }
+
+    #[cfg(all(feature = "flatgeobuf", feature = "geojson"))]
+    #[test]
+    fn reads_detected_formats() -> anyhow::Result<()> {
+        use geo::Geometry;
+        use serde::Deserialize;
+
+        use super::{from_reader, from_reader_with_format};
+        use crate::{error::Error, DeserializeOptions};
+
+        #[derive(Deserialize)]
+        #[allow(dead_code)]
+        struct Named {
+            geometry: Geometry,
+            name: String,
+        }
+
+        let file = std::io::BufReader::new(std::fs::File::open("test-data/countries.fgb")?);
+        let countries: Vec<Named> = from_reader(file)?;
+        assert_eq!(countries[0].name, "Antarctica");
+
+        let geojson = r#"{"type": "Feature", "properties": {"name": "a"},
+            "geometry": {"type": "Point", "coordinates": [1, 2]}}"#;
+        let features: Vec<Named> = from_reader(Cursor::new(geojson))?;
+        assert_eq!(features[0].name, "a");
+
+        let lines = format!(
+            "{}\n{}\n",
+            geojson.replace('\n', ""),
+            geojson.replace('\n', "")
+        );
+        let features: Vec<Named> = from_reader_with_format(
+            Cursor::new(lines),
+            Format::GeoJsonLines,
+            &DeserializeOptions::new(),
+        )?;
+        assert_eq!(features.len(), 2);
+
+        assert!(matches!(
+            from_reader_with_format::<Named, _>(
+                Cursor::new(""),
+                Format::Shapefile,
+                &DeserializeOptions::new()
+            ),
+            Err(Error::UnsupportedFormat(_))
+        ));
+        Ok(())
+    }
+}
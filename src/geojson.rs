@@ -0,0 +1,1181 @@
+//! A `GeoJSON` writer tailored to the serialization path of this crate.
+//!
+//! geozero's `GeoJsonWriter` cannot express features without a geometry and relies on column
+//! indices to separate properties. [`GeoJsonWriter`] writes `"geometry": null` for features that
+//! did not emit any geometry and separates properties by their position within the feature.
+//! geozero's `GeoJsonReader` drops feature ids, [`GeoJsonReader`] reads them as a property.
+#[cfg(feature = "geojson")]
+use hashbrown::HashMap;
+use std::fmt::Display;
+#[cfg(feature = "geojson")]
+use std::io::Read;
+use std::io::Write;
+
+#[cfg(feature = "geojson")]
+use ::geojson::feature::Id;
+#[cfg(feature = "geojson")]
+use geo::Geometry;
+use geozero::{
+    error::{GeozeroError, Result as GeozeroResult},
+    ColumnValue, CoordDimensions, FeatureProcessor, GeomProcessor, PropertyProcessor,
+};
+#[cfg(feature = "geojson")]
+use geozero::{geojson::GeoJsonString, GeozeroDatasource, GeozeroGeometry};
+#[cfg(feature = "geojson")]
+use serde::Deserialize;
+use serde::Serialize;
+#[cfg(feature = "geojson")]
+use serde_json::Value as JsonValue;
+
+#[cfg(feature = "geojson")]
+use crate::{
+    de::{from_feature_iter_with_options, DeserializeOptions, Feature},
+    ser::process_properties,
+    value::Value,
+};
+use crate::{
+    error::{Error, Result},
+    schema::DatasetInfo,
+    ser::{
+        to_geozero_datasource_with_options, to_geozero_feature_with_options, LayerSink,
+        SerializeOptions,
+    },
+};
+
+/// How [`GeoJsonWriter`] lays out the features it writes.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Layout {
+    Collection,
+    Lines,
+    Geometries,
+}
+
+/// Writes features as a `GeoJSON` `FeatureCollection`, or as newline delimited features.
+///
+/// NaN and infinite coordinates or property values are rejected, JSON can't represent them.
+/// Binary properties are written as arrays of their bytes, like `serde_json` writes bytes.
+pub struct GeoJsonWriter<W: Write> {
+    dims: CoordDimensions,
+    out: W,
+    layout: Layout,
+    has_geometry: bool,
+    has_properties: bool,
+    property_count: usize,
+    id_field: Option<String>,
+    /// The JSON text of the current feature's id.
+    id: Option<String>,
+}
+
+impl<W: Write> GeoJsonWriter<W> {
+    pub fn new(out: W) -> Self {
+        Self::with_dims(out, CoordDimensions::default())
+    }
+
+    /// Creates a writer that writes the given coordinate dimensions, e.g. Z values.
+    pub fn with_dims(out: W, dims: CoordDimensions) -> Self {
+        Self {
+            dims,
+            out,
+            layout: Layout::Collection,
+            has_geometry: false,
+            has_properties: false,
+            property_count: 0,
+            id_field: None,
+            id: None,
+        }
+    }
+
+    /// Creates a writer that writes one feature per line (NDJSON) instead of a
+    /// `FeatureCollection`.
+    pub fn ndjson(out: W) -> Self {
+        Self {
+            layout: Layout::Lines,
+            ..Self::new(out)
+        }
+    }
+
+    /// Creates a writer that writes only the bare geometry of every feature, one per line.
+    /// Features without a geometry are written as `null`.
+    pub fn geometries(out: W) -> Self {
+        Self {
+            layout: Layout::Geometries,
+            ..Self::new(out)
+        }
+    }
+
+    /// Writes the property `field` as the `id` member of the features instead of as a property,
+    /// see [`SerializeOptions::geojson_id`].
+    #[must_use]
+    pub fn id_field(mut self, field: &str) -> Self {
+        self.id_field = Some(field.to_string());
+        self
+    }
+
+    /// Applies the options of `options` the writer handles itself.
+    fn configure(self, options: &SerializeOptions) -> Self {
+        match options.geojson_id_field() {
+            Some(field) => self.id_field(field),
+            None => self,
+        }
+    }
+
+    /// Consumes the writer and returns the underlying output.
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    fn comma(&mut self, idx: usize) -> GeozeroResult<()> {
+        if idx > 0 {
+            self.out.write_all(b",")?;
+        }
+        Ok(())
+    }
+
+    fn write_null_geometry(&mut self) -> GeozeroResult<()> {
+        if !self.has_geometry {
+            if self.layout == Layout::Geometries {
+                self.out.write_all(b"null")?;
+            } else {
+                self.out.write_all(br#", "geometry": null"#)?;
+            }
+            self.has_geometry = true;
+        }
+        Ok(())
+    }
+}
+
+/// Serializes features into a `GeoJSON` `FeatureCollection` string using [`GeoJsonWriter`].
+///
+/// # Examples
+///
+/// ```
+/// use geo::Geometry;
+/// use serde::Serialize;
+/// use serde_geozero::geojson::to_geojson_string;
+/// use serde_geozero::ser::{NullGeometry, SerializeOptions};
+///
+/// #[derive(Serialize)]
+/// struct Record {
+///     geometry: Option<Geometry>,
+///     name: String,
+/// }
+///
+/// let records = [Record { geometry: None, name: "no location".to_string() }];
+/// let options = SerializeOptions::new().null_geometry(NullGeometry::WriteNull);
+/// let geojson = to_geojson_string(&records, &options).unwrap();
+/// assert!(geojson.contains(r#""geometry": null"#));
+/// ```
+///
+/// # Errors
+///
+/// Returns the same errors as [`to_geozero_datasource_with_options`].
+pub fn to_geojson_string<T: Serialize>(input: &[T], options: &SerializeOptions) -> Result<String> {
+    let mut writer = GeoJsonWriter::new(Vec::new()).configure(options);
+    to_geozero_datasource_with_options(input, &mut writer, options)?;
+    into_string(writer)
+}
+
+/// Serializes features into newline delimited `GeoJSON` features, each line ending with `\n`.
+///
+/// # Errors
+///
+/// Returns the same errors as [`to_geozero_datasource_with_options`].
+pub fn to_ndjson_string<T: Serialize>(input: &[T], options: &SerializeOptions) -> Result<String> {
+    let mut writer = GeoJsonWriter::ndjson(Vec::new()).configure(options);
+    to_geozero_datasource_with_options(input, &mut writer, options)?;
+    into_string(writer)
+}
+
+/// Serializes `value` into a single `GeoJSON` feature.
+///
+/// The string is empty if the feature is left out, see
+/// [`NullGeometry::Skip`](crate::ser::NullGeometry::Skip).
+///
+/// # Errors
+///
+/// Returns the same errors as [`to_geozero_feature_with_options`].
+pub fn to_geojson_feature_string<T: Serialize + ?Sized>(
+    value: &T,
+    options: &SerializeOptions,
+) -> Result<String> {
+    let mut writer = GeoJsonWriter::new(Vec::new()).configure(options);
+    to_geozero_feature_with_options(value, &mut writer, options, 0)?;
+    into_string(writer)
+}
+
+/// The top level `GeoJSON` object written by [`to_geojson_string_with_format`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GeoJsonShape {
+    /// A `FeatureCollection` holding all features.
+    #[default]
+    FeatureCollection,
+    /// A bare `Feature`, the input must hold exactly one feature.
+    Feature,
+    /// The bare geometry of a single feature, `null` if it has none.
+    Geometry,
+}
+
+/// Formatting options for [`to_geojson_string_with_format`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GeoJsonFormat {
+    shape: GeoJsonShape,
+    pretty: bool,
+}
+
+impl GeoJsonFormat {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Which top level object to write.
+    #[must_use]
+    pub fn shape(mut self, shape: GeoJsonShape) -> Self {
+        self.shape = shape;
+        self
+    }
+
+    /// Indents the output by two spaces per level. Coordinate positions stay on one line.
+    #[must_use]
+    pub fn pretty(mut self, pretty: bool) -> Self {
+        self.pretty = pretty;
+        self
+    }
+}
+
+/// Serializes features into `GeoJSON` with the given [`GeoJsonFormat`].
+///
+/// # Examples
+///
+/// ```
+/// use geo::{point, Geometry};
+/// use serde::Serialize;
+/// use serde_geozero::geojson::{to_geojson_string_with_format, GeoJsonFormat, GeoJsonShape};
+/// use serde_geozero::ser::SerializeOptions;
+///
+/// #[derive(Serialize)]
+/// struct Record {
+///     geometry: Geometry,
+/// }
+///
+/// let records = [Record { geometry: point!(x: 1.0, y: 2.0).into() }];
+/// let format = GeoJsonFormat::new().shape(GeoJsonShape::Geometry).pretty(true);
+/// let geojson = to_geojson_string_with_format(&records, &SerializeOptions::new(), &format);
+/// assert_eq!(
+///     geojson.unwrap(),
+///     "{\n  \"type\": \"Point\",\n  \"coordinates\": [1, 2]\n}"
+/// );
+/// ```
+///
+/// # Errors
+///
+/// Returns [`Error::Message`] if a bare feature or geometry is
+/// requested for an input that does not hold exactly one feature, and otherwise the same errors
+/// as [`to_geozero_datasource_with_options`].
+pub fn to_geojson_string_with_format<T: Serialize>(
+    input: &[T],
+    options: &SerializeOptions,
+    format: &GeoJsonFormat,
+) -> Result<String> {
+    let geojson = match format.shape {
+        GeoJsonShape::FeatureCollection => to_geojson_string(input, options)?,
+        GeoJsonShape::Feature | GeoJsonShape::Geometry => {
+            let [value] = input else {
+                return Err(Error::Message(format!(
+                    "a bare GeoJSON {:?} requires exactly one feature, got {}",
+                    format.shape,
+                    input.len()
+                )));
+            };
+            if format.shape == GeoJsonShape::Feature {
+                to_geojson_feature_string(value, options)?
+            } else {
+                let mut writer = GeoJsonWriter::geometries(Vec::new());
+                to_geozero_feature_with_options(value, &mut writer, options, 0)?;
+                let geometry = into_string(writer)?;
+                geometry.trim_end().to_string()
+            }
+        }
+    };
+    Ok(if format.pretty {
+        indent(&geojson)
+    } else {
+        geojson
+    })
+}
+
+/// Re-indents compact JSON text while keeping the order of its keys.
+///
+/// Arrays holding only scalars, i.e. coordinate positions, stay on one line.
+fn indent(json: &str) -> String {
+    fn newline(out: &mut String, depth: usize) {
+        out.push('\n');
+        out.extend(std::iter::repeat_n("  ", depth));
+    }
+
+    let chars: Vec<char> = json.chars().collect();
+    let mut out = String::with_capacity(json.len() * 2);
+    let mut depth = 0;
+    let mut inline = 0;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        i += 1;
+        match c {
+            '"' => {
+                out.push(c);
+                let mut escaped = false;
+                while i < chars.len() {
+                    let c = chars[i];
+                    i += 1;
+                    out.push(c);
+                    match c {
+                        _ if escaped => escaped = false,
+                        '\\' => escaped = true,
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '[' if inline > 0 || scalar_array(&chars[i..]) => {
+                inline += 1;
+                out.push(c);
+            }
+            ']' if inline > 0 => {
+                inline -= 1;
+                out.push(c);
+            }
+            ',' if inline > 0 => out.push_str(", "),
+            '{' | '[' => {
+                let close = if c == '{' { '}' } else { ']' };
+                match chars[i..].iter().position(|c| !c.is_whitespace()) {
+                    Some(n) if chars[i + n] == close => {
+                        out.push(c);
+                        out.push(close);
+                        i += n + 1;
+                    }
+                    _ => {
+                        depth += 1;
+                        out.push(c);
+                        newline(&mut out, depth);
+                    }
+                }
+            }
+            '}' | ']' => {
+                depth -= 1;
+                newline(&mut out, depth);
+                out.push(c);
+            }
+            ',' => {
+                out.push(',');
+                newline(&mut out, depth);
+            }
+            ':' => out.push_str(": "),
+            c if c.is_whitespace() => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Whether the array starting after `[` holds no objects, strings or nested non-empty arrays.
+fn scalar_array(rest: &[char]) -> bool {
+    for c in rest {
+        match c {
+            ']' => return true,
+            '[' | '{' | '"' => return false,
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Writes every layer of a [`Dataset`](crate::ser::Dataset) as its own `FeatureCollection`.
+#[derive(Default)]
+pub struct GeoJsonLayers {
+    layers: Vec<(String, String)>,
+    current: Option<(String, GeoJsonWriter<Vec<u8>>)>,
+}
+
+impl GeoJsonLayers {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The names and `GeoJSON` strings of the written layers.
+    #[must_use]
+    pub fn into_layers(self) -> Vec<(String, String)> {
+        self.layers
+    }
+}
+
+impl LayerSink for GeoJsonLayers {
+    type Processor = GeoJsonWriter<Vec<u8>>;
+
+    fn begin_layer(&mut self, info: &DatasetInfo) -> Result<&mut Self::Processor> {
+        let name = info.name.clone().unwrap_or_default();
+        Ok(&mut self
+            .current
+            .insert((name, GeoJsonWriter::new(Vec::new())))
+            .1)
+    }
+
+    fn end_layer(&mut self) -> Result<()> {
+        if let Some((name, writer)) = self.current.take() {
+            let geojson = into_string(writer)?;
+            self.layers.push((name, geojson));
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> FeatureProcessor for GeoJsonWriter<W> {
+    fn dataset_begin(&mut self, name: Option<&str>) -> GeozeroResult<()> {
+        if self.layout != Layout::Collection {
+            return Ok(());
+        }
+        self.out.write_all(br#"{"type": "FeatureCollection""#)?;
+        if let Some(name) = name {
+            write!(self.out, r#", "name": {}"#, json_string(name))?;
+        }
+        self.out.write_all(br#", "features": ["#)?;
+        Ok(())
+    }
+
+    fn dataset_end(&mut self) -> GeozeroResult<()> {
+        if self.layout != Layout::Collection {
+            return Ok(());
+        }
+        self.out.write_all(b"]}")?;
+        Ok(())
+    }
+
+    fn feature_begin(&mut self, idx: u64) -> GeozeroResult<()> {
+        self.has_geometry = false;
+        self.has_properties = false;
+        self.id = None;
+        match self.layout {
+            Layout::Geometries => return Ok(()),
+            Layout::Collection if idx > 0 => self.out.write_all(b",\n")?,
+            _ => {}
+        }
+        self.out.write_all(br#"{"type": "Feature""#)?;
+        Ok(())
+    }
+
+    fn feature_end(&mut self, _idx: u64) -> GeozeroResult<()> {
+        self.write_null_geometry()?;
+        if self.layout == Layout::Geometries {
+            self.out.write_all(b"\n")?;
+            return Ok(());
+        }
+        if !self.has_properties {
+            self.out.write_all(br#", "properties": {}"#)?;
+        }
+        if let Some(id) = self.id.take() {
+            write!(self.out, r#", "id": {id}"#)?;
+        }
+        self.out.write_all(b"}")?;
+        if self.layout == Layout::Lines {
+            self.out.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    fn properties_begin(&mut self) -> GeozeroResult<()> {
+        if self.layout == Layout::Geometries {
+            return Ok(());
+        }
+        self.write_null_geometry()?;
+        self.out.write_all(br#", "properties": {"#)?;
+        self.has_properties = true;
+        self.property_count = 0;
+        Ok(())
+    }
+
+    fn properties_end(&mut self) -> GeozeroResult<()> {
+        if self.layout == Layout::Geometries {
+            return Ok(());
+        }
+        self.out.write_all(b"}")?;
+        Ok(())
+    }
+
+    fn geometry_begin(&mut self) -> GeozeroResult<()> {
+        if self.layout != Layout::Geometries {
+            self.out.write_all(br#", "geometry": "#)?;
+        }
+        self.has_geometry = true;
+        Ok(())
+    }
+}
+
+impl<W: Write> PropertyProcessor for GeoJsonWriter<W> {
+    fn property(&mut self, _idx: usize, name: &str, value: &ColumnValue) -> GeozeroResult<bool> {
+        if self.layout == Layout::Geometries {
+            return Ok(false);
+        }
+        let number = match value {
+            ColumnValue::Float(v) => Some(f64::from(*v)),
+            ColumnValue::Double(v) => Some(*v),
+            _ => None,
+        };
+        if let Some(number) = number.filter(|number| !number.is_finite()) {
+            return Err(GeozeroError::Property(format!(
+                "non-finite value {number} of `{name}` can't be written as GeoJSON"
+            )));
+        }
+        // `GeoJSON` ids are strings or numbers
+        let is_id = !matches!(
+            value,
+            ColumnValue::Bool(_) | ColumnValue::Json(_) | ColumnValue::Binary(_)
+        );
+        let value: &dyn Display = match value {
+            ColumnValue::Byte(v) => v,
+            ColumnValue::UByte(v) => v,
+            ColumnValue::Bool(v) => v,
+            ColumnValue::Short(v) => v,
+            ColumnValue::UShort(v) => v,
+            ColumnValue::Int(v) => v,
+            ColumnValue::UInt(v) => v,
+            ColumnValue::Long(v) => v,
+            ColumnValue::ULong(v) => v,
+            ColumnValue::Float(v) => v,
+            ColumnValue::Double(v) => v,
+            ColumnValue::String(v) | ColumnValue::DateTime(v) => &json_string(v),
+            ColumnValue::Json(v) => v,
+            // like serde_json, bytes are written as an array of numbers
+            ColumnValue::Binary(v) => &serde_json::Value::from(*v),
+        };
+        if is_id && self.id_field.as_deref() == Some(name) {
+            self.id = Some(value.to_string());
+            return Ok(false);
+        }
+        if self.property_count > 0 {
+            self.out.write_all(b", ")?;
+        }
+        write!(self.out, "{}: {value}", json_string(name))?;
+        self.property_count += 1;
+        Ok(false)
+    }
+}
+
+impl<W: Write> GeomProcessor for GeoJsonWriter<W> {
+    fn dimensions(&self) -> CoordDimensions {
+        self.dims
+    }
+
+    fn xy(&mut self, x: f64, y: f64, idx: usize) -> GeozeroResult<()> {
+        finite(&[x, y])?;
+        self.comma(idx)?;
+        write!(self.out, "[{x},{y}]")?;
+        Ok(())
+    }
+
+    fn coordinate(
+        &mut self,
+        x: f64,
+        y: f64,
+        z: Option<f64>,
+        _m: Option<f64>,
+        _t: Option<f64>,
+        _tm: Option<u64>,
+        idx: usize,
+    ) -> GeozeroResult<()> {
+        finite(&[x, y, z.unwrap_or_default()])?;
+        self.comma(idx)?;
+        match z {
+            Some(z) => write!(self.out, "[{x},{y},{z}]")?,
+            None => write!(self.out, "[{x},{y}]")?,
+        }
+        Ok(())
+    }
+
+    fn empty_point(&mut self, idx: usize) -> GeozeroResult<()> {
+        self.comma(idx)?;
+        self.out
+            .write_all(br#"{"type": "Point", "coordinates": []}"#)?;
+        Ok(())
+    }
+
+    fn point_begin(&mut self, idx: usize) -> GeozeroResult<()> {
+        self.comma(idx)?;
+        self.out
+            .write_all(br#"{"type": "Point", "coordinates": "#)?;
+        Ok(())
+    }
+
+    fn point_end(&mut self, _idx: usize) -> GeozeroResult<()> {
+        self.out.write_all(b"}")?;
+        Ok(())
+    }
+
+    fn multipoint_begin(&mut self, _size: usize, idx: usize) -> GeozeroResult<()> {
+        self.comma(idx)?;
+        self.out
+            .write_all(br#"{"type": "MultiPoint", "coordinates": ["#)?;
+        Ok(())
+    }
+
+    fn multipoint_end(&mut self, _idx: usize) -> GeozeroResult<()> {
+        self.out.write_all(b"]}")?;
+        Ok(())
+    }
+
+    fn linestring_begin(&mut self, tagged: bool, _size: usize, idx: usize) -> GeozeroResult<()> {
+        self.comma(idx)?;
+        if tagged {
+            self.out
+                .write_all(br#"{"type": "LineString", "coordinates": ["#)?;
+        } else {
+            self.out.write_all(b"[")?;
+        }
+        Ok(())
+    }
+
+    fn linestring_end(&mut self, tagged: bool, _idx: usize) -> GeozeroResult<()> {
+        self.out.write_all(if tagged { b"]}" } else { b"]" })?;
+        Ok(())
+    }
+
+    fn multilinestring_begin(&mut self, _size: usize, idx: usize) -> GeozeroResult<()> {
+        self.comma(idx)?;
+        self.out
+            .write_all(br#"{"type": "MultiLineString", "coordinates": ["#)?;
+        Ok(())
+    }
+
+    fn multilinestring_end(&mut self, _idx: usize) -> GeozeroResult<()> {
+        self.out.write_all(b"]}")?;
+        Ok(())
+    }
+
+    fn polygon_begin(&mut self, tagged: bool, _size: usize, idx: usize) -> GeozeroResult<()> {
+        self.comma(idx)?;
+        if tagged {
+            self.out
+                .write_all(br#"{"type": "Polygon", "coordinates": ["#)?;
+        } else {
+            self.out.write_all(b"[")?;
+        }
+        Ok(())
+    }
+
+    fn polygon_end(&mut self, tagged: bool, _idx: usize) -> GeozeroResult<()> {
+        self.out.write_all(if tagged { b"]}" } else { b"]" })?;
+        Ok(())
+    }
+
+    fn multipolygon_begin(&mut self, _size: usize, idx: usize) -> GeozeroResult<()> {
+        self.comma(idx)?;
+        self.out
+            .write_all(br#"{"type": "MultiPolygon", "coordinates": ["#)?;
+        Ok(())
+    }
+
+    fn multipolygon_end(&mut self, _idx: usize) -> GeozeroResult<()> {
+        self.out.write_all(b"]}")?;
+        Ok(())
+    }
+
+    fn geometrycollection_begin(&mut self, _size: usize, idx: usize) -> GeozeroResult<()> {
+        self.comma(idx)?;
+        self.out
+            .write_all(br#"{"type": "GeometryCollection", "geometries": ["#)?;
+        Ok(())
+    }
+
+    fn geometrycollection_end(&mut self, _idx: usize) -> GeozeroResult<()> {
+        self.out.write_all(b"]}")?;
+        Ok(())
+    }
+}
+
+/// JSON has no representation for NaN and infinite numbers, writing them would produce output
+/// other parsers reject.
+fn finite(ordinates: &[f64]) -> GeozeroResult<()> {
+    if let Some(ordinate) = ordinates.iter().find(|ordinate| !ordinate.is_finite()) {
+        return Err(GeozeroError::Geometry(format!(
+            "non-finite coordinate {ordinate} can't be written as GeoJSON"
+        )));
+    }
+    Ok(())
+}
+
+/// The output of a writer into a `Vec`.
+fn into_string(writer: GeoJsonWriter<Vec<u8>>) -> Result<String> {
+    String::from_utf8(writer.into_inner())
+        .map_err(|err| Error::Message(format!("GeoJSON output is not UTF-8: {err}")))
+}
+
+fn json_string(value: &str) -> String {
+    // serializing a str can not fail
+    serde_json::to_string(value).unwrap_or_default()
+}
+
+/// Reads `GeoJSON` like geozero's `GeoJsonReader`, additionally handing the `id` of every
+/// feature to the processor as the property `id`, unless the feature has a property of that
+/// name.
+///
+/// The ids written for [`SerializeOptions::geojson_id`] thus reach
+/// [`from_datasource`](crate::from_datasource) like they reach [`from_feature_collection`]:
+///
+/// ```
+/// use geo::Geometry;
+/// use serde::{Deserialize, Serialize};
+/// use serde_geozero::geojson::{to_geojson_string, GeoJsonReader};
+/// use serde_geozero::{from_datasource, SerializeOptions};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Stop {
+///     geometry: Geometry,
+///     id: String,
+/// }
+///
+/// let stops = [Stop { geometry: geo::point! { x: 1., y: 2. }.into(), id: "s1".to_string() }];
+/// let geojson = to_geojson_string(&stops, &SerializeOptions::new().geojson_id("id")).unwrap();
+/// let stops: Vec<Stop> = from_datasource(&mut GeoJsonReader(geojson.as_bytes())).unwrap();
+/// assert_eq!(stops[0].id, "s1");
+/// ```
+#[cfg(feature = "geojson")]
+pub struct GeoJsonReader<R: Read>(pub R);
+
+#[cfg(feature = "geojson")]
+impl<R: Read> GeozeroDatasource for GeoJsonReader<R> {
+    fn process<P: FeatureProcessor>(&mut self, processor: &mut P) -> GeozeroResult<()> {
+        let mut json = String::new();
+        self.0.read_to_string(&mut json)?;
+        let geojson = json
+            .parse::<::geojson::GeoJson>()
+            .map_err(|err| GeozeroError::Dataset(err.to_string()))?;
+        let features = match geojson {
+            ::geojson::GeoJson::FeatureCollection(collection) => collection.features,
+            ::geojson::GeoJson::Feature(feature) => vec![feature],
+            // like geozero, a bare geometry is processed without a dataset
+            ::geojson::GeoJson::Geometry(_) => return GeoJsonString(json).process_geom(processor),
+        };
+        let mut column_mapping = HashMap::new();
+        processor.dataset_begin(None)?;
+        for (idx, feature) in features.into_iter().enumerate() {
+            processor.feature_begin(idx as u64)?;
+            let mut properties: HashMap<String, JsonValue> =
+                feature.properties.unwrap_or_default().into_iter().collect();
+            if let Some(id) = feature.id {
+                let id = match id {
+                    Id::String(id) => JsonValue::String(id),
+                    Id::Number(id) => JsonValue::Number(id),
+                };
+                properties.entry("id".to_string()).or_insert(id);
+            }
+            processor.properties_begin()?;
+            process_properties(&properties, &mut column_mapping, processor)?;
+            processor.properties_end()?;
+            if let Some(geometry) = feature.geometry {
+                processor.geometry_begin()?;
+                GeoJsonString(geometry.to_string()).process_geom(processor)?;
+                processor.geometry_end()?;
+            }
+            processor.feature_end(idx as u64)?;
+        }
+        processor.dataset_end()
+    }
+}
+
+/// Converts a feature of the `geojson` crate, its properties become [`Value`]s like those read
+/// by [`GeoJsonReader`]. The `id` becomes the property `id` unless the feature has a property of
+/// that name, foreign members are dropped.
+///
+/// # Errors
+///
+/// Returns [`Error::MissingGeometry`] for a feature without geometry and
+/// [`Error::InvalidGeometry`] for a geometry `geo` can't represent.
+#[cfg(feature = "geojson")]
+impl TryFrom<::geojson::Feature> for Feature {
+    type Error = Error;
+
+    fn try_from(feature: ::geojson::Feature) -> Result<Self> {
+        from_geojson_feature(feature, 0)
+    }
+}
+
+#[cfg(feature = "geojson")]
+fn from_geojson_feature(feature: ::geojson::Feature, index: usize) -> Result<Feature> {
+    let geometry = feature.geometry.ok_or(Error::MissingGeometry(index))?;
+    let mut properties: HashMap<String, Value> = feature
+        .properties
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(key, value)| (key, Value::from(value)))
+        .collect();
+    if let Some(id) = feature.id {
+        let id = match id {
+            Id::String(id) => Value::String(id),
+            Id::Number(id) => Value::from(serde_json::Value::Number(id)),
+        };
+        properties.entry("id".to_string()).or_insert(id);
+    }
+    let geometry = Geometry::try_from(geometry).map_err(|err| Error::InvalidGeometry {
+        index,
+        reason: err.to_string(),
+    })?;
+    Ok(Feature::new(geometry, properties))
+}
+
+/// Converts into a feature of the `geojson` crate. An empty `GeometryCollection`, the geometry
+/// of a feature read without one, becomes `"geometry": null`.
+#[cfg(feature = "geojson")]
+impl From<Feature> for ::geojson::Feature {
+    fn from(feature: Feature) -> Self {
+        let geometry = match &feature.geometry {
+            Geometry::GeometryCollection(collection) if collection.0.is_empty() => None,
+            geometry => Some(::geojson::Geometry::new(geometry.into())),
+        };
+        let properties = feature
+            .properties
+            .into_iter()
+            .map(|(key, value)| {
+                // `Value`s serialize into JSON without failing, non-finite floats become `null`
+                let value = serde_json::to_value(value).unwrap_or_default();
+                (key, value)
+            })
+            .collect();
+        ::geojson::Feature {
+            geometry,
+            properties: Some(properties),
+            ..Default::default()
+        }
+    }
+}
+
+/// Serializes features into a `FeatureCollection` of the `geojson` crate.
+///
+/// ```
+/// use geo::Geometry;
+/// use serde::{Deserialize, Serialize};
+/// use serde_geozero::geojson::{from_feature_collection, to_feature_collection};
+/// use serde_geozero::{DeserializeOptions, SerializeOptions};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct City {
+///     geometry: Geometry,
+///     name: String,
+/// }
+///
+/// let cities = [City {
+///     geometry: geo::point! { x: 13.4, y: 52.5 }.into(),
+///     name: "Berlin".to_string(),
+/// }];
+/// let collection = to_feature_collection(&cities, &SerializeOptions::new()).unwrap();
+/// assert_eq!(collection.features[0].property("name").unwrap(), "Berlin");
+///
+/// let cities: Vec<City> = from_feature_collection(collection, &DeserializeOptions::new()).unwrap();
+/// assert_eq!(cities[0].name, "Berlin");
+/// ```
+///
+/// # Errors
+///
+/// Returns the same errors as [`to_geozero_datasource_with_options`].
+#[cfg(feature = "geojson")]
+pub fn to_feature_collection<T: Serialize>(
+    input: &[T],
+    options: &SerializeOptions,
+) -> Result<::geojson::FeatureCollection> {
+    // the writer applies every option and writes features without geometry, parsing its output
+    // is simpler than building the `geojson` values from processor events
+    let mut writer = GeoJsonWriter::new(Vec::new()).configure(options);
+    to_geozero_datasource_with_options(input, &mut writer, options)?;
+    Ok(serde_json::from_slice(&writer.into_inner())?)
+}
+
+/// Deserializes the features of a `FeatureCollection` of the `geojson` crate, configured by
+/// `options`.
+///
+/// # Errors
+///
+/// Returns [`Error::MissingGeometry`] for a feature without geometry and the errors of
+/// [`from_feature_iter_with_options`].
+#[cfg(feature = "geojson")]
+pub fn from_feature_collection<'de, T: Deserialize<'de> + 'static>(
+    collection: ::geojson::FeatureCollection,
+    options: &DeserializeOptions,
+) -> Result<Vec<T>> {
+    let features = collection
+        .features
+        .into_iter()
+        .enumerate()
+        .map(|(index, feature)| from_geojson_feature(feature, index))
+        .collect::<Result<Vec<_>>>()?;
+    from_feature_iter_with_options(&features, options)
+}
+
+#[cfg(test)]
+mod test {
+    use geo::{point, Geometry};
+    use serde::Serialize;
+
+    use geozero::FeatureProcessor;
+
+    use super::{
+        to_geojson_feature_string, to_geojson_string, to_geojson_string_with_format, GeoJsonFormat,
+        GeoJsonShape, GeoJsonWriter,
+    };
+    use crate::ser::{to_geozero_feature_with_options, NullGeometry, SerializeOptions};
+
+    #[derive(Serialize)]
+    struct Record {
+        geometry: Option<Geometry>,
+        name: &'static str,
+    }
+
+    #[test]
+    fn null_geometries() -> anyhow::Result<()> {
+        let records = [
+            Record {
+                geometry: None,
+                name: "first \"quoted\"",
+            },
+            Record {
+                geometry: Some((point! { x: 1., y: 2. }).into()),
+                name: "second",
+            },
+        ];
+
+        let written = to_geojson_string(
+            &records,
+            &SerializeOptions::new().null_geometry(NullGeometry::WriteNull),
+        )?;
+        let parsed: serde_json::Value = serde_json::from_str(&written)?;
+        assert_eq!(parsed["features"][0]["geometry"], serde_json::Value::Null);
+        assert_eq!(
+            parsed["features"][0]["properties"]["name"],
+            "first \"quoted\""
+        );
+        assert_eq!(parsed["features"][1]["geometry"]["type"], "Point");
+
+        let skipped = to_geojson_string(
+            &records,
+            &SerializeOptions::new().null_geometry(NullGeometry::Skip),
+        )?;
+        let parsed: serde_json::Value = serde_json::from_str(&skipped)?;
+        assert_eq!(parsed["features"].as_array().map(Vec::len), Some(1));
+
+        assert!(to_geojson_string(&records, &SerializeOptions::new()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn single_features() -> anyhow::Result<()> {
+        let record = Record {
+            geometry: Some((point! { x: 1., y: 2. }).into()),
+            name: "single",
+        };
+        let written = to_geojson_feature_string(&record, &SerializeOptions::new())?;
+        let parsed: serde_json::Value = serde_json::from_str(&written)?;
+        assert_eq!(parsed["type"], "Feature");
+        assert_eq!(parsed["properties"]["name"], "single");
+
+        // appending to a collection opened by the caller
+        let mut writer = GeoJsonWriter::new(Vec::new());
+        writer.dataset_begin(None)?;
+        for idx in 0..2 {
+            to_geozero_feature_with_options(&record, &mut writer, &SerializeOptions::new(), idx)?;
+        }
+        writer.dataset_end()?;
+        let parsed: serde_json::Value = serde_json::from_slice(&writer.into_inner())?;
+        assert_eq!(parsed["features"].as_array().map(Vec::len), Some(2));
+
+        let skipped = Record {
+            geometry: None,
+            name: "skipped",
+        };
+        let options = SerializeOptions::new().null_geometry(NullGeometry::Skip);
+        assert_eq!(to_geojson_feature_string(&skipped, &options)?, "");
+        Ok(())
+    }
+
+    #[test]
+    fn formatted_output() -> anyhow::Result<()> {
+        let record = Record {
+            geometry: Some((point! { x: 1., y: 2. }).into()),
+            name: "a, \"b\": [c]",
+        };
+        let options = SerializeOptions::new().null_geometry(NullGeometry::WriteNull);
+        let format = GeoJsonFormat::new()
+            .shape(GeoJsonShape::Feature)
+            .pretty(true);
+        let written = to_geojson_string_with_format(&[&record], &options, &format)?;
+        assert_eq!(
+            written,
+            r#"{
+  "type": "Feature",
+  "geometry": {
+    "type": "Point",
+    "coordinates": [1, 2]
+  },
+  "properties": {
+    "name": "a, \"b\": [c]"
+  }
+}"#
+        );
+
+        let collection = to_geojson_string_with_format(
+            &[&record, &record],
+            &options,
+            &GeoJsonFormat::new().pretty(true),
+        )?;
+        let parsed: serde_json::Value = serde_json::from_str(&collection)?;
+        assert_eq!(parsed["features"].as_array().map(Vec::len), Some(2));
+
+        let geometry = GeoJsonFormat::new().shape(GeoJsonShape::Geometry);
+        let missing = Record {
+            geometry: None,
+            name: "missing",
+        };
+        assert_eq!(
+            to_geojson_string_with_format(&[&missing], &options, &geometry)?,
+            "null"
+        );
+        assert!(to_geojson_string_with_format(&[&record, &record], &options, &geometry).is_err());
+        Ok(())
+    }
+
+    #[cfg(feature = "geojson")]
+    #[test]
+    fn geojson_crate_conversion() -> anyhow::Result<()> {
+        use hashbrown::HashMap;
+
+        use super::{from_feature_collection, to_feature_collection};
+        use crate::{de::Feature, error::Error, value::Value, DeserializeOptions};
+
+        let feature = Feature::new(
+            (point! { x: 1., y: 2. }).into(),
+            HashMap::from_iter([("name".to_string(), Value::from("a"))]),
+        );
+        let converted = ::geojson::Feature::from(feature.clone());
+        assert_eq!(converted.property("name"), Some(&serde_json::json!("a")));
+        assert_eq!(Feature::try_from(converted)?, feature);
+
+        let records = [
+            Record {
+                geometry: Some((point! { x: 1., y: 2. }).into()),
+                name: "first",
+            },
+            Record {
+                geometry: None,
+                name: "second",
+            },
+        ];
+        let options = SerializeOptions::new().null_geometry(NullGeometry::WriteNull);
+        let collection = to_feature_collection(&records, &options)?;
+        assert_eq!(collection.features.len(), 2);
+        assert!(collection.features[1].geometry.is_none());
+
+        let err =
+            from_feature_collection::<Feature>(collection, &DeserializeOptions::new()).unwrap_err();
+        assert!(matches!(err, Error::MissingGeometry(1)));
+        Ok(())
+    }
+
+    #[cfg(feature = "geojson")]
+    #[test]
+    fn feature_ids() -> anyhow::Result<()> {
+        use serde::Deserialize;
+
+        use super::{
+            from_feature_collection, to_feature_collection, to_ndjson_string, GeoJsonReader,
+        };
+        use crate::DeserializeOptions;
+
+        #[derive(Serialize, Deserialize)]
+        struct Stop {
+            geometry: Geometry,
+            id: String,
+            name: String,
+        }
+
+        let stops = [Stop {
+            geometry: (point! { x: 1., y: 2. }).into(),
+            id: "s1".to_string(),
+            name: "Main St".to_string(),
+        }];
+        let options = SerializeOptions::new().geojson_id("id");
+        let line = to_ndjson_string(&stops, &options)?;
+        let parsed: serde_json::Value = serde_json::from_str(&line)?;
+        assert_eq!(parsed["id"], "s1");
+        assert_eq!(
+            parsed["properties"],
+            serde_json::json!({ "name": "Main St" })
+        );
+
+        let collection = to_feature_collection(&stops, &options)?;
+        let read: Vec<Stop> = from_feature_collection(collection, &DeserializeOptions::new())?;
+        assert_eq!(read[0].id, "s1");
+
+        // any string or number property can be the id
+        let written = to_geojson_string(&stops, &SerializeOptions::new().geojson_id("name"))?;
+        assert!(written.contains(r#""id": "Main St""#));
+        let parsed: serde_json::Value = serde_json::from_str(&written)?;
+        assert!(parsed["features"][0]["properties"].get("name").is_none());
+
+        // the collector path reads the id back as well
+        let written = to_geojson_string(&stops, &options)?;
+        let read: Vec<Stop> = crate::from_datasource(&mut GeoJsonReader(written.as_bytes()))?;
+        assert_eq!(
+            (read[0].id.as_str(), read[0].name.as_str()),
+            ("s1", "Main St")
+        );
+        let numbered = r#"{"type": "Feature", "id": 7, "properties": {"name": "a"},
+            "geometry": {"type": "Point", "coordinates": [1, 2]}}"#;
+        let read: Vec<crate::de::Feature> =
+            crate::from_datasource(&mut GeoJsonReader(numbered.as_bytes()))?;
+        assert_eq!(read[0].get::<i64>("id")?, 7);
+        Ok(())
+    }
+
+    #[test]
+    fn non_finite_coordinates() {
+        let record = Record {
+            geometry: Some((point! { x: f64::NAN, y: 2. }).into()),
+            name: "nan",
+        };
+        let err = to_geojson_string(&[record], &SerializeOptions::new()).unwrap_err();
+        assert!(err.to_string().contains("non-finite"), "{err}");
+    }
+
+    #[test]
+    fn non_finite_properties() -> anyhow::Result<()> {
+        use geozero::{ColumnValue, PropertyProcessor};
+
+        for value in [
+            ColumnValue::Double(f64::NAN),
+            ColumnValue::Double(f64::NEG_INFINITY),
+            ColumnValue::Float(f32::INFINITY),
+        ] {
+            let mut writer = GeoJsonWriter::new(Vec::new());
+            writer.feature_begin(0)?;
+            writer.properties_begin()?;
+            let err = writer.property(0, "value", &value).unwrap_err();
+            assert!(err.to_string().contains("non-finite"), "{err}");
+        }
+        let mut writer = GeoJsonWriter::new(Vec::new());
+        writer.feature_begin(0)?;
+        writer.properties_begin()?;
+        writer.property(0, "value", &ColumnValue::Double(1.5))?;
+        assert!(String::from_utf8(writer.into_inner())?.contains(r#""value": 1.5"#));
+        Ok(())
+    }
+
+    #[test]
+    fn binary_properties() -> anyhow::Result<()> {
+        use geozero::{ColumnValue, PropertyProcessor};
+
+        let mut writer = GeoJsonWriter::new(Vec::new()).id_field("blob");
+        writer.feature_begin(0)?;
+        writer.properties_begin()?;
+        writer.property(0, "blob", &ColumnValue::Binary(&[0, 1, 255]))?;
+        let written = String::from_utf8(writer.into_inner())?;
+        assert!(written.contains(r#""blob": [0,1,255]"#), "{written}");
+        Ok(())
+    }
+}
@@ -0,0 +1,179 @@
+//! Pulling features lazily from push-based datasources.
+//!
+//! `geozero` datasources push every feature into a processor, while an [`Iterator`] is pulled by
+//! the caller. [`from_datasource_iter`] bridges the two by processing the datasource on a
+//! background thread that hands each deserialized feature over a bounded channel. At most
+//! [`BUFFERED_FEATURES`] features are held in memory, whatever the format:
+//!
+//! ```
+//! use geo::Geometry;
+//! use serde::Deserialize;
+//! use serde_geozero::iter::from_datasource_iter;
+//! use serde_geozero::DeserializeOptions;
+//!
+//! #[derive(Deserialize)]
+//! struct Country {
+//!     geometry: Geometry,
+//!     name: String,
+//! }
+//!
+//! let file = std::fs::File::open("test-data/countries.fgb").unwrap();
+//! let features = flatgeobuf::FgbReader::open(file).unwrap().select_all().unwrap();
+//! let mut countries = from_datasource_iter::<Country, _>(features, &DeserializeOptions::new());
+//! assert_eq!(countries.next().unwrap().unwrap().name, "Antarctica");
+//! ```
+//!
+//! Dropping the iterator stops the datasource at the next feature and waits for the thread to
+//! finish.
+//!
+//! The thread is what lets a datasource that only knows how to push run at the pace of the
+//! caller: [`GeozeroDatasource::process`] returns only once every feature was processed, and
+//! can be stopped but not suspended in between. Hence the datasource and the features have to
+//! be `Send + 'static`. Borrowed or thread bound datasources can be read in bounded memory with
+//! [`from_datasource_chunks`](crate::de::from_datasource_chunks) instead.
+use std::{
+    sync::mpsc::{sync_channel, Receiver},
+    thread::JoinHandle,
+};
+
+use geozero::GeozeroDatasource;
+use serde::de::DeserializeOwned;
+
+use crate::{
+    collector::GeozeroCollector,
+    de::DeserializeOptions,
+    error::{Error, Result},
+};
+
+/// How many deserialized features the background thread may read ahead.
+pub const BUFFERED_FEATURES: usize = 64;
+
+/// Deserializes the features of `source` into `T` while they are pulled from the iterator.
+///
+/// The iterator yields an error and ends if the datasource fails or a feature can't be
+/// deserialized, just like [`crate::from_datasource_with_options`] would. `source` is processed
+/// on a thread of its own, see the [module documentation](self).
+pub fn from_datasource_iter<T, S>(mut source: S, options: &DeserializeOptions) -> FeatureIter<T>
+where
+    T: DeserializeOwned + Send + 'static,
+    S: GeozeroDatasource + Send + 'static,
+{
+    let (sender, receiver) = sync_channel(BUFFERED_FEATURES);
+    let options = options.clone();
+    let thread = std::thread::spawn(move || {
+        let result = GeozeroCollector::with_options(&options)
+            .and_then(|collector| collector.with_sender(sender.clone()).process(&mut source));
+        if let Err(err) = result {
+            // nobody is left to report to if the iterator was dropped
+            let _ = sender.send(Err(err));
+        }
+    });
+    FeatureIter {
+        receiver: Some(receiver),
+        thread: Some(thread),
+    }
+}
+
+/// Features pulled from a datasource, see [`from_datasource_iter`].
+#[derive(Debug)]
+pub struct FeatureIter<T> {
+    /// `None` once the iterator is dropped, which closes the channel.
+    receiver: Option<Receiver<Result<T>>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl<T> Iterator for FeatureIter<T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Result<T>> {
+        if let Ok(item) = self.receiver.as_ref()?.recv() {
+            return Some(item);
+        }
+        // the channel closes once the thread is done, a panic is the only error left to report
+        let thread = self.thread.take()?;
+        thread
+            .join()
+            .err()
+            .map(|_| Err(Error::Message("datasource thread panicked".to_string())))
+    }
+}
+
+impl<T> Drop for FeatureIter<T> {
+    fn drop(&mut self) {
+        // the collector stops once it can't send the next feature
+        self.receiver = None;
+        if let Some(thread) = self.thread.take() {
+            // a panic was not reported as the features weren't pulled to the end
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        ops::ControlFlow,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    };
+
+    use geo::Geometry;
+    use serde::Deserialize;
+
+    use super::{from_datasource_iter, BUFFERED_FEATURES};
+    use crate::{error::Error, DeserializeOptions};
+
+    #[test]
+    fn pulls_features() -> anyhow::Result<()> {
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Record {
+            geometry: Geometry,
+            value: u8,
+        }
+
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Country {
+            geometry: Geometry,
+            name: String,
+        }
+
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "properties": {"value": 1},
+             "geometry": {"type": "Point", "coordinates": [1, 1]}},
+            {"type": "Feature", "properties": {"value": "text"},
+             "geometry": {"type": "Point", "coordinates": [2, 2]}}
+        ]}"#;
+        let reader = geozero::geojson::GeoJsonReader(std::io::Cursor::new(geojson.to_string()));
+        let mut records = from_datasource_iter::<Record, _>(reader, &DeserializeOptions::new());
+        assert_eq!(
+            records.next().transpose()?.map(|record| record.value),
+            Some(1)
+        );
+        assert!(matches!(
+            records.next(),
+            Some(Err(Error::Feature { index: 1, .. }))
+        ));
+        assert!(records.next().is_none());
+
+        let file = std::fs::File::open("test-data/countries.fgb")?;
+        let features = flatgeobuf::FgbReader::open(file)?.select_all()?;
+        let processed = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&processed);
+        let options = DeserializeOptions::new().on_feature(move |_| {
+            counter.fetch_add(1, Ordering::Relaxed);
+            ControlFlow::Continue(())
+        });
+        let mut countries = from_datasource_iter::<Country, _>(features, &options);
+        drop(options);
+        assert!(countries.next().is_some());
+        // dropping the iterator early stops the background thread, which releases the counter
+        drop(countries);
+        assert_eq!(Arc::strong_count(&processed), 1);
+        assert!(processed.load(Ordering::Relaxed) <= BUFFERED_FEATURES + 2);
+        Ok(())
+    }
+}
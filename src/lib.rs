@@ -5,20 +5,19 @@
 //! This crate provides functionality to convert between geospatial data sources and
 //! Rust types using serde's serialization framework and geozero's processing capabilities.
 //!
-//! ## Disclaimer ##
-//! This isn't a fully fledged cargo crate as it's still missing some functionality it claims to
-//! provide (serialization).
-//!
 //! ## Features
 //!
 //! - Deserialize from various geospatial formats (`GeoJSON`, `FlatGeobuf`, etc.) into Rust structs
 //! - Collect geometry and property data from geospatial sources
-//! - Serialize geometry data into different formats
+//! - Serialize Rust structs with their geometry into any geozero processor, e.g. `GeoJSON`
+//! - Deserialize already collected features, e.g. [`de::Feature`]s, without a datasource
 //!
 //! ## Main Components
 //!
 //! - [`collector::GeozeroCollector`] - Collects geometry and property data from geospatial sources
 //! - [`from_datasource`] - Helper function to deserialize data from any `GeozeroDatasource`
+//! - [`to_geozero_datasource`] - Helper function to serialize data into any `FeatureProcessor`
+//! - [`de::from_feature_iter`] - Deserializes features that were already read
 //! - [`error::Error`] - Custom error types for the library
 //!
 //! ## Example
@@ -66,21 +65,83 @@
 //!     );
 //! ```
 //!
-//! ## TODO:
-//!  - Serialization
-//!  - Deserialization for non `GeozeroDatasource`
-//!
 //! ## Modules
 //!
+//! - [`case`] - Property name case conversion
 //! - [`collector`] - Contains the `GeozeroCollector` implementation
+//! - [`coord`] - Geometry fields with coordinate types other than `f64`
+//! - [`contract`] - Struct schemas embedded in and verified against dataset metadata
+//! - [`compare`] - Tolerant equality, stable hashing and diffs of geometries and features
+//! - `conformance` - Cross-format conformance cases for datasources and processors (requires the
+//!   `conformance` feature)
+//! - `datetime` - Date and time properties (requires the `chrono` feature)
 //! - [`de`] - Deserialization functionality
+//! - [`derived`] - Properties derived from the geometry while writing
+//! - [`dynamic`] - Features with a schema defined at runtime
+//! - [`edit`] - Copy-on-write editing of collected features
+//! - [`encoded`] - Geometry fields read from WKT or WKB encoded properties
 //! - [`error`] - Error types and handling
+//! - [`finite`] - Handling of NaN and infinite coordinates and properties
+//! - [`format`] - Reading files with auto-detected formats
+//! - [`geojson`] - `GeoJSON` writer supporting features without geometry, pretty printing and bare
+//!   features or geometries, and a reader keeping feature ids and conversions to and from the
+//!   `geojson` crate (requires the `geojson` feature)
+//! - [`iter`] - Pulling features lazily from push-based datasources
+//! - [`lookup`] - Enriching features with lookup tables while reading
+//! - [`merge`] - Reading several datasources as one
+//! - [`quality`] - Rule based dataset quality checks
+//! - [`registry`] - Deserialization into trait objects selected by a discriminator property
+//! - `reproject` - Coordinate reprojection (requires the `proj` feature)
+//! - [`schema`] - Column metadata and dataset profiling
 //! - [`ser`] - Serialization functionality
+//! - `stream` - Streaming features to live clients as NDJSON (requires the `stream` feature)
+//! - `testing` - Round-trip fidelity checks for your own types (requires the `testing` feature)
+//! - [`value`] - Property values keeping the type of the source column
+//! - `web` - `GeoJSON` request bodies and responses for web services (requires the `web`
+//!   feature, the `axum` and `actix-web` features add extractors for those frameworks)
+//! - [`with`] - `#[serde(with = "...")]` adapters choosing the encoding of a geometry field
+//! - [`zm`] - Z and M coordinate support
 
+pub mod case;
 #[allow(clippy::module_name_repetitions)]
+pub mod collector;
+pub mod compare;
+#[cfg(feature = "conformance")]
+pub mod conformance;
+pub mod contract;
+pub mod coord;
+#[cfg(feature = "chrono")]
+pub mod datetime;
 pub mod de;
+pub mod derived;
+pub mod dynamic;
+pub mod edit;
+pub mod encoded;
 pub mod error;
+pub mod finite;
+pub mod format;
+pub mod geojson;
+pub mod iter;
+pub mod lookup;
+pub mod merge;
+pub mod quality;
+pub mod registry;
+#[cfg(feature = "proj")]
+pub mod reproject;
+pub mod schema;
 pub mod ser;
+#[cfg(feature = "stream")]
+pub mod stream;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod value;
+#[cfg(feature = "web")]
+pub mod web;
+pub mod with;
+pub mod zm;
 
-pub use de::from_datasource;
-pub use ser::to_geozero_datasource;
+pub use de::{from_datasource, from_datasource_with_options, DeserializeOptions};
+pub use format::{from_reader, Format};
+pub use ser::{
+    to_geozero_datasource, to_geozero_datasource_with_options, to_geozero_feature, SerializeOptions,
+};
@@ -0,0 +1,155 @@
+//! Enriching features with lookup tables while reading.
+//!
+//! A [`Lookup`] maps the value of one property, e.g. a land use code, to additional properties
+//! which are merged into every matching feature before it is deserialized. Code lists are thus
+//! expanded inline instead of in a second pass over the collected features:
+//!
+//! ```
+//! use std::collections::HashMap;
+//!
+//! use geo::Geometry;
+//! use serde::{Deserialize, Serialize};
+//! use serde_geozero::lookup::Lookup;
+//! use serde_geozero::{from_datasource_with_options, DeserializeOptions};
+//!
+//! #[derive(Serialize)]
+//! struct LandUse {
+//!     label: &'static str,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! struct Parcel {
+//!     geometry: Geometry,
+//!     code: u32,
+//!     label: Option<String>,
+//! }
+//!
+//! let land_use = HashMap::from([(10, LandUse { label: "Forest" })]);
+//! let options = DeserializeOptions::new().lookup(Lookup::new("code", land_use).unwrap());
+//!
+//! let geojson = r#"{"type": "Feature", "properties": {"code": 10},
+//!     "geometry": {"type": "Point", "coordinates": [1, 1]}}"#;
+//! let mut reader = geozero::geojson::GeoJsonReader(geojson.as_bytes());
+//! let parcels: Vec<Parcel> = from_datasource_with_options(&mut reader, &options).unwrap();
+//! assert_eq!(parcels[0].label.as_deref(), Some("Forest"));
+//! ```
+use std::sync::Arc;
+
+use hashbrown::HashMap;
+use serde::Serialize;
+
+use crate::{de::Feature, error::Result, ser::to_properties, value::Value};
+
+/// Additional properties keyed by the value of a property.
+///
+/// Keys are compared by their textual form, so the integer code `10` also matches a property
+/// holding the string `"10"`. Features without the key property or without a matching entry
+/// are left unchanged, fields filled by a lookup should therefore be `Option`s or have a
+/// default.
+#[derive(Debug, Clone)]
+pub struct Lookup {
+    property: String,
+    entries: Arc<HashMap<String, HashMap<String, Value>>>,
+    overwrite: bool,
+}
+
+impl Lookup {
+    /// Creates a lookup on `property` from `(key, extra)` pairs.
+    ///
+    /// Every `extra` has to serialize as a struct or map, its fields become properties.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an `extra` fails to serialize or isn't a struct or map.
+    pub fn new<K, E, I>(property: &str, table: I) -> Result<Self>
+    where
+        K: Into<Value>,
+        E: Serialize,
+        I: IntoIterator<Item = (K, E)>,
+    {
+        let entries = table
+            .into_iter()
+            .filter_map(|(key, extra)| {
+                key_of(&key.into()).map(|key| Ok((key, to_properties(&extra)?)))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            property: property.to_string(),
+            entries: Arc::new(entries),
+            overwrite: false,
+        })
+    }
+
+    /// Replaces properties the feature already has, by default they are kept.
+    #[must_use]
+    pub fn overwrite(mut self, overwrite: bool) -> Self {
+        self.overwrite = overwrite;
+        self
+    }
+
+    /// The property whose value is looked up.
+    pub(crate) fn property(&self) -> &str {
+        &self.property
+    }
+
+    /// Merges the entry matching `feature` into its properties.
+    pub(crate) fn apply(&self, feature: &mut Feature) {
+        let Some(extra) = feature
+            .properties
+            .get(&self.property)
+            .and_then(key_of)
+            .and_then(|key| self.entries.get(&key))
+        else {
+            return;
+        };
+        for (name, value) in extra {
+            if self.overwrite || !feature.properties.contains_key(name) {
+                feature.properties.insert(name.clone(), value.clone());
+            }
+        }
+    }
+}
+
+/// The textual form keys are compared by, `None` for values that can't be keys.
+fn key_of(value: &Value) -> Option<String> {
+    match value {
+        Value::Null | Value::Float(_) | Value::Double(_) | Value::Binary(_) => None,
+        Value::String(value) | Value::Json(value) | Value::DateTime(value) => Some(value.clone()),
+        Value::Shared(value) => Some(value.to_string()),
+        value => Some(value.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use geo::point;
+    use hashbrown::HashMap;
+
+    use crate::{de::Feature, value::Value};
+
+    use super::Lookup;
+
+    #[test]
+    fn merge() -> anyhow::Result<()> {
+        let table = [(
+            "A",
+            BTreeMap::from([("label", "Forest"), ("code", "overwritten")]),
+        )];
+        let mut feature = Feature::new(
+            point! { x: 1., y: 2. }.into(),
+            HashMap::from([("code".to_string(), Value::from("A"))]),
+        );
+
+        Lookup::new("code", table.clone())?.apply(&mut feature);
+        assert_eq!(feature.properties["label"], "Forest");
+        assert_eq!(feature.properties["code"], "A");
+
+        Lookup::new("code", table)?
+            .overwrite(true)
+            .apply(&mut feature);
+        assert_eq!(feature.properties["code"], "overwritten");
+        Ok(())
+    }
+}
@@ -0,0 +1,425 @@
+//! Reading several datasources as one, e.g. regional `FlatGeobuf` tiles.
+//!
+//! [`MergeSource`] concatenates the features of its sources into a single dataset. Features are
+//! numbered across all sources, columns can be renamed per source to resolve name collisions,
+//! and every feature can be tagged with the index of the source it was read from:
+//!
+//! ```
+//! use geo::Geometry;
+//! use serde::Deserialize;
+//! use serde_geozero::merge::MergeSource;
+//! use serde_geozero::from_datasource;
+//!
+//! #[derive(Deserialize)]
+//! struct City {
+//!     geometry: Geometry,
+//!     name: String,
+//!     tile: usize,
+//! }
+//!
+//! let north = r#"{"type": "Feature", "properties": {"name": "Hamburg"},
+//!     "geometry": {"type": "Point", "coordinates": [10.0, 53.6]}}"#;
+//! let south = r#"{"type": "Feature", "properties": {"NAME": "Munich"},
+//!     "geometry": {"type": "Point", "coordinates": [11.6, 48.1]}}"#;
+//! let mut tiles = [
+//!     geozero::geojson::GeoJsonReader(north.as_bytes()),
+//!     geozero::geojson::GeoJsonReader(south.as_bytes()),
+//! ];
+//! let mut merged = MergeSource::new(&mut tiles)
+//!     .rename(1, "NAME", "name")
+//!     .source_index("tile");
+//! let cities: Vec<City> = from_datasource(&mut merged).unwrap();
+//! assert_eq!((cities[1].name.as_str(), cities[1].tile), ("Munich", 1));
+//! ```
+use geozero::{
+    error::Result as GeozeroResult, ColumnValue, CoordDimensions, FeatureProcessor, GeomProcessor,
+    GeozeroDatasource, PropertyProcessor,
+};
+use hashbrown::HashMap;
+
+/// A datasource reading the features of all `sources` one after another.
+///
+/// The merged dataset is reported without a name. Features keep the order of their sources and
+/// are numbered from `0` across all of them.
+pub struct MergeSource<'a, S> {
+    sources: &'a mut [S],
+    /// Column renames per source index.
+    renames: HashMap<usize, HashMap<String, String>>,
+    source_column: Option<String>,
+}
+
+impl<'a, S: GeozeroDatasource> MergeSource<'a, S> {
+    /// Merges the features of `sources`.
+    pub fn new(sources: &'a mut [S]) -> Self {
+        Self {
+            sources,
+            renames: HashMap::new(),
+            source_column: None,
+        }
+    }
+
+    /// Renames the column `from` of the source at `source` to `to`, e.g. if tiles were written
+    /// with different spellings of the same column.
+    #[must_use]
+    pub fn rename(mut self, source: usize, from: &str, to: &str) -> Self {
+        self.renames
+            .entry(source)
+            .or_default()
+            .insert(from.to_string(), to.to_string());
+        self
+    }
+
+    /// Adds the index of its source to every feature as the unsigned integer property
+    /// `column`.
+    ///
+    /// A property of the sources with the same name is dropped in favour of the index, rename
+    /// it to keep it.
+    #[must_use]
+    pub fn source_index(mut self, column: &str) -> Self {
+        self.source_column = Some(column.to_string());
+        self
+    }
+}
+
+impl<S: GeozeroDatasource> GeozeroDatasource for MergeSource<'_, S> {
+    fn process<P: FeatureProcessor>(&mut self, processor: &mut P) -> GeozeroResult<()> {
+        let no_renames = HashMap::new();
+        processor.dataset_begin(None)?;
+        let mut offset = 0;
+        for (index, source) in self.sources.iter_mut().enumerate() {
+            let mut merge = MergeProcessor {
+                inner: processor,
+                source: index,
+                renames: self.renames.get(&index).unwrap_or(&no_renames),
+                source_column: self.source_column.as_deref(),
+                offset,
+                feature_count: 0,
+                property_count: 0,
+                source_written: false,
+                inner_finished: false,
+            };
+            source.process(&mut merge)?;
+            offset += merge.feature_count;
+        }
+        processor.dataset_end()
+    }
+}
+
+/// Passes the events of one source on to the merged dataset.
+struct MergeProcessor<'a, P> {
+    inner: &'a mut P,
+    source: usize,
+    renames: &'a HashMap<String, String>,
+    source_column: Option<&'a str>,
+    /// The number of features read from the previous sources.
+    offset: u64,
+    /// The number of features read from this source, one past its highest index.
+    feature_count: u64,
+    /// The number of properties passed on for the current feature.
+    property_count: usize,
+    source_written: bool,
+    /// Set once the wrapped processor needs no more properties of the current feature.
+    inner_finished: bool,
+}
+
+impl<P: FeatureProcessor> MergeProcessor<'_, P> {
+    fn write_source_index(&mut self) -> GeozeroResult<()> {
+        self.source_written = true;
+        let Some(column) = self.source_column else {
+            return Ok(());
+        };
+        let value = ColumnValue::ULong(self.source as u64);
+        self.inner.property(self.property_count, column, &value)?;
+        self.property_count += 1;
+        Ok(())
+    }
+}
+
+impl<P: PropertyProcessor> PropertyProcessor for MergeProcessor<'_, P> {
+    fn property(&mut self, _idx: usize, name: &str, value: &ColumnValue) -> GeozeroResult<bool> {
+        if self.inner_finished {
+            return Ok(true);
+        }
+        let name = self.renames.get(name).map_or(name, String::as_str);
+        if self.source_column == Some(name) {
+            return Ok(false);
+        }
+        self.inner_finished = self.inner.property(self.property_count, name, value)?;
+        self.property_count += 1;
+        // the source index is still to come
+        Ok(self.inner_finished && self.source_column.is_none())
+    }
+}
+
+impl<P: GeomProcessor> GeomProcessor for MergeProcessor<'_, P> {
+    fn dimensions(&self) -> CoordDimensions {
+        self.inner.dimensions()
+    }
+
+    fn multi_dim(&self) -> bool {
+        self.inner.multi_dim()
+    }
+
+    fn srid(&mut self, srid: Option<i32>) -> GeozeroResult<()> {
+        self.inner.srid(srid)
+    }
+
+    fn xy(&mut self, x: f64, y: f64, idx: usize) -> GeozeroResult<()> {
+        self.inner.xy(x, y, idx)
+    }
+
+    #[allow(clippy::many_single_char_names)]
+    fn coordinate(
+        &mut self,
+        x: f64,
+        y: f64,
+        z: Option<f64>,
+        m: Option<f64>,
+        t: Option<f64>,
+        tm: Option<u64>,
+        idx: usize,
+    ) -> GeozeroResult<()> {
+        self.inner.coordinate(x, y, z, m, t, tm, idx)
+    }
+
+    fn empty_point(&mut self, idx: usize) -> GeozeroResult<()> {
+        self.inner.empty_point(idx)
+    }
+
+    fn point_begin(&mut self, idx: usize) -> GeozeroResult<()> {
+        self.inner.point_begin(idx)
+    }
+
+    fn point_end(&mut self, idx: usize) -> GeozeroResult<()> {
+        self.inner.point_end(idx)
+    }
+
+    fn multipoint_begin(&mut self, size: usize, idx: usize) -> GeozeroResult<()> {
+        self.inner.multipoint_begin(size, idx)
+    }
+
+    fn multipoint_end(&mut self, idx: usize) -> GeozeroResult<()> {
+        self.inner.multipoint_end(idx)
+    }
+
+    fn linestring_begin(&mut self, tagged: bool, size: usize, idx: usize) -> GeozeroResult<()> {
+        self.inner.linestring_begin(tagged, size, idx)
+    }
+
+    fn linestring_end(&mut self, tagged: bool, idx: usize) -> GeozeroResult<()> {
+        self.inner.linestring_end(tagged, idx)
+    }
+
+    fn multilinestring_begin(&mut self, size: usize, idx: usize) -> GeozeroResult<()> {
+        self.inner.multilinestring_begin(size, idx)
+    }
+
+    fn multilinestring_end(&mut self, idx: usize) -> GeozeroResult<()> {
+        self.inner.multilinestring_end(idx)
+    }
+
+    fn polygon_begin(&mut self, tagged: bool, size: usize, idx: usize) -> GeozeroResult<()> {
+        self.inner.polygon_begin(tagged, size, idx)
+    }
+
+    fn polygon_end(&mut self, tagged: bool, idx: usize) -> GeozeroResult<()> {
+        self.inner.polygon_end(tagged, idx)
+    }
+
+    fn multipolygon_begin(&mut self, size: usize, idx: usize) -> GeozeroResult<()> {
+        self.inner.multipolygon_begin(size, idx)
+    }
+
+    fn multipolygon_end(&mut self, idx: usize) -> GeozeroResult<()> {
+        self.inner.multipolygon_end(idx)
+    }
+
+    fn geometrycollection_begin(&mut self, size: usize, idx: usize) -> GeozeroResult<()> {
+        self.inner.geometrycollection_begin(size, idx)
+    }
+
+    fn geometrycollection_end(&mut self, idx: usize) -> GeozeroResult<()> {
+        self.inner.geometrycollection_end(idx)
+    }
+
+    fn circularstring_begin(&mut self, size: usize, idx: usize) -> GeozeroResult<()> {
+        self.inner.circularstring_begin(size, idx)
+    }
+
+    fn circularstring_end(&mut self, idx: usize) -> GeozeroResult<()> {
+        self.inner.circularstring_end(idx)
+    }
+
+    fn compoundcurve_begin(&mut self, size: usize, idx: usize) -> GeozeroResult<()> {
+        self.inner.compoundcurve_begin(size, idx)
+    }
+
+    fn compoundcurve_end(&mut self, idx: usize) -> GeozeroResult<()> {
+        self.inner.compoundcurve_end(idx)
+    }
+
+    fn curvepolygon_begin(&mut self, size: usize, idx: usize) -> GeozeroResult<()> {
+        self.inner.curvepolygon_begin(size, idx)
+    }
+
+    fn curvepolygon_end(&mut self, idx: usize) -> GeozeroResult<()> {
+        self.inner.curvepolygon_end(idx)
+    }
+
+    fn multicurve_begin(&mut self, size: usize, idx: usize) -> GeozeroResult<()> {
+        self.inner.multicurve_begin(size, idx)
+    }
+
+    fn multicurve_end(&mut self, idx: usize) -> GeozeroResult<()> {
+        self.inner.multicurve_end(idx)
+    }
+
+    fn multisurface_begin(&mut self, size: usize, idx: usize) -> GeozeroResult<()> {
+        self.inner.multisurface_begin(size, idx)
+    }
+
+    fn multisurface_end(&mut self, idx: usize) -> GeozeroResult<()> {
+        self.inner.multisurface_end(idx)
+    }
+
+    fn triangle_begin(&mut self, tagged: bool, size: usize, idx: usize) -> GeozeroResult<()> {
+        self.inner.triangle_begin(tagged, size, idx)
+    }
+
+    fn triangle_end(&mut self, tagged: bool, idx: usize) -> GeozeroResult<()> {
+        self.inner.triangle_end(tagged, idx)
+    }
+
+    fn polyhedralsurface_begin(&mut self, size: usize, idx: usize) -> GeozeroResult<()> {
+        self.inner.polyhedralsurface_begin(size, idx)
+    }
+
+    fn polyhedralsurface_end(&mut self, idx: usize) -> GeozeroResult<()> {
+        self.inner.polyhedralsurface_end(idx)
+    }
+
+    fn tin_begin(&mut self, size: usize, idx: usize) -> GeozeroResult<()> {
+        self.inner.tin_begin(size, idx)
+    }
+
+    fn tin_end(&mut self, idx: usize) -> GeozeroResult<()> {
+        self.inner.tin_end(idx)
+    }
+}
+
+impl<P: FeatureProcessor> FeatureProcessor for MergeProcessor<'_, P> {
+    // the merged dataset begins and ends only once
+    fn dataset_begin(&mut self, _name: Option<&str>) -> GeozeroResult<()> {
+        Ok(())
+    }
+
+    fn dataset_end(&mut self) -> GeozeroResult<()> {
+        Ok(())
+    }
+
+    fn feature_begin(&mut self, idx: u64) -> GeozeroResult<()> {
+        self.feature_count = self.feature_count.max(idx + 1);
+        self.property_count = 0;
+        self.source_written = false;
+        self.inner_finished = false;
+        self.inner.feature_begin(self.offset + idx)
+    }
+
+    fn feature_end(&mut self, idx: u64) -> GeozeroResult<()> {
+        if !self.source_written && self.source_column.is_some() {
+            self.inner.properties_begin()?;
+            self.write_source_index()?;
+            self.inner.properties_end()?;
+        }
+        self.inner.feature_end(self.offset + idx)
+    }
+
+    fn properties_begin(&mut self) -> GeozeroResult<()> {
+        self.inner.properties_begin()
+    }
+
+    fn properties_end(&mut self) -> GeozeroResult<()> {
+        self.write_source_index()?;
+        self.inner.properties_end()
+    }
+
+    fn geometry_begin(&mut self) -> GeozeroResult<()> {
+        self.inner.geometry_begin()
+    }
+
+    fn geometry_end(&mut self) -> GeozeroResult<()> {
+        self.inner.geometry_end()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use geozero::{geojson::GeoJsonReader, GeozeroDatasource};
+
+    use super::MergeSource;
+    use crate::{
+        collector::GeozeroCollector,
+        de::{from_datasources, DeserializeOptions, Feature},
+        value::Value,
+    };
+
+    #[test]
+    fn merge_sources() -> anyhow::Result<()> {
+        let first = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "properties": {"name": "a", "tile": "x"},
+             "geometry": {"type": "Point", "coordinates": [1, 1]}},
+            {"type": "Feature", "geometry": {"type": "Point", "coordinates": [2, 2]}}
+        ]}"#;
+        let second = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "properties": {"label": "c"},
+             "geometry": {"type": "Point", "coordinates": [3, 3]}}
+        ]}"#;
+        let mut sources = [
+            GeoJsonReader(first.as_bytes()),
+            GeoJsonReader(second.as_bytes()),
+        ];
+        let mut merged = MergeSource::new(&mut sources)
+            .rename(1, "label", "name")
+            .source_index("tile");
+        let mut collector = GeozeroCollector::<Feature>::new();
+        merged.process(&mut collector)?;
+
+        let features = collector.features();
+        assert_eq!(features.len(), 3);
+        let tiles: Vec<_> = features.iter().map(|f| f.value("tile").cloned()).collect();
+        assert_eq!(
+            tiles,
+            [Value::ULong(0), Value::ULong(0), Value::ULong(1)].map(Some)
+        );
+        assert_eq!(features[0].get_str("name"), Some("a"));
+        assert_eq!(features[2].get_str("name"), Some("c"));
+
+        let mut sources = [
+            GeoJsonReader(first.as_bytes()),
+            GeoJsonReader(second.as_bytes()),
+        ];
+        let mut fids = Vec::new();
+        let mut merged = MergeSource::new(&mut sources);
+        merged.process(&mut Fids(&mut fids))?;
+        assert_eq!(fids, [0, 1, 2]);
+
+        let mut sources = [
+            GeoJsonReader(first.as_bytes()),
+            GeoJsonReader(second.as_bytes()),
+        ];
+        let features: Vec<Feature> = from_datasources(&mut sources, &DeserializeOptions::new())?;
+        assert_eq!(features[2].get_str("label"), Some("c"));
+        Ok(())
+    }
+
+    struct Fids<'a>(&'a mut Vec<u64>);
+
+    impl geozero::PropertyProcessor for Fids<'_> {}
+    impl geozero::GeomProcessor for Fids<'_> {}
+    impl geozero::FeatureProcessor for Fids<'_> {
+        fn feature_begin(&mut self, idx: u64) -> geozero::error::Result<()> {
+            self.0.push(idx);
+            Ok(())
+        }
+    }
+}
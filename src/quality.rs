@@ -0,0 +1,341 @@
+//! Dataset quality checks.
+//!
+//! A [`QualityChecker`] runs a set of [`Rule`]s against every feature while it is read and
+//! collects all findings into a single [`QualityReport`]. The built-in rules cover the checks
+//! most ingest pipelines need; custom checks implement [`Rule`].
+use geo::{CoordsIter, Geometry, HasDimensions};
+use geozero::GeozeroDatasource;
+use hashbrown::HashMap;
+use serde::Deserialize;
+
+use crate::{
+    de::{DataSourceDeserializer, Feature},
+    error::Result,
+};
+
+/// A single finding of a [`Rule`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Issue {
+    /// Name of the rule that raised the issue.
+    pub rule: &'static str,
+    /// Index of the offending feature, `None` for dataset level issues.
+    pub feature: Option<usize>,
+    pub message: String,
+}
+
+/// All issues found while checking a dataset.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QualityReport {
+    pub feature_count: usize,
+    pub issues: Vec<Issue>,
+}
+
+impl QualityReport {
+    /// Returns `true` if no rule raised an issue.
+    #[must_use]
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Issues raised by the rule with the given name.
+    pub fn issues_for<'a>(&'a self, rule: &'a str) -> impl Iterator<Item = &'a Issue> + 'a {
+        self.issues.iter().filter(move |issue| issue.rule == rule)
+    }
+}
+
+/// A check that is run against every feature of a dataset.
+pub trait Rule {
+    /// Name used to identify issues raised by this rule.
+    fn name(&self) -> &'static str;
+
+    /// Checks a single feature and returns a message for every problem found.
+    fn check(&mut self, idx: usize, feature: &Feature) -> Vec<String>;
+
+    /// Called once after the last feature, for rules that need to see the whole dataset.
+    fn finish(&mut self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// Flags features whose geometry has no coordinates.
+#[derive(Debug, Default)]
+pub struct EmptyGeometry;
+
+impl Rule for EmptyGeometry {
+    fn name(&self) -> &'static str {
+        "empty_geometry"
+    }
+
+    fn check(&mut self, _idx: usize, feature: &Feature) -> Vec<String> {
+        if feature.geometry.is_empty() {
+            vec!["geometry is empty".to_string()]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+/// Flags features that repeat the value of a column that is expected to be unique.
+#[derive(Debug)]
+pub struct DuplicateKey {
+    column: String,
+    seen: HashMap<String, usize>,
+}
+
+impl DuplicateKey {
+    #[must_use]
+    pub fn new(column: &str) -> Self {
+        Self {
+            column: column.to_string(),
+            seen: HashMap::new(),
+        }
+    }
+}
+
+impl Rule for DuplicateKey {
+    fn name(&self) -> &'static str {
+        "duplicate_key"
+    }
+
+    fn check(&mut self, idx: usize, feature: &Feature) -> Vec<String> {
+        let Some(value) = feature.properties.get(&self.column) else {
+            return Vec::new();
+        };
+        let key = value.to_string();
+        if let Some(first) = self.seen.get(&key) {
+            return vec![format!(
+                "value {key} of column {:?} is already used by feature {first}",
+                self.column
+            )];
+        }
+        self.seen.insert(key, idx);
+        Vec::new()
+    }
+}
+
+/// Flags coordinates at exactly (0, 0), which usually indicate a missing or failed geocode.
+#[derive(Debug, Default)]
+pub struct NullIsland;
+
+impl Rule for NullIsland {
+    fn name(&self) -> &'static str {
+        "null_island"
+    }
+
+    fn check(&mut self, _idx: usize, feature: &Feature) -> Vec<String> {
+        #[allow(clippy::float_cmp)]
+        if feature
+            .geometry
+            .coords_iter()
+            .any(|coord| coord.x == 0. && coord.y == 0.)
+        {
+            vec!["geometry contains the coordinate (0, 0)".to_string()]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+/// Flags features whose geometry type differs from the first feature of the dataset.
+#[derive(Debug, Default)]
+pub struct MixedGeometryTypes {
+    first: Option<&'static str>,
+}
+
+impl Rule for MixedGeometryTypes {
+    fn name(&self) -> &'static str {
+        "mixed_geometry_types"
+    }
+
+    fn check(&mut self, _idx: usize, feature: &Feature) -> Vec<String> {
+        let found = geometry_type_name(&feature.geometry);
+        match self.first {
+            None => {
+                self.first = Some(found);
+                Vec::new()
+            }
+            Some(expected) if expected != found => {
+                vec![format!(
+                    "expected a {expected} geometry but found a {found}"
+                )]
+            }
+            Some(_) => Vec::new(),
+        }
+    }
+}
+
+/// Returns the name of the geometry type, e.g. `"MultiPolygon"`.
+#[must_use]
+pub fn geometry_type_name(geometry: &Geometry) -> &'static str {
+    match geometry {
+        Geometry::Point(_) => "Point",
+        Geometry::Line(_) => "Line",
+        Geometry::LineString(_) => "LineString",
+        Geometry::Polygon(_) => "Polygon",
+        Geometry::MultiPoint(_) => "MultiPoint",
+        Geometry::MultiLineString(_) => "MultiLineString",
+        Geometry::MultiPolygon(_) => "MultiPolygon",
+        Geometry::GeometryCollection(_) => "GeometryCollection",
+        Geometry::Rect(_) => "Rect",
+        Geometry::Triangle(_) => "Triangle",
+    }
+}
+
+/// Runs a set of rules against features and accumulates a [`QualityReport`].
+#[derive(Default)]
+pub struct QualityChecker {
+    rules: Vec<Box<dyn Rule>>,
+    report: QualityReport,
+}
+
+impl QualityChecker {
+    /// Creates a checker without any rules.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a checker with the built-in [`EmptyGeometry`], [`NullIsland`] and
+    /// [`MixedGeometryTypes`] rules.
+    #[must_use]
+    pub fn with_default_rules() -> Self {
+        Self::new()
+            .with_rule(EmptyGeometry)
+            .with_rule(NullIsland)
+            .with_rule(MixedGeometryTypes::default())
+    }
+
+    /// Adds a rule to the checker.
+    #[must_use]
+    pub fn with_rule<R: Rule + 'static>(mut self, rule: R) -> Self {
+        self.rules.push(Box::new(rule));
+        self
+    }
+
+    /// Runs all rules against the next feature.
+    pub fn check(&mut self, feature: &Feature) {
+        let idx = self.report.feature_count;
+        for rule in &mut self.rules {
+            let name = rule.name();
+            self.report
+                .issues
+                .extend(rule.check(idx, feature).into_iter().map(|message| Issue {
+                    rule: name,
+                    feature: Some(idx),
+                    message,
+                }));
+        }
+        self.report.feature_count += 1;
+    }
+
+    /// Finishes all rules and returns the consolidated report.
+    #[must_use]
+    pub fn finish(mut self) -> QualityReport {
+        for rule in &mut self.rules {
+            let name = rule.name();
+            self.report
+                .issues
+                .extend(rule.finish().into_iter().map(|message| Issue {
+                    rule: name,
+                    feature: None,
+                    message,
+                }));
+        }
+        self.report
+    }
+}
+
+/// Deserializes a datasource like [`crate::from_datasource`] while running the checker's rules
+/// against every feature.
+///
+/// # Examples
+///
+/// ```
+/// use geo::Geometry;
+/// use serde::Deserialize;
+/// use serde_geozero::quality::{from_datasource_checked, DuplicateKey, QualityChecker};
+///
+/// #[derive(Deserialize)]
+/// struct Place {
+///     geometry: Geometry,
+///     id: i64,
+/// }
+///
+/// let geojson = r#"{"type": "FeatureCollection", "features": [
+///     {"type": "Feature", "geometry": {"type": "Point", "coordinates": [0, 0]}, "properties": {"id": 1}},
+///     {"type": "Feature", "geometry": {"type": "Point", "coordinates": [1, 1]}, "properties": {"id": 1}}
+/// ]}"#;
+///
+/// let checker = QualityChecker::with_default_rules().with_rule(DuplicateKey::new("id"));
+/// let mut reader = geozero::geojson::GeoJsonReader(geojson.as_bytes());
+/// let (places, report): (Vec<Place>, _) = from_datasource_checked(&mut reader, checker).unwrap();
+///
+/// assert_eq!(places.len(), 2);
+/// assert_eq!(report.issues_for("null_island").count(), 1);
+/// assert_eq!(report.issues_for("duplicate_key").count(), 1);
+/// ```
+///
+/// # Errors
+///
+/// Returns the same errors as [`crate::from_datasource`]. Rule violations are reported, not
+/// raised.
+pub fn from_datasource_checked<'de, T: Deserialize<'de> + 'static, S: GeozeroDatasource>(
+    processor: &mut S,
+    checker: QualityChecker,
+) -> Result<(Vec<T>, QualityReport)> {
+    let mut collector = DataSourceDeserializer::new().with_quality_checker(checker);
+    collector.process(processor)?;
+
+    let report = collector.take_quality_report().unwrap_or_default();
+    Ok((collector.features, report))
+}
+
+#[cfg(test)]
+mod test {
+    use geo::{line_string, point, Geometry, LineString};
+    use hashbrown::HashMap;
+
+    use super::{DuplicateKey, QualityChecker};
+    use crate::{de::Feature, value::Value};
+
+    #[test]
+    fn built_in_rules() {
+        let features = vec![
+            Feature::new(
+                (point! { x: 1.0, y: 1.0 }).into(),
+                HashMap::from_iter(vec![("id".to_string(), Value::from(1))]),
+            ),
+            Feature::new(
+                (point! { x: 0.0, y: 0.0 }).into(),
+                HashMap::from_iter(vec![("id".to_string(), Value::from(1))]),
+            ),
+            Feature::new(
+                Geometry::LineString(LineString::new(vec![])),
+                HashMap::from_iter(vec![("id".to_string(), Value::from(2))]),
+            ),
+            Feature::new(
+                (line_string![(x: 1.0, y: 1.0), (x: 2.0, y: 2.0)]).into(),
+                HashMap::new(),
+            ),
+        ];
+
+        let mut checker = QualityChecker::with_default_rules().with_rule(DuplicateKey::new("id"));
+        for feature in &features {
+            checker.check(feature);
+        }
+        let report = checker.finish();
+
+        assert_eq!(report.feature_count, 4);
+        assert!(!report.is_clean());
+        let feature_of = |rule| {
+            report
+                .issues_for(rule)
+                .map(|issue| issue.feature.unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(feature_of("empty_geometry"), vec![2]);
+        assert_eq!(feature_of("null_island"), vec![1]);
+        assert_eq!(feature_of("duplicate_key"), vec![1]);
+        assert_eq!(feature_of("mixed_geometry_types"), vec![2, 3]);
+    }
+}
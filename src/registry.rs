@@ -0,0 +1,270 @@
+//! Deserialization into trait objects.
+//!
+//! A [`TypeRegistry`] maps the values of a discriminator property to concrete types. Every
+//! feature is deserialized into the type registered for its discriminator and returned as a
+//! `Box<dyn FeatureRecord>`, so consumers don't have to know all feature types at compile time.
+use std::{any::Any, fmt::Debug};
+
+use geo::Geometry;
+use geozero::GeozeroDatasource;
+use hashbrown::HashMap;
+use serde::de::DeserializeOwned;
+
+use crate::{
+    collector::{Convert, Converted, GeozeroCollector},
+    de::{DeserializeOptions, Feature, FeatureRef},
+    error::{Error, Result},
+    value::Value,
+};
+
+/// A feature type that can be registered in a [`TypeRegistry`].
+pub trait FeatureRecord: Any + Debug {
+    /// The geometry of the feature.
+    fn geometry(&self) -> &Geometry;
+
+    /// Returns `self`, used to downcast to the concrete type.
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl dyn FeatureRecord {
+    /// Returns the record as `T` if it is of that type.
+    #[must_use]
+    pub fn downcast_ref<T: FeatureRecord>(&self) -> Option<&T> {
+        self.as_any().downcast_ref()
+    }
+
+    /// Returns `true` if the record is of type `T`.
+    #[must_use]
+    pub fn is<T: FeatureRecord>(&self) -> bool {
+        self.as_any().is::<T>()
+    }
+}
+
+/// Deserializes a feature into a registered type, either moving or borrowing the feature.
+#[derive(Debug, Clone, Copy)]
+struct Constructor {
+    owned: fn(Feature) -> Result<Box<dyn FeatureRecord>>,
+    borrowed: fn(&Feature) -> Result<Box<dyn FeatureRecord>>,
+}
+
+impl Constructor {
+    fn new<T: FeatureRecord + DeserializeOwned>() -> Self {
+        Self {
+            owned: |feature| Ok(Box::new(T::deserialize(feature)?)),
+            borrowed: |feature| Ok(Box::new(T::deserialize(FeatureRef::new(feature))?)),
+        }
+    }
+}
+
+/// Maps discriminator values to the types features are deserialized into.
+///
+/// # Examples
+///
+/// ```
+/// use std::any::Any;
+/// use geo::Geometry;
+/// use serde::Deserialize;
+/// use serde_geozero::registry::{from_datasource_registry, FeatureRecord, TypeRegistry};
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Road {
+///     geometry: Geometry,
+///     lanes: u8,
+/// }
+///
+/// impl FeatureRecord for Road {
+///     fn geometry(&self) -> &Geometry {
+///         &self.geometry
+///     }
+///
+///     fn as_any(&self) -> &dyn Any {
+///         self
+///     }
+/// }
+///
+/// let geojson = r#"{"type": "Feature", "properties": {"kind": "road", "lanes": 2},
+///     "geometry": {"type": "LineString", "coordinates": [[0, 0], [1, 1]]}}"#;
+///
+/// let registry = TypeRegistry::new("kind").register::<Road>("road");
+/// let mut reader = geozero::geojson::GeoJsonReader(geojson.as_bytes());
+/// let records = from_datasource_registry(&mut reader, &registry).unwrap();
+/// assert_eq!(records[0].downcast_ref::<Road>().unwrap().lanes, 2);
+/// ```
+#[derive(Debug, Clone)]
+pub struct TypeRegistry {
+    discriminator: String,
+    constructors: HashMap<String, Constructor>,
+}
+
+impl TypeRegistry {
+    /// Creates an empty registry selecting types by the property `discriminator`.
+    #[must_use]
+    pub fn new(discriminator: impl Into<String>) -> Self {
+        Self {
+            discriminator: discriminator.into(),
+            constructors: HashMap::new(),
+        }
+    }
+
+    /// Registers `T` for features whose discriminator equals `tag`.
+    ///
+    /// Registering a tag a second time replaces the previous type.
+    #[must_use]
+    pub fn register<T: FeatureRecord + DeserializeOwned>(mut self, tag: impl Into<String>) -> Self {
+        self.constructors
+            .insert(tag.into(), Constructor::new::<T>());
+        self
+    }
+
+    /// The name of the discriminator property.
+    #[must_use]
+    pub fn discriminator(&self) -> &str {
+        &self.discriminator
+    }
+
+    /// Deserializes a single feature into the type registered for its discriminator.
+    ///
+    /// Non-string discriminators are matched by their JSON representation, e.g. `1` or `true`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the feature has no discriminator, no type is registered for it, or
+    /// the feature can't be deserialized into the registered type.
+    pub fn deserialize(&self, feature: Feature) -> Result<Box<dyn FeatureRecord>> {
+        (self.constructor(&feature)?.owned)(feature)
+    }
+
+    /// The constructor registered for the discriminator of `feature`.
+    fn constructor(&self, feature: &Feature) -> Result<Constructor> {
+        let tag = match feature.properties.get(&self.discriminator) {
+            None | Some(Value::Null) => {
+                return Err(Error::MissingDiscriminator(self.discriminator.clone()))
+            }
+            Some(Value::String(tag)) => tag.clone(),
+            Some(Value::Shared(tag)) => tag.to_string(),
+            Some(tag) => tag.to_string(),
+        };
+        self.constructors
+            .get(&tag)
+            .copied()
+            .ok_or(Error::UnregisteredType(tag))
+    }
+}
+
+impl Convert<Box<dyn FeatureRecord>> for &TypeRegistry {
+    fn convert(&mut self, feature: Feature) -> Result<Option<Box<dyn FeatureRecord>>> {
+        self.deserialize(feature).map(Some)
+    }
+
+    fn try_convert(&mut self, feature: Feature) -> Converted<Box<dyn FeatureRecord>> {
+        let record = self
+            .constructor(&feature)
+            .and_then(|constructor| (constructor.borrowed)(&feature));
+        match record {
+            Ok(record) => Ok(Some(record)),
+            Err(err) => Err(Box::new((err, feature))),
+        }
+    }
+}
+
+/// Deserializes every feature of the datasource into the type registered for its discriminator.
+///
+/// Features are converted as they are read, so only the records are kept in memory.
+///
+/// # Errors
+///
+/// Returns an error if the datasource can't be read, and [`Error::Feature`] with the error of
+/// [`TypeRegistry::deserialize`] if a feature can't be deserialized.
+pub fn from_datasource_registry(
+    processor: &mut impl GeozeroDatasource,
+    registry: &TypeRegistry,
+) -> Result<Vec<Box<dyn FeatureRecord>>> {
+    let mut collector = GeozeroCollector::with_options_and_converter(
+        &DeserializeOptions::new(),
+        None,
+        &[],
+        Box::new(registry),
+    )?;
+    collector.process(processor)?;
+    Ok(collector.features)
+}
+
+#[cfg(test)]
+mod test {
+    use std::any::Any;
+
+    use geo::Geometry;
+    use serde::Deserialize;
+
+    use super::{from_datasource_registry, FeatureRecord, TypeRegistry};
+    use crate::error::Error;
+
+    #[derive(Debug, Deserialize)]
+    struct Building {
+        geometry: Geometry,
+        levels: u8,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Tree {
+        geometry: Geometry,
+        species: String,
+    }
+
+    impl FeatureRecord for Building {
+        fn geometry(&self) -> &Geometry {
+            &self.geometry
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    impl FeatureRecord for Tree {
+        fn geometry(&self) -> &Geometry {
+            &self.geometry
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    const GEOJSON: &str = r#"{"type": "FeatureCollection", "features": [
+        {"type": "Feature", "properties": {"kind": "building", "levels": 3},
+         "geometry": {"type": "Point", "coordinates": [1, 1]}},
+        {"type": "Feature", "properties": {"kind": "tree", "species": "oak"},
+         "geometry": {"type": "Point", "coordinates": [2, 2]}}
+    ]}"#;
+
+    #[test]
+    fn registered_types() -> anyhow::Result<()> {
+        let registry = TypeRegistry::new("kind")
+            .register::<Building>("building")
+            .register::<Tree>("tree");
+
+        let mut reader = geozero::geojson::GeoJsonReader(GEOJSON.as_bytes());
+        let records = from_datasource_registry(&mut reader, &registry)?;
+
+        assert_eq!(records.len(), 2);
+        assert!(records[0].is::<Building>());
+        assert_eq!(records[0].downcast_ref::<Building>().unwrap().levels, 3);
+        assert_eq!(records[1].downcast_ref::<Tree>().unwrap().species, "oak");
+        assert_eq!(records[1].geometry(), &Geometry::Point((2., 2.).into()));
+        Ok(())
+    }
+
+    #[test]
+    fn unregistered_type() {
+        let registry = TypeRegistry::new("kind").register::<Building>("building");
+
+        let mut reader = geozero::geojson::GeoJsonReader(GEOJSON.as_bytes());
+        let err = from_datasource_registry(&mut reader, &registry).unwrap_err();
+        assert_eq!(err.feature_index(), Some(1));
+        assert!(
+            matches!(err.root_cause(), Error::UnregisteredType(tag) if tag == "tree"),
+            "{err:?}"
+        );
+    }
+}
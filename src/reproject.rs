@@ -0,0 +1,42 @@
+//! Coordinate reprojection using [PROJ](https://proj.org), enabled by the `proj` feature.
+use geo::Geometry;
+use proj::{Proj, Transform};
+
+use crate::error::Result;
+
+/// A coordinate transformation between two coordinate reference systems.
+pub struct Reprojection {
+    proj: Proj,
+    target_srid: Option<i32>,
+}
+
+impl Reprojection {
+    /// Creates a transformation between two CRS definitions understood by PROJ,
+    /// e.g. `"EPSG:3857"` and `"EPSG:4326"`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if PROJ does not know one of the definitions.
+    pub fn new(from: &str, to: &str) -> Result<Self> {
+        Ok(Self {
+            proj: Proj::new_known_crs(from, to, None)?,
+            target_srid: to.strip_prefix("EPSG:").and_then(|code| code.parse().ok()),
+        })
+    }
+
+    /// The EPSG code of the target CRS, if it was given as `EPSG:<code>`.
+    #[must_use]
+    pub fn target_srid(&self) -> Option<i32> {
+        self.target_srid
+    }
+
+    /// Transforms all coordinates of the geometry in place.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if PROJ fails to transform a coordinate.
+    pub fn apply(&self, geometry: &mut Geometry) -> Result<()> {
+        geometry.transform(&self.proj)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,751 @@
+//! Column metadata, dataset profiling and schema compatibility checks.
+use std::{
+    fmt::{self, Display},
+    path::Path,
+};
+
+use geozero::{
+    error::Result as GeozeroResult, ColumnValue, CoordDimensions, FeatureProcessor, GeomProcessor,
+    GeozeroDatasource, ProcessorSink, PropertyProcessor,
+};
+use hashbrown::HashMap;
+use serde::de::DeserializeOwned;
+
+use crate::{
+    collector::GeozeroCollector,
+    error::{Error, Result},
+};
+
+/// The type of a column, mirroring the variants of [`ColumnValue`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColumnType {
+    Byte,
+    UByte,
+    Bool,
+    Short,
+    UShort,
+    Int,
+    UInt,
+    Long,
+    ULong,
+    Float,
+    Double,
+    String,
+    Json,
+    DateTime,
+    Binary,
+}
+
+impl From<&ColumnValue<'_>> for ColumnType {
+    fn from(value: &ColumnValue<'_>) -> Self {
+        match value {
+            ColumnValue::Byte(_) => ColumnType::Byte,
+            ColumnValue::UByte(_) => ColumnType::UByte,
+            ColumnValue::Bool(_) => ColumnType::Bool,
+            ColumnValue::Short(_) => ColumnType::Short,
+            ColumnValue::UShort(_) => ColumnType::UShort,
+            ColumnValue::Int(_) => ColumnType::Int,
+            ColumnValue::UInt(_) => ColumnType::UInt,
+            ColumnValue::Long(_) => ColumnType::Long,
+            ColumnValue::ULong(_) => ColumnType::ULong,
+            ColumnValue::Float(_) => ColumnType::Float,
+            ColumnValue::Double(_) => ColumnType::Double,
+            ColumnValue::String(_) => ColumnType::String,
+            ColumnValue::Json(_) => ColumnType::Json,
+            ColumnValue::DateTime(_) => ColumnType::DateTime,
+            ColumnValue::Binary(_) => ColumnType::Binary,
+        }
+    }
+}
+
+/// A column as it is passed to a `PropertyProcessor`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnInfo {
+    /// The `idx` passed to `PropertyProcessor::property` for this column.
+    pub index: usize,
+    pub name: String,
+    /// The type of the first non-null value, `None` if only nulls were seen.
+    pub column_type: Option<ColumnType>,
+}
+
+/// Metadata describing a whole dataset.
+///
+/// Readers report the name and SRID through the processor events, see
+/// [`GeozeroCollector::dataset_info`]. `geozero` has no event for the description, it is only
+/// read from and written to `FlatGeobuf` headers by `DatasetInfo::from_fgb` and
+/// `DatasetInfo::fgb_writer_options` (requires the `flatgeobuf` feature).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DatasetInfo {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    /// The SRID of the geometries, e.g. `4326`.
+    pub srid: Option<i32>,
+}
+
+impl DatasetInfo {
+    /// Reads the name, description and CRS code of a `FlatGeobuf` header.
+    #[cfg(feature = "flatgeobuf")]
+    #[must_use]
+    pub fn from_fgb<R: std::io::Read>(reader: &flatgeobuf::FgbReader<R>) -> Self {
+        let header = reader.header();
+        DatasetInfo {
+            name: header.name().map(ToString::to_string),
+            description: header.description().map(ToString::to_string),
+            srid: header.crs().map(|crs| crs.code()).filter(|code| *code != 0),
+        }
+    }
+
+    /// Writer options storing the description and CRS code in a `FlatGeobuf` header.
+    ///
+    /// The name is passed on as the `name` of `FgbWriter::create_with_options`.
+    #[cfg(feature = "flatgeobuf")]
+    #[must_use]
+    pub fn fgb_writer_options(&self) -> flatgeobuf::FgbWriterOptions<'_> {
+        flatgeobuf::FgbWriterOptions {
+            description: self.description.as_deref(),
+            crs: flatgeobuf::FgbCrs {
+                code: self.srid.unwrap_or_default(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+}
+
+/// A difference between a dataset and the struct it is read into, see [`StrictMode`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaDrift {
+    /// The dataset has a property the struct has no field for.
+    UnknownProperty(String),
+    /// The struct has a field none of the features had a property for.
+    MissingColumn(String),
+}
+
+impl Display for SchemaDrift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaDrift::UnknownProperty(name) => write!(f, "unknown property {name:?}"),
+            SchemaDrift::MissingColumn(name) => write!(f, "no column for field {name:?}"),
+        }
+    }
+}
+
+/// Receives every [`SchemaDrift`] found in [`StrictMode::Warn`].
+pub type DriftCallback = fn(&SchemaDrift);
+
+/// How differences between a dataset and the target struct are handled.
+///
+/// Unknown properties are reported the first time they occur, missing columns once the
+/// dataset is finished. Only structs without flattened fields can be checked. Fields with a
+/// `#[serde(alias)]` are reported as missing unless all of their names occur.
+#[derive(Debug, Clone, Copy, Default)]
+pub enum StrictMode {
+    /// Ignore unknown properties and missing columns, like serde does by default.
+    #[default]
+    Off,
+    /// Fail with [`Error::SchemaMismatch`], like `#[serde(deny_unknown_fields)]` but also for
+    /// missing columns.
+    Error,
+    /// Pass every difference to the callback and continue.
+    Warn(DriftCallback),
+}
+
+impl StrictMode {
+    /// Reports `drift`, returning the error to raise in [`StrictMode::Error`].
+    pub(crate) fn report(self, drift: &SchemaDrift) -> Option<Error> {
+        match self {
+            StrictMode::Off => None,
+            StrictMode::Error => Some(Error::SchemaMismatch(drift.to_string())),
+            StrictMode::Warn(callback) => {
+                callback(drift);
+                None
+            }
+        }
+    }
+}
+
+/// Statistics of a single column, see [`SchemaCollector`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnStats {
+    pub name: String,
+    /// The types of the column's values in the order they were first seen, usually only one.
+    pub types: Vec<ColumnType>,
+    /// Number of features with a value for the column.
+    pub value_count: usize,
+    /// Number of features without a value, including features read before the column first
+    /// appeared. Most readers leave out null properties, so they are counted as missing.
+    pub null_count: usize,
+}
+
+impl ColumnStats {
+    /// The type of the first value, `None` if only nulls were seen.
+    #[must_use]
+    pub fn column_type(&self) -> Option<ColumnType> {
+        self.types.first().copied()
+    }
+}
+
+/// The columns of a dataset and how they are filled, see [`SchemaCollector`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DatasetSchema {
+    pub info: DatasetInfo,
+    pub feature_count: usize,
+    /// The columns in the order they first appeared.
+    pub columns: Vec<ColumnStats>,
+}
+
+impl DatasetSchema {
+    /// The statistics of the column `name`.
+    #[must_use]
+    pub fn column(&self, name: &str) -> Option<&ColumnStats> {
+        self.columns.iter().find(|column| column.name == name)
+    }
+}
+
+/// A processor recording the columns, types, value and null counts of a dataset.
+///
+/// Every event is passed on to the wrapped processor, so the schema can be recorded while
+/// deserializing. Use [`SchemaCollector::new`] to only profile the dataset.
+///
+/// ```
+/// use geozero::GeozeroDatasource;
+/// use serde::Deserialize;
+/// use serde_geozero::collector::TypedCollector;
+/// use serde_geozero::schema::{ColumnType, SchemaCollector};
+///
+/// #[derive(Deserialize)]
+/// struct City {
+///     name: String,
+/// }
+///
+/// let geojson = r#"{"type": "FeatureCollection", "features": [
+///     {"type": "Feature", "properties": {"name": "Berlin", "population": 3669495},
+///         "geometry": {"type": "Point", "coordinates": [13.4, 52.5]}},
+///     {"type": "Feature", "properties": {"name": "Hamburg"},
+///         "geometry": {"type": "Point", "coordinates": [10.0, 53.6]}}]}"#;
+/// let mut reader = geozero::geojson::GeoJsonReader(geojson.as_bytes());
+/// let mut collector = SchemaCollector::wrap(TypedCollector::<City>::new());
+/// reader.process(&mut collector).unwrap();
+///
+/// let (schema, cities) = collector.into_parts();
+/// assert_eq!(cities.len(), 2);
+/// let population = schema.column("population").unwrap();
+/// assert_eq!(population.column_type(), Some(ColumnType::Long));
+/// assert_eq!((population.value_count, population.null_count), (1, 1));
+/// ```
+pub struct SchemaCollector<P = ProcessorSink> {
+    inner: P,
+    schema: DatasetSchema,
+    indices: HashMap<String, usize>,
+    /// Set once the wrapped processor needs no more properties of the current feature.
+    inner_finished: bool,
+}
+
+impl SchemaCollector {
+    /// Creates a collector that only records the schema.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::wrap(ProcessorSink::new())
+    }
+}
+
+impl Default for SchemaCollector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P> SchemaCollector<P> {
+    /// Creates a collector passing every event on to `inner`.
+    pub fn wrap(inner: P) -> Self {
+        Self {
+            inner,
+            schema: DatasetSchema::default(),
+            indices: HashMap::new(),
+            inner_finished: false,
+        }
+    }
+
+    /// The schema of the features processed so far.
+    #[must_use]
+    pub fn schema(&self) -> DatasetSchema {
+        let mut schema = self.schema.clone();
+        for column in &mut schema.columns {
+            column.null_count = schema.feature_count - column.value_count;
+        }
+        schema
+    }
+
+    /// The wrapped processor.
+    pub fn inner(&mut self) -> &mut P {
+        &mut self.inner
+    }
+
+    /// Returns the schema and the wrapped processor.
+    pub fn into_parts(self) -> (DatasetSchema, P) {
+        (self.schema(), self.inner)
+    }
+}
+
+/// Records the schema of all features of `source`.
+///
+/// # Errors
+///
+/// Returns an error if the datasource fails to process.
+pub fn profile(source: &mut impl GeozeroDatasource) -> Result<DatasetSchema> {
+    let mut collector = SchemaCollector::new();
+    source.process(&mut collector)?;
+    Ok(collector.schema())
+}
+
+impl<P: PropertyProcessor> PropertyProcessor for SchemaCollector<P> {
+    fn property(&mut self, idx: usize, name: &str, value: &ColumnValue) -> GeozeroResult<bool> {
+        let index = if let Some(index) = self.indices.get(name) {
+            *index
+        } else {
+            self.schema.columns.push(ColumnStats {
+                name: name.to_string(),
+                types: Vec::new(),
+                value_count: 0,
+                null_count: 0,
+            });
+            self.indices
+                .insert(name.to_string(), self.schema.columns.len() - 1);
+            self.schema.columns.len() - 1
+        };
+        if !matches!(value, ColumnValue::Json("null")) {
+            let column = &mut self.schema.columns[index];
+            column.value_count += 1;
+            let column_type = ColumnType::from(value);
+            if !column.types.contains(&column_type) {
+                column.types.push(column_type);
+            }
+        }
+        // the schema needs every column, even if the wrapped processor is done
+        if !self.inner_finished {
+            self.inner_finished = self.inner.property(idx, name, value)?;
+        }
+        Ok(false)
+    }
+}
+
+impl<P: GeomProcessor> GeomProcessor for SchemaCollector<P> {
+    fn dimensions(&self) -> CoordDimensions {
+        self.inner.dimensions()
+    }
+
+    fn multi_dim(&self) -> bool {
+        self.inner.multi_dim()
+    }
+
+    fn srid(&mut self, srid: Option<i32>) -> GeozeroResult<()> {
+        if self.schema.info.srid.is_none() {
+            self.schema.info.srid = srid;
+        }
+        self.inner.srid(srid)
+    }
+
+    fn xy(&mut self, x: f64, y: f64, idx: usize) -> GeozeroResult<()> {
+        self.inner.xy(x, y, idx)
+    }
+
+    #[allow(clippy::many_single_char_names)]
+    fn coordinate(
+        &mut self,
+        x: f64,
+        y: f64,
+        z: Option<f64>,
+        m: Option<f64>,
+        t: Option<f64>,
+        tm: Option<u64>,
+        idx: usize,
+    ) -> GeozeroResult<()> {
+        self.inner.coordinate(x, y, z, m, t, tm, idx)
+    }
+
+    fn empty_point(&mut self, idx: usize) -> GeozeroResult<()> {
+        self.inner.empty_point(idx)
+    }
+
+    fn point_begin(&mut self, idx: usize) -> GeozeroResult<()> {
+        self.inner.point_begin(idx)
+    }
+
+    fn point_end(&mut self, idx: usize) -> GeozeroResult<()> {
+        self.inner.point_end(idx)
+    }
+
+    fn multipoint_begin(&mut self, size: usize, idx: usize) -> GeozeroResult<()> {
+        self.inner.multipoint_begin(size, idx)
+    }
+
+    fn multipoint_end(&mut self, idx: usize) -> GeozeroResult<()> {
+        self.inner.multipoint_end(idx)
+    }
+
+    fn linestring_begin(&mut self, tagged: bool, size: usize, idx: usize) -> GeozeroResult<()> {
+        self.inner.linestring_begin(tagged, size, idx)
+    }
+
+    fn linestring_end(&mut self, tagged: bool, idx: usize) -> GeozeroResult<()> {
+        self.inner.linestring_end(tagged, idx)
+    }
+
+    fn multilinestring_begin(&mut self, size: usize, idx: usize) -> GeozeroResult<()> {
+        self.inner.multilinestring_begin(size, idx)
+    }
+
+    fn multilinestring_end(&mut self, idx: usize) -> GeozeroResult<()> {
+        self.inner.multilinestring_end(idx)
+    }
+
+    fn polygon_begin(&mut self, tagged: bool, size: usize, idx: usize) -> GeozeroResult<()> {
+        self.inner.polygon_begin(tagged, size, idx)
+    }
+
+    fn polygon_end(&mut self, tagged: bool, idx: usize) -> GeozeroResult<()> {
+        self.inner.polygon_end(tagged, idx)
+    }
+
+    fn multipolygon_begin(&mut self, size: usize, idx: usize) -> GeozeroResult<()> {
+        self.inner.multipolygon_begin(size, idx)
+    }
+
+    fn multipolygon_end(&mut self, idx: usize) -> GeozeroResult<()> {
+        self.inner.multipolygon_end(idx)
+    }
+
+    fn geometrycollection_begin(&mut self, size: usize, idx: usize) -> GeozeroResult<()> {
+        self.inner.geometrycollection_begin(size, idx)
+    }
+
+    fn geometrycollection_end(&mut self, idx: usize) -> GeozeroResult<()> {
+        self.inner.geometrycollection_end(idx)
+    }
+
+    fn circularstring_begin(&mut self, size: usize, idx: usize) -> GeozeroResult<()> {
+        self.inner.circularstring_begin(size, idx)
+    }
+
+    fn circularstring_end(&mut self, idx: usize) -> GeozeroResult<()> {
+        self.inner.circularstring_end(idx)
+    }
+
+    fn compoundcurve_begin(&mut self, size: usize, idx: usize) -> GeozeroResult<()> {
+        self.inner.compoundcurve_begin(size, idx)
+    }
+
+    fn compoundcurve_end(&mut self, idx: usize) -> GeozeroResult<()> {
+        self.inner.compoundcurve_end(idx)
+    }
+
+    fn curvepolygon_begin(&mut self, size: usize, idx: usize) -> GeozeroResult<()> {
+        self.inner.curvepolygon_begin(size, idx)
+    }
+
+    fn curvepolygon_end(&mut self, idx: usize) -> GeozeroResult<()> {
+        self.inner.curvepolygon_end(idx)
+    }
+
+    fn multicurve_begin(&mut self, size: usize, idx: usize) -> GeozeroResult<()> {
+        self.inner.multicurve_begin(size, idx)
+    }
+
+    fn multicurve_end(&mut self, idx: usize) -> GeozeroResult<()> {
+        self.inner.multicurve_end(idx)
+    }
+
+    fn multisurface_begin(&mut self, size: usize, idx: usize) -> GeozeroResult<()> {
+        self.inner.multisurface_begin(size, idx)
+    }
+
+    fn multisurface_end(&mut self, idx: usize) -> GeozeroResult<()> {
+        self.inner.multisurface_end(idx)
+    }
+
+    fn triangle_begin(&mut self, tagged: bool, size: usize, idx: usize) -> GeozeroResult<()> {
+        self.inner.triangle_begin(tagged, size, idx)
+    }
+
+    fn triangle_end(&mut self, tagged: bool, idx: usize) -> GeozeroResult<()> {
+        self.inner.triangle_end(tagged, idx)
+    }
+
+    fn polyhedralsurface_begin(&mut self, size: usize, idx: usize) -> GeozeroResult<()> {
+        self.inner.polyhedralsurface_begin(size, idx)
+    }
+
+    fn polyhedralsurface_end(&mut self, idx: usize) -> GeozeroResult<()> {
+        self.inner.polyhedralsurface_end(idx)
+    }
+
+    fn tin_begin(&mut self, size: usize, idx: usize) -> GeozeroResult<()> {
+        self.inner.tin_begin(size, idx)
+    }
+
+    fn tin_end(&mut self, idx: usize) -> GeozeroResult<()> {
+        self.inner.tin_end(idx)
+    }
+}
+
+impl<P: FeatureProcessor> FeatureProcessor for SchemaCollector<P> {
+    fn dataset_begin(&mut self, name: Option<&str>) -> GeozeroResult<()> {
+        self.schema.info.name = name.map(ToString::to_string);
+        self.inner.dataset_begin(name)
+    }
+
+    fn dataset_end(&mut self) -> GeozeroResult<()> {
+        self.inner.dataset_end()
+    }
+
+    fn feature_begin(&mut self, idx: u64) -> GeozeroResult<()> {
+        self.schema.feature_count += 1;
+        self.inner_finished = false;
+        self.inner.feature_begin(idx)
+    }
+
+    fn feature_end(&mut self, idx: u64) -> GeozeroResult<()> {
+        self.inner.feature_end(idx)
+    }
+
+    fn properties_begin(&mut self) -> GeozeroResult<()> {
+        self.inner.properties_begin()
+    }
+
+    fn properties_end(&mut self) -> GeozeroResult<()> {
+        self.inner.properties_end()
+    }
+
+    fn geometry_begin(&mut self) -> GeozeroResult<()> {
+        self.inner.geometry_begin()
+    }
+
+    fn geometry_end(&mut self) -> GeozeroResult<()> {
+        self.inner.geometry_end()
+    }
+}
+
+/// Checks that the first feature of the datasource can be deserialized into `T`.
+///
+/// Only the first feature is read, so this is cheap even for large datasets.
+///
+/// # Errors
+///
+/// Returns an error if the datasource is empty or its first feature doesn't match `T`.
+pub fn check_compatible<T: DeserializeOwned + 'static>(
+    source: &mut impl GeozeroDatasource,
+) -> Result<()> {
+    let mut collector = GeozeroCollector::<T>::new().with_limit(1);
+    collector.process(source)?;
+    if collector.features.is_empty() {
+        return Err(Error::Message(
+            "The dataset has no features to check against".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Checks that the first feature of the file at `path` can be deserialized into `T`.
+///
+/// The format is chosen by the file extension. `.fgb` requires the `flatgeobuf` feature,
+/// `.geojson` and `.json` the `geojson` feature.
+///
+/// # Errors
+///
+/// Returns an error if the file can't be read, its format is unsupported, or its first feature
+/// doesn't match `T`.
+pub fn check_file_compatible<T: DeserializeOwned + 'static>(path: impl AsRef<Path>) -> Result<()> {
+    let path = path.as_ref();
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase);
+
+    #[cfg(any(feature = "flatgeobuf", feature = "geojson"))]
+    let open = || std::fs::File::open(path).map_err(|err| Error::Message(err.to_string()));
+    match extension.as_deref() {
+        #[cfg(feature = "flatgeobuf")]
+        Some("fgb") => {
+            let reader = flatgeobuf::FgbReader::open(std::io::BufReader::new(open()?))?;
+            check_compatible::<T>(&mut reader.select_all()?)
+        }
+        #[cfg(feature = "geojson")]
+        Some("geojson" | "json") => check_compatible::<T>(&mut geozero::geojson::GeoJsonReader(
+            std::io::BufReader::new(open()?),
+        )),
+        _ => Err(Error::UnsupportedFormat(path.display().to_string())),
+    }
+}
+
+/// Asserts that the first feature of a dataset file can be deserialized into a type.
+///
+/// Meant for tests, to catch drift between a struct and the data it is read from before it
+/// reaches production. See [`check_file_compatible`] for the supported formats.
+///
+/// ```
+/// # #[cfg(feature = "flatgeobuf")]
+/// # {
+/// use geo::Geometry;
+/// use serde::Deserialize;
+/// use serde_geozero::assert_schema_compatible;
+///
+/// #[derive(Deserialize)]
+/// struct Country {
+///     geometry: Geometry,
+///     name: String,
+/// }
+///
+/// assert_schema_compatible!(Country, "test-data/countries.fgb");
+/// # }
+/// ```
+///
+/// # Panics
+///
+/// Panics with the deserialization error if the type doesn't match the dataset.
+#[macro_export]
+macro_rules! assert_schema_compatible {
+    ($ty:ty, $path:expr) => {
+        let path = $path;
+        if let Err(err) = $crate::schema::check_file_compatible::<$ty>(&path) {
+            panic!(
+                "{} is not compatible with {:?}: {}",
+                stringify!($ty),
+                path,
+                err
+            );
+        }
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs::File;
+
+    use flatgeobuf::FgbReader;
+    use geo::Geometry;
+    use geozero::GeozeroDatasource;
+    use serde::Deserialize;
+
+    use super::{check_compatible, profile, ColumnType, DatasetInfo, SchemaCollector};
+    use crate::{
+        de::{DataSourceDeserializer, Feature},
+        SerializeOptions,
+    };
+
+    #[test]
+    fn compatibility() -> anyhow::Result<()> {
+        #[derive(Deserialize)]
+        #[allow(dead_code)]
+        struct Country {
+            geometry: Geometry,
+            name: String,
+        }
+
+        #[derive(Deserialize)]
+        #[allow(dead_code)]
+        struct Drifted {
+            geometry: Geometry,
+            population: u64,
+        }
+
+        let reader = FgbReader::open(File::open("test-data/countries.fgb")?)?;
+        check_compatible::<Country>(&mut reader.select_all()?)?;
+
+        let reader = FgbReader::open(File::open("test-data/countries.fgb")?)?;
+        assert!(check_compatible::<Drifted>(&mut reader.select_all()?).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn schema_collector() -> anyhow::Result<()> {
+        let geojson = r#"{"type": "FeatureCollection", "name": "mixed", "features": [
+            {"type": "Feature", "properties": {"a": 1, "b": null},
+                "geometry": {"type": "Point", "coordinates": [1, 2]}},
+            {"type": "Feature", "properties": {"a": "x", "c": [1]},
+                "geometry": {"type": "Point", "coordinates": [3, 4]}}]}"#;
+        let schema = profile(&mut geozero::geojson::GeoJsonReader(geojson.as_bytes()))?;
+        assert_eq!(schema.feature_count, 2);
+        let names: Vec<_> = schema.columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["a", "c"]);
+        let a = schema.column("a").unwrap();
+        assert_eq!(a.types, [ColumnType::Long, ColumnType::String]);
+        let c = schema.column("c").unwrap();
+        assert_eq!(c.column_type(), Some(ColumnType::Json));
+        assert_eq!((c.value_count, c.null_count), (1, 1));
+
+        // the wrapped deserializer stops reading properties early, the schema doesn't
+        let options = crate::DeserializeOptions::new().columns(&["name"]);
+        let collector = DataSourceDeserializer::<Feature>::with_options(&options)?;
+        let mut collector = SchemaCollector::wrap(collector);
+        let reader = FgbReader::open(File::open("test-data/countries.fgb")?)?;
+        reader.select_all()?.process(&mut collector)?;
+        let (schema, features) = collector.into_parts();
+        assert_eq!(schema.info.name.as_deref(), Some("countries"));
+        assert!(schema.columns.len() > 1);
+        assert_eq!(features.features[0].properties.len(), 1);
+        assert_eq!(schema.feature_count, features.features.len());
+        Ok(())
+    }
+
+    #[test]
+    fn dataset_info() -> anyhow::Result<()> {
+        let reader = FgbReader::open(File::open("test-data/countries.fgb")?)?;
+        let mut collector = DataSourceDeserializer::<Feature>::new();
+        collector.process(&mut reader.select_all()?)?;
+
+        let info = DatasetInfo {
+            name: Some("countries".to_string()),
+            description: None,
+            srid: Some(4326),
+        };
+        let mut copy = DataSourceDeserializer::<Feature>::new();
+        crate::to_geozero_datasource_with_options(
+            &collector.features,
+            &mut copy,
+            &SerializeOptions::new().dataset(info.clone()),
+        )?;
+        assert_eq!(copy.dataset_info(), &info);
+        // `Feature` keeps the srid of the dataset
+        assert_eq!(copy.features[0].srid(), Some(4326));
+        Ok(())
+    }
+
+    #[cfg(feature = "flatgeobuf")]
+    #[test]
+    fn fgb_header() -> anyhow::Result<()> {
+        use flatgeobuf::{FgbWriter, GeometryType};
+
+        let info = DatasetInfo {
+            name: Some("countries".to_string()),
+            description: Some("Natural Earth countries".to_string()),
+            srid: Some(4326),
+        };
+        let reader = FgbReader::open(File::open("test-data/countries.fgb")?)?;
+        let mut writer = FgbWriter::create_with_options(
+            "countries",
+            GeometryType::MultiPolygon,
+            info.fgb_writer_options(),
+        )?;
+        reader.select_all()?.process_features(&mut writer)?;
+        let mut buffer = Vec::new();
+        writer.write(&mut buffer)?;
+
+        let reader = FgbReader::open(std::io::Cursor::new(buffer))?;
+        assert_eq!(DatasetInfo::from_fgb(&reader), info);
+        Ok(())
+    }
+
+    #[cfg(feature = "flatgeobuf")]
+    #[test]
+    #[should_panic(expected = "is not compatible")]
+    fn assert_macro() {
+        #[derive(Deserialize)]
+        #[allow(dead_code)]
+        struct Drifted {
+            geometry: Geometry,
+            population: u64,
+        }
+
+        assert_schema_compatible!(Drifted, "test-data/countries.fgb");
+    }
+}
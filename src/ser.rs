@@ -1,12 +1,35 @@
+use std::{
+    borrow::Cow,
+    collections::BTreeSet,
+    fmt,
+    ops::Range,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use geo::{
+    orient::Direction, BooleanOps, Contains, Coord, CoordsIter, Geometry, GeometryCollection,
+    Intersects, LineString, MapCoordsInPlace, MultiLineString, MultiPoint, MultiPolygon, Orient,
+    Polygon, Rect, RemoveRepeatedPoints, Simplify, SimplifyVw,
+};
 use geozero::{
-    error::GeozeroError, geo_types::process_geom, ColumnValue, FeatureProcessor, PropertyProcessor,
+    geo_types::process_geom, ColumnValue, FeatureProcessor, ProcessorSink, PropertyProcessor,
 };
-use hashbrown::HashMap;
-use serde::{ser, Deserialize};
+use hashbrown::{HashMap, HashSet};
+use serde::{de::DeserializeOwned, ser, Deserialize};
 
+#[cfg(feature = "proj")]
+use crate::reproject::Reprojection;
 use crate::{
-    de::Feature,
+    case::PropertyCase,
+    coord::GEOMETRY_TYPES,
+    de::{ordered_properties, Feature, FeatureLayout, FEATURE_NAME, GEOMETRY_COL, PROPERTIES_COL},
+    derived::Derived,
+    encoded::decode_json,
     error::{Error, Result},
+    finite::{self, NonFinite},
+    schema::{ColumnInfo, ColumnType, DatasetInfo},
+    value::{to_value, Value, ValueSerializer},
+    zm::{process_geom_zm, M_COL, Z_COL},
 };
 use serde_json::Value as JsonValue;
 
@@ -36,51 +59,186 @@ impl ser::Serialize for ColumnValueSerializer<'_> {
     }
 }
 
-/// borrowed from geozero as it is private
-/// # Panics
-/// If unsupported fields arise.
+/// Writes JSON `properties` to `processor`, numbering new columns in `column_mapping`.
+///
+/// Borrowed from geozero as it is private. Integers are written as `Long`, or `ULong` if they
+/// don't fit, other numbers as `Double`, objects and arrays as `Json`. Null properties are
+/// omitted.
+///
 /// # Errors
 ///
+/// Returns [`Error::GeozeroError`] if the processor fails to handle a property.
 pub fn process_properties<P: PropertyProcessor, S: ::std::hash::BuildHasher>(
     properties: &HashMap<String, JsonValue, S>,
     column_mapping: &mut HashMap<String, usize, S>,
     processor: &mut P,
 ) -> Result<()> {
+    let properties: HashMap<String, Value> = properties
+        .iter()
+        .map(|(key, value)| (key.clone(), Value::from(value.clone())))
+        .collect();
+    process_properties_with_columns(
+        &properties,
+        ColumnAssignment {
+            mapping: column_mapping,
+            columns: &mut Vec::new(),
+            positions: &mut HashMap::new(),
+        },
+        PropertyWriting {
+            widen_numbers: true,
+            ..PropertyWriting::default()
+        },
+        processor,
+    )
+}
+
+/// Writes `properties` sorted by name and numbers the columns in that order.
+///
+/// Used where features are processed one at a time, so the same properties always end up in
+/// the same columns.
+pub(crate) fn process_sorted_properties<P: PropertyProcessor>(
+    properties: &HashMap<String, Value>,
+    processor: &mut P,
+) -> Result<()> {
+    let mut sorted: Vec<(&String, &Value)> = properties.iter().collect();
+    sorted.sort_unstable_by_key(|(name, _)| *name);
+    let mut column_mapping: HashMap<String, usize> = sorted
+        .iter()
+        .enumerate()
+        .map(|(idx, (name, _))| ((*name).clone(), idx))
+        .collect();
+    process_properties_with_columns(
+        sorted,
+        ColumnAssignment {
+            mapping: &mut column_mapping,
+            columns: &mut Vec::new(),
+            positions: &mut HashMap::new(),
+        },
+        PropertyWriting::default(),
+        processor,
+    )
+}
+
+/// Whether `value` is written as `ColumnValue::DateTime`, see [`crate::datetime`].
+#[cfg(feature = "chrono")]
+fn is_datetime(value: &str) -> bool {
+    crate::datetime::is_datetime(value)
+}
+
+#[cfg(not(feature = "chrono"))]
+fn is_datetime(_value: &str) -> bool {
+    false
+}
+
+/// The column indices of property names and the columns assigned so far.
+struct ColumnAssignment<'c, S> {
+    mapping: &'c mut HashMap<String, usize, S>,
+    /// Newly assigned columns and their types.
+    columns: &'c mut Vec<ColumnInfo>,
+    /// Maps the column indices to their position in `columns`.
+    positions: &'c mut HashMap<usize, usize>,
+}
+
+/// How property values are converted to column values.
+#[derive(Debug, Clone, Copy, Default)]
+struct PropertyWriting {
+    /// Writes strings holding a date or time as `ColumnValue::DateTime`.
+    detect_datetimes: bool,
+    /// Writes integers as `Long` and floats as `Double`.
+    widen_numbers: bool,
+    /// Writes null properties as JSON `null` instead of omitting them.
+    emit_nulls: bool,
+}
+
+/// Like [`process_properties`], additionally recording newly assigned columns and their types.
+fn process_properties_with_columns<'a, P: PropertyProcessor, S: ::std::hash::BuildHasher>(
+    properties: impl IntoIterator<Item = (&'a String, &'a Value)>,
+    assignment: ColumnAssignment<'_, S>,
+    writing: PropertyWriting,
+    processor: &mut P,
+) -> Result<()> {
+    let ColumnAssignment {
+        mapping: column_mapping,
+        columns,
+        positions,
+    } = assignment;
+    let PropertyWriting {
+        detect_datetimes,
+        widen_numbers,
+        emit_nulls,
+    } = writing;
     for (key, value) in properties {
         let id = if let Some(val) = column_mapping.get(key) {
             *val
         } else {
             let new_id = column_mapping.len();
-            column_mapping.insert(key.clone(), column_mapping.len());
+            column_mapping.insert(key.clone(), new_id);
             new_id
         };
+        // columns of a registry may have been assigned by an earlier run
+        let position = *positions.entry(id).or_insert_with(|| {
+            columns.push(ColumnInfo {
+                index: id,
+                name: key.clone(),
+                column_type: None,
+            });
+            columns.len() - 1
+        });
+        let mut emit = |value: &ColumnValue| -> Result<()> {
+            columns[position]
+                .column_type
+                .get_or_insert(ColumnType::from(value));
+            processor.property(id, key, value)?;
+            Ok(())
+        };
         match value {
-            JsonValue::String(v) => processor.property(id, key, &ColumnValue::String(v))?,
-            JsonValue::Number(v) => {
-                if v.is_f64() {
-                    processor.property(id, key, &ColumnValue::Double(v.as_f64().unwrap()))?
-                } else if v.is_i64() {
-                    processor.property(id, key, &ColumnValue::Long(v.as_i64().unwrap()))?
-                } else if v.is_u64() {
-                    processor.property(id, key, &ColumnValue::ULong(v.as_u64().unwrap()))?
-                } else {
-                    unreachable!()
-                }
+            Value::String(v) if detect_datetimes && is_datetime(v) => {
+                emit(&ColumnValue::DateTime(v))?;
+            }
+            Value::String(v) => emit(&ColumnValue::String(v))?,
+            Value::Shared(v) if detect_datetimes && is_datetime(v) => {
+                emit(&ColumnValue::DateTime(v))?;
             }
-            JsonValue::Bool(v) => processor.property(id, key, &ColumnValue::Bool(*v))?,
-            JsonValue::Array(v) => {
-                let json_string =
-                    serde_json::to_string(v).map_err(|_err| GeozeroError::Property(key.clone()))?;
-                processor.property(id, key, &ColumnValue::Json(&json_string))?
+            Value::Shared(v) => emit(&ColumnValue::String(v))?,
+            Value::Byte(v) if !widen_numbers => emit(&ColumnValue::Byte(*v))?,
+            Value::UByte(v) if !widen_numbers => emit(&ColumnValue::UByte(*v))?,
+            Value::Short(v) if !widen_numbers => emit(&ColumnValue::Short(*v))?,
+            Value::UShort(v) if !widen_numbers => emit(&ColumnValue::UShort(*v))?,
+            Value::Int(v) if !widen_numbers => emit(&ColumnValue::Int(*v))?,
+            Value::UInt(v) if !widen_numbers => emit(&ColumnValue::UInt(*v))?,
+            Value::ULong(v) if !widen_numbers => emit(&ColumnValue::ULong(*v))?,
+            Value::Float(v) if !widen_numbers => {
+                if v.is_finite() {
+                    emit(&ColumnValue::Float(*v))?;
+                }
             }
-            JsonValue::Object(v) => {
-                let json_string =
-                    serde_json::to_string(v).map_err(|_err| GeozeroError::Property(key.clone()))?;
-                processor.property(id, key, &ColumnValue::Json(&json_string))?
+            // integers and floats are widened, as all of them used to pass through JSON numbers
+            Value::Byte(_)
+            | Value::Short(_)
+            | Value::Int(_)
+            | Value::Long(_)
+            | Value::UByte(_)
+            | Value::UShort(_)
+            | Value::UInt(_) => emit(&ColumnValue::Long(value.as_i64().unwrap_or_default()))?,
+            Value::ULong(v) => match i64::try_from(*v) {
+                Ok(v) => emit(&ColumnValue::Long(v))?,
+                Err(_) => emit(&ColumnValue::ULong(*v))?,
+            },
+            Value::Float(_) | Value::Double(_) => match value.as_f64() {
+                Some(v) if v.is_finite() => emit(&ColumnValue::Double(v))?,
+                // like JSON, NaN and infinities are written as null and omitted
+                _ => {}
+            },
+            Value::Bool(v) => emit(&ColumnValue::Bool(*v))?,
+            Value::Json(v) => emit(&ColumnValue::Json(v))?,
+            Value::DateTime(v) => emit(&ColumnValue::DateTime(v))?,
+            Value::Binary(v) => emit(&ColumnValue::Binary(v))?,
+            // null has no column type of its own, JSON aware processors write it as null
+            Value::Null if emit_nulls => {
+                processor.property(id, key, &ColumnValue::Json("null"))?;
             }
-            // For null values omit the property
-            JsonValue::Null => false,
-        };
+            Value::Null => {}
+        }
     }
     Ok(())
 }
@@ -88,7 +246,9 @@ pub fn process_properties<P: PropertyProcessor, S: ::std::hash::BuildHasher>(
 /// Converts a slice of serializable features into a `GeoZero` data source.
 ///
 /// This function processes a collection of features and writes them to a `GeoZero` processor.
-/// It handles both geometry and property data for each feature.
+/// It handles both geometry and property data for each feature. The `geometry` field may be a
+/// `geo::Geometry` or any single geometry type like `geo::Point` or `geo::Polygon`, also wrapped
+/// in an `Option`.
 ///
 /// # Arguments
 ///
@@ -103,13 +263,14 @@ pub fn process_properties<P: PropertyProcessor, S: ::std::hash::BuildHasher>(
 /// use hashbrown::HashMap;
 /// use serde_geozero::de::Feature;
 /// use serde_geozero::to_geozero_datasource;
+/// use serde_geozero::value::Value;
 ///
 /// // Create sample features
 /// let feature = Feature::new(
 ///     (point! { x: 123.4, y: 345.6 }).into(),
 ///     HashMap::from_iter(vec![
-///         ("name".to_string(), serde_json::to_value("Location A").unwrap()),
-///         ("value".to_string(), serde_json::to_value(42).unwrap()),
+///         ("name".to_string(), Value::from("Location A")),
+///         ("value".to_string(), Value::from(42)),
 ///     ]),
 /// );
 ///
@@ -133,67 +294,3637 @@ pub fn to_geozero_datasource<T: ser::Serialize, S: FeatureProcessor>(
     input: &[T],
     processor: &mut S,
 ) -> Result<()> {
-    processor.dataset_begin(None)?;
-    let mut columns: hashbrown::HashMap<String, usize> = HashMap::new();
-    for (fid, data) in input.iter().enumerate() {
-        processor.feature_begin(fid as u64)?;
-        let deserialized = serde_json::to_value(data)
-            .and_then(Feature::deserialize)
-            .map_err(Error::SerdeError)?;
-        process_geom(&deserialized.geometry, processor)?;
-
-        processor.properties_begin()?;
-        process_properties(&deserialized.properties, &mut columns, processor)?;
-        processor.properties_end()?;
-        processor.feature_end(fid as u64)?;
+    to_geozero_datasource_with_options(input, processor, &SerializeOptions::default())?;
+    Ok(())
+}
+
+/// How features without a geometry (e.g. an `Option<Geometry>` field set to `None`) are written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NullGeometry {
+    /// Abort serialization with [`Error::MissingGeometry`].
+    #[default]
+    Error,
+    /// Leave the feature out of the output.
+    Skip,
+    /// Write the feature without emitting any geometry events. [`crate::geojson::GeoJsonWriter`]
+    /// writes these as `"geometry": null`.
+    WriteNull,
+    /// Write an empty `GeometryCollection`, for processors that expect a geometry for every
+    /// feature.
+    WriteEmpty,
+}
+
+/// How properties set to null (e.g. an `Option` field set to `None`) are written.
+///
+/// Omitting them changes the set of columns between features, which writers expecting the
+/// same columns for every feature may reject.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NullProperties {
+    /// Leave the property out of the feature.
+    #[default]
+    Omit,
+    /// Write the property as a `ColumnValue::Json` holding `null`, e.g. `"name": null` with
+    /// [`crate::geojson::GeoJsonWriter`]. Processors without JSON columns may not support it.
+    EmitNull,
+    /// Abort serialization with [`Error::NullProperty`].
+    Error,
+}
+
+/// How nested structs and maps are written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NestedProperties {
+    /// Write them as a single `ColumnValue::Json` column, like sequences.
+    #[default]
+    Json,
+    /// Write every nested field as its own column named by the dotted path, e.g. `address.city`.
+    /// Sequences are still written as JSON.
+    Flatten,
+}
+
+/// The winding order polygon rings are written with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RingOrientation {
+    /// Write rings as they are.
+    #[default]
+    Preserve,
+    /// Exterior rings counter-clockwise, holes clockwise, as required by RFC 7946 (`GeoJSON`).
+    Rfc7946,
+    /// Exterior rings clockwise, holes counter-clockwise, as used by Shapefiles.
+    Clockwise,
+}
+
+impl RingOrientation {
+    fn direction(self) -> Option<Direction> {
+        match self {
+            RingOrientation::Preserve => None,
+            RingOrientation::Rfc7946 => Some(Direction::Default),
+            RingOrientation::Clockwise => Some(Direction::Reversed),
+        }
     }
-    processor.dataset_end()?;
+}
 
-    Ok(())
+/// Rewinds the rings of all polygons in `geometry`.
+fn orient_rings(geometry: &mut Geometry, direction: Direction) {
+    match geometry {
+        Geometry::Polygon(polygon) => *polygon = polygon.orient(direction),
+        Geometry::MultiPolygon(polygons) => *polygons = polygons.orient(direction),
+        Geometry::GeometryCollection(collection) => {
+            for geometry in collection {
+                orient_rings(geometry, direction);
+            }
+        }
+        _ => {}
+    }
 }
 
-#[cfg(test)]
-mod test {
-    use std::str::from_utf8;
+/// The algorithm [`SerializeOptions::simplify`] simplifies geometries with, and its tolerance.
+///
+/// Points are written as they are.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Simplification {
+    /// Ramer–Douglas–Peucker, dropping coordinates closer than the distance to the simplified
+    /// line.
+    DouglasPeucker(f64),
+    /// Visvalingam–Whyatt, dropping coordinates whose triangle with their neighbours has a
+    /// smaller area.
+    Visvalingam(f64),
+}
 
-    use geo::point;
-    use geozero::geojson::GeoJsonWriter;
-    use hashbrown::HashMap;
+impl Simplification {
+    fn apply(self, geometry: &mut Geometry) {
+        match geometry {
+            Geometry::LineString(line) => *line = self.simplified(line),
+            Geometry::MultiLineString(lines) => *lines = self.simplified(lines),
+            Geometry::Polygon(polygon) => *polygon = self.simplified(polygon),
+            Geometry::MultiPolygon(polygons) => *polygons = self.simplified(polygons),
+            Geometry::GeometryCollection(collection) => {
+                for geometry in collection {
+                    self.apply(geometry);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn simplified<G: Simplify<f64> + SimplifyVw<f64>>(self, geometry: &G) -> G {
+        match self {
+            Simplification::DouglasPeucker(epsilon) => geometry.simplify(epsilon),
+            Simplification::Visvalingam(epsilon) => geometry.simplify_vw(epsilon),
+        }
+    }
+}
 
-    use crate::de::Feature;
+/// The part of `geometry` within `bbox`, `None` if nothing is left of it.
+fn clip(geometry: &Geometry, bbox: Rect) -> Option<Geometry> {
+    let area = bbox.to_polygon();
+    let clipped: Geometry = match geometry {
+        Geometry::Point(point) => return bbox.intersects(point).then(|| (*point).into()),
+        Geometry::MultiPoint(points) => MultiPoint::new(
+            points
+                .iter()
+                .filter(|point| bbox.intersects(*point))
+                .copied()
+                .collect(),
+        )
+        .into(),
+        Geometry::Line(line) => single_line(area.clip(&LineString::from(*line).into(), false)),
+        Geometry::LineString(line) => single_line(area.clip(&line.clone().into(), false)),
+        Geometry::MultiLineString(lines) => area.clip(lines, false).into(),
+        Geometry::Polygon(polygon) => single_polygon(area.intersection(polygon)),
+        Geometry::MultiPolygon(polygons) => area.intersection(polygons).into(),
+        Geometry::Rect(rect) => single_polygon(area.intersection(&rect.to_polygon())),
+        Geometry::Triangle(triangle) => single_polygon(area.intersection(&triangle.to_polygon())),
+        Geometry::GeometryCollection(collection) => {
+            Geometry::GeometryCollection(GeometryCollection::new_from(
+                collection
+                    .iter()
+                    .filter_map(|geometry| clip(geometry, bbox))
+                    .collect(),
+            ))
+        }
+    };
+    (clipped.coords_count() > 0).then_some(clipped)
+}
 
-    use super::to_geozero_datasource;
+/// Clips `geometry` to `bbox` if set, see [`SerializeOptions::clip`].
+///
+/// Returns `None` if nothing is left of it, otherwise whether it was cut.
+fn clip_in_place(geometry: &mut Geometry, bbox: Option<Rect>) -> Option<bool> {
+    // empty geometries, e.g. of `NullGeometry::WriteEmpty`, have nothing to clip
+    let Some(bbox) = bbox.filter(|_| geometry.coords_count() > 0) else {
+        return Some(false);
+    };
+    if bbox.contains(&*geometry) {
+        return Some(false);
+    }
+    *geometry = clip(geometry, bbox)?;
+    Some(true)
+}
 
-    #[test]
-    fn test_to_geojson() {
-        let data_1 = Feature::new(
-            (point! { x: 123.4, y: 345.6 }).into(),
-            HashMap::from_iter(vec![
-                ("prop1".to_string(), serde_json::to_value(1.).unwrap()),
-                ("prop2".to_string(), serde_json::to_value("123").unwrap()),
-            ]),
-        );
-        let data_2 = Feature::new(
-            (point! { x: 123.4, y: 345.6 }).into(),
-            HashMap::from_iter(vec![
-                ("prop1".to_string(), serde_json::to_value(1.).unwrap()),
-                ("prop2".to_string(), serde_json::to_value("1234").unwrap()),
-            ]),
-        );
+/// A line string clipped into a single part stays a `LineString`.
+fn single_line(mut lines: MultiLineString) -> Geometry {
+    match lines.0.len() {
+        1 => lines.0.swap_remove(0).into(),
+        _ => lines.into(),
+    }
+}
 
-        let mut out = Vec::new();
+/// A polygon clipped into a single part stays a `Polygon`.
+fn single_polygon(mut polygons: MultiPolygon) -> Geometry {
+    match polygons.0.len() {
+        1 => polygons.0.swap_remove(0).into(),
+        _ => polygons.into(),
+    }
+}
 
-        let mut writer = GeoJsonWriter::new(&mut out);
-        let data_vec = vec![data_1, data_2];
+/// The integer grid [`SerializeOptions::quantize`] snaps coordinates to, e.g. the extent of a
+/// vector tile.
+///
+/// Coordinates within `bounds` are scaled to `0..=extent` on both axes and rounded, the grid
+/// origin is the lower left corner of `bounds`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quantization {
+    bounds: Rect,
+    extent: u32,
+    flip_y: bool,
+}
 
-        assert!(to_geozero_datasource(data_vec.as_slice(), &mut writer).is_ok());
+impl Quantization {
+    /// A grid of 4096 by 4096 cells, the default extent of Mapbox Vector Tiles, covering
+    /// `bounds`.
+    #[must_use]
+    pub fn new(bounds: Rect) -> Self {
+        Self {
+            bounds,
+            extent: 4096,
+            flip_y: false,
+        }
+    }
 
-        let string = from_utf8(out.as_slice()).unwrap();
+    /// The number of grid cells per axis.
+    #[must_use]
+    pub fn extent(mut self, extent: u32) -> Self {
+        self.extent = extent;
+        self
+    }
 
-        assert!(string.contains("\"type\": \"Feature\""));
-        assert!(string.contains("\"coordinates\": [123.4,345.6]"));
-        assert!(string.contains("\"prop1\": 1"));
-        assert!(string.contains("\"prop2\": \"123\""));
-        assert!(string.contains("\"prop2\": \"1234\""));
+    /// Puts the grid origin into the upper left corner of `bounds`, with Y growing downwards
+    /// like in Mapbox Vector Tiles.
+    #[must_use]
+    pub fn flip_y(mut self, flip_y: bool) -> Self {
+        self.flip_y = flip_y;
+        self
+    }
+
+    /// Snaps the coordinates of `geometry` to the grid and removes the parts that collapsed.
+    ///
+    /// Returns `None` if nothing valid is left of it, otherwise whether coordinates were
+    /// removed.
+    fn apply(self, geometry: &mut Geometry) -> Option<bool> {
+        let coords = geometry.coords_count();
+        // empty geometries, e.g. of `NullGeometry::WriteEmpty`, have nothing to collapse
+        if coords == 0 {
+            return Some(false);
+        }
+        let extent = f64::from(self.extent);
+        // bounds without a width or height put all coordinates onto the origin
+        let scale = |size: f64| if size > 0. { extent / size } else { 0. };
+        let (scale_x, scale_y) = (scale(self.bounds.width()), scale(self.bounds.height()));
+        let (min, max) = (self.bounds.min(), self.bounds.max());
+        geometry.map_coords_in_place(|coord| {
+            let y = if self.flip_y {
+                max.y - coord.y
+            } else {
+                coord.y - min.y
+            };
+            Coord {
+                x: ((coord.x - min.x) * scale_x).round(),
+                y: (y * scale_y).round(),
+            }
+        });
+        geometry.remove_repeated_points_mut();
+        let empty = Geometry::GeometryCollection(GeometryCollection::default());
+        *geometry = without_collapsed_parts(std::mem::replace(geometry, empty))?;
+        Some(geometry.coords_count() != coords)
+    }
+}
+
+/// `geometry` without the line strings of a single coordinate and the rings without area left
+/// by quantizing, `None` if nothing is left.
+fn without_collapsed_parts(geometry: Geometry) -> Option<Geometry> {
+    fn line(line: LineString) -> Option<LineString> {
+        (line.0.len() >= 2).then_some(line)
+    }
+    fn ring(ring: &LineString) -> bool {
+        // twice the signed area, rings with the same point four times have none either
+        ring.0.len() >= 4 && ring.lines().map(|line| line.determinant()).sum::<f64>() != 0.
+    }
+    fn polygon(polygon: Polygon) -> Option<Polygon> {
+        let (exterior, interiors) = polygon.into_inner();
+        ring(&exterior)
+            .then(|| Polygon::new(exterior, interiors.into_iter().filter(ring).collect()))
+    }
+
+    let geometry: Geometry = match geometry {
+        Geometry::Point(point) => point.into(),
+        Geometry::Line(segment) => return (segment.start != segment.end).then_some(segment.into()),
+        Geometry::LineString(linestring) => line(linestring)?.into(),
+        Geometry::Polygon(shape) => polygon(shape)?.into(),
+        Geometry::MultiPoint(points) => points.into(),
+        Geometry::MultiLineString(lines) => {
+            MultiLineString::new(lines.into_iter().filter_map(line).collect()).into()
+        }
+        Geometry::MultiPolygon(polygons) => {
+            MultiPolygon::new(polygons.into_iter().filter_map(polygon).collect()).into()
+        }
+        Geometry::GeometryCollection(collection) => {
+            Geometry::GeometryCollection(GeometryCollection::new_from(
+                collection
+                    .into_iter()
+                    .filter_map(without_collapsed_parts)
+                    .collect(),
+            ))
+        }
+        Geometry::Rect(rect) => polygon(rect.to_polygon())?.into(),
+        Geometry::Triangle(triangle) => polygon(triangle.to_polygon())?.into(),
+    };
+    (geometry.coords_count() > 0).then_some(geometry)
+}
+
+/// How multi geometries are written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MultiParts {
+    /// Write them as a single feature.
+    #[default]
+    Keep,
+    /// Write a feature for every point, line string or polygon of a `MultiPoint`,
+    /// `MultiLineString` or `MultiPolygon`, each with all properties of the input.
+    Explode,
+}
+
+/// Splits `geometry` into its parts if it is a multi geometry.
+fn explode(geometry: Geometry) -> Vec<Option<Geometry>> {
+    match geometry {
+        Geometry::MultiPoint(points) => points.into_iter().map(|p| Some(p.into())).collect(),
+        Geometry::MultiLineString(lines) => lines.into_iter().map(|l| Some(l.into())).collect(),
+        Geometry::MultiPolygon(polygons) => polygons.into_iter().map(|p| Some(p.into())).collect(),
+        geometry => vec![Some(geometry)],
+    }
+}
+
+/// The values of `values` belonging to the coordinates `coords`, clamped to the values given.
+fn part_values(values: &[f64], coords: Range<usize>) -> &[f64] {
+    let end = coords.end.min(values.len());
+    &values[coords.start.min(end)..end]
+}
+
+/// The order properties are written in, see [`SerializeOptions::column_order`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColumnOrder {
+    /// The order the fields are declared in, for maps the order they are iterated in. Computed
+    /// and derived properties follow in the order they were added.
+    #[default]
+    Declaration,
+    /// Sorted by name.
+    Sorted,
+}
+
+/// Column indices shared by several serialization runs, see
+/// [`SerializeOptions::column_registry`].
+///
+/// Every run assigns its columns their own indices, so the same property may get a different
+/// index when several batches are written to one processor. With a registry, a property keeps
+/// the index it got in the first run it appeared in. Clones share the same columns, e.g. to
+/// write from several threads.
+///
+/// ```
+/// use geo::{point, Geometry};
+/// use serde::Serialize;
+/// use serde_geozero::ser::{column_table, ColumnRegistry, SerializeOptions};
+///
+/// #[derive(Serialize)]
+/// struct Stop {
+///     geometry: Geometry,
+///     #[serde(skip_serializing_if = "Option::is_none")]
+///     name: Option<&'static str>,
+///     line: u32,
+/// }
+///
+/// let registry = ColumnRegistry::new();
+/// let options = SerializeOptions::new().column_registry(registry.clone());
+/// let named = [Stop { geometry: point!(x: 1., y: 2.).into(), name: Some("Main St"), line: 4 }];
+/// let unnamed = [Stop { geometry: point!(x: 3., y: 4.).into(), name: None, line: 7 }];
+///
+/// column_table(&named, &options).unwrap();
+/// let columns = column_table(&unnamed, &options).unwrap();
+/// assert_eq!(columns[0].name, "line");
+/// assert_eq!(columns[0].index, 1);
+/// assert_eq!(registry.index("name"), Some(0));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ColumnRegistry(Arc<Mutex<HashMap<String, usize>>>);
+
+impl ColumnRegistry {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The index assigned to the property `name`.
+    #[must_use]
+    pub fn index(&self, name: &str) -> Option<usize> {
+        self.lock().get(name).copied()
+    }
+
+    /// The names of all columns, ordered by their index.
+    #[must_use]
+    pub fn names(&self) -> Vec<String> {
+        let columns = self.lock();
+        let mut names = vec![String::new(); columns.len()];
+        for (name, index) in columns.iter() {
+            names[*index].clone_from(name);
+        }
+        names
+    }
+
+    /// The number of columns assigned so far.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Returns `true` if no column was assigned yet.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, usize>> {
+        // columns are only ever added, a run that panicked left them consistent
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// What happens with the properties when features are aggregated, see
+/// [`SerializeOptions::aggregate`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PropertyMerge {
+    /// Write the aggregated feature without properties.
+    #[default]
+    Drop,
+    /// Keep the properties of the first feature.
+    First,
+    /// Write every property as a JSON array with one value per feature, `null` for features
+    /// without the property.
+    Array,
+}
+
+/// Options for [`to_geozero_datasource_with_options`].
+#[derive(Debug, Clone, Default)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct SerializeOptions {
+    #[cfg(feature = "proj")]
+    reproject: Option<(String, String)>,
+    preserve_zm: bool,
+    null_geometry: NullGeometry,
+    null_properties: NullProperties,
+    non_finite: NonFinite,
+    nested_properties: NestedProperties,
+    ring_orientation: RingOrientation,
+    simplify: Option<Simplification>,
+    clip: Option<Rect>,
+    quantize: Option<Quantization>,
+    multi_parts: MultiParts,
+    part_index: Option<String>,
+    aggregate: Option<PropertyMerge>,
+    layout: FeatureLayout,
+    property_case: PropertyCase,
+    column_order: ColumnOrder,
+    dataset: DatasetInfo,
+    fid_field: Option<String>,
+    geojson_id: Option<String>,
+    widen_numbers: bool,
+    compact: bool,
+    enum_index: bool,
+    #[cfg(feature = "chrono")]
+    ignore_datetimes: bool,
+    computed: Vec<ComputedProperty>,
+    derived: Vec<(String, Derived)>,
+    encoders: Vec<PropertyEncoder>,
+    column_registry: Option<ColumnRegistry>,
+}
+
+/// Encodes a value as a property.
+///
+/// Every `Serialize` type is encoded like any other property. Implement it for types without a
+/// `Serialize` implementation, or for wrappers of types whose default encoding doesn't fit, and
+/// write fields of that type with `#[serde(serialize_with = "serde_geozero::ser::encode_with")]`.
+/// To change the encoding of a single field without touching its type, use
+/// [`SerializeOptions::encode_property`].
+///
+/// ```
+/// use geo::{point, Geometry};
+/// use serde::Serialize;
+/// use serde_geozero::geojson::to_geojson_string;
+/// use serde_geozero::ser::{encode_with, PropertyEncode};
+/// use serde_geozero::value::Value;
+/// use serde_geozero::SerializeOptions;
+///
+/// /// An entrance written as WKT.
+/// struct Entrance(geo::Point);
+///
+/// impl PropertyEncode for Entrance {
+///     fn encode(&self) -> serde_geozero::error::Result<Value> {
+///         Ok(Value::from(format!("POINT({} {})", self.0.x(), self.0.y())))
+///     }
+/// }
+///
+/// #[derive(Serialize)]
+/// struct Building {
+///     geometry: Geometry,
+///     #[serde(serialize_with = "encode_with")]
+///     entrance: Entrance,
+/// }
+///
+/// let buildings = [Building {
+///     geometry: point! { x: 1., y: 2. }.into(),
+///     entrance: Entrance(point! { x: 1., y: 3. }),
+/// }];
+/// let geojson = to_geojson_string(&buildings, &SerializeOptions::new()).unwrap();
+/// assert!(geojson.contains(r#""entrance": "POINT(1 3)""#));
+/// ```
+pub trait PropertyEncode {
+    /// The property value written for `self`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `self` can't be encoded.
+    fn encode(&self) -> Result<Value>;
+}
+
+impl<T: ser::Serialize + ?Sized> PropertyEncode for T {
+    fn encode(&self) -> Result<Value> {
+        to_value(self)
+    }
+}
+
+/// Writes a field with its [`PropertyEncode`] implementation, for
+/// `#[serde(serialize_with = "serde_geozero::ser::encode_with")]`.
+///
+/// # Errors
+///
+/// Returns the error of the encoding and of the serializer.
+pub fn encode_with<T: PropertyEncode + ?Sized, S: ser::Serializer>(
+    value: &T,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    ser::Serialize::serialize(
+        &value.encode().map_err(<S::Error as ser::Error>::custom)?,
+        serializer,
+    )
+}
+
+/// A property written with its own encoding, see [`SerializeOptions::encode_property`].
+#[derive(Clone)]
+struct PropertyEncoder {
+    name: String,
+    encode: Arc<dyn Fn(Value) -> Result<Value> + Send + Sync>,
+}
+
+impl fmt::Debug for PropertyEncoder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PropertyEncoder")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A property derived from the feature while writing, see [`SerializeOptions::computed`].
+#[derive(Clone)]
+struct ComputedProperty {
+    name: String,
+    compute: Arc<dyn Fn(Feature) -> Result<Value> + Send + Sync>,
+}
+
+impl fmt::Debug for ComputedProperty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ComputedProperty")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SerializeOptions {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Emits the values of the companion fields described in [`crate::zm`] as Z and M
+    /// coordinates instead of writing them as properties.
+    ///
+    /// Only processors whose dimensions include Z or M, e.g. a `GeoJsonWriter` created with
+    /// `with_dims`, receive the values. Others get the XY coordinates.
+    #[must_use]
+    pub fn preserve_zm(mut self, preserve_zm: bool) -> Self {
+        self.preserve_zm = preserve_zm;
+        self
+    }
+
+    /// Selects how features without a geometry are written.
+    #[must_use]
+    pub fn null_geometry(mut self, null_geometry: NullGeometry) -> Self {
+        self.null_geometry = null_geometry;
+        self
+    }
+
+    /// Selects how properties set to null are written, computed and derived ones included.
+    #[must_use]
+    pub fn null_properties(mut self, null_properties: NullProperties) -> Self {
+        self.null_properties = null_properties;
+        self
+    }
+
+    /// Selects how NaN and infinite coordinates and property values are handled, see
+    /// [`crate::finite`].
+    ///
+    /// Applies to the serialized fields, before computed and derived properties are added.
+    #[must_use]
+    pub fn non_finite(mut self, non_finite: NonFinite) -> Self {
+        self.non_finite = non_finite;
+        self
+    }
+
+    /// Selects how nested structs and maps are written.
+    #[must_use]
+    pub fn nested_properties(mut self, nested_properties: NestedProperties) -> Self {
+        self.nested_properties = nested_properties;
+        self
+    }
+
+    /// Rewinds polygon rings to `ring_orientation` before they are written.
+    ///
+    /// Use [`RingOrientation::Rfc7946`] for `GeoJSON` output when the serialized structs don't
+    /// guarantee the winding order.
+    #[must_use]
+    pub fn ring_orientation(mut self, ring_orientation: RingOrientation) -> Self {
+        self.ring_orientation = ring_orientation;
+        self
+    }
+
+    /// Simplifies the written geometries, e.g. to produce output for a zoom level.
+    ///
+    /// The serialized structs are left untouched. The tolerance is given in the units of the
+    /// written coordinates, after reprojecting.
+    ///
+    /// ```
+    /// use geo::line_string;
+    /// use serde_geozero::de::Feature;
+    /// use serde_geozero::geojson::to_geojson_string;
+    /// use serde_geozero::ser::Simplification;
+    /// use serde_geozero::SerializeOptions;
+    ///
+    /// let line = line_string![(x: 0., y: 0.), (x: 1., y: 0.1), (x: 2., y: 0.)];
+    /// let features = [Feature::new(line.into(), Default::default())];
+    /// let options = SerializeOptions::new().simplify(Simplification::DouglasPeucker(0.5));
+    /// let geojson = to_geojson_string(&features, &options).unwrap();
+    /// assert!(geojson.contains("[[0,0],[2,0]]"));
+    /// ```
+    #[must_use]
+    pub fn simplify(mut self, simplification: Simplification) -> Self {
+        self.simplify = Some(simplification);
+        self
+    }
+
+    /// Clips geometries to `bbox`, e.g. the bounds of a tile, and leaves out features entirely
+    /// outside of it.
+    ///
+    /// Geometries are clipped after they were reprojected and simplified, so `bbox` is in the
+    /// target CRS. Polygons and line strings cut into several parts become multi geometries,
+    /// points on the border of `bbox` are kept. Left out features are counted in
+    /// [`WriteSummary::skipped`]. The Z and M values of clipped geometries are dropped, those of
+    /// geometries within `bbox` are kept.
+    ///
+    /// ```
+    /// use geo::{line_string, Rect};
+    /// use serde_geozero::de::Feature;
+    /// use serde_geozero::geojson::to_geojson_string;
+    /// use serde_geozero::SerializeOptions;
+    ///
+    /// let line = line_string![(x: -1., y: 0.5), (x: 2., y: 0.5)];
+    /// let features = [Feature::new(line.into(), Default::default())];
+    /// let options = SerializeOptions::new().clip(Rect::new((0., 0.), (1., 1.)));
+    /// let geojson = to_geojson_string(&features, &options).unwrap();
+    /// assert!(geojson.contains("[[0,0.5],[1,0.5]]"));
+    /// ```
+    #[must_use]
+    pub fn clip(mut self, bbox: Rect) -> Self {
+        self.clip = Some(bbox);
+        self
+    }
+
+    /// Snaps coordinates to the integer grid of `quantization`, e.g. for encoding vector tiles.
+    ///
+    /// Geometries are quantized after they were clipped, combine it with
+    /// [`SerializeOptions::clip`] to the same bounds to leave out features outside of the grid.
+    /// Coordinates that fall onto the same grid point as their predecessor are removed, which
+    /// drops the Z and M values of the geometry. Line strings and polygon rings that collapse
+    /// to a single point or lose their area are removed as well, features without any valid
+    /// part left are left out and counted in [`WriteSummary::skipped`].
+    ///
+    /// ```
+    /// use geo::{line_string, Rect};
+    /// use serde_geozero::de::Feature;
+    /// use serde_geozero::geojson::to_geojson_string;
+    /// use serde_geozero::ser::Quantization;
+    /// use serde_geozero::SerializeOptions;
+    ///
+    /// let tile = Rect::new((10., 50.), (11., 51.));
+    /// let line = line_string![(x: 10.25, y: 50.5), (x: 10.5, y: 50.75)];
+    /// let features = [Feature::new(line.into(), Default::default())];
+    /// let options = SerializeOptions::new()
+    ///     .clip(tile)
+    ///     .quantize(Quantization::new(tile).flip_y(true));
+    /// let geojson = to_geojson_string(&features, &options).unwrap();
+    /// assert!(geojson.contains("[[1024,2048],[2048,1024]]"));
+    /// ```
+    #[must_use]
+    pub fn quantize(mut self, quantization: Quantization) -> Self {
+        self.quantize = Some(quantization);
+        self
+    }
+
+    /// Selects how multi geometries are written, e.g. for tools that only read single part
+    /// geometries.
+    ///
+    /// Exploded parts are numbered like separate features, or share the id read from
+    /// [`SerializeOptions::fid_field`].
+    #[must_use]
+    pub fn multi_parts(mut self, multi_parts: MultiParts) -> Self {
+        self.multi_parts = multi_parts;
+        self
+    }
+
+    /// Writes the index of every part within its multi geometry as the property `part_index`
+    /// (e.g. `"part_index"`) when exploding, see [`SerializeOptions::multi_parts`].
+    ///
+    /// Features that weren't split get index 0.
+    #[must_use]
+    pub fn part_index(mut self, part_index: &str) -> Self {
+        self.part_index = Some(part_index.to_string());
+        self
+    }
+
+    /// Writes all features as a single feature with a `GeometryCollection` of their geometries,
+    /// merging their properties as selected by `merge`.
+    ///
+    /// Features without a geometry are handled as configured by
+    /// [`SerializeOptions::null_geometry`]. Z and M values are dropped.
+    #[must_use]
+    pub fn aggregate(mut self, merge: PropertyMerge) -> Self {
+        self.aggregate = Some(merge);
+        self
+    }
+
+    /// Selects the order properties are written in.
+    ///
+    /// Columns get their indices in the order their properties are first written, so the order
+    /// is stable between runs, e.g. for comparing output against golden files. Sort maps with a
+    /// random iteration order, like `HashMap`, by name.
+    #[must_use]
+    pub fn column_order(mut self, column_order: ColumnOrder) -> Self {
+        self.column_order = column_order;
+        self
+    }
+
+    /// Takes the column indices from `registry` and records new columns in it, so they stay the
+    /// same across runs, see [`ColumnRegistry`].
+    #[must_use]
+    pub fn column_registry(mut self, registry: ColumnRegistry) -> Self {
+        self.column_registry = Some(registry);
+        self
+    }
+
+    /// Passes the dataset name to `dataset_begin` and the SRID to `srid` before every geometry.
+    ///
+    /// The description has no `geozero` event, see [`DatasetInfo`] for writing it to
+    /// `FlatGeobuf`. When reprojecting, the SRID of the target CRS is reported instead.
+    #[must_use]
+    pub fn dataset(mut self, dataset: DatasetInfo) -> Self {
+        self.dataset = dataset;
+        self
+    }
+
+    /// Takes the feature id passed to `feature_begin` from the property `fid_field` instead of
+    /// numbering the written features.
+    ///
+    /// The property has to hold a non-negative integer, it is still written as a property.
+    /// Computed properties can be used as well.
+    #[must_use]
+    pub fn fid_field(mut self, fid_field: &str) -> Self {
+        self.fid_field = Some(fid_field.to_string());
+        self
+    }
+
+    /// Writes the property `field` as the `id` member of `GeoJSON` features instead of as a
+    /// property, e.g. `"id"` for a struct field of that name.
+    ///
+    /// Applies to the writers of [`crate::geojson`], other processors get the property like any
+    /// other. Values other than strings and numbers stay properties, `GeoJSON` ids can't hold
+    /// them.
+    #[must_use]
+    pub fn geojson_id(mut self, field: &str) -> Self {
+        self.geojson_id = Some(field.to_string());
+        self
+    }
+
+    /// The property written as the `GeoJSON` feature id, see [`SerializeOptions::geojson_id`].
+    pub(crate) fn geojson_id_field(&self) -> Option<&str> {
+        self.geojson_id.as_deref()
+    }
+
+    /// Writes all integers as `ColumnValue::Long` and all floats as `ColumnValue::Double`.
+    ///
+    /// By default numbers are written with the type of the Rust field, e.g. a `u8` field as
+    /// `ColumnValue::UByte`, which keeps `FlatGeobuf` schemas small. Enable widening for
+    /// consumers that only handle 64-bit columns. `ULong` is only used for values above
+    /// `i64::MAX` then.
+    #[must_use]
+    pub fn widen_numbers(mut self, widen_numbers: bool) -> Self {
+        self.widen_numbers = widen_numbers;
+        self
+    }
+
+    /// Whether property values are serialized in their human readable form, the default.
+    ///
+    /// Types like `Ipv4Addr` or `uuid::Uuid` check
+    /// [`Serializer::is_human_readable`](serde::Serializer::is_human_readable) and are written as
+    /// text for formats meant to be read, like `GeoJSON`. Disable it for binary sinks like
+    /// `FlatGeobuf` to write them as compact `ColumnValue::Binary` instead, and read them back
+    /// with [`DeserializeOptions::human_readable`](crate::DeserializeOptions::human_readable).
+    #[must_use]
+    pub fn human_readable(mut self, human_readable: bool) -> Self {
+        self.compact = !human_readable;
+        self
+    }
+
+    /// Writes unit variants of enum properties as their index instead of their name.
+    ///
+    /// The index is the position of the variant in the enum declaration as `Value::UInt`, not
+    /// an explicit discriminant. Both forms are read back into the enum, so data written with
+    /// and without this option can be mixed.
+    #[must_use]
+    pub fn enum_index(mut self, enum_index: bool) -> Self {
+        self.enum_index = enum_index;
+        self
+    }
+
+    /// Selects where the serialized structs keep the properties.
+    ///
+    /// With [`FeatureLayout::Nested`] the fields of the `properties` field are written as
+    /// properties, fields next to it (other than `geometry`) are written as well.
+    #[must_use]
+    pub fn layout(mut self, layout: FeatureLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Converts property names to `property_case` before they are written, see [`crate::case`].
+    ///
+    /// Computed properties are converted as well, the geometry field is always `geometry`.
+    #[must_use]
+    pub fn property_case(mut self, property_case: PropertyCase) -> Self {
+        self.property_case = property_case;
+        self
+    }
+
+    /// Writes strings that look like dates as `ColumnValue::DateTime`, enabled by default.
+    ///
+    /// See [`crate::datetime`] for the recognized formats.
+    #[cfg(feature = "chrono")]
+    #[must_use]
+    pub fn detect_datetimes(mut self, detect_datetimes: bool) -> Self {
+        self.ignore_datetimes = !detect_datetimes;
+        self
+    }
+
+    #[cfg(feature = "chrono")]
+    fn datetimes_enabled(&self) -> bool {
+        !self.ignore_datetimes
+    }
+
+    #[cfg(not(feature = "chrono"))]
+    #[allow(clippy::unused_self)]
+    fn datetimes_enabled(&self) -> bool {
+        false
+    }
+
+    /// Adds the output property `name`, computed by `compute` for every feature.
+    ///
+    /// The closure receives the written feature deserialized into `V`, which may be the
+    /// serialized struct itself or a smaller view of it. Export-only fields thus don't have to be
+    /// added to the domain struct. Computed properties replace serialized properties of the same
+    /// name and are evaluated in the order they were added, each one seeing the ones before.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geo::{point, Geometry};
+    /// use serde::{Deserialize, Serialize};
+    /// use serde_geozero::geojson::to_geojson_string;
+    /// use serde_geozero::SerializeOptions;
+    ///
+    /// #[derive(Serialize, Deserialize)]
+    /// struct Station {
+    ///     geometry: Geometry,
+    ///     name: String,
+    ///     id: u32,
+    /// }
+    ///
+    /// let options = SerializeOptions::new()
+    ///     .computed("display_name", |t: &Station| format!("{} ({})", t.name, t.id));
+    /// let stations = [Station { geometry: point! { x: 1., y: 2. }.into(), name: "Zoo".into(), id: 7 }];
+    /// let geojson = to_geojson_string(&stations, &options).unwrap();
+    /// assert!(geojson.contains(r#""display_name": "Zoo (7)""#));
+    /// ```
+    #[must_use]
+    pub fn computed<V, T, F>(mut self, name: &str, compute: F) -> Self
+    where
+        V: DeserializeOwned,
+        T: Into<Value>,
+        F: Fn(&V) -> T + Send + Sync + 'static,
+    {
+        self.computed.push(ComputedProperty {
+            name: name.to_string(),
+            compute: Arc::new(move |feature| Ok(compute(&V::deserialize(feature)?).into())),
+        });
+        self
+    }
+
+    /// Writes the property `name` with `encode` instead of its own serialization, e.g. an enum
+    /// as an integer code.
+    ///
+    /// The serialized value is read back into `V` and passed to `encode`. `name` is the name of
+    /// the field before [`SerializeOptions::property_case`] is applied. Null values are written
+    /// as they are, encoders run after [computed properties](SerializeOptions::computed).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geo::{point, Geometry};
+    /// use serde::{Deserialize, Serialize};
+    /// use serde_geozero::geojson::to_geojson_string;
+    /// use serde_geozero::SerializeOptions;
+    ///
+    /// #[derive(Clone, Copy, Serialize, Deserialize)]
+    /// enum Surface {
+    ///     Asphalt = 1,
+    ///     Gravel = 2,
+    /// }
+    ///
+    /// #[derive(Serialize)]
+    /// struct Road {
+    ///     geometry: Geometry,
+    ///     surface: Surface,
+    /// }
+    ///
+    /// let options =
+    ///     SerializeOptions::new().encode_property("surface", |surface: &Surface| *surface as i16);
+    /// let roads = [Road { geometry: point! { x: 1., y: 2. }.into(), surface: Surface::Gravel }];
+    /// let geojson = to_geojson_string(&roads, &options).unwrap();
+    /// assert!(geojson.contains(r#""surface": 2"#));
+    /// ```
+    #[must_use]
+    pub fn encode_property<V, T, F>(mut self, name: &str, encode: F) -> Self
+    where
+        V: DeserializeOwned,
+        T: PropertyEncode,
+        F: Fn(&V) -> T + Send + Sync + 'static,
+    {
+        self.encoders.push(PropertyEncoder {
+            name: name.to_string(),
+            encode: Arc::new(move |value| encode(&V::deserialize(value)?).encode()),
+        });
+        self
+    }
+
+    /// Adds the output property `name` holding a value derived from the written geometry, see
+    /// [`crate::derived`].
+    ///
+    /// Exploded parts get the values of their own geometry.
+    #[must_use]
+    pub fn derived(mut self, name: &str, derived: Derived) -> Self {
+        self.derived.push((name.to_string(), derived));
+        self
+    }
+
+    /// Transforms all geometries from the `from` to the `to` CRS while writing,
+    /// e.g. `reproject("EPSG:4326", "EPSG:3857")`.
+    ///
+    /// If `to` is an `EPSG:<code>` definition the code is passed on to the processor's `srid`.
+    #[cfg(feature = "proj")]
+    #[must_use]
+    pub fn reproject(mut self, from: &str, to: &str) -> Self {
+        self.reproject = Some((from.to_string(), to.to_string()));
+        self
+    }
+}
+
+/// Summary of a serialization run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WriteSummary {
+    /// Number of features passed to the processor.
+    pub feature_count: usize,
+    /// Number of input items that were left out, e.g. because of [`NullGeometry::Skip`] or
+    /// because they were outside of [`SerializeOptions::clip`] or collapsed by
+    /// [`SerializeOptions::quantize`].
+    pub skipped: usize,
+    /// The columns in the order they were first written, which is the order their indices
+    /// were assigned unless a [`ColumnRegistry`] assigned them before.
+    pub columns: Vec<ColumnInfo>,
+}
+
+/// Computes the column table [`to_geozero_datasource_with_options`] would use for `input`,
+/// without writing anything.
+///
+/// Use this to configure processors that key on the property index before writing.
+///
+/// # Examples
+///
+/// ```
+/// use geo::{point, Geometry};
+/// use serde::Serialize;
+/// use serde_geozero::schema::ColumnType;
+/// use serde_geozero::ser::{column_table, SerializeOptions};
+///
+/// #[derive(Serialize)]
+/// struct City {
+///     geometry: Geometry,
+///     name: String,
+/// }
+///
+/// let cities = [City { geometry: (point! { x: 13.4, y: 52.5 }).into(), name: "Berlin".to_string() }];
+/// let columns = column_table(&cities, &SerializeOptions::new()).unwrap();
+/// assert_eq!(columns[0].name, "name");
+/// assert_eq!(columns[0].column_type, Some(ColumnType::String));
+/// ```
+///
+/// # Errors
+///
+/// Returns the same errors as [`to_geozero_datasource_with_options`].
+pub fn column_table<T: ser::Serialize>(
+    input: &[T],
+    options: &SerializeOptions,
+) -> Result<Vec<ColumnInfo>> {
+    Ok(to_geozero_datasource_with_options(input, &mut ProcessorSink::new(), options)?.columns)
+}
+
+/// Serializes features like [`to_geozero_datasource`], applying the given options.
+///
+/// Returns a [`WriteSummary`] describing what was written.
+///
+/// # Errors
+///
+/// Returns the same errors as [`to_geozero_datasource`], and an error if the options cannot be
+/// applied (e.g. an unknown CRS).
+pub fn to_geozero_datasource_with_options<T: ser::Serialize, S: FeatureProcessor>(
+    input: &[T],
+    processor: &mut S,
+    options: &SerializeOptions,
+) -> Result<WriteSummary> {
+    let mut state = WriteState::new(Cow::Borrowed(options))?;
+    processor.dataset_begin(options.dataset.name.as_deref())?;
+    if let Some(merge) = options.aggregate {
+        let aggregated = state.aggregate(input, merge)?;
+        state.write(0, &aggregated, processor)?;
+    } else {
+        for (idx, data) in input.iter().enumerate() {
+            state.write(idx, data, processor)?;
+        }
+    }
+    processor.dataset_end()?;
+    Ok(state.finish())
+}
+
+/// Splits `input` into partitions by `partition` and serializes every partition into its own
+/// processor in parallel (requires the `rayon` feature).
+///
+/// `make_processor` creates the processor of a partition from its key, e.g. a writer per tile
+/// or per output file. Features keep their input order within a partition, and the partitions
+/// are returned in the order their first feature appeared, together with their processor and
+/// [`WriteSummary`].
+///
+/// ```
+/// use geo::{point, Geometry};
+/// use serde::Serialize;
+/// use serde_geozero::geojson::GeoJsonWriter;
+/// use serde_geozero::ser::{par_to_datasources, SerializeOptions};
+///
+/// #[derive(Serialize)]
+/// struct City {
+///     geometry: Geometry,
+///     name: &'static str,
+/// }
+///
+/// let cities = [
+///     City { geometry: point!(x: 13.4, y: 52.5).into(), name: "Berlin" },
+///     City { geometry: point!(x: -74.0, y: 40.7).into(), name: "New York" },
+///     City { geometry: point!(x: 10.0, y: 53.6).into(), name: "Hamburg" },
+/// ];
+/// let hemispheres = par_to_datasources(
+///     &cities,
+///     |_east: &bool| GeoJsonWriter::new(Vec::new()),
+///     |city| matches!(&city.geometry, Geometry::Point(p) if p.x() >= 0.),
+///     &SerializeOptions::new(),
+/// )
+/// .unwrap();
+/// let (east, _, summary) = &hemispheres[0];
+/// assert!(*east);
+/// assert_eq!(summary.feature_count, 2);
+/// ```
+///
+/// # Errors
+///
+/// Returns the error of the first partition that fails, see
+/// [`to_geozero_datasource_with_options`].
+#[cfg(feature = "rayon")]
+pub fn par_to_datasources<T, K, P, M, F>(
+    input: &[T],
+    make_processor: M,
+    partition: F,
+    options: &SerializeOptions,
+) -> Result<Vec<(K, P, WriteSummary)>>
+where
+    T: ser::Serialize + Sync,
+    K: std::hash::Hash + Eq + Clone + Send + Sync,
+    P: FeatureProcessor + Send,
+    M: Fn(&K) -> P + Sync,
+    F: Fn(&T) -> K,
+{
+    use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+    let mut indices = HashMap::new();
+    let mut partitions: Vec<(K, Vec<&T>)> = Vec::new();
+    for feature in input {
+        let key = partition(feature);
+        let index = *indices.entry(key.clone()).or_insert_with(|| {
+            partitions.push((key, Vec::new()));
+            partitions.len() - 1
+        });
+        partitions[index].1.push(feature);
+    }
+    partitions
+        .into_par_iter()
+        .map(|(key, features)| {
+            let mut processor = make_processor(&key);
+            let summary = to_geozero_datasource_with_options(&features, &mut processor, options)?;
+            Ok((key, processor, summary))
+        })
+        .collect()
+}
+
+/// Collects the geometries of all features in `input` into a single collection, e.g. to render
+/// a preview or compute the extent of a dataset.
+///
+/// Features without a geometry are left out.
+///
+/// ```
+/// use geo::{point, BoundingRect, Geometry};
+/// use serde::Serialize;
+/// use serde_geozero::ser::to_geometry_collection;
+///
+/// #[derive(Serialize)]
+/// struct City {
+///     geometry: Geometry,
+///     name: String,
+/// }
+///
+/// let cities = [
+///     City { geometry: point! { x: 13.4, y: 52.5 }.into(), name: "Berlin".to_string() },
+///     City { geometry: point! { x: 2.35, y: 48.9 }.into(), name: "Paris".to_string() },
+/// ];
+/// let collection = to_geometry_collection(&cities).unwrap();
+/// assert_eq!(collection.len(), 2);
+/// assert_eq!(collection.bounding_rect().unwrap().min().x, 2.35);
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if an input can't be serialized as a feature.
+pub fn to_geometry_collection<T: ser::Serialize>(input: &[T]) -> Result<GeometryCollection> {
+    input
+        .iter()
+        .filter_map(
+            |data| match data.serialize(FeatureSerializer::new(FeatureLayout::default())) {
+                Ok(serialized) => serialized.geometry.map(Ok),
+                Err(err) => Some(Err(err)),
+            },
+        )
+        .collect()
+}
+
+/// Writes `value` as a single feature, without `dataset_begin` and `dataset_end`.
+///
+/// Use this when the dataset framing is managed elsewhere, e.g. to append to a writer that is
+/// already open:
+///
+/// ```
+/// use geo::Geometry;
+/// use geozero::FeatureProcessor;
+/// use serde::Serialize;
+/// use serde_geozero::geojson::GeoJsonWriter;
+/// use serde_geozero::ser::to_geozero_feature;
+///
+/// #[derive(Serialize)]
+/// struct City {
+///     geometry: Geometry,
+///     name: String,
+/// }
+///
+/// let city = City {
+///     geometry: geo::point! { x: 13.4, y: 52.5 }.into(),
+///     name: "Berlin".to_string(),
+/// };
+/// let mut writer = GeoJsonWriter::new(Vec::new());
+/// writer.dataset_begin(None).unwrap();
+/// to_geozero_feature(&city, &mut writer).unwrap();
+/// writer.dataset_end().unwrap();
+/// ```
+///
+/// # Errors
+///
+/// Returns the same errors as [`to_geozero_datasource`].
+pub fn to_geozero_feature<T: ser::Serialize + ?Sized, S: FeatureProcessor>(
+    value: &T,
+    processor: &mut S,
+) -> Result<()> {
+    to_geozero_feature_with_options(value, processor, &SerializeOptions::default(), 0)?;
+    Ok(())
+}
+
+/// Like [`to_geozero_feature`], using `options`.
+///
+/// `idx` is passed to `feature_begin` unless [`SerializeOptions::fid_field`] is set, writers
+/// like `GeoJSON` use it to separate features. Column indices are assigned for this feature
+/// alone. Returns `false` if the feature was left out, see [`NullGeometry::Skip`].
+///
+/// # Errors
+///
+/// Returns the same errors as [`to_geozero_datasource_with_options`].
+pub fn to_geozero_feature_with_options<T: ser::Serialize + ?Sized, S: FeatureProcessor>(
+    value: &T,
+    processor: &mut S,
+    options: &SerializeOptions,
+    idx: u64,
+) -> Result<bool> {
+    let mut state = WriteState::new(Cow::Borrowed(options))?;
+    state.written = idx;
+    state.write(usize::try_from(idx).unwrap_or(usize::MAX), value, processor)
+}
+
+/// Writes features one at a time, e.g. while they are produced or interleaved with other IO.
+///
+/// Unlike [`to_geozero_datasource_with_options`] it doesn't need all features up front, but
+/// keeps the column of every property between calls just the same:
+///
+/// ```
+/// use geo::Geometry;
+/// use serde::Serialize;
+/// use serde_geozero::geojson::GeoJsonWriter;
+/// use serde_geozero::ser::FeatureWriter;
+/// use serde_geozero::SerializeOptions;
+///
+/// #[derive(Serialize)]
+/// struct City {
+///     geometry: Geometry,
+///     name: String,
+/// }
+///
+/// let mut writer =
+///     FeatureWriter::new(GeoJsonWriter::new(Vec::new()), &SerializeOptions::new()).unwrap();
+/// writer.begin().unwrap();
+/// for name in ["Berlin", "Hamburg"] {
+///     let city = City {
+///         geometry: geo::point! { x: 10., y: 52. }.into(),
+///         name: name.to_string(),
+///     };
+///     writer.write(&city).unwrap();
+/// }
+/// let (geojson, summary) = writer.finish().unwrap();
+/// assert_eq!(summary.feature_count, 2);
+/// assert!(String::from_utf8(geojson.into_inner()).unwrap().contains("Hamburg"));
+/// ```
+pub struct FeatureWriter<P: FeatureProcessor> {
+    processor: P,
+    state: WriteState<'static>,
+    inputs: usize,
+    begun: bool,
+}
+
+impl<P: FeatureProcessor> FeatureWriter<P> {
+    /// Creates a writer that passes features to `processor`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the options cannot be applied (e.g. an unknown CRS).
+    pub fn new(processor: P, options: &SerializeOptions) -> Result<Self> {
+        Ok(Self {
+            processor,
+            state: WriteState::new(Cow::Owned(options.clone()))?,
+            inputs: 0,
+            begun: false,
+        })
+    }
+
+    /// Starts the dataset, [`FeatureWriter::write`] does so if it wasn't called before.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the processor.
+    pub fn begin(&mut self) -> Result<()> {
+        if !self.begun {
+            self.begun = true;
+            self.processor
+                .dataset_begin(self.state.options.dataset.name.as_deref())?;
+        }
+        Ok(())
+    }
+
+    /// Writes `value` as the next feature, returns `false` if it was left out.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`to_geozero_datasource_with_options`], with indices counting
+    /// the values passed to this writer.
+    pub fn write<T: ser::Serialize + ?Sized>(&mut self, value: &T) -> Result<bool> {
+        self.begin()?;
+        let idx = self.inputs;
+        self.inputs += 1;
+        self.state.write(idx, value, &mut self.processor)
+    }
+
+    /// The processor features are written to.
+    pub fn processor_mut(&mut self) -> &mut P {
+        &mut self.processor
+    }
+
+    /// Ends the dataset and returns the processor along with a summary of what was written.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the processor.
+    pub fn finish(mut self) -> Result<(P, WriteSummary)> {
+        self.begin()?;
+        self.processor.dataset_end()?;
+        let summary = self.state.finish();
+        Ok((self.processor, summary))
+    }
+}
+
+/// Receives the layers written by [`Dataset::write`], e.g. one file per layer or the tables of
+/// a multi-layer format like `GeoPackage`.
+pub trait LayerSink {
+    /// The processor the features of a layer are written to.
+    type Processor: FeatureProcessor;
+
+    /// Starts a layer and returns the processor for its features.
+    ///
+    /// `info` holds the layer name along with the description and SRID shared by all layers.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the layer cannot be created.
+    fn begin_layer(&mut self, info: &DatasetInfo) -> Result<&mut Self::Processor>;
+
+    /// Finishes the layer started last, e.g. to write its file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the layer cannot be written.
+    fn end_layer(&mut self) -> Result<()>;
+}
+
+type WriteLayer<'a, P> = Box<dyn Fn(&mut P, &SerializeOptions) -> Result<WriteSummary> + 'a>;
+
+/// Several named layers written together, sharing their options and dataset metadata.
+///
+/// Every layer is written like [`to_geozero_datasource_with_options`], with the layer name as
+/// the dataset name:
+///
+/// ```
+/// use geo::{line_string, point, Geometry};
+/// use serde::Serialize;
+/// use serde_geozero::geojson::GeoJsonLayers;
+/// use serde_geozero::ser::{Dataset, SerializeOptions};
+///
+/// #[derive(Serialize)]
+/// struct Road {
+///     geometry: Geometry,
+///     lanes: u8,
+/// }
+///
+/// #[derive(Serialize)]
+/// struct Poi {
+///     geometry: Geometry,
+///     name: String,
+/// }
+///
+/// let roads = [Road { geometry: line_string![(x: 0., y: 0.), (x: 1., y: 1.)].into(), lanes: 2 }];
+/// let pois = [Poi { geometry: point! { x: 1., y: 1. }.into(), name: "Zoo".to_string() }];
+///
+/// let mut sink = GeoJsonLayers::new();
+/// let summaries = Dataset::new(&SerializeOptions::new())
+///     .layer("roads", &roads)
+///     .layer("pois", &pois)
+///     .write(&mut sink)
+///     .unwrap();
+/// assert_eq!(summaries[1].0, "pois");
+/// let layers = sink.into_layers();
+/// assert!(layers[1].1.contains("Zoo"));
+/// ```
+pub struct Dataset<'a, P> {
+    options: SerializeOptions,
+    layers: Vec<(String, Option<SerializeOptions>, WriteLayer<'a, P>)>,
+}
+
+impl<'a, P: FeatureProcessor> Dataset<'a, P> {
+    /// Creates a dataset whose layers are written with `options`, including its
+    /// [`DatasetInfo`] description and SRID.
+    #[must_use]
+    pub fn new(options: &SerializeOptions) -> Self {
+        Self {
+            options: options.clone(),
+            layers: Vec::new(),
+        }
+    }
+
+    /// Adds the layer `name` holding the features in `input`.
+    #[must_use]
+    pub fn layer<T: ser::Serialize>(self, name: &str, input: &'a [T]) -> Self {
+        self.add_layer(name, None, input)
+    }
+
+    /// Adds the layer `name` written with its own `options`.
+    ///
+    /// The description and SRID of the dataset are used unless `options` set their own.
+    #[must_use]
+    pub fn layer_with_options<T: ser::Serialize>(
+        self,
+        name: &str,
+        input: &'a [T],
+        options: &SerializeOptions,
+    ) -> Self {
+        self.add_layer(name, Some(options.clone()), input)
+    }
+
+    fn add_layer<T: ser::Serialize>(
+        mut self,
+        name: &str,
+        options: Option<SerializeOptions>,
+        input: &'a [T],
+    ) -> Self {
+        self.layers.push((
+            name.to_string(),
+            options,
+            Box::new(move |processor, options| {
+                to_geozero_datasource_with_options(input, processor, options)
+            }),
+        ));
+        self
+    }
+
+    /// Writes every layer to `sink`, in the order they were added.
+    ///
+    /// Returns the name and [`WriteSummary`] of each layer.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of the sink and [`to_geozero_datasource_with_options`].
+    pub fn write<S: LayerSink<Processor = P>>(
+        &self,
+        sink: &mut S,
+    ) -> Result<Vec<(String, WriteSummary)>> {
+        let mut summaries = Vec::with_capacity(self.layers.len());
+        for (name, options, write) in &self.layers {
+            let options = options.as_ref().unwrap_or(&self.options);
+            let shared = &self.options.dataset;
+            let info = DatasetInfo {
+                name: Some(name.clone()),
+                description: options
+                    .dataset
+                    .description
+                    .clone()
+                    .or_else(|| shared.description.clone()),
+                srid: options.dataset.srid.or(shared.srid),
+            };
+            let options = options.clone().dataset(info.clone());
+            let summary = write(sink.begin_layer(&info)?, &options)?;
+            sink.end_layer()?;
+            summaries.push((name.clone(), summary));
+        }
+        Ok(summaries)
+    }
+}
+
+/// Writes every layer of a [`Dataset`] to a `FlatGeobuf` file named after the layer.
+///
+/// The geometry type of each file is detected from its features.
+#[cfg(feature = "flatgeobuf")]
+pub struct FgbLayers {
+    dir: std::path::PathBuf,
+    current: Option<(String, flatgeobuf::FgbWriter<'static>)>,
+}
+
+#[cfg(feature = "flatgeobuf")]
+impl FgbLayers {
+    /// Writes `<dir>/<layer>.fgb` files, `dir` has to exist.
+    pub fn new(dir: impl Into<std::path::PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            current: None,
+        }
+    }
+}
+
+#[cfg(feature = "flatgeobuf")]
+impl LayerSink for FgbLayers {
+    type Processor = flatgeobuf::FgbWriter<'static>;
+
+    fn begin_layer(&mut self, info: &DatasetInfo) -> Result<&mut Self::Processor> {
+        let name = info.name.clone().unwrap_or_default();
+        let writer = fgb_writer(&name, info)?;
+        Ok(&mut self.current.insert((name, writer)).1)
+    }
+
+    fn end_layer(&mut self) -> Result<()> {
+        if let Some((name, writer)) = self.current.take() {
+            let file = std::fs::File::create(self.dir.join(format!("{name}.fgb")))
+                .map_err(|err| Error::Message(err.to_string()))?;
+            writer.write(std::io::BufWriter::new(file))?;
+        }
+        Ok(())
+    }
+}
+
+/// Serializes features into an indexed `FlatGeobuf` file written to `out`.
+///
+/// Features are buffered in a temporary file first. Once all of them are known, the header
+/// with the extent of the dataset, the packed Hilbert R-tree index and the features in index
+/// order are written, so readers can select by bounding box. The name, description and SRID
+/// are taken from [`SerializeOptions::dataset`], the geometry type is detected from the
+/// features.
+///
+/// ```
+/// use geo::{point, Geometry};
+/// use serde::Serialize;
+/// use serde_geozero::ser::{to_fgb, SerializeOptions};
+///
+/// #[derive(Serialize)]
+/// struct City {
+///     geometry: Geometry,
+///     name: &'static str,
+/// }
+///
+/// let cities = [
+///     City { geometry: point!(x: 13.4, y: 52.5).into(), name: "Berlin" },
+///     City { geometry: point!(x: 10.0, y: 53.6).into(), name: "Hamburg" },
+/// ];
+/// let mut buffer = Vec::new();
+/// to_fgb(&cities, &mut buffer, &SerializeOptions::new()).unwrap();
+///
+/// let reader = flatgeobuf::FgbReader::open(std::io::Cursor::new(buffer)).unwrap();
+/// assert!(reader.header().index_node_size() > 0);
+/// let envelope: Vec<f64> = reader.header().envelope().unwrap().iter().collect();
+/// assert_eq!(envelope, [10.0, 52.5, 13.4, 53.6]);
+/// ```
+///
+/// # Errors
+///
+/// Returns the errors of [`to_geozero_datasource_with_options`] and of the `FlatGeobuf` writer,
+/// e.g. if `out` fails.
+#[cfg(feature = "flatgeobuf")]
+pub fn to_fgb<T: ser::Serialize>(
+    input: &[T],
+    out: impl std::io::Write,
+    options: &SerializeOptions,
+) -> Result<WriteSummary> {
+    let name = options.dataset.name.as_deref().unwrap_or("features");
+    let mut writer = fgb_writer(name, &options.dataset)?;
+    let summary = to_geozero_datasource_with_options(input, &mut writer, options)?;
+    writer.write(out)?;
+    Ok(summary)
+}
+
+/// Creates a `FlatGeobuf` writer building a spatial index and detecting the geometry type.
+#[cfg(feature = "flatgeobuf")]
+fn fgb_writer(name: &str, info: &DatasetInfo) -> Result<flatgeobuf::FgbWriter<'static>> {
+    Ok(flatgeobuf::FgbWriter::create_with_options(
+        name,
+        flatgeobuf::GeometryType::Unknown,
+        flatgeobuf::FgbWriterOptions {
+            write_index: true,
+            detect_type: true,
+            promote_to_multi: true,
+            ..info.fgb_writer_options()
+        },
+    )?)
+}
+
+/// The state kept while features are written one by one.
+///
+/// Properties keep the column indices they got assigned for the first feature they appeared in.
+struct WriteState<'a> {
+    options: Cow<'a, SerializeOptions>,
+    #[cfg(feature = "proj")]
+    reprojection: Option<Reprojection>,
+    columns: HashMap<String, usize>,
+    /// The position of every column index in [`WriteSummary::columns`].
+    column_positions: HashMap<usize, usize>,
+    property_names: HashMap<String, String>,
+    summary: WriteSummary,
+    written: u64,
+}
+
+impl<'a> WriteState<'a> {
+    // only setting up a reprojection can fail
+    #[cfg_attr(not(feature = "proj"), allow(clippy::unnecessary_wraps))]
+    fn new(options: Cow<'a, SerializeOptions>) -> Result<Self> {
+        Ok(Self {
+            #[cfg(feature = "proj")]
+            reprojection: options
+                .reproject
+                .as_ref()
+                .map(|(from, to)| Reprojection::new(from, to))
+                .transpose()?,
+            columns: HashMap::new(),
+            column_positions: HashMap::new(),
+            property_names: HashMap::new(),
+            summary: WriteSummary::default(),
+            written: 0,
+            options,
+        })
+    }
+
+    /// Writes the `idx`th input, returns `false` if it was left out.
+    fn write<T: ser::Serialize + ?Sized, S: FeatureProcessor>(
+        &mut self,
+        idx: usize,
+        data: &T,
+        processor: &mut S,
+    ) -> Result<bool> {
+        let mut deserialized = data.serialize(FeatureSerializer::with_options(&self.options))?;
+        if !self.check_finite(idx, &mut deserialized)? {
+            return Ok(false);
+        }
+        let options = &*self.options;
+        if deserialized.geometry.is_none() {
+            match options.null_geometry {
+                NullGeometry::Error => return Err(Error::MissingGeometry(idx)),
+                NullGeometry::Skip => {
+                    self.summary.skipped += 1;
+                    return Ok(false);
+                }
+                NullGeometry::WriteNull => {}
+                NullGeometry::WriteEmpty => {
+                    deserialized.geometry =
+                        Some(Geometry::GeometryCollection(GeometryCollection::default()));
+                }
+            }
+        }
+
+        deserialized.compute_properties(&options.computed)?;
+        deserialized.encode_properties(&options.encoders)?;
+        if options.nested_properties == NestedProperties::Flatten {
+            deserialized.flatten_properties()?;
+        }
+
+        // without a fid field the written features are numbered
+        let fid = options
+            .fid_field
+            .as_ref()
+            .map(|fid_field| deserialized.fid(fid_field).ok_or(Error::InvalidFid(idx)))
+            .transpose()?;
+        let srid = self.srid();
+        let Some(reshaped) = self.transform(deserialized.geometry.as_mut())? else {
+            self.summary.skipped += 1;
+            return Ok(false);
+        };
+        let (z, m) = if options.preserve_zm {
+            // the values no longer match the coordinates of a clipped or quantized geometry
+            deserialized.take_zm(!reshaped)?
+        } else {
+            (Vec::new(), Vec::new())
+        };
+
+        deserialized.convert_case(options.property_case, &mut self.property_names);
+
+        let parts = match (options.multi_parts, deserialized.geometry.take()) {
+            (MultiParts::Explode, Some(geometry)) => explode(geometry),
+            (_, geometry) => vec![geometry],
+        };
+        // the Z and M values of a part start after the coordinates of the parts before it
+        let mut first_coord = 0;
+        for (part_index, part) in (0u64..).zip(parts) {
+            for (name, derived) in &options.derived {
+                deserialized.set(name.clone(), derived.compute(part.as_ref()));
+            }
+            if let Some(part_index_column) = &options.part_index {
+                deserialized.set(part_index_column.clone(), Value::from(part_index));
+            }
+            if options.null_properties == NullProperties::Error {
+                if let Some(name) = deserialized.null_property(options.column_order) {
+                    return Err(Error::NullProperty { name, index: idx });
+                }
+            }
+            let fid = fid.unwrap_or(self.written);
+            processor.feature_begin(fid)?;
+            if let Some(geometry) = &part {
+                if srid.is_some() {
+                    processor.srid(srid)?;
+                }
+                processor.geometry_begin()?;
+                if options.preserve_zm {
+                    let coords = first_coord..first_coord + geometry.coords_count();
+                    process_geom_zm(
+                        geometry,
+                        part_values(&z, coords.clone()),
+                        part_values(&m, coords.clone()),
+                        processor,
+                    )?;
+                    first_coord = coords.end;
+                } else {
+                    process_geom(geometry, processor)?;
+                }
+                processor.geometry_end()?;
+            }
+
+            processor.properties_begin()?;
+            let mut registry = options.column_registry.as_ref().map(ColumnRegistry::lock);
+            let column_mapping = match &mut registry {
+                Some(registry) => &mut **registry,
+                None => &mut self.columns,
+            };
+            process_properties_with_columns(
+                deserialized.ordered(options.column_order),
+                ColumnAssignment {
+                    mapping: column_mapping,
+                    columns: &mut self.summary.columns,
+                    positions: &mut self.column_positions,
+                },
+                PropertyWriting {
+                    detect_datetimes: options.datetimes_enabled(),
+                    widen_numbers: options.widen_numbers,
+                    emit_nulls: options.null_properties == NullProperties::EmitNull,
+                },
+                processor,
+            )?;
+            processor.properties_end()?;
+            processor.feature_end(fid)?;
+            self.written += 1;
+        }
+        Ok(true)
+    }
+
+    /// Applies [`SerializeOptions::non_finite`], returns `false` if the feature is left out.
+    fn check_finite(&mut self, idx: usize, feature: &mut SerializedFeature) -> Result<bool> {
+        match self.options.non_finite {
+            NonFinite::Allow => {}
+            NonFinite::Error => {
+                if let Some(location) = finite::find(feature.geometry.as_ref(), &feature.properties)
+                {
+                    return Err(Error::NonFinite {
+                        index: idx,
+                        location,
+                    });
+                }
+            }
+            NonFinite::SkipFeature => {
+                if finite::find(feature.geometry.as_ref(), &feature.properties).is_some() {
+                    self.summary.skipped += 1;
+                    return Ok(false);
+                }
+            }
+            NonFinite::ClampOrNull => {
+                if !finite::clamp(feature.geometry.as_mut(), &mut feature.properties) {
+                    feature.geometry = None;
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    /// Merges all of `input` into a single feature, see [`SerializeOptions::aggregate`].
+    fn aggregate<T: ser::Serialize>(
+        &mut self,
+        input: &[T],
+        merge: PropertyMerge,
+    ) -> Result<Feature> {
+        let options = &*self.options;
+        let mut geometries = Vec::with_capacity(input.len());
+        let mut properties = Vec::new();
+        for (idx, data) in input.iter().enumerate() {
+            let mut serialized = data.serialize(FeatureSerializer::with_options(options))?;
+            match serialized.geometry {
+                Some(geometry) => geometries.push(geometry),
+                None => match options.null_geometry {
+                    NullGeometry::Error => return Err(Error::MissingGeometry(idx)),
+                    NullGeometry::Skip => {
+                        self.summary.skipped += 1;
+                        continue;
+                    }
+                    // an empty collection adds nothing to the merged geometry
+                    NullGeometry::WriteNull | NullGeometry::WriteEmpty => {}
+                },
+            }
+            serialized.properties.remove(Z_COL);
+            serialized.properties.remove(M_COL);
+            if merge == PropertyMerge::Array || properties.is_empty() {
+                properties.push(serialized.properties);
+            }
+        }
+
+        let merged = match merge {
+            PropertyMerge::Drop => HashMap::new(),
+            PropertyMerge::First => properties.into_iter().next().unwrap_or_default(),
+            PropertyMerge::Array => {
+                let names: BTreeSet<&String> = properties.iter().flat_map(HashMap::keys).collect();
+                names
+                    .into_iter()
+                    .map(|name| {
+                        let values = properties
+                            .iter()
+                            .map(|feature| {
+                                feature
+                                    .get(name)
+                                    .map_or(Ok(JsonValue::Null), serde_json::to_value)
+                            })
+                            .collect::<std::result::Result<_, _>>()?;
+                        Ok((name.clone(), Value::from(JsonValue::Array(values))))
+                    })
+                    .collect::<Result<_>>()?
+            }
+        };
+        Ok(Feature::new(
+            Geometry::GeometryCollection(GeometryCollection::new_from(geometries)),
+            merged,
+        ))
+    }
+
+    /// The SRID of the written geometries.
+    fn srid(&self) -> Option<i32> {
+        #[cfg(feature = "proj")]
+        if let Some(reprojection) = &self.reprojection {
+            return reprojection.target_srid();
+        }
+        self.options.dataset.srid
+    }
+
+    /// Reprojects, simplifies, clips, quantizes and rewinds `geometry` as configured.
+    ///
+    /// Returns `None` if clipping or quantizing left nothing of it, otherwise whether its
+    /// coordinates changed in number, i.e. its Z and M values no longer match.
+    // only reprojecting can fail
+    #[cfg_attr(not(feature = "proj"), allow(clippy::unnecessary_wraps))]
+    fn transform(&self, geometry: Option<&mut Geometry>) -> Result<Option<bool>> {
+        let Some(geometry) = geometry else {
+            return Ok(Some(false));
+        };
+        #[cfg(feature = "proj")]
+        if let Some(reprojection) = &self.reprojection {
+            reprojection.apply(geometry)?;
+        }
+        if let Some(simplification) = self.options.simplify {
+            simplification.apply(geometry);
+        }
+        let Some(mut reshaped) = clip_in_place(geometry, self.options.clip) else {
+            return Ok(None);
+        };
+        if let Some(quantization) = self.options.quantize {
+            let Some(collapsed) = quantization.apply(geometry) else {
+                return Ok(None);
+            };
+            reshaped |= collapsed;
+        }
+        if let Some(direction) = self.options.ring_orientation.direction() {
+            orient_rings(geometry, direction);
+        }
+        Ok(Some(reshaped))
+    }
+
+    fn finish(&mut self) -> WriteSummary {
+        self.summary.feature_count = usize::try_from(self.written).unwrap_or(usize::MAX);
+        self.column_positions.clear();
+        std::mem::take(&mut self.summary)
+    }
+}
+
+/// The shape every serialized struct is split into before it is written.
+#[derive(Default)]
+struct SerializedFeature {
+    geometry: Option<Geometry>,
+    properties: HashMap<String, Value>,
+    /// The property names in the order they were set, see [`ColumnOrder::Declaration`].
+    order: Vec<String>,
+}
+
+impl SerializedFeature {
+    fn set(&mut self, name: String, value: Value) {
+        if !self.properties.contains_key(&name) {
+            self.order.push(name.clone());
+        }
+        self.properties.insert(name, value);
+    }
+
+    /// Renames the properties, `names` caches the converted names.
+    fn convert_case(&mut self, property_case: PropertyCase, names: &mut HashMap<String, String>) {
+        if property_case == PropertyCase::Unchanged {
+            return;
+        }
+        let mut convert = |name: &String| {
+            names
+                .entry_ref(name)
+                .or_insert_with(|| property_case.convert(name))
+                .clone()
+        };
+        self.properties = std::mem::take(&mut self.properties)
+            .into_iter()
+            .map(|(name, value)| (convert(&name), value))
+            .collect();
+        // names converted to the same one are only listed once
+        let mut listed = HashSet::new();
+        self.order = self
+            .order
+            .iter()
+            .map(convert)
+            .filter(|name| listed.insert(name.clone()))
+            .collect();
+    }
+
+    /// The first property set to null, in the order they are written.
+    fn null_property(&self, column_order: ColumnOrder) -> Option<String> {
+        self.ordered(column_order)
+            .into_iter()
+            .find(|(_, value)| matches!(value, Value::Null))
+            .map(|(name, _)| name.clone())
+    }
+
+    /// The properties in the order they are written.
+    ///
+    /// Properties set without recording their order, e.g. flattened ones, follow sorted by name.
+    fn ordered(&self, column_order: ColumnOrder) -> Vec<(&String, &Value)> {
+        let order: &[String] = match column_order {
+            ColumnOrder::Declaration => &self.order,
+            ColumnOrder::Sorted => &[],
+        };
+        ordered_properties(&self.properties, order).collect()
+    }
+
+    /// Removes the Z and M values of [`SerializeOptions::preserve_zm`] from the properties,
+    /// returns them if `keep`.
+    fn take_zm(&mut self, keep: bool) -> Result<(Vec<f64>, Vec<f64>)> {
+        let z = take_zm_values(&mut self.properties, Z_COL)?;
+        let m = take_zm_values(&mut self.properties, M_COL)?;
+        Ok(if keep {
+            (z, m)
+        } else {
+            (Vec::new(), Vec::new())
+        })
+    }
+
+    /// The non-negative integer property `fid_field`.
+    fn fid(&self, fid_field: &str) -> Option<u64> {
+        match self.properties.get(fid_field)? {
+            Value::ULong(fid) => Some(*fid),
+            value => value.as_i64().and_then(|fid| u64::try_from(fid).ok()),
+        }
+    }
+
+    fn compute_properties(&mut self, computed: &[ComputedProperty]) -> Result<()> {
+        for property in computed {
+            let feature = Feature::new(
+                self.geometry
+                    .clone()
+                    .unwrap_or_else(|| Geometry::GeometryCollection(GeometryCollection::default())),
+                self.properties.clone(),
+            );
+            let value = (property.compute)(feature)?;
+            self.set(property.name.clone(), value);
+        }
+        Ok(())
+    }
+
+    fn encode_properties(&mut self, encoders: &[PropertyEncoder]) -> Result<()> {
+        for encoder in encoders {
+            if let Some(value) = self.properties.get_mut(&encoder.name) {
+                if !matches!(value, Value::Null) {
+                    *value = (encoder.encode)(std::mem::take(value))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Replaces JSON objects by one property per field, see [`NestedProperties::Flatten`].
+    fn flatten_properties(&mut self) -> Result<()> {
+        let nested: Vec<String> = self
+            .properties
+            .iter()
+            .filter(|(_, value)| matches!(value, Value::Json(json) if json.starts_with('{')))
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in nested {
+            if let Some(Value::Json(json)) = self.properties.remove(&key) {
+                flatten_into(&mut self.properties, key, serde_json::from_str(&json)?);
+            }
+        }
+        Ok(())
+    }
+    fn insert<T: ser::Serialize + ?Sized>(
+        &mut self,
+        key: String,
+        value: &T,
+        values: ValueSerializer,
+    ) -> Result<()> {
+        if key == GEOMETRY_COL {
+            let name = value.serialize(GeometryTypeName).ok().flatten();
+            #[cfg(feature = "geos")]
+            if name == Some(crate::with::geos_geometry::GEOS_NAME) {
+                let (result, geometry) =
+                    crate::with::geos_geometry::capture(|| value.serialize(values));
+                result?;
+                self.geometry = geometry;
+                return Ok(());
+            }
+            let mut json = serde_json::to_value(value)?;
+            // single geometry types like `geo::Point` are read as the `geo::Geometry` variant
+            // of the same name
+            if let Some(name) = name {
+                if GEOMETRY_TYPES.contains(&name) && !json.is_null() {
+                    json = serde_json::json!({ name: json });
+                }
+            }
+            self.geometry = decode_json(json)?;
+        } else {
+            self.set(key, value.serialize(values)?);
+        }
+        Ok(())
+    }
+}
+
+/// Finds the name of the struct a value serializes as, e.g. `Point` for a `geo::Point`, without
+/// serializing its fields. Sequences, maps and enum variants have no name and fail.
+struct GeometryTypeName;
+
+/// Ignores the fields of the struct whose name is kept.
+struct IgnoreFields(&'static str);
+
+macro_rules! no_name {
+    ($($method:ident($($arg:ty),*)),*) => {
+        $(
+            fn $method(self, $(_: $arg),*) -> Result<Option<&'static str>> {
+                Ok(None)
+            }
+        )*
+    };
+}
+
+impl ser::Serializer for GeometryTypeName {
+    type Ok = Option<&'static str>;
+    type Error = Error;
+    type SerializeSeq = ser::Impossible<Self::Ok, Error>;
+    type SerializeTuple = ser::Impossible<Self::Ok, Error>;
+    type SerializeTupleStruct = IgnoreFields;
+    type SerializeTupleVariant = ser::Impossible<Self::Ok, Error>;
+    type SerializeMap = ser::Impossible<Self::Ok, Error>;
+    type SerializeStruct = IgnoreFields;
+    type SerializeStructVariant = ser::Impossible<Self::Ok, Error>;
+
+    no_name!(
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_f32(f32),
+        serialize_f64(f64),
+        serialize_char(char),
+        serialize_str(&str),
+        serialize_bytes(&[u8]),
+        serialize_none(),
+        serialize_unit(),
+        serialize_unit_struct(&'static str),
+        serialize_unit_variant(&'static str, u32, &'static str)
+    );
+
+    fn serialize_some<T: ser::Serialize + ?Sized>(self, value: &T) -> Result<Self::Ok> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_struct<T: ser::Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        _value: &T,
+    ) -> Result<Self::Ok> {
+        Ok(Some(name))
+    }
+
+    fn serialize_newtype_variant<T: ser::Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Self::Ok> {
+        Ok(None)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(Error::Unknown)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(Error::Unknown)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Ok(IgnoreFields(name))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(Error::Unknown)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(Error::Unknown)
+    }
+
+    fn serialize_struct(self, name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Ok(IgnoreFields(name))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(Error::Unknown)
+    }
+}
+
+impl ser::SerializeStruct for IgnoreFields {
+    type Ok = Option<&'static str>;
+    type Error = Error;
+
+    fn serialize_field<T: ser::Serialize + ?Sized>(
+        &mut self,
+        _key: &'static str,
+        _value: &T,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(Some(self.0))
+    }
+}
+
+impl ser::SerializeTupleStruct for IgnoreFields {
+    type Ok = Option<&'static str>;
+    type Error = Error;
+
+    fn serialize_field<T: ser::Serialize + ?Sized>(&mut self, _value: &T) -> Result<()> {
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(Some(self.0))
+    }
+}
+
+/// Splits a struct or map into its geometry and properties.
+///
+/// Properties are serialized into [`Value`]s directly, so bytes stay `Value::Binary` instead of
+/// turning into JSON arrays.
+struct FeatureSerializer {
+    layout: FeatureLayout,
+    values: ValueSerializer,
+}
+
+impl FeatureSerializer {
+    fn new(layout: FeatureLayout) -> Self {
+        Self {
+            layout,
+            values: ValueSerializer::default(),
+        }
+    }
+
+    fn with_options(options: &SerializeOptions) -> Self {
+        Self {
+            layout: options.layout,
+            values: ValueSerializer {
+                human_readable: !options.compact,
+                enum_index: options.enum_index,
+            },
+        }
+    }
+}
+
+fn expected_struct<T>() -> Result<T> {
+    Err(Error::Message(
+        "expected a struct or map of properties".to_string(),
+    ))
+}
+
+macro_rules! unsupported {
+    ($($method:ident($($arg:ty),*)),*) => {
+        $(
+            fn $method(self, $(_: $arg),*) -> Result<SerializedFeature> {
+                expected_struct()
+            }
+        )*
+    };
+}
+
+impl ser::Serializer for FeatureSerializer {
+    type Ok = SerializedFeature;
+    type Error = Error;
+    type SerializeSeq = ser::Impossible<SerializedFeature, Error>;
+    type SerializeTuple = ser::Impossible<SerializedFeature, Error>;
+    type SerializeTupleStruct = ser::Impossible<SerializedFeature, Error>;
+    type SerializeTupleVariant = ser::Impossible<SerializedFeature, Error>;
+    type SerializeMap = FeatureMapSerializer;
+    type SerializeStruct = FeatureMapSerializer;
+    type SerializeStructVariant = ser::Impossible<SerializedFeature, Error>;
+
+    unsupported!(
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_f32(f32),
+        serialize_f64(f64),
+        serialize_char(char),
+        serialize_str(&str),
+        serialize_bytes(&[u8]),
+        serialize_none(),
+        serialize_unit(),
+        serialize_unit_struct(&'static str),
+        serialize_unit_variant(&'static str, u32, &'static str)
+    );
+
+    fn serialize_some<T: ser::Serialize + ?Sized>(self, value: &T) -> Result<SerializedFeature> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_struct<T: ser::Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<SerializedFeature> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ser::Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<SerializedFeature> {
+        expected_struct()
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        expected_struct()
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        expected_struct()
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        expected_struct()
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        expected_struct()
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<FeatureMapSerializer> {
+        Ok(FeatureMapSerializer::new(self))
+    }
+
+    fn serialize_struct(self, name: &'static str, _len: usize) -> Result<FeatureMapSerializer> {
+        if name == FEATURE_NAME {
+            // a `GeoJSON` feature object written by `Feature` or `GeozeroFeature`
+            let mut map = FeatureMapSerializer::new(FeatureSerializer {
+                layout: FeatureLayout::Nested,
+                values: self.values,
+            });
+            map.geojson = true;
+            return Ok(map);
+        }
+        Ok(FeatureMapSerializer::new(self))
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.values.human_readable
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        expected_struct()
+    }
+}
+
+struct FeatureMapSerializer {
+    feature: SerializedFeature,
+    key: Option<String>,
+    serializer: FeatureSerializer,
+    /// Whether the `type` member of a `GeoJSON` feature object is dropped.
+    geojson: bool,
+}
+
+impl FeatureMapSerializer {
+    fn new(serializer: FeatureSerializer) -> Self {
+        Self {
+            feature: SerializedFeature::default(),
+            key: None,
+            serializer,
+            geojson: false,
+        }
+    }
+
+    fn insert<T: ser::Serialize + ?Sized>(&mut self, key: String, value: &T) -> Result<()> {
+        let values = self.serializer.values;
+        if self.geojson && key == "type" {
+            Ok(())
+        } else if self.serializer.layout == FeatureLayout::Nested && key == PROPERTIES_COL {
+            let properties = value.serialize(FeatureSerializer {
+                layout: FeatureLayout::Flat,
+                values,
+            })?;
+            self.feature.properties.extend(properties.properties);
+            Ok(())
+        } else {
+            self.feature.insert(key, value, values)
+        }
+    }
+}
+
+impl ser::SerializeMap for FeatureMapSerializer {
+    type Ok = SerializedFeature;
+    type Error = Error;
+
+    fn serialize_key<T: ser::Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        self.key = Some(match to_value(key)? {
+            Value::String(key) => key,
+            key => key.to_string(),
+        });
+        Ok(())
+    }
+
+    fn serialize_value<T: ser::Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| Error::Message("value serialized before key".to_string()))?;
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<SerializedFeature> {
+        Ok(self.feature)
+    }
+}
+
+impl ser::SerializeStruct for FeatureMapSerializer {
+    type Ok = SerializedFeature;
+    type Error = Error;
+
+    fn serialize_field<T: ser::Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.insert(key.to_string(), value)
+    }
+
+    fn end(self) -> Result<SerializedFeature> {
+        Ok(self.feature)
+    }
+}
+
+fn flatten_into(properties: &mut HashMap<String, Value>, prefix: String, value: JsonValue) {
+    match value {
+        JsonValue::Object(map) => {
+            for (key, value) in map {
+                flatten_into(properties, format!("{prefix}.{key}"), value);
+            }
+        }
+        value => {
+            properties.insert(prefix, Value::from(value));
+        }
+    }
+}
+
+/// Serializes a struct or map into properties, as they would be written.
+pub(crate) fn to_properties<T: ser::Serialize + ?Sized>(
+    value: &T,
+) -> Result<HashMap<String, Value>> {
+    Ok(value
+        .serialize(FeatureSerializer::new(FeatureLayout::Flat))?
+        .properties)
+}
+
+fn take_zm_values(properties: &mut HashMap<String, Value>, col: &str) -> Result<Vec<f64>> {
+    let values: Vec<Option<f64>> = match properties.remove(col) {
+        None | Some(Value::Null) => return Ok(Vec::new()),
+        Some(Value::Json(values)) => serde_json::from_str(&values)?,
+        Some(value) => Vec::deserialize(value)?,
+    };
+    Ok(values
+        .into_iter()
+        .map(|value| value.unwrap_or(f64::NAN))
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::from_utf8;
+
+    use geo::point;
+    use geozero::geojson::GeoJsonWriter;
+    use hashbrown::HashMap;
+
+    use crate::{
+        case::PropertyCase,
+        de::Feature,
+        derived::Derived,
+        finite::NonFinite,
+        geojson::GeoJsonLayers,
+        schema::{ColumnType, DatasetInfo},
+        value::Value,
+    };
+
+    use super::{
+        to_geozero_datasource, to_geozero_datasource_with_options, ColumnOrder, Dataset,
+        FeatureWriter, MultiParts, NestedProperties, NullGeometry, NullProperties, PropertyMerge,
+        RingOrientation, SerializeOptions, Simplification,
+    };
+
+    #[test]
+    fn test_to_geojson() {
+        let data_1 = Feature::new(
+            (point! { x: 123.4, y: 345.6 }).into(),
+            HashMap::from_iter(vec![
+                ("prop1".to_string(), Value::from(1.)),
+                ("prop2".to_string(), Value::from("123")),
+            ]),
+        );
+        let data_2 = Feature::new(
+            (point! { x: 123.4, y: 345.6 }).into(),
+            HashMap::from_iter(vec![
+                ("prop1".to_string(), Value::from(1.)),
+                ("prop2".to_string(), Value::from("1234")),
+            ]),
+        );
+
+        let mut out = Vec::new();
+
+        let mut writer = GeoJsonWriter::new(&mut out);
+        let data_vec = vec![data_1, data_2];
+
+        assert!(to_geozero_datasource(data_vec.as_slice(), &mut writer).is_ok());
+
+        let string = from_utf8(out.as_slice()).unwrap();
+
+        assert!(string.contains("\"type\": \"Feature\""));
+        assert!(string.contains("\"coordinates\": [123.4,345.6]"));
+        assert!(string.contains("\"prop1\": 1"));
+        assert!(string.contains("\"prop2\": \"123\""));
+        assert!(string.contains("\"prop2\": \"1234\""));
+    }
+
+    #[test]
+    fn geometry_primitives() -> anyhow::Result<()> {
+        use geo::Geometry;
+
+        use crate::collector::RawCollector;
+
+        #[derive(serde::Serialize)]
+        struct Stop {
+            geometry: geo::Point,
+            name: &'static str,
+        }
+
+        #[derive(serde::Serialize)]
+        struct Zone {
+            geometry: Option<geo::Polygon>,
+        }
+
+        #[derive(serde::Serialize)]
+        struct Segment {
+            geometry: geo::Line,
+        }
+
+        let stops = [Stop {
+            geometry: point! { x: 1., y: 2. },
+            name: "Main St",
+        }];
+        let mut collector = RawCollector::new();
+        to_geozero_datasource(&stops, &mut collector)?;
+        let features = collector.take_features();
+        assert_eq!(features[0].geometry, (point! { x: 1., y: 2. }).into());
+        assert_eq!(features[0].get_str("name"), Some("Main St"));
+
+        let square = geo::Rect::new((0., 0.), (1., 1.)).to_polygon();
+        let zones = [
+            Zone {
+                geometry: Some(square.clone()),
+            },
+            Zone { geometry: None },
+        ];
+        let mut out = Vec::new();
+        let summary = to_geozero_datasource_with_options(
+            &zones,
+            &mut GeoJsonWriter::new(&mut out),
+            &SerializeOptions::new().null_geometry(NullGeometry::Skip),
+        )?;
+        assert_eq!((summary.feature_count, summary.skipped), (1, 1));
+        assert!(from_utf8(&out)?.contains("\"Polygon\""));
+
+        let segments = [Segment {
+            geometry: geo::Line::new((0., 0.), (1., 1.)),
+        }];
+        to_geozero_datasource(&segments, &mut collector)?;
+        let features = collector.take_features();
+        // geozero has no line type, lines are written as line strings
+        assert!(matches!(&features[0].geometry, Geometry::LineString(line) if line.0.len() == 2));
+        Ok(())
+    }
+
+    #[test]
+    fn empty_null_geometry() -> anyhow::Result<()> {
+        use geo::{Geometry, GeometryCollection};
+
+        use crate::collector::RawCollector;
+
+        #[derive(serde::Serialize)]
+        struct Record {
+            geometry: Option<geo::Geometry>,
+            name: &'static str,
+        }
+
+        let records = [
+            Record {
+                geometry: None,
+                name: "a",
+            },
+            Record {
+                geometry: Some((point! { x: 1., y: 2. }).into()),
+                name: "b",
+            },
+        ];
+        let options = SerializeOptions::new().null_geometry(NullGeometry::WriteEmpty);
+        let mut collector = RawCollector::new();
+        let summary = to_geozero_datasource_with_options(&records, &mut collector, &options)?;
+        assert_eq!((summary.feature_count, summary.skipped), (2, 0));
+        let features = collector.into_features();
+        assert_eq!(
+            features[0].geometry,
+            Geometry::GeometryCollection(GeometryCollection::default())
+        );
+        assert_eq!(features[0].get_str("name"), Some("a"));
+
+        let mut out = Vec::new();
+        to_geozero_datasource_with_options(&records, &mut GeoJsonWriter::new(&mut out), &options)?;
+        assert!(
+            from_utf8(&out)?.contains(r#""geometries": []"#),
+            "{}",
+            from_utf8(&out)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn write_summary() -> anyhow::Result<()> {
+        #[derive(serde::Serialize)]
+        struct Record {
+            geometry: Option<geo::Geometry>,
+            value: Option<i32>,
+        }
+
+        let records = [
+            Record {
+                geometry: Some((point! { x: 1., y: 2. }).into()),
+                value: None,
+            },
+            Record {
+                geometry: None,
+                value: Some(1),
+            },
+            Record {
+                geometry: Some((point! { x: 1., y: 2. }).into()),
+                value: Some(2),
+            },
+        ];
+
+        let mut out = Vec::new();
+        let summary = to_geozero_datasource_with_options(
+            &records,
+            &mut GeoJsonWriter::new(&mut out),
+            &SerializeOptions::new().null_geometry(NullGeometry::Skip),
+        )?;
+
+        assert_eq!(summary.feature_count, 2);
+        assert_eq!(summary.skipped, 1);
+        assert_eq!(summary.columns.len(), 1);
+        assert_eq!(summary.columns[0].index, 0);
+        assert_eq!(summary.columns[0].name, "value");
+        assert_eq!(summary.columns[0].column_type, Some(ColumnType::Int));
+        Ok(())
+    }
+
+    #[test]
+    fn feature_writer() -> anyhow::Result<()> {
+        let features = [
+            crate::de::FeatureBuilder::new(point! { x: 1., y: 2. })
+                .property("a", 1)
+                .build(),
+            crate::de::FeatureBuilder::new(point! { x: 3., y: 4. })
+                .property("b", "x")
+                .build(),
+        ];
+        let options = SerializeOptions::new();
+
+        let mut all_at_once = Vec::new();
+        let expected = to_geozero_datasource_with_options(
+            &features,
+            &mut GeoJsonWriter::new(&mut all_at_once),
+            &options,
+        )?;
+
+        let mut one_by_one = Vec::new();
+        let mut writer = FeatureWriter::new(GeoJsonWriter::new(&mut one_by_one), &options)?;
+        writer.begin()?;
+        for feature in &features {
+            assert!(writer.write(feature)?);
+        }
+        let (_, summary) = writer.finish()?;
+        assert_eq!(summary, expected);
+        assert_eq!(summary.columns[1].index, 1);
+        assert_eq!(one_by_one, all_at_once);
+        Ok(())
+    }
+
+    #[test]
+    fn numeric_types() -> anyhow::Result<()> {
+        #[derive(serde::Serialize)]
+        struct Record {
+            geometry: geo::Geometry,
+            a: u8,
+            b: i16,
+            c: f32,
+            d: u64,
+        }
+
+        let records = [Record {
+            geometry: (point! { x: 1., y: 2. }).into(),
+            a: 1,
+            b: -2,
+            c: 0.5,
+            d: 3,
+        }];
+        let column_types = |options| -> anyhow::Result<Vec<_>> {
+            let mut columns = super::column_table(&records, &options)?;
+            columns.sort_by(|a, b| a.name.cmp(&b.name));
+            Ok(columns
+                .into_iter()
+                .map(|column| column.column_type)
+                .collect())
+        };
+        assert_eq!(
+            column_types(SerializeOptions::new())?,
+            [
+                Some(ColumnType::UByte),
+                Some(ColumnType::Short),
+                Some(ColumnType::Float),
+                Some(ColumnType::ULong)
+            ]
+        );
+        assert_eq!(
+            column_types(SerializeOptions::new().widen_numbers(true))?,
+            [
+                Some(ColumnType::Long),
+                Some(ColumnType::Long),
+                Some(ColumnType::Double),
+                Some(ColumnType::Long)
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn nested_properties() -> anyhow::Result<()> {
+        #[derive(serde::Serialize)]
+        struct Address {
+            city: String,
+            zip: Option<u32>,
+        }
+
+        #[derive(serde::Serialize)]
+        struct Shop {
+            geometry: geo::Geometry,
+            address: Address,
+            tags: Vec<String>,
+        }
+
+        let shops = [Shop {
+            geometry: (point! { x: 1., y: 2. }).into(),
+            address: Address {
+                city: "Berlin".to_string(),
+                zip: None,
+            },
+            tags: vec!["a".to_string()],
+        }];
+        let columns = |options: &SerializeOptions| -> anyhow::Result<_> {
+            let mut columns = super::column_table(&shops, options)?
+                .into_iter()
+                .map(|column| (column.name, column.column_type))
+                .collect::<Vec<_>>();
+            columns.sort_by(|a, b| a.0.cmp(&b.0));
+            Ok(columns)
+        };
+
+        assert_eq!(
+            columns(&SerializeOptions::new())?,
+            [
+                ("address".to_string(), Some(ColumnType::Json)),
+                ("tags".to_string(), Some(ColumnType::Json))
+            ]
+        );
+        assert_eq!(
+            columns(&SerializeOptions::new().nested_properties(NestedProperties::Flatten))?,
+            [
+                ("address.city".to_string(), Some(ColumnType::String)),
+                ("address.zip".to_string(), None),
+                ("tags".to_string(), Some(ColumnType::Json))
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn property_case() -> anyhow::Result<()> {
+        #[derive(serde::Serialize)]
+        struct Country {
+            geometry: geo::Geometry,
+            pop_est: u64,
+        }
+
+        let countries = [Country {
+            geometry: (point! { x: 1., y: 2. }).into(),
+            pop_est: 1,
+        }];
+        let columns = super::column_table(
+            &countries,
+            &SerializeOptions::new().property_case(PropertyCase::ScreamingSnakeCase),
+        )?;
+        assert_eq!(columns[0].name, "POP_EST");
+        Ok(())
+    }
+
+    #[test]
+    fn ring_orientation() -> anyhow::Result<()> {
+        use geo::{polygon, Geometry, Winding};
+
+        use crate::collector::RawCollector;
+
+        let clockwise = polygon![(x: 0., y: 0.), (x: 0., y: 1.), (x: 1., y: 1.), (x: 1., y: 0.)];
+        let features = [Feature::new(clockwise.into(), HashMap::new())];
+        let write = |ring_orientation| -> anyhow::Result<bool> {
+            let mut collector = RawCollector::new();
+            to_geozero_datasource_with_options(
+                &features,
+                &mut collector,
+                &SerializeOptions::new().ring_orientation(ring_orientation),
+            )?;
+            let Geometry::Polygon(polygon) = &collector.features[0].geometry else {
+                anyhow::bail!("expected a polygon");
+            };
+            Ok(polygon.exterior().is_ccw())
+        };
+
+        assert!(!write(RingOrientation::Preserve)?);
+        assert!(write(RingOrientation::Rfc7946)?);
+        assert!(!write(RingOrientation::Clockwise)?);
+        Ok(())
+    }
+
+    #[test]
+    fn simplification() -> anyhow::Result<()> {
+        use geo::{line_string, CoordsIter};
+
+        use crate::collector::RawCollector;
+
+        let line = line_string![(x: 0., y: 0.), (x: 1., y: 0.1), (x: 2., y: -0.1), (x: 3., y: 0.)];
+        let features = [Feature::new(line.into(), HashMap::new())];
+        let coords = |simplification| -> anyhow::Result<usize> {
+            let mut collector = RawCollector::new();
+            to_geozero_datasource_with_options(
+                &features,
+                &mut collector,
+                &SerializeOptions::new().simplify(simplification),
+            )?;
+            Ok(collector.features[0].geometry.coords_count())
+        };
+
+        assert_eq!(coords(Simplification::DouglasPeucker(0.5))?, 2);
+        assert_eq!(coords(Simplification::DouglasPeucker(0.01))?, 4);
+        assert_eq!(coords(Simplification::Visvalingam(1.))?, 2);
+        assert_eq!(features[0].geometry.coords_count(), 4);
+        Ok(())
+    }
+
+    #[test]
+    fn clipping() -> anyhow::Result<()> {
+        use geo::{line_string, polygon, Area, Geometry, Rect};
+
+        use crate::collector::RawCollector;
+
+        let features = [
+            // half inside
+            Feature::new(
+                polygon![(x: -1., y: 0.), (x: 1., y: 0.), (x: 1., y: 2.), (x: -1., y: 2.)].into(),
+                HashMap::new(),
+            ),
+            // leaves and enters again
+            Feature::new(
+                line_string![(x: 0.5, y: 0.5), (x: 0.5, y: 3.), (x: 1.5, y: 3.), (x: 1.5, y: 0.5)]
+                    .into(),
+                HashMap::new(),
+            ),
+            Feature::new((point! { x: 1., y: 1. }).into(), HashMap::new()),
+            Feature::new((point! { x: 5., y: 1. }).into(), HashMap::new()),
+        ];
+        let mut collector = RawCollector::new();
+        let summary = to_geozero_datasource_with_options(
+            &features,
+            &mut collector,
+            &SerializeOptions::new().clip(Rect::new((0., 0.), (2., 2.))),
+        )?;
+        assert_eq!((summary.feature_count, summary.skipped), (3, 1));
+        let clipped = collector.take_features();
+        let Geometry::Polygon(polygon) = &clipped[0].geometry else {
+            panic!("expected a polygon, got {:?}", clipped[0].geometry);
+        };
+        assert!((polygon.unsigned_area() - 2.).abs() < 1e-9);
+        let Geometry::MultiLineString(lines) = &clipped[1].geometry else {
+            panic!("expected two lines, got {:?}", clipped[1].geometry);
+        };
+        assert_eq!(lines.0.len(), 2);
+        assert_eq!(clipped[2].geometry, features[2].geometry);
+        Ok(())
+    }
+
+    #[test]
+    fn quantization() -> anyhow::Result<()> {
+        use geo::{line_string, CoordsIter, Rect};
+
+        use super::Quantization;
+        use crate::collector::RawCollector;
+
+        let line = line_string![(x: 0.1, y: 0.1), (x: 0.12, y: 0.11), (x: 1., y: 0.5)];
+        let features = [Feature::new(line.into(), HashMap::new())];
+        let quantized = |quantization| -> anyhow::Result<Vec<(f64, f64)>> {
+            let mut collector = RawCollector::new();
+            to_geozero_datasource_with_options(
+                &features,
+                &mut collector,
+                &SerializeOptions::new().quantize(quantization),
+            )?;
+            Ok(collector.features[0]
+                .geometry
+                .coords_iter()
+                .map(|coord| coord.x_y())
+                .collect())
+        };
+
+        let grid = Quantization::new(Rect::new((0., 0.), (1., 1.))).extent(10);
+        // the second coordinate falls onto the grid point of the first
+        assert_eq!(quantized(grid)?, [(1., 1.), (10., 5.)]);
+        assert_eq!(quantized(grid.flip_y(true))?, [(1., 9.), (10., 5.)]);
+        assert_eq!(
+            quantized(Quantization::new(Rect::new((0., 0.), (2., 1.))))?,
+            [(205., 410.), (246., 451.), (2048., 2048.)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn quantization_collapsed_parts() -> anyhow::Result<()> {
+        use geo::{polygon, CoordsIter, MultiPolygon, Rect};
+
+        use super::Quantization;
+        use crate::collector::RawCollector;
+
+        // falls onto a single grid cell
+        let small = polygon![
+            (x: 0.51, y: 0.51), (x: 0.52, y: 0.51), (x: 0.52, y: 0.52), (x: 0.51, y: 0.52)
+        ];
+        let square = polygon![(x: 0., y: 0.), (x: 1., y: 0.), (x: 1., y: 1.), (x: 0., y: 1.)];
+        let features = [
+            Feature::new(small.clone().into(), HashMap::new()),
+            Feature::new(
+                MultiPolygon::new(vec![small, square]).into(),
+                HashMap::new(),
+            ),
+        ];
+
+        let mut collector = RawCollector::new();
+        let summary = to_geozero_datasource_with_options(
+            &features,
+            &mut collector,
+            &SerializeOptions::new()
+                .quantize(Quantization::new(Rect::new((0., 0.), (1., 1.))).extent(10)),
+        )?;
+        assert_eq!(summary.feature_count, 1);
+        assert_eq!(summary.skipped, 1);
+        let geo::Geometry::MultiPolygon(polygons) = &collector.features[0].geometry else {
+            panic!("expected a multi polygon");
+        };
+        assert_eq!(polygons.0.len(), 1);
+        assert_eq!(polygons.coords_count(), 5);
+        Ok(())
+    }
+
+    #[test]
+    fn exploded_multi_parts() -> anyhow::Result<()> {
+        use geo::{polygon, Geometry, MultiPolygon};
+
+        use crate::collector::RawCollector;
+
+        let square = polygon![(x: 0., y: 0.), (x: 1., y: 0.), (x: 1., y: 1.), (x: 0., y: 1.)];
+        let parts = MultiPolygon::new(vec![square.clone(), square]);
+        let properties = HashMap::from([("name".to_string(), Value::from("a"))]);
+        let features = [
+            Feature::new(parts.into(), properties.clone()),
+            Feature::new((point! { x: 1., y: 2. }).into(), properties),
+        ];
+
+        let mut collector = RawCollector::new();
+        let summary = to_geozero_datasource_with_options(
+            &features,
+            &mut collector,
+            &SerializeOptions::new()
+                .multi_parts(MultiParts::Explode)
+                .part_index("part"),
+        )?;
+        assert_eq!(summary.feature_count, 3);
+        let exploded = collector.features();
+        assert!(exploded[..2]
+            .iter()
+            .all(|feature| matches!(feature.geometry, Geometry::Polygon(_))));
+        assert!(exploded
+            .iter()
+            .all(|feature| feature.get_str("name") == Some("a")));
+        let part_indices: Vec<u64> = exploded
+            .iter()
+            .map(|feature| feature.get("part"))
+            .collect::<crate::error::Result<_>>()?;
+        assert_eq!(part_indices, [0, 1, 0]);
+        Ok(())
+    }
+
+    #[test]
+    fn aggregation() -> anyhow::Result<()> {
+        use geo::Geometry;
+
+        use crate::collector::RawCollector;
+
+        let features = [
+            Feature::new(
+                (point! { x: 1., y: 2. }).into(),
+                HashMap::from([("name".to_string(), Value::from("a"))]),
+            ),
+            Feature::new(
+                (point! { x: 3., y: 4. }).into(),
+                HashMap::from([("rank".to_string(), Value::from(2))]),
+            ),
+        ];
+        let aggregate = |merge| -> anyhow::Result<Feature> {
+            let mut collector = RawCollector::new();
+            let summary = to_geozero_datasource_with_options(
+                &features,
+                &mut collector,
+                &SerializeOptions::new().aggregate(merge),
+            )?;
+            assert_eq!(summary.feature_count, 1);
+            Ok(collector.into_features().remove(0))
+        };
+
+        let dropped = aggregate(PropertyMerge::Drop)?;
+        assert!(matches!(&dropped.geometry, Geometry::GeometryCollection(c) if c.len() == 2));
+        assert!(dropped.properties.is_empty());
+        assert_eq!(aggregate(PropertyMerge::First)?.get_str("name"), Some("a"));
+        let arrays = aggregate(PropertyMerge::Array)?;
+        assert_eq!(
+            arrays.get::<Vec<Option<String>>>("name")?,
+            [Some("a".to_string()), None]
+        );
+        assert_eq!(arrays.get::<Vec<Option<i32>>>("rank")?, [None, Some(2)]);
+
+        assert_eq!(super::to_geometry_collection(&features)?.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn nested_layout() -> anyhow::Result<()> {
+        #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+        struct City {
+            geometry: geo::Geometry,
+            properties: CityProperties,
+        }
+
+        #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+        struct CityProperties {
+            name: String,
+            population: u64,
+        }
+
+        let cities = [City {
+            geometry: (point! { x: 13.4, y: 52.5 }).into(),
+            properties: CityProperties {
+                name: "Berlin".to_string(),
+                population: 3_669_495,
+            },
+        }];
+        let geojson = crate::geojson::to_geojson_string(
+            &cities,
+            &SerializeOptions::new().layout(crate::de::FeatureLayout::Nested),
+        )?;
+        let json: serde_json::Value = serde_json::from_str(&geojson)?;
+        assert_eq!(json["features"][0]["properties"]["name"], "Berlin");
+
+        let read: Vec<City> = crate::from_datasource_with_options(
+            &mut geozero::geojson::GeoJsonReader(geojson.as_bytes()),
+            &crate::DeserializeOptions::new().layout(crate::de::FeatureLayout::Nested),
+        )?;
+        assert_eq!(read, cities);
+        Ok(())
+    }
+
+    #[test]
+    fn fid_field() -> anyhow::Result<()> {
+        #[derive(serde::Serialize)]
+        struct Parcel {
+            geometry: geo::Geometry,
+            id: i64,
+        }
+
+        #[derive(Default)]
+        struct Fids(Vec<u64>);
+
+        impl geozero::GeomProcessor for Fids {}
+        impl geozero::PropertyProcessor for Fids {}
+        impl geozero::FeatureProcessor for Fids {
+            fn feature_begin(&mut self, idx: u64) -> geozero::error::Result<()> {
+                self.0.push(idx);
+                Ok(())
+            }
+        }
+
+        let parcel = |id| Parcel {
+            geometry: (point! { x: 1., y: 2. }).into(),
+            id,
+        };
+        let mut fids = Fids::default();
+        to_geozero_datasource_with_options(
+            &[parcel(17), parcel(4)],
+            &mut fids,
+            &SerializeOptions::new().fid_field("id"),
+        )?;
+        assert_eq!(fids.0, [17, 4]);
+
+        let err = to_geozero_datasource_with_options(
+            &[parcel(1), parcel(-1)],
+            &mut Fids::default(),
+            &SerializeOptions::new().fid_field("id"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, crate::error::Error::InvalidFid(1)));
+        Ok(())
+    }
+
+    #[test]
+    fn computed_properties() -> anyhow::Result<()> {
+        #[derive(serde::Serialize)]
+        struct Station {
+            geometry: geo::Geometry,
+            name: String,
+        }
+
+        #[derive(serde::Deserialize)]
+        struct Named {
+            name: String,
+        }
+
+        let stations = [Station {
+            geometry: (point! { x: 1., y: 2. }).into(),
+            name: "zoo".to_string(),
+        }];
+        let options = SerializeOptions::new()
+            .computed("label", |t: &Named| t.name.to_uppercase())
+            .computed("name", |t: &Named| format!("{}!", t.name))
+            .computed("length", |t: &Named| t.name.len() as u64);
+
+        let mut out = Vec::new();
+        let summary = to_geozero_datasource_with_options(
+            &stations,
+            &mut GeoJsonWriter::new(&mut out),
+            &options,
+        )?;
+        assert_eq!(summary.columns.len(), 3);
+        let geojson = from_utf8(&out)?;
+        assert!(geojson.contains(r#""label": "ZOO""#));
+        assert!(geojson.contains(r#""name": "zoo!""#));
+        assert!(geojson.contains(r#""length": 4"#));
+        Ok(())
+    }
+
+    #[cfg(feature = "flatgeobuf")]
+    #[test]
+    fn binary_round_trip() -> anyhow::Result<()> {
+        use flatgeobuf::{FgbReader, FgbWriter, GeometryType};
+        use std::io::Cursor;
+
+        #[derive(serde::Serialize, serde::Deserialize)]
+        struct Tile {
+            geometry: geo::Geometry,
+            data: Vec<u8>,
+        }
+
+        let tiles = [Tile {
+            geometry: (point! { x: 1., y: 2. }).into(),
+            data: vec![0, 1, 255],
+        }];
+        let mut writer = FgbWriter::create("tiles", GeometryType::Point)?;
+        let summary =
+            to_geozero_datasource_with_options(&tiles, &mut writer, &SerializeOptions::new())?;
+        assert_eq!(summary.columns[0].column_type, Some(ColumnType::Binary));
+        let mut buffer = Vec::new();
+        writer.write(&mut buffer)?;
+
+        let mut reader = FgbReader::open(Cursor::new(buffer))?.select_all()?;
+        let read: Vec<Tile> = crate::from_datasource(&mut reader)?;
+        assert_eq!(read[0].data, [0, 1, 255]);
+        Ok(())
+    }
+
+    #[cfg(feature = "flatgeobuf")]
+    #[test]
+    fn human_readable() -> anyhow::Result<()> {
+        use flatgeobuf::{FgbReader, FgbWriter, GeometryType};
+        use std::{io::Cursor, net::Ipv4Addr};
+
+        #[derive(serde::Serialize, serde::Deserialize)]
+        struct Host {
+            geometry: geo::Geometry,
+            address: Ipv4Addr,
+        }
+
+        let hosts = [Host {
+            geometry: (point! { x: 1., y: 2. }).into(),
+            address: Ipv4Addr::new(10, 0, 0, 1),
+        }];
+        let mut out = Vec::new();
+        to_geozero_datasource(&hosts, &mut GeoJsonWriter::new(&mut out))?;
+        assert!(from_utf8(&out)?.contains(r#""address": "10.0.0.1""#));
+
+        let mut writer = FgbWriter::create("hosts", GeometryType::Point)?;
+        let summary = to_geozero_datasource_with_options(
+            &hosts,
+            &mut writer,
+            &SerializeOptions::new().human_readable(false),
+        )?;
+        assert_eq!(summary.columns[0].column_type, Some(ColumnType::Binary));
+        let mut buffer = Vec::new();
+        writer.write(&mut buffer)?;
+
+        let mut reader = FgbReader::open(Cursor::new(buffer))?.select_all()?;
+        let read: Vec<Host> = crate::from_datasource_with_options(
+            &mut reader,
+            &crate::DeserializeOptions::new().human_readable(false),
+        )?;
+        assert_eq!(read[0].address, hosts[0].address);
+        Ok(())
+    }
+
+    #[test]
+    fn null_properties() -> anyhow::Result<()> {
+        #[derive(serde::Serialize)]
+        struct Record {
+            geometry: geo::Geometry,
+            name: Option<String>,
+        }
+
+        let records = [Record {
+            geometry: (point! { x: 1., y: 2. }).into(),
+            name: None,
+        }];
+        let write = |null_properties| {
+            crate::geojson::to_geojson_string(
+                &records,
+                &SerializeOptions::new().null_properties(null_properties),
+            )
+        };
+        assert!(!write(NullProperties::Omit)?.contains("name"));
+        assert!(write(NullProperties::EmitNull)?.contains(r#""name": null"#));
+        assert!(matches!(
+            write(NullProperties::Error),
+            Err(crate::error::Error::NullProperty { name, index: 0 }) if name == "name"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn non_finite_values() -> anyhow::Result<()> {
+        #[derive(serde::Serialize)]
+        struct Sample {
+            geometry: geo::Geometry,
+            value: f64,
+        }
+
+        let samples = [
+            Sample {
+                geometry: (point! { x: 1., y: 2. }).into(),
+                value: f64::NEG_INFINITY,
+            },
+            Sample {
+                geometry: (point! { x: f64::NAN, y: 2. }).into(),
+                value: 1.,
+            },
+        ];
+        let write =
+            |options: SerializeOptions| crate::geojson::to_geojson_string(&samples, &options);
+        // the GeoJSON writer rejects coordinates passed on unchecked
+        assert!(write(SerializeOptions::new()).is_err());
+        assert!(matches!(
+            write(SerializeOptions::new().non_finite(NonFinite::Error)),
+            Err(crate::error::Error::NonFinite { index: 0, location }) if location == "value"
+        ));
+        let geojson = write(
+            SerializeOptions::new()
+                .non_finite(NonFinite::ClampOrNull)
+                .null_geometry(NullGeometry::WriteNull),
+        )?;
+        assert!(!geojson.contains("NaN"), "{geojson}");
+        assert!(geojson.contains(r#""geometry": null"#), "{geojson}");
+        assert!(geojson.contains(&format!("{}", f64::MIN)), "{geojson}");
+        Ok(())
+    }
+
+    #[test]
+    fn encoded_properties() -> anyhow::Result<()> {
+        use crate::collector::RawCollector;
+
+        #[derive(serde::Serialize)]
+        struct Record {
+            geometry: geo::Geometry,
+            code: Option<&'static str>,
+            rank: u8,
+        }
+
+        let records = [
+            Record {
+                geometry: (point! { x: 1., y: 2. }).into(),
+                code: Some("ab"),
+                rank: 1,
+            },
+            Record {
+                geometry: (point! { x: 1., y: 2. }).into(),
+                code: None,
+                rank: 2,
+            },
+        ];
+        let options = SerializeOptions::new()
+            .null_properties(NullProperties::EmitNull)
+            .computed("label", |record: &HashMap<String, Value>| {
+                record["rank"].to_string()
+            })
+            .encode_property("code", |code: &String| code.to_uppercase())
+            .encode_property("label", |label: &String| format!("#{label}"));
+        let mut collector = RawCollector::new();
+        to_geozero_datasource_with_options(&records, &mut collector, &options)?;
+        let features = collector.take_features();
+        assert_eq!(features[0].get_str("code"), Some("AB"));
+        assert_eq!(features[0].get_str("label"), Some("#1"));
+        // nulls aren't passed to the encoder
+        assert_eq!(features[1].value("code"), Some(&Value::Json("null".into())));
+
+        let options = SerializeOptions::new().encode_property("rank", |rank: &bool| *rank);
+        let err = to_geozero_datasource_with_options(&records, &mut collector, &options);
+        assert!(err.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn column_registry() -> anyhow::Result<()> {
+        use super::{column_table, ColumnRegistry, FeatureWriter};
+
+        #[derive(serde::Serialize)]
+        struct First {
+            geometry: geo::Geometry,
+            a: u8,
+            b: u8,
+        }
+        #[derive(serde::Serialize)]
+        struct Second {
+            geometry: geo::Geometry,
+            b: u8,
+            c: u8,
+        }
+
+        let first = [First {
+            geometry: (point! { x: 1., y: 2. }).into(),
+            a: 1,
+            b: 2,
+        }];
+        let second = [Second {
+            geometry: (point! { x: 1., y: 2. }).into(),
+            b: 3,
+            c: 4,
+        }];
+        let indices = |columns: Vec<crate::schema::ColumnInfo>| {
+            columns
+                .into_iter()
+                .map(|column| (column.name, column.index))
+                .collect::<Vec<_>>()
+        };
+
+        // every run assigns its own indices
+        column_table(&first, &SerializeOptions::new())?;
+        let unshared = column_table(&second, &SerializeOptions::new())?;
+        assert_eq!(indices(unshared), [("b".into(), 0), ("c".into(), 1)]);
+
+        let registry = ColumnRegistry::new();
+        let options = SerializeOptions::new().column_registry(registry.clone());
+        let mut writer = FeatureWriter::new(geozero::ProcessorSink::new(), &options)?;
+        writer.write(&first[0])?;
+        let (_, summary) = writer.finish()?;
+        assert_eq!(indices(summary.columns), [("a".into(), 0), ("b".into(), 1)]);
+        let shared = column_table(&second, &options)?;
+        assert_eq!(indices(shared), [("b".into(), 1), ("c".into(), 2)]);
+        assert_eq!(registry.names(), ["a", "b", "c"]);
+        assert_eq!(registry.index("c"), Some(2));
+        Ok(())
+    }
+
+    #[test]
+    fn column_order() -> anyhow::Result<()> {
+        #[derive(serde::Serialize)]
+        struct Record {
+            geometry: geo::Geometry,
+            zone: u8,
+            area: f64,
+            name: &'static str,
+        }
+        #[derive(serde::Deserialize)]
+        struct Named {
+            name: String,
+        }
+
+        let records = [Record {
+            geometry: (point! { x: 1., y: 2. }).into(),
+            zone: 3,
+            area: 1.5,
+            name: "a",
+        }];
+        let write = |options: SerializeOptions| {
+            let summary = to_geozero_datasource_with_options(
+                &records,
+                &mut geozero::ProcessorSink::new(),
+                &options.computed("label", |named: &Named| named.name.to_uppercase()),
+            )?;
+            anyhow::Ok(
+                summary
+                    .columns
+                    .into_iter()
+                    .map(|column| column.name)
+                    .collect::<Vec<_>>(),
+            )
+        };
+        assert_eq!(
+            write(SerializeOptions::new())?,
+            ["zone", "area", "name", "label"]
+        );
+        assert_eq!(
+            write(SerializeOptions::new().column_order(ColumnOrder::Sorted))?,
+            ["area", "label", "name", "zone"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn dataset_layers() -> anyhow::Result<()> {
+        #[derive(serde::Serialize)]
+        struct Poi {
+            geometry: geo::Geometry,
+            name: &'static str,
+        }
+
+        let pois = [Poi {
+            geometry: (point! { x: 1., y: 2. }).into(),
+            name: "Zoo",
+        }];
+        let shared = SerializeOptions::new().dataset(DatasetInfo {
+            srid: Some(4326),
+            ..DatasetInfo::default()
+        });
+        let mut sink = GeoJsonLayers::new();
+        let summaries = Dataset::new(&shared)
+            .layer("pois", &pois)
+            .layer_with_options(
+                "labels",
+                &pois,
+                &SerializeOptions::new().derived("area", Derived::Area),
+            )
+            .write(&mut sink)?;
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[1].1.columns.len(), 2);
+
+        let layers = sink.into_layers();
+        assert_eq!(layers[0].0, "pois");
+        assert!(layers[0].1.contains(r#""name": "pois""#), "{}", layers[0].1);
+        assert!(
+            layers[1].1.contains(r#""name": "labels""#),
+            "{}",
+            layers[1].1
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "flatgeobuf")]
+    #[test]
+    fn dataset_fgb_files() -> anyhow::Result<()> {
+        use flatgeobuf::FgbReader;
+
+        #[derive(serde::Serialize)]
+        struct Poi {
+            geometry: geo::Geometry,
+        }
+
+        let dir = std::env::temp_dir().join(format!("serde_geozero_{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        let pois = [Poi {
+            geometry: (point! { x: 1., y: 2. }).into(),
+        }];
+        let shared = SerializeOptions::new().dataset(DatasetInfo {
+            description: Some("points of interest".to_string()),
+            ..DatasetInfo::default()
+        });
+        Dataset::new(&shared)
+            .layer("a", &pois)
+            .layer("b", &pois)
+            .write(&mut super::FgbLayers::new(&dir))?;
+
+        let reader = FgbReader::open(std::fs::File::open(dir.join("b.fgb"))?)?;
+        let info = DatasetInfo::from_fgb(&reader);
+        assert_eq!(info.name.as_deref(), Some("b"));
+        assert_eq!(info.description.as_deref(), Some("points of interest"));
+        std::fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[cfg(feature = "flatgeobuf")]
+    #[test]
+    fn indexed_fgb() -> anyhow::Result<()> {
+        use flatgeobuf::{FallibleStreamingIterator, FgbReader};
+
+        #[derive(serde::Serialize)]
+        struct Poi {
+            geometry: geo::Geometry,
+            id: u32,
+        }
+
+        let pois: Vec<Poi> = (0..20)
+            .map(|id| Poi {
+                geometry: (point! { x: f64::from(id), y: -f64::from(id) }).into(),
+                id,
+            })
+            .collect();
+        let options = SerializeOptions::new().dataset(DatasetInfo {
+            name: Some("pois".to_string()),
+            srid: Some(4326),
+            ..DatasetInfo::default()
+        });
+        let mut buffer = Vec::new();
+        let summary = super::to_fgb(&pois, &mut buffer, &options)?;
+        assert_eq!(summary.feature_count, 20);
+
+        let reader = FgbReader::open(std::io::Cursor::new(buffer))?;
+        let header = reader.header();
+        assert_eq!(header.features_count(), 20);
+        assert_eq!(header.geometry_type(), flatgeobuf::GeometryType::Point);
+        assert_eq!(DatasetInfo::from_fgb(&reader).srid, Some(4326));
+        let envelope: Vec<f64> = header.envelope().unwrap().iter().collect();
+        assert_eq!(envelope, [0., -19., 19., 0.]);
+
+        // the index answers bounding box queries
+        let mut selected = reader.select_bbox(4.5, -10.5, 10.5, -4.5)?;
+        let mut count = 0;
+        while selected.next()?.is_some() {
+            count += 1;
+        }
+        assert_eq!(count, 6);
+        Ok(())
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn datetimes() -> anyhow::Result<()> {
+        use chrono::{DateTime, Utc};
+
+        #[derive(serde::Serialize)]
+        struct Event {
+            geometry: geo::Geometry,
+            start: DateTime<Utc>,
+        }
+
+        let events = [Event {
+            geometry: (point! { x: 1., y: 2. }).into(),
+            start: DateTime::from_timestamp(1_714_564_800, 0).unwrap(),
+        }];
+
+        let column_type = |options: &SerializeOptions| -> anyhow::Result<_> {
+            let summary = to_geozero_datasource_with_options(
+                &events,
+                &mut GeoJsonWriter::new(&mut Vec::new()),
+                options,
+            )?;
+            Ok(summary.columns[0].column_type)
+        };
+        assert_eq!(
+            column_type(&SerializeOptions::new())?,
+            Some(ColumnType::DateTime)
+        );
+        assert_eq!(
+            column_type(&SerializeOptions::new().detect_datetimes(false))?,
+            Some(ColumnType::String)
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_partitions() -> anyhow::Result<()> {
+        let features: Vec<Feature> = (0..100)
+            .map(|i| {
+                Feature::new(
+                    (point! { x: f64::from(i), y: 0. }).into(),
+                    HashMap::from_iter([("id".to_string(), Value::from(i64::from(i)))]),
+                )
+            })
+            .collect();
+        let partitions = super::par_to_datasources(
+            &features,
+            |_: &i64| crate::geojson::GeoJsonWriter::new(Vec::new()),
+            |feature| feature.properties["id"].as_i64().unwrap() % 3,
+            &SerializeOptions::new(),
+        )?;
+
+        assert_eq!(
+            partitions.iter().map(|(key, ..)| *key).collect::<Vec<_>>(),
+            [0, 1, 2]
+        );
+        for (key, writer, summary) in partitions {
+            let collection: serde_json::Value = serde_json::from_slice(&writer.into_inner())?;
+            let ids: Vec<i64> = collection["features"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|feature| feature["properties"]["id"].as_i64().unwrap())
+                .collect();
+            assert_eq!(summary.feature_count, ids.len());
+            assert_eq!(ids, (key..100).step_by(3).collect::<Vec<_>>());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn enum_properties() -> anyhow::Result<()> {
+        #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+        enum Surface {
+            Paved,
+            Gravel,
+        }
+
+        #[derive(serde::Serialize, serde::Deserialize)]
+        struct Road {
+            geometry: geo::Geometry,
+            surface: Surface,
+            shoulder: Option<Surface>,
+        }
+
+        let roads = [Road {
+            geometry: (point! { x: 1., y: 2. }).into(),
+            surface: Surface::Gravel,
+            shoulder: Some(Surface::Paved),
+        }];
+        let mut out = Vec::new();
+        to_geozero_datasource(&roads, &mut GeoJsonWriter::new(&mut out))?;
+        assert!(from_utf8(&out)?.contains(r#""surface": "Gravel""#));
+
+        let mut indexed = Vec::new();
+        to_geozero_datasource_with_options(
+            &roads,
+            &mut GeoJsonWriter::new(&mut indexed),
+            &SerializeOptions::new().enum_index(true),
+        )?;
+        assert!(from_utf8(&indexed)?.contains(r#""surface": 1"#));
+
+        for geojson in [out, indexed] {
+            let read: Vec<Road> =
+                crate::from_datasource(&mut geozero::geojson::GeoJsonReader(geojson.as_slice()))?;
+            assert_eq!(read[0].surface, Surface::Gravel);
+            assert_eq!(read[0].shoulder, Some(Surface::Paved));
+        }
+        Ok(())
     }
 }
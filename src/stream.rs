@@ -0,0 +1,287 @@
+//! Pushing features to live clients (requires the `stream` feature).
+//!
+//! A [`FeatureStreamer`] serializes features into newline delimited `GeoJSON` and sends them as
+//! text messages to a [`MessageSink`], e.g. a WebSocket connection or a server-sent events
+//! response. Features can be batched into fewer messages, and [`FeatureStreamer::keep_alive`]
+//! sends heartbeats so proxies don't close idle connections.
+//!
+//! The streamer is independent of the async runtime, transports only need to implement
+//! [`MessageSink`]:
+//!
+//! ```
+//! use geo::{point, Geometry};
+//! use serde::Serialize;
+//! use serde_geozero::stream::{FeatureStreamer, Framing};
+//!
+//! #[derive(Serialize)]
+//! struct Vehicle {
+//!     geometry: Geometry,
+//!     id: u32,
+//! }
+//!
+//! # async fn example() -> serde_geozero::error::Result<()> {
+//! let mut streamer = FeatureStreamer::new(Vec::<String>::new()).framing(Framing::Sse);
+//! streamer
+//!     .send(&Vehicle { geometry: point! { x: 13.4, y: 52.5 }.into(), id: 7 })
+//!     .await?;
+//! assert!(streamer.into_inner()[0].starts_with("data: {\"type\": \"Feature\""));
+//! # Ok(())
+//! # }
+//! ```
+use std::{
+    convert::Infallible,
+    fmt::Write,
+    future::Future,
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+
+use crate::{
+    error::{Error, Result},
+    geojson::to_ndjson_string,
+    ser::SerializeOptions,
+};
+
+/// A transport that accepts text messages.
+pub trait MessageSink {
+    type Error: std::error::Error + Send + Sync + 'static;
+
+    /// Sends a single message.
+    fn send(
+        &mut self,
+        message: String,
+    ) -> impl Future<Output = std::result::Result<(), Self::Error>>;
+}
+
+/// Collects messages, mostly useful for tests.
+impl MessageSink for Vec<String> {
+    type Error = Infallible;
+
+    async fn send(&mut self, message: String) -> std::result::Result<(), Self::Error> {
+        self.push(message);
+        Ok(())
+    }
+}
+
+/// How features are framed into messages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Framing {
+    /// Plain NDJSON, one feature per line, e.g. for WebSocket text messages.
+    #[default]
+    Ndjson,
+    /// Server-sent events, every line prefixed with `data: ` and every message terminated by an
+    /// empty line.
+    Sse,
+}
+
+impl Framing {
+    fn heartbeat(self) -> &'static str {
+        match self {
+            Framing::Ndjson => "\n",
+            Framing::Sse => ": keep-alive\n\n",
+        }
+    }
+}
+
+/// Serializes features and sends them to a [`MessageSink`].
+pub struct FeatureStreamer<S: MessageSink> {
+    sink: S,
+    framing: Framing,
+    options: SerializeOptions,
+    batch_size: usize,
+    keep_alive: Option<Duration>,
+    buffer: String,
+    buffered: usize,
+    last_sent: Instant,
+}
+
+impl<S: MessageSink> FeatureStreamer<S> {
+    #[must_use]
+    pub fn new(sink: S) -> Self {
+        Self {
+            sink,
+            framing: Framing::default(),
+            options: SerializeOptions::default(),
+            batch_size: 1,
+            keep_alive: None,
+            buffer: String::new(),
+            buffered: 0,
+            last_sent: Instant::now(),
+        }
+    }
+
+    #[must_use]
+    pub fn framing(mut self, framing: Framing) -> Self {
+        self.framing = framing;
+        self
+    }
+
+    /// Options used to serialize every feature.
+    #[must_use]
+    pub fn serialize_options(mut self, options: SerializeOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Sends a message once `batch_size` features were collected, defaults to 1.
+    #[must_use]
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Interval after which [`FeatureStreamer::keep_alive`] sends a heartbeat.
+    #[must_use]
+    pub fn keep_alive_interval(mut self, interval: Duration) -> Self {
+        self.keep_alive = Some(interval);
+        self
+    }
+
+    /// Serializes a feature, sending the batch if it is full.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the feature can't be serialized or the sink fails.
+    pub async fn send<T: Serialize>(&mut self, feature: &T) -> Result<()> {
+        let line = to_ndjson_string(std::slice::from_ref(feature), &self.options)?;
+        match self.framing {
+            Framing::Ndjson => self.buffer.push_str(&line),
+            Framing::Sse => {
+                for line in line.lines() {
+                    // writing to a String can't fail
+                    let _ = writeln!(self.buffer, "data: {line}");
+                }
+            }
+        }
+        self.buffered += 1;
+
+        if self.buffered >= self.batch_size {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// Sends all buffered features.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the sink fails.
+    pub async fn flush(&mut self) -> Result<()> {
+        if self.buffered == 0 {
+            return Ok(());
+        }
+        let mut message = std::mem::take(&mut self.buffer);
+        if self.framing == Framing::Sse {
+            message.push('\n');
+        }
+        self.buffered = 0;
+        self.send_message(message).await
+    }
+
+    /// Sends a heartbeat if nothing was sent for longer than the keep-alive interval.
+    ///
+    /// Call this periodically, e.g. from a timer of the async runtime. Buffered features are
+    /// flushed instead of sending a heartbeat. Returns `true` if a message was sent.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the sink fails.
+    pub async fn keep_alive(&mut self) -> Result<bool> {
+        match self.keep_alive {
+            Some(interval) if self.last_sent.elapsed() >= interval => {
+                if self.buffered > 0 {
+                    self.flush().await?;
+                } else {
+                    let heartbeat = self.framing.heartbeat().to_string();
+                    self.send_message(heartbeat).await?;
+                }
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Returns the sink, dropping features that were not flushed.
+    pub fn into_inner(self) -> S {
+        self.sink
+    }
+
+    async fn send_message(&mut self, message: String) -> Result<()> {
+        self.sink
+            .send(message)
+            .await
+            .map_err(|err| Error::Sink(Box::new(err)))?;
+        self.last_sent = Instant::now();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        future::Future,
+        pin::pin,
+        task::{Context, Poll, Waker},
+        time::Duration,
+    };
+
+    use geo::{point, Geometry};
+    use serde::Serialize;
+
+    use super::{FeatureStreamer, Framing};
+
+    #[derive(Serialize)]
+    struct Vehicle {
+        geometry: Geometry,
+        id: u32,
+    }
+
+    /// Polls a future that never waits, which holds for the `Vec` sink.
+    fn ready<F: Future>(future: F) -> F::Output {
+        match pin!(future).poll(&mut Context::from_waker(Waker::noop())) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("future is pending"),
+        }
+    }
+
+    fn vehicle(id: u32) -> Vehicle {
+        Vehicle {
+            geometry: point! { x: 1., y: 2. }.into(),
+            id,
+        }
+    }
+
+    #[test]
+    fn batching() -> anyhow::Result<()> {
+        let mut streamer = FeatureStreamer::new(Vec::new()).batch_size(2);
+        for id in 0..3 {
+            ready(streamer.send(&vehicle(id)))?;
+        }
+        ready(streamer.flush())?;
+
+        let messages = streamer.into_inner();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].lines().count(), 2);
+        for line in messages.iter().flat_map(|message| message.lines()) {
+            let feature: serde_json::Value = serde_json::from_str(line)?;
+            assert_eq!(feature["type"], "Feature");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn sse_keep_alive() -> anyhow::Result<()> {
+        let mut streamer = FeatureStreamer::new(Vec::new())
+            .framing(Framing::Sse)
+            .keep_alive_interval(Duration::ZERO);
+        ready(streamer.send(&vehicle(1)))?;
+        assert!(ready(streamer.keep_alive())?);
+
+        let messages = streamer.into_inner();
+        assert!(messages[0].starts_with("data: {"));
+        assert!(messages[0].ends_with("}\n\n"));
+        assert_eq!(messages[1], ": keep-alive\n\n");
+        Ok(())
+    }
+}
@@ -0,0 +1,249 @@
+//! Round-trip fidelity checks for your own types (requires the `testing` feature).
+//!
+//! [`RoundTrip`] writes values to a [`Format`], reads them back and compares both sides as
+//! [`Feature`]s, allowing coordinates to differ by a tolerance:
+//!
+//! ```
+//! use geo::{point, Geometry};
+//! use serde::{Deserialize, Serialize};
+//! use serde_geozero::testing::{assert_round_trip, RoundTrip};
+//! use serde_geozero::Format;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct City {
+//!     geometry: Geometry,
+//!     name: String,
+//! }
+//!
+//! let cities = vec![City {
+//!     geometry: point!(x: 13.4, y: 52.5).into(),
+//!     name: "Berlin".to_string(),
+//! }];
+//! assert_round_trip(&cities, Format::GeoJson);
+//!
+//! let read: Vec<City> = RoundTrip::new(Format::GeoJsonLines).epsilon(1e-9).run(&cities).unwrap();
+//! assert_eq!(read[0].name, "Berlin");
+//! ```
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    collector::GeozeroCollector,
+    de::{DeserializeOptions, Feature},
+    error::{Error, Result},
+    format::{from_reader_with_format, Format},
+    geojson::{to_geojson_string, to_ndjson_string},
+    ser::{to_geozero_datasource_with_options, SerializeOptions},
+};
+
+/// Writes values to a format and reads them back.
+#[derive(Clone, Debug)]
+pub struct RoundTrip {
+    format: Format,
+    epsilon: f64,
+    serialize: SerializeOptions,
+    deserialize: DeserializeOptions,
+}
+
+impl RoundTrip {
+    /// A round trip through `format` that requires coordinates to be equal.
+    #[must_use]
+    pub fn new(format: Format) -> Self {
+        Self {
+            format,
+            epsilon: 0.,
+            serialize: SerializeOptions::new(),
+            deserialize: DeserializeOptions::new(),
+        }
+    }
+
+    /// The largest difference allowed between a written and a read coordinate.
+    #[must_use]
+    pub fn epsilon(mut self, epsilon: f64) -> Self {
+        self.epsilon = epsilon;
+        self
+    }
+
+    /// Options used to write the values.
+    #[must_use]
+    pub fn serialize_options(mut self, options: SerializeOptions) -> Self {
+        self.serialize = options;
+        self
+    }
+
+    /// Options used to read the values back.
+    #[must_use]
+    pub fn deserialize_options(mut self, options: DeserializeOptions) -> Self {
+        self.deserialize = options;
+        self
+    }
+
+    /// Writes `input` in the format and returns its encoded bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnsupportedFormat`] if the format can't be written, e.g. because the
+    /// feature it requires is disabled, and the serialization errors otherwise.
+    pub fn write<T: Serialize>(&self, input: &[T]) -> Result<Vec<u8>> {
+        match self.format {
+            Format::GeoJson => Ok(to_geojson_string(input, &self.serialize)?.into_bytes()),
+            Format::GeoJsonLines => Ok(to_ndjson_string(input, &self.serialize)?.into_bytes()),
+            #[cfg(feature = "flatgeobuf")]
+            Format::FlatGeobuf => {
+                // without an index features keep their order
+                let mut writer = flatgeobuf::FgbWriter::create_with_options(
+                    "round_trip",
+                    flatgeobuf::GeometryType::Unknown,
+                    flatgeobuf::FgbWriterOptions {
+                        write_index: false,
+                        ..Default::default()
+                    },
+                )?;
+                to_geozero_datasource_with_options(input, &mut writer, &self.serialize)?;
+                let mut buffer = Vec::new();
+                writer.write(&mut buffer)?;
+                Ok(buffer)
+            }
+            format => Err(Error::UnsupportedFormat(format.to_string())),
+        }
+    }
+
+    /// Writes `input` and reads it back into `U`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`RoundTrip::write`] and of reading the format.
+    pub fn read_back<T: Serialize, U: DeserializeOwned + 'static>(
+        &self,
+        input: &[T],
+    ) -> Result<Vec<U>> {
+        self.read(self.write(input)?)
+    }
+
+    fn read<U: DeserializeOwned + 'static>(&self, bytes: Vec<u8>) -> Result<Vec<U>> {
+        from_reader_with_format(std::io::Cursor::new(bytes), self.format, &self.deserialize)
+    }
+
+    /// Writes `input` and reads it back into `T`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`RoundTrip::read_back`].
+    pub fn run<T: Serialize + DeserializeOwned + 'static>(&self, input: &[T]) -> Result<Vec<T>> {
+        self.read_back(input)
+    }
+
+    /// Round-trips `input` and checks that nothing was lost.
+    ///
+    /// Both sides are compared as [`Feature`]s with [`Feature::approx_eq`], so every property has
+    /// to come back with the same value and every coordinate within the tolerance. Finally the
+    /// read features have to deserialize into `T` again.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Message`] describing the first difference, or the errors of the round
+    /// trip.
+    pub fn check<T: Serialize + DeserializeOwned + 'static>(&self, input: &[T]) -> Result<()> {
+        let mut collector = GeozeroCollector::<Feature>::new();
+        to_geozero_datasource_with_options(input, &mut collector, &self.serialize)?;
+        let expected = collector.into_features();
+        let bytes = self.write(input)?;
+        let actual: Vec<Feature> = self.read(bytes.clone())?;
+        self.compare(&expected, &actual)?;
+        self.read::<T>(bytes).map(|_| ())
+    }
+
+    fn compare(&self, expected: &[Feature], actual: &[Feature]) -> Result<()> {
+        if expected.len() != actual.len() {
+            return Err(Error::Message(format!(
+                "{}: wrote {} features, read {}",
+                self.format,
+                expected.len(),
+                actual.len()
+            )));
+        }
+        for (index, (expected, actual)) in expected.iter().zip(actual).enumerate() {
+            if !expected.approx_eq(actual, self.epsilon) {
+                return Err(Error::Message(format!(
+                    "{}: feature {index} differs, expected {expected:?}, got {actual:?}",
+                    self.format
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Panics if `input` does not survive a round trip through `format` unchanged.
+///
+/// # Panics
+///
+/// Panics with the error of [`RoundTrip::check`].
+#[track_caller]
+pub fn assert_round_trip<T: Serialize + DeserializeOwned + 'static>(input: &[T], format: Format) {
+    if let Err(err) = RoundTrip::new(format).check(input) {
+        panic!("round trip failed: {err}");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hashbrown::HashMap;
+
+    use geo::{point, Geometry};
+    use serde::{Deserialize, Serialize};
+
+    use super::RoundTrip;
+    use crate::{de::Feature, format::Format};
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Record {
+        geometry: Geometry,
+        name: String,
+        count: i64,
+    }
+
+    fn records() -> Vec<Record> {
+        vec![
+            Record {
+                geometry: point!(x: 1.5, y: -2.25).into(),
+                name: "first".to_string(),
+                count: 1,
+            },
+            Record {
+                geometry: point!(x: 0.1, y: 0.2).into(),
+                name: "second".to_string(),
+                count: -7,
+            },
+        ]
+    }
+
+    #[test]
+    fn round_trips() -> anyhow::Result<()> {
+        let records = records();
+        RoundTrip::new(Format::GeoJson).check(&records)?;
+        RoundTrip::new(Format::GeoJsonLines).check(&records)?;
+        #[cfg(feature = "flatgeobuf")]
+        RoundTrip::new(Format::FlatGeobuf).check(&records)?;
+
+        assert!(RoundTrip::new(Format::Shapefile).check(&records).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn tolerance() {
+        let feature = |x: f64| Feature::new(point!(x: x, y: 2.0).into(), HashMap::new());
+        let written = [feature(1.0)];
+        let read = [feature(1.0 + 1e-7)];
+
+        assert!(RoundTrip::new(Format::GeoJson)
+            .compare(&written, &read)
+            .is_err());
+        assert!(RoundTrip::new(Format::GeoJson)
+            .epsilon(1e-6)
+            .compare(&written, &read)
+            .is_ok());
+        assert!(RoundTrip::new(Format::GeoJson)
+            .compare(&written, &[])
+            .is_err());
+    }
+}
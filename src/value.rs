@@ -0,0 +1,1248 @@
+//! Property values.
+//!
+//! [`Value`] mirrors geozero's [`ColumnValue`] so properties keep the type they were read with:
+//! a `UByte` column stays a `u8`, binary columns stay bytes and date times keep their own
+//! variant.
+//!
+//! `Json` values deserialize into whatever the target field expects, e.g. a nested struct or a
+//! `Vec<String>`, while `String` fields receive the JSON text as is. See
+//! [`DeserializeOptions::parse_json`](crate::DeserializeOptions::parse_json) to turn parsing off.
+use std::{
+    fmt::{self, Display},
+    sync::Arc,
+};
+
+use geozero::ColumnValue;
+use serde::{
+    de::{self, value::SeqDeserializer, IntoDeserializer, Visitor},
+    forward_to_deserialize_any, ser, Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::error::Error;
+
+/// A single property value.
+///
+/// [`Value::String`] and [`Value::Shared`] holding the same text compare equal.
+#[derive(Debug, Clone, Default)]
+pub enum Value {
+    #[default]
+    Null,
+    Byte(i8),
+    UByte(u8),
+    Bool(bool),
+    Short(i16),
+    UShort(u16),
+    Int(i32),
+    UInt(u32),
+    Long(i64),
+    ULong(u64),
+    Float(f32),
+    Double(f64),
+    String(String),
+    /// A string whose allocation is shared with equal values, see
+    /// [`DeserializeOptions::intern_strings`](crate::DeserializeOptions::intern_strings).
+    Shared(Arc<str>),
+    /// JSON encoded text.
+    Json(String),
+    /// A date time in ISO 8601 format.
+    DateTime(String),
+    Binary(Vec<u8>),
+}
+
+impl Value {
+    #[must_use]
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+
+    /// The text of `String`, `Shared`, `Json` and `DateTime` values.
+    #[must_use]
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(val) | Value::Json(val) | Value::DateTime(val) => Some(val),
+            Value::Shared(val) => Some(val),
+            _ => None,
+        }
+    }
+
+    /// Integer values as `i64`, `None` for other values and `ULong`s that don't fit.
+    #[must_use]
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Value::Byte(val) => Some(val.into()),
+            Value::UByte(val) => Some(val.into()),
+            Value::Short(val) => Some(val.into()),
+            Value::UShort(val) => Some(val.into()),
+            Value::Int(val) => Some(val.into()),
+            Value::UInt(val) => Some(val.into()),
+            Value::Long(val) => Some(val),
+            Value::ULong(val) => i64::try_from(val).ok(),
+            _ => None,
+        }
+    }
+
+    /// Numeric values as `f64`.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Value::Float(val) => Some(val.into()),
+            Value::Double(val) => Some(val),
+            Value::ULong(val) => Some(val as f64),
+            _ => self.as_i64().map(|val| val as f64),
+        }
+    }
+
+    #[must_use]
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            Value::Bool(val) => Some(val),
+            _ => None,
+        }
+    }
+}
+
+impl From<&ColumnValue<'_>> for Value {
+    fn from(value: &ColumnValue<'_>) -> Self {
+        match *value {
+            ColumnValue::Byte(val) => Value::Byte(val),
+            ColumnValue::UByte(val) => Value::UByte(val),
+            ColumnValue::Bool(val) => Value::Bool(val),
+            ColumnValue::Short(val) => Value::Short(val),
+            ColumnValue::UShort(val) => Value::UShort(val),
+            ColumnValue::Int(val) => Value::Int(val),
+            ColumnValue::UInt(val) => Value::UInt(val),
+            ColumnValue::Long(val) => Value::Long(val),
+            ColumnValue::ULong(val) => Value::ULong(val),
+            ColumnValue::Float(val) => Value::Float(val),
+            ColumnValue::Double(val) => Value::Double(val),
+            ColumnValue::String(val) => Value::String(val.to_string()),
+            ColumnValue::Json(val) => Value::Json(val.to_string()),
+            ColumnValue::DateTime(val) => Value::DateTime(val.to_string()),
+            ColumnValue::Binary(val) => Value::Binary(val.to_vec()),
+        }
+    }
+}
+
+macro_rules! impl_from {
+    ($($ty:ty => $variant:ident),*) => {
+        $(
+            impl From<$ty> for Value {
+                fn from(value: $ty) -> Self {
+                    Value::$variant(value.into())
+                }
+            }
+        )*
+    };
+}
+
+impl_from!(
+    i8 => Byte, u8 => UByte, bool => Bool, i16 => Short, u16 => UShort, i32 => Int, u32 => UInt,
+    i64 => Long, u64 => ULong, f32 => Float, f64 => Double, String => String, &str => String,
+    Vec<u8> => Binary
+);
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Value::Null, Into::into)
+    }
+}
+
+/// Arrays and objects become [`Value::Json`].
+impl From<serde_json::Value> for Value {
+    fn from(value: serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Null => Value::Null,
+            serde_json::Value::Bool(val) => Value::Bool(val),
+            serde_json::Value::Number(val) => {
+                if let Some(val) = val.as_i64() {
+                    Value::Long(val)
+                } else if let Some(val) = val.as_u64() {
+                    Value::ULong(val)
+                } else if cfg!(feature = "arbitrary_precision")
+                    && val.as_f64().map(|float| float.to_string()) != Some(val.to_string())
+                {
+                    // keep the digits a float can't hold
+                    Value::Json(val.to_string())
+                } else {
+                    Value::Double(val.as_f64().unwrap_or(f64::NAN))
+                }
+            }
+            serde_json::Value::String(val) => Value::String(val),
+            val @ (serde_json::Value::Array(_) | serde_json::Value::Object(_)) => {
+                Value::Json(val.to_string())
+            }
+        }
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Null, Value::Null) => true,
+            (Value::Byte(a), Value::Byte(b)) => a == b,
+            (Value::UByte(a), Value::UByte(b)) => a == b,
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::Short(a), Value::Short(b)) => a == b,
+            (Value::UShort(a), Value::UShort(b)) => a == b,
+            (Value::Int(a), Value::Int(b)) => a == b,
+            (Value::UInt(a), Value::UInt(b)) => a == b,
+            (Value::Long(a), Value::Long(b)) => a == b,
+            (Value::ULong(a), Value::ULong(b)) => a == b,
+            (Value::Float(a), Value::Float(b)) => a == b,
+            (Value::Double(a), Value::Double(b)) => a == b,
+            (Value::String(_) | Value::Shared(_), Value::String(_) | Value::Shared(_)) => {
+                self.as_str() == other.as_str()
+            }
+            (Value::Json(a), Value::Json(b)) | (Value::DateTime(a), Value::DateTime(b)) => a == b,
+            (Value::Binary(a), Value::Binary(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl PartialEq<str> for Value {
+    fn eq(&self, other: &str) -> bool {
+        match self {
+            Value::String(val) => val == other,
+            Value::Shared(val) => &**val == other,
+            _ => false,
+        }
+    }
+}
+
+impl PartialEq<&str> for Value {
+    fn eq(&self, other: &&str) -> bool {
+        self == *other
+    }
+}
+
+/// Formats values like JSON, binary values as an array of bytes.
+impl Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => write!(f, "null"),
+            Value::Byte(val) => write!(f, "{val}"),
+            Value::UByte(val) => write!(f, "{val}"),
+            Value::Bool(val) => write!(f, "{val}"),
+            Value::Short(val) => write!(f, "{val}"),
+            Value::UShort(val) => write!(f, "{val}"),
+            Value::Int(val) => write!(f, "{val}"),
+            Value::UInt(val) => write!(f, "{val}"),
+            Value::Long(val) => write!(f, "{val}"),
+            Value::ULong(val) => write!(f, "{val}"),
+            Value::Float(val) => write!(f, "{val}"),
+            Value::Double(val) => write!(f, "{val}"),
+            Value::String(val) | Value::DateTime(val) => {
+                write!(f, "{}", serde_json::Value::from(val.as_str()))
+            }
+            Value::Shared(val) => write!(f, "{}", serde_json::Value::from(&**val)),
+            Value::Json(val) => write!(f, "{val}"),
+            Value::Binary(val) => write!(f, "{val:?}"),
+        }
+    }
+}
+
+impl Serialize for Value {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Value::Null => serializer.serialize_none(),
+            Value::Byte(val) => serializer.serialize_i8(*val),
+            Value::UByte(val) => serializer.serialize_u8(*val),
+            Value::Bool(val) => serializer.serialize_bool(*val),
+            Value::Short(val) => serializer.serialize_i16(*val),
+            Value::UShort(val) => serializer.serialize_u16(*val),
+            Value::Int(val) => serializer.serialize_i32(*val),
+            Value::UInt(val) => serializer.serialize_u32(*val),
+            Value::Long(val) => serializer.serialize_i64(*val),
+            Value::ULong(val) => serializer.serialize_u64(*val),
+            Value::Float(val) => serializer.serialize_f32(*val),
+            Value::Double(val) => serializer.serialize_f64(*val),
+            Value::String(val) | Value::DateTime(val) => serializer.serialize_str(val),
+            Value::Shared(val) => serializer.serialize_str(val),
+            // keep the structure, so JSON columns are written as JSON again
+            Value::Json(val) => match serde_json::from_str::<serde_json::Value>(val) {
+                Ok(json) => json.serialize(serializer),
+                Err(_) => serializer.serialize_str(val),
+            },
+            Value::Binary(val) => serializer.serialize_bytes(val),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+macro_rules! visit {
+    ($($method:ident($ty:ty) => $variant:ident),*) => {
+        $(
+            fn $method<E: de::Error>(self, value: $ty) -> std::result::Result<Value, E> {
+                Ok(Value::$variant(value.into()))
+            }
+        )*
+    };
+}
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a property value")
+    }
+
+    visit!(
+        visit_i8(i8) => Byte, visit_u8(u8) => UByte, visit_bool(bool) => Bool,
+        visit_i16(i16) => Short, visit_u16(u16) => UShort, visit_i32(i32) => Int,
+        visit_u32(u32) => UInt, visit_i64(i64) => Long, visit_u64(u64) => ULong,
+        visit_f32(f32) => Float, visit_f64(f64) => Double, visit_str(&str) => String,
+        visit_string(String) => String, visit_bytes(&[u8]) => Binary,
+        visit_byte_buf(Vec<u8>) => Binary
+    );
+
+    fn visit_i128<E: de::Error>(self, value: i128) -> std::result::Result<Value, E> {
+        Ok(wide_integer(value))
+    }
+
+    fn visit_u128<E: de::Error>(self, value: u128) -> std::result::Result<Value, E> {
+        Ok(wide_integer(value))
+    }
+
+    fn visit_none<E: de::Error>(self) -> std::result::Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> std::result::Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> std::result::Result<Value, D::Error> {
+        Value::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, seq: A) -> std::result::Result<Value, A::Error> {
+        let json = serde_json::Value::deserialize(de::value::SeqAccessDeserializer::new(seq))?;
+        Ok(Value::Json(json.to_string()))
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, map: A) -> std::result::Result<Value, A::Error> {
+        let json = serde_json::Value::deserialize(de::value::MapAccessDeserializer::new(map))?;
+        Ok(Value::Json(json.to_string()))
+    }
+}
+
+impl<'de> Deserializer<'de> for Value {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::Null => visitor.visit_unit(),
+            Value::Byte(val) => visitor.visit_i8(val),
+            Value::UByte(val) => visitor.visit_u8(val),
+            Value::Bool(val) => visitor.visit_bool(val),
+            Value::Short(val) => visitor.visit_i16(val),
+            Value::UShort(val) => visitor.visit_u16(val),
+            Value::Int(val) => visitor.visit_i32(val),
+            Value::UInt(val) => visitor.visit_u32(val),
+            Value::Long(val) => visitor.visit_i64(val),
+            Value::ULong(val) => visitor.visit_u64(val),
+            Value::Float(val) => visitor.visit_f32(val),
+            Value::Double(val) => visitor.visit_f64(val),
+            Value::String(val) | Value::DateTime(val) => visitor.visit_string(val),
+            Value::Shared(val) => visitor.visit_str(&val),
+            Value::Json(val) => parse_json(&val)?
+                .deserialize_any(visitor)
+                .map_err(Error::SerdeError),
+            Value::Binary(val) => visitor.visit_byte_buf(val),
+        }
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            // string fields receive JSON columns unparsed
+            Value::String(val) | Value::Json(val) | Value::DateTime(val) => {
+                visitor.visit_string(val)
+            }
+            Value::Shared(val) => visitor.visit_str(&val),
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_i128<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        // integers beyond 64 bits are written as text, see `wide_integer`
+        match self.as_str().and_then(|text| text.trim().parse().ok()) {
+            Some(value) => visitor.visit_i128(value),
+            None => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_u128<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.as_str().and_then(|text| text.trim().parse().ok()) {
+            Some(value) => visitor.visit_u128(value),
+            None => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::Null => visitor.visit_none(),
+            value => visitor.visit_some(value),
+        }
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> std::result::Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            // `Vec<u8>` and `[u8; N]` fields expect a sequence, not bytes
+            Value::Binary(val) => visitor.visit_seq(SeqDeserializer::new(val.into_iter())),
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_tuple<V>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> std::result::Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> std::result::Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Value::String(val) => visitor.visit_enum(val.into_deserializer()),
+            Value::Shared(val) => visitor.visit_enum(val.to_string().into_deserializer()),
+            Value::Json(val) => parse_json(&val)?
+                .deserialize_enum(name, variants, visitor)
+                .map_err(Error::SerdeError),
+            // unit variants written with `SerializeOptions::enum_index`
+            value => match value.as_i64().and_then(|index| u32::try_from(index).ok()) {
+                Some(index) => visitor.visit_enum(index.into_deserializer()),
+                None => value.deserialize_any(visitor),
+            },
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char
+        bytes byte_buf unit unit_struct newtype_struct
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+/// Deserializes a [`Value`] leniently, see
+/// [`DeserializeOptions::coerce_types`](crate::DeserializeOptions::coerce_types).
+///
+/// Numeric text goes into number fields, numbers and booleans into string fields and `0`, `1`,
+/// `"true"` or `"false"` into `bool` fields. Values that can't be coerced fail like without
+/// coercion.
+pub(crate) struct Coerced(pub(crate) Value);
+
+impl Coerced {
+    /// The text a number field parses: strings, and floats without a fraction for integers.
+    fn numeric_text(&self, integer: bool) -> Option<String> {
+        match &self.0 {
+            Value::String(text) => Some(text.trim().to_string()),
+            Value::Shared(text) => Some(text.trim().to_string()),
+            Value::Float(val) if integer && val.fract() == 0. => Some(val.to_string()),
+            Value::Double(val) if integer && val.fract() == 0. => Some(val.to_string()),
+            _ => None,
+        }
+    }
+}
+
+macro_rules! coerce_number {
+    ($($method:ident => $visit:ident($ty:ty, $integer:literal)),*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                match self.numeric_text($integer).and_then(|text| text.parse::<$ty>().ok()) {
+                    Some(value) => visitor.$visit(value),
+                    None => self.0.$method(visitor),
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! forward_to_value {
+    ($($method:ident),*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                self.0.$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for Coerced {
+    type Error = Error;
+
+    coerce_number!(
+        deserialize_i8 => visit_i8(i8, true), deserialize_i16 => visit_i16(i16, true),
+        deserialize_i32 => visit_i32(i32, true), deserialize_i64 => visit_i64(i64, true),
+        deserialize_u8 => visit_u8(u8, true), deserialize_u16 => visit_u16(u16, true),
+        deserialize_u32 => visit_u32(u32, true), deserialize_u64 => visit_u64(u64, true),
+        deserialize_f32 => visit_f32(f32, false), deserialize_f64 => visit_f64(f64, false)
+    );
+
+    forward_to_value!(
+        deserialize_any,
+        deserialize_i128,
+        deserialize_u128,
+        deserialize_char,
+        deserialize_bytes,
+        deserialize_byte_buf,
+        deserialize_unit,
+        deserialize_seq,
+        deserialize_map,
+        deserialize_identifier,
+        deserialize_ignored_any
+    );
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let coerced = match &self.0 {
+            Value::String(_) | Value::Shared(_) => {
+                self.0
+                    .as_str()
+                    .and_then(|text| match text.trim().to_ascii_lowercase().as_str() {
+                        "true" | "1" => Some(true),
+                        "false" | "0" => Some(false),
+                        _ => None,
+                    })
+            }
+            value => match value.as_i64() {
+                Some(1) => Some(true),
+                Some(0) => Some(false),
+                _ => None,
+            },
+        };
+        match coerced {
+            Some(value) => visitor.visit_bool(value),
+            None => self.0.deserialize_bool(visitor),
+        }
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.0 {
+            Value::Byte(_)
+            | Value::UByte(_)
+            | Value::Bool(_)
+            | Value::Short(_)
+            | Value::UShort(_)
+            | Value::Int(_)
+            | Value::UInt(_)
+            | Value::Long(_)
+            | Value::ULong(_)
+            | Value::Float(_)
+            | Value::Double(_) => visitor.visit_string(self.0.to_string()),
+            value => value.deserialize_string(visitor),
+        }
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.0 {
+            Value::Null => visitor.visit_none(),
+            value => visitor.visit_some(Coerced(value)),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.0.deserialize_unit_struct(name, visitor)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, Error> {
+        self.0.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.0.deserialize_tuple_struct(name, len, visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.0.deserialize_struct(name, fields, visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.0.deserialize_enum(name, variants, visitor)
+    }
+}
+
+/// Deserializes like `D` but tells the target type that the format is not human readable, see
+/// [`DeserializeOptions::human_readable`](crate::DeserializeOptions::human_readable).
+pub(crate) struct Compact<D>(pub(crate) D);
+
+/// The deserializers [`Compact`] wraps.
+pub(crate) trait PropertyDeserializer {
+    fn is_null(&self) -> bool;
+}
+
+impl PropertyDeserializer for Value {
+    fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+}
+
+impl PropertyDeserializer for Coerced {
+    fn is_null(&self) -> bool {
+        self.0.is_null()
+    }
+}
+
+impl<'de, D> Deserializer<'de> for Compact<D>
+where
+    D: Deserializer<'de, Error = Error> + PropertyDeserializer,
+{
+    type Error = Error;
+
+    forward_to_value!(
+        deserialize_any,
+        deserialize_bool,
+        deserialize_i8,
+        deserialize_i16,
+        deserialize_i32,
+        deserialize_i64,
+        deserialize_i128,
+        deserialize_u8,
+        deserialize_u16,
+        deserialize_u32,
+        deserialize_u64,
+        deserialize_u128,
+        deserialize_f32,
+        deserialize_f64,
+        deserialize_char,
+        deserialize_str,
+        deserialize_string,
+        deserialize_bytes,
+        deserialize_byte_buf,
+        deserialize_unit,
+        deserialize_seq,
+        deserialize_map,
+        deserialize_identifier,
+        deserialize_ignored_any
+    );
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if self.0.is_null() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.0.deserialize_unit_struct(name, visitor)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, Error> {
+        self.0.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.0.deserialize_tuple_struct(name, len, visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.0.deserialize_struct(name, fields, visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.0.deserialize_enum(name, variants, visitor)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+/// A 128 bit integer as `Long` or `ULong` if it fits, as decimal text otherwise.
+///
+/// No supported format has a wider integer column and `GeoJSON` readers turn large numbers into
+/// floats, so text is the only representation that survives a round trip.
+fn wide_integer<T: Copy + Display + TryInto<i64> + TryInto<u64>>(value: T) -> Value {
+    match (
+        TryInto::<i64>::try_into(value),
+        TryInto::<u64>::try_into(value),
+    ) {
+        (Ok(value), _) => Value::Long(value),
+        (_, Ok(value)) => Value::ULong(value),
+        _ => Value::String(value.to_string()),
+    }
+}
+
+fn parse_json(json: &str) -> Result<serde_json::Value, Error> {
+    serde_json::from_str(json)
+        .map_err(|err| Error::Message(format!("invalid JSON column value {json:?}: {err}")))
+}
+
+impl IntoDeserializer<'_, Error> for Value {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+/// Serializes `value` into a [`Value`].
+///
+/// Scalars keep their type, byte slices and non-empty sequences of `u8` become
+/// [`Value::Binary`]. Other sequences, maps, structs and enum variants with data are encoded as
+/// [`Value::Json`]. Integers beyond 64 bits become decimal [`Value::String`]s, numbers of
+/// `serde_json`'s `arbitrary_precision` feature, e.g. decimals, [`Value::Json`] with their exact
+/// text.
+///
+/// # Errors
+///
+/// Returns an error if `value` fails to serialize.
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<Value, Error> {
+    value.serialize(ValueSerializer::default())
+}
+
+/// Serializes into [`Value`], see [`to_value`].
+#[derive(Clone, Copy)]
+pub(crate) struct ValueSerializer {
+    /// Reported to the serialized type, nested values are always human readable.
+    pub(crate) human_readable: bool,
+    /// Writes unit variants as their index, see
+    /// [`SerializeOptions::enum_index`](crate::SerializeOptions::enum_index).
+    pub(crate) enum_index: bool,
+}
+
+impl Default for ValueSerializer {
+    fn default() -> Self {
+        Self {
+            human_readable: true,
+            enum_index: false,
+        }
+    }
+}
+
+fn to_json(value: &Value) -> Result<serde_json::Value, Error> {
+    Ok(match value {
+        Value::Json(json) => serde_json::from_str(json)?,
+        value => serde_json::to_value(value)?,
+    })
+}
+
+fn json_value(value: &serde_json::Value) -> Result<Value, Error> {
+    Ok(Value::Json(serde_json::to_string(value)?))
+}
+
+macro_rules! serialize_scalar {
+    ($($method:ident($ty:ty) => $variant:ident),*) => {
+        $(
+            fn $method(self, value: $ty) -> Result<Value, Error> {
+                Ok(Value::$variant(value.into()))
+            }
+        )*
+    };
+}
+
+impl Serializer for ValueSerializer {
+    type Ok = Value;
+    type Error = Error;
+    type SerializeSeq = SeqSerializer;
+    type SerializeTuple = SeqSerializer;
+    type SerializeTupleStruct = SeqSerializer;
+    type SerializeTupleVariant = VariantSerializer<SeqSerializer>;
+    type SerializeMap = MapSerializer;
+    type SerializeStruct = MapSerializer;
+    type SerializeStructVariant = VariantSerializer<MapSerializer>;
+
+    serialize_scalar!(
+        serialize_bool(bool) => Bool, serialize_i8(i8) => Byte, serialize_i16(i16) => Short,
+        serialize_i32(i32) => Int, serialize_i64(i64) => Long, serialize_u8(u8) => UByte,
+        serialize_u16(u16) => UShort, serialize_u32(u32) => UInt, serialize_u64(u64) => ULong,
+        serialize_f32(f32) => Float, serialize_f64(f64) => Double, serialize_str(&str) => String
+    );
+
+    fn serialize_i128(self, value: i128) -> Result<Value, Error> {
+        Ok(wide_integer(value))
+    }
+
+    fn serialize_u128(self, value: u128) -> Result<Value, Error> {
+        Ok(wide_integer(value))
+    }
+
+    fn serialize_char(self, value: char) -> Result<Value, Error> {
+        Ok(Value::String(value.to_string()))
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<Value, Error> {
+        Ok(Value::Binary(value.to_vec()))
+    }
+
+    fn serialize_none(self) -> Result<Value, Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value, Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value, Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Value, Error> {
+        if self.enum_index {
+            return Ok(Value::UInt(variant_index));
+        }
+        Ok(Value::String(variant.to_string()))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Value, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Value, Error> {
+        let mut object = serde_json::Map::new();
+        object.insert(variant.to_string(), to_json(&to_value(value)?)?);
+        json_value(&serde_json::Value::Object(object))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SeqSerializer, Error> {
+        Ok(SeqSerializer(Vec::with_capacity(len.unwrap_or(0))))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SeqSerializer, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SeqSerializer, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        Ok(VariantSerializer {
+            variant,
+            inner: self.serialize_seq(Some(len))?,
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<MapSerializer, Error> {
+        Ok(MapSerializer {
+            map: serde_json::Map::new(),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<MapSerializer, Error> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        Ok(VariantSerializer {
+            variant,
+            inner: self.serialize_map(Some(len))?,
+        })
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.human_readable
+    }
+}
+
+/// The field name of numbers serialized with `serde_json`'s `arbitrary_precision` feature.
+const NUMBER_TOKEN: &str = "$serde_json::private::Number";
+
+/// Collects the elements of a sequence.
+pub(crate) struct SeqSerializer(Vec<Value>);
+
+impl SeqSerializer {
+    fn finish(self) -> Result<Value, Error> {
+        if !self.0.is_empty() && self.0.iter().all(|value| matches!(value, Value::UByte(_))) {
+            return Ok(Value::Binary(
+                self.0
+                    .into_iter()
+                    .filter_map(|value| match value {
+                        Value::UByte(byte) => Some(byte),
+                        _ => None,
+                    })
+                    .collect(),
+            ));
+        }
+        let values = self.0.iter().map(to_json).collect::<Result<Vec<_>, _>>()?;
+        json_value(&serde_json::Value::Array(values))
+    }
+}
+
+macro_rules! impl_seq {
+    ($($trait:ident::$method:ident),*) => {
+        $(
+            impl ser::$trait for SeqSerializer {
+                type Ok = Value;
+                type Error = Error;
+
+                fn $method<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+                    self.0.push(to_value(value)?);
+                    Ok(())
+                }
+
+                fn end(self) -> Result<Value, Error> {
+                    self.finish()
+                }
+            }
+        )*
+    };
+}
+
+impl_seq!(
+    SerializeSeq::serialize_element,
+    SerializeTuple::serialize_element,
+    SerializeTupleStruct::serialize_field
+);
+
+/// Collects the entries of a map or struct into a JSON object.
+pub(crate) struct MapSerializer {
+    map: serde_json::Map<String, serde_json::Value>,
+    key: Option<String>,
+}
+
+impl MapSerializer {
+    fn insert(&mut self, key: String, value: &Value) -> Result<(), Error> {
+        self.map.insert(key, to_json(value)?);
+        Ok(())
+    }
+}
+
+impl ser::SerializeMap for MapSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        self.key = Some(match to_value(key)? {
+            Value::String(key) => key,
+            key => key.to_string(),
+        });
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| Error::Message("value serialized before key".to_string()))?;
+        self.insert(key, &to_value(value)?)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        // numbers of `serde_json`'s `arbitrary_precision` feature serialize as a single field
+        // struct holding their exact text
+        if let Some(serde_json::Value::String(number)) = self.map.get(NUMBER_TOKEN) {
+            if self.map.len() == 1 {
+                return Ok(Value::Json(number.clone()));
+            }
+        }
+        json_value(&serde_json::Value::Object(self.map))
+    }
+}
+
+impl ser::SerializeStruct for MapSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.insert(key.to_string(), &to_value(value)?)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        ser::SerializeMap::end(self)
+    }
+}
+
+/// Wraps the data of an enum variant into `{"variant": data}`.
+pub(crate) struct VariantSerializer<S> {
+    variant: &'static str,
+    inner: S,
+}
+
+impl<S> VariantSerializer<S> {
+    fn finish(variant: &str, value: &Value) -> Result<Value, Error> {
+        let mut object = serde_json::Map::new();
+        object.insert(variant.to_string(), to_json(value)?);
+        json_value(&serde_json::Value::Object(object))
+    }
+}
+
+impl ser::SerializeTupleVariant for VariantSerializer<SeqSerializer> {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        ser::SerializeSeq::serialize_element(&mut self.inner, value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        // variant data is always JSON, even if it only consists of bytes
+        let values = self
+            .inner
+            .0
+            .iter()
+            .map(to_json)
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::finish(
+            self.variant,
+            &json_value(&serde_json::Value::Array(values))?,
+        )
+    }
+}
+
+impl ser::SerializeStructVariant for VariantSerializer<MapSerializer> {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        ser::SerializeStruct::serialize_field(&mut self.inner, key, value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        let value = ser::SerializeMap::end(self.inner)?;
+        Self::finish(self.variant, &value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use geozero::ColumnValue;
+    use serde::{Deserialize, Serialize};
+
+    use super::{to_value, Coerced, Value};
+    use crate::{geojson::to_geojson_string, ser::SerializeOptions};
+
+    #[test]
+    fn keeps_column_types() -> anyhow::Result<()> {
+        let byte = Value::from(&ColumnValue::UByte(7));
+        assert_eq!(byte, Value::UByte(7));
+        assert_eq!(u8::deserialize(byte.clone())?, 7);
+        assert_eq!(i64::deserialize(byte)?, 7);
+
+        let binary = Value::from(&ColumnValue::Binary(&[1, 2, 3]));
+        assert_eq!(Value::deserialize(binary)?, Value::Binary(vec![1, 2, 3]));
+
+        assert_eq!(Option::<String>::deserialize(Value::Null)?, None);
+        assert!(u8::deserialize(Value::Int(300)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn wide_integers() -> anyhow::Result<()> {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Account {
+            geometry: geo::Geometry,
+            balance: i128,
+            small: u128,
+        }
+
+        assert_eq!(to_value(&-5_i128)?, Value::Long(-5));
+        assert_eq!(to_value(&u128::from(u64::MAX))?, Value::ULong(u64::MAX));
+        assert_eq!(to_value(&i128::MIN)?, Value::from(i128::MIN.to_string()));
+        assert_eq!(i128::deserialize(to_value(&i128::MIN)?)?, i128::MIN);
+        assert_eq!(u128::deserialize(Value::Int(3))?, 3);
+
+        let accounts = [Account {
+            geometry: geo::point!(x: 1., y: 2.).into(),
+            balance: i128::MAX,
+            small: 7,
+        }];
+        let geojson = to_geojson_string(&accounts, &SerializeOptions::new())?;
+        let read: Vec<Account> =
+            crate::from_datasource(&mut geozero::geojson::GeoJsonReader(geojson.as_bytes()))?;
+        assert_eq!(read, accounts);
+        Ok(())
+    }
+
+    #[cfg(feature = "arbitrary_precision")]
+    #[test]
+    fn arbitrary_precision() -> anyhow::Result<()> {
+        let number: serde_json::Number = serde_json::from_str("12345.678901234567890")?;
+        let value = to_value(&number)?;
+        assert_eq!(value, Value::Json("12345.678901234567890".to_string()));
+        assert_eq!(serde_json::Number::deserialize(value)?, number);
+        Ok(())
+    }
+
+    #[test]
+    fn coercion() -> anyhow::Result<()> {
+        assert_eq!(i64::deserialize(Coerced(Value::from(" 42 ")))?, 42);
+        assert_eq!(u8::deserialize(Coerced(Value::Double(7.)))?, 7);
+        assert!((f64::deserialize(Coerced(Value::from("1.5")))? - 1.5).abs() < f64::EPSILON);
+        assert_eq!(String::deserialize(Coerced(Value::Long(42)))?, "42");
+        assert_eq!(String::deserialize(Coerced(Value::Bool(true)))?, "true");
+        assert!(bool::deserialize(Coerced(Value::Int(1)))?);
+        assert!(!bool::deserialize(Coerced(Value::from("FALSE")))?);
+        assert_eq!(
+            Option::<i32>::deserialize(Coerced(Value::from("-3")))?,
+            Some(-3)
+        );
+
+        assert!(i64::deserialize(Coerced(Value::from("4x"))).is_err());
+        assert!(u8::deserialize(Coerced(Value::Double(1.5))).is_err());
+        assert!(bool::deserialize(Coerced(Value::Int(2))).is_err());
+        assert!(i64::deserialize(Value::from("42")).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn display_like_json() {
+        assert_eq!(Value::from("a\"b").to_string(), r#""a\"b""#);
+        assert_eq!(Value::Int(1).to_string(), Value::Long(1).to_string());
+        assert_eq!(Value::Null.to_string(), "null");
+    }
+
+    #[test]
+    fn bytes() -> anyhow::Result<()> {
+        assert_eq!(to_value(&vec![1_u8, 2])?, Value::Binary(vec![1, 2]));
+        assert_eq!(to_value(&[1_u16, 2])?, Value::Json("[1,2]".to_string()));
+        assert_eq!(Vec::<u8>::deserialize(Value::Binary(vec![1, 2]))?, [1, 2]);
+        assert_eq!(<[u8; 2]>::deserialize(Value::Binary(vec![1, 2]))?, [1, 2]);
+        Ok(())
+    }
+}
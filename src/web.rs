@@ -0,0 +1,336 @@
+//! `GeoJSON` request bodies and responses for web services (requires the `web` feature).
+//!
+//! [`GeoJson`] reads a `FeatureCollection` body into a `Vec<T>` and writes handler outputs back
+//! as one. It works with the types of the [`http`] crate, which `axum` and most other frameworks
+//! build on:
+//!
+//! ```
+//! use geo::Geometry;
+//! use serde::{Deserialize, Serialize};
+//! use serde_geozero::web::GeoJson;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Stop {
+//!     geometry: Geometry,
+//!     name: String,
+//! }
+//!
+//! let request = http::Request::builder()
+//!     .header(http::header::CONTENT_TYPE, "application/geo+json")
+//!     .body(r#"{"type": "FeatureCollection", "features": [{"type": "Feature",
+//!         "properties": {"name": "Zoo"}, "geometry": {"type": "Point", "coordinates": [1, 2]}}]}"#)
+//!     .unwrap();
+//! let GeoJson(stops): GeoJson<Stop> = GeoJson::from_request(&request).unwrap();
+//! assert_eq!(stops[0].name, "Zoo");
+//!
+//! let response = GeoJson(stops).into_response().unwrap();
+//! assert_eq!(response.headers()[http::header::CONTENT_TYPE], "application/geo+json");
+//! ```
+//!
+//! With the `axum` or `actix-web` feature, [`GeoJson`] is also an extractor and a response type
+//! of that framework. Rejected requests are answered with [`rejection`], and features that
+//! can't be written with `500 Internal Server Error`.
+use http::{header, HeaderValue, Request, Response, StatusCode};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    error::{Error, Result},
+    geojson::to_geojson_string,
+    ser::SerializeOptions,
+};
+
+/// The media type of `GeoJSON` (RFC 7946).
+pub const CONTENT_TYPE: &str = "application/geo+json";
+
+/// Features read from or written as a `GeoJSON` body.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeoJson<T>(pub Vec<T>);
+
+impl<T: DeserializeOwned + 'static> GeoJson<T> {
+    /// Reads the features of a `GeoJSON` body.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`from_datasource`](crate::from_datasource).
+    pub fn from_slice(body: &[u8]) -> Result<Self> {
+        let mut reader = geozero::geojson::GeoJsonReader(body);
+        crate::from_datasource(&mut reader).map(GeoJson)
+    }
+
+    /// Reads the features of a request, which has to declare a JSON content type if it declares
+    /// one at all.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnsupportedFormat`] for other content types and the errors of
+    /// [`GeoJson::from_slice`] otherwise.
+    pub fn from_request<B: AsRef<[u8]>>(request: &Request<B>) -> Result<Self> {
+        check_content_type(
+            request
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .map(|value| value.to_str().unwrap_or_default()),
+        )?;
+        Self::from_slice(request.body().as_ref())
+    }
+}
+
+/// Checks that a request declaring the content type `content_type` has a JSON body.
+fn check_content_type(content_type: Option<&str>) -> Result<()> {
+    let Some(content_type) = content_type else {
+        return Ok(());
+    };
+    let media_type = content_type.split(';').next().unwrap_or_default().trim();
+    if matches!(media_type, CONTENT_TYPE | "application/json") {
+        Ok(())
+    } else {
+        Err(Error::UnsupportedFormat(content_type.to_string()))
+    }
+}
+
+impl<T: Serialize> GeoJson<T> {
+    /// Writes the features as a `FeatureCollection` response.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`to_geojson_string`].
+    pub fn into_response(self) -> Result<Response<String>> {
+        self.into_response_with_options(&SerializeOptions::new())
+    }
+
+    /// Writes the features as a `FeatureCollection` response, configured by `options`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`to_geojson_string`].
+    pub fn into_response_with_options(
+        self,
+        options: &SerializeOptions,
+    ) -> Result<Response<String>> {
+        let mut response = Response::new(to_geojson_string(&self.0, options)?);
+        response
+            .headers_mut()
+            .insert(header::CONTENT_TYPE, HeaderValue::from_static(CONTENT_TYPE));
+        Ok(response)
+    }
+}
+
+/// The response for a request [`GeoJson::from_request`] rejected with `err`.
+///
+/// Unsupported content types are answered with `415 Unsupported Media Type`, all other errors
+/// with `422 Unprocessable Entity` and the error message.
+#[must_use]
+pub fn rejection(err: &Error) -> Response<String> {
+    let status = match err {
+        Error::UnsupportedFormat(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+        _ => StatusCode::UNPROCESSABLE_ENTITY,
+    };
+    let mut response = Response::new(err.to_string());
+    *response.status_mut() = status;
+    response
+}
+
+#[cfg(feature = "axum")]
+impl<S, T> axum::extract::FromRequest<S> for GeoJson<T>
+where
+    S: Send + Sync,
+    T: DeserializeOwned + 'static,
+{
+    type Rejection = axum::response::Response;
+
+    async fn from_request(
+        request: axum::extract::Request,
+        state: &S,
+    ) -> std::result::Result<Self, Self::Rejection> {
+        use axum::{extract::FromRequest, response::IntoResponse};
+
+        let reject = |err: Error| rejection(&err).into_response();
+        check_content_type(
+            request
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .map(|value| value.to_str().unwrap_or_default()),
+        )
+        .map_err(reject)?;
+        let body = axum::body::Bytes::from_request(request, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        Self::from_slice(&body).map_err(reject)
+    }
+}
+
+#[cfg(feature = "axum")]
+impl<T: Serialize> axum::response::IntoResponse for GeoJson<T> {
+    fn into_response(self) -> axum::response::Response {
+        match self.into_response_with_options(&SerializeOptions::new()) {
+            Ok(response) => response.into_response(),
+            Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+        }
+    }
+}
+
+#[cfg(feature = "actix-web")]
+impl<T: DeserializeOwned + 'static> actix_web::FromRequest for GeoJson<T> {
+    type Error = actix_web::Error;
+    type Future = std::pin::Pin<
+        Box<dyn std::future::Future<Output = std::result::Result<Self, actix_web::Error>>>,
+    >;
+
+    fn from_request(
+        request: &actix_web::HttpRequest,
+        payload: &mut actix_web::dev::Payload,
+    ) -> Self::Future {
+        use actix_web::FromRequest;
+
+        let checked = check_content_type(
+            request
+                .headers()
+                .get(actix_web::http::header::CONTENT_TYPE)
+                .map(|value| value.to_str().unwrap_or_default()),
+        );
+        let body = actix_web::web::Bytes::from_request(request, payload);
+        Box::pin(async move {
+            checked.map_err(|err| actix_rejection(&err))?;
+            let body = body.await?;
+            Self::from_slice(&body).map_err(|err| actix_rejection(&err))
+        })
+    }
+}
+
+/// [`rejection`] as an `actix-web` error.
+#[cfg(feature = "actix-web")]
+fn actix_rejection(err: &Error) -> actix_web::Error {
+    let response = rejection(err);
+    let status = actix_web::http::StatusCode::from_u16(response.status().as_u16())
+        .unwrap_or(actix_web::http::StatusCode::UNPROCESSABLE_ENTITY);
+    let response = actix_web::HttpResponse::build(status).body(response.into_body());
+    actix_web::error::InternalError::from_response(err.to_string(), response).into()
+}
+
+#[cfg(feature = "actix-web")]
+impl<T: Serialize> actix_web::Responder for GeoJson<T> {
+    type Body = actix_web::body::BoxBody;
+
+    fn respond_to(self, _request: &actix_web::HttpRequest) -> actix_web::HttpResponse {
+        match to_geojson_string(&self.0, &SerializeOptions::new()) {
+            Ok(body) => actix_web::HttpResponse::Ok()
+                .content_type(CONTENT_TYPE)
+                .body(body),
+            Err(err) => actix_web::HttpResponse::InternalServerError().body(err.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use geo::Geometry;
+    use http::{header, Request, StatusCode};
+    use serde::{Deserialize, Serialize};
+
+    use super::{rejection, GeoJson};
+
+    #[derive(Debug, Deserialize, Serialize)]
+    struct Stop {
+        geometry: Geometry,
+        name: String,
+    }
+
+    #[cfg(any(feature = "axum", feature = "actix-web"))]
+    const STOPS: &str = r#"{"type": "FeatureCollection", "features": [{"type": "Feature",
+        "properties": {"name": "Zoo"}, "geometry": {"type": "Point", "coordinates": [1, 2]}}]}"#;
+
+    #[test]
+    fn rejections() {
+        let request = |content_type: &str, body: &'static str| {
+            Request::builder()
+                .header(header::CONTENT_TYPE, content_type)
+                .body(body)
+                .unwrap()
+        };
+
+        let err = GeoJson::<Stop>::from_request(&request("text/csv", "")).unwrap_err();
+        assert_eq!(rejection(&err).status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let body = r#"{"type": "Feature", "properties": {},
+            "geometry": {"type": "Point", "coordinates": [1, 2]}}"#;
+        let err = GeoJson::<Stop>::from_request(&request("application/json; charset=utf-8", body))
+            .unwrap_err();
+        let response = rejection(&err);
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(response.body().contains("name"), "{}", response.body());
+    }
+
+    #[cfg(feature = "axum")]
+    #[tokio::test]
+    async fn axum() {
+        use axum::{body::Body, extract::FromRequest, response::IntoResponse};
+
+        let request = |content_type: &str, body: &'static str| {
+            Request::builder()
+                .header(header::CONTENT_TYPE, content_type)
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        let GeoJson(stops) = <GeoJson<Stop> as FromRequest<()>>::from_request(
+            request(super::CONTENT_TYPE, STOPS),
+            &(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(stops[0].name, "Zoo");
+        let rejected =
+            <GeoJson<Stop> as FromRequest<()>>::from_request(request("text/csv", STOPS), &())
+                .await
+                .unwrap_err();
+        assert_eq!(rejected.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let response = IntoResponse::into_response(GeoJson(stops));
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            super::CONTENT_TYPE
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(String::from_utf8_lossy(&body).contains("Zoo"));
+    }
+
+    #[cfg(feature = "actix-web")]
+    #[tokio::test]
+    async fn actix_web() {
+        use actix_web::{body::MessageBody, test::TestRequest, FromRequest, Responder};
+
+        let request = |content_type: &str| {
+            TestRequest::default()
+                .insert_header((actix_web::http::header::CONTENT_TYPE, content_type))
+                .set_payload(STOPS)
+                .to_http_parts()
+        };
+
+        let (http_request, mut payload) = request(super::CONTENT_TYPE);
+        let GeoJson(stops) =
+            <GeoJson<Stop> as FromRequest>::from_request(&http_request, &mut payload)
+                .await
+                .unwrap();
+        assert_eq!(stops[0].name, "Zoo");
+        let (rejected, mut payload) = request("text/csv");
+        let err = <GeoJson<Stop> as FromRequest>::from_request(&rejected, &mut payload)
+            .await
+            .unwrap_err();
+        assert_eq!(err.error_response().status().as_u16(), 415);
+
+        let response = GeoJson(stops).respond_to(&http_request);
+        assert_eq!(response.status().as_u16(), 200);
+        assert_eq!(
+            response
+                .headers()
+                .get(actix_web::http::header::CONTENT_TYPE)
+                .unwrap(),
+            super::CONTENT_TYPE
+        );
+        let body = response.into_body().try_into_bytes().unwrap();
+        assert!(String::from_utf8_lossy(&body).contains("Zoo"));
+    }
+}
@@ -0,0 +1,371 @@
+//! `#[serde(with = "...")]` adapters choosing the encoding of a geometry field.
+//!
+//! Structs used with this crate are often also used with plain `serde_json`, e.g. in APIs or
+//! JSON database columns, where `geo`'s own representation isn't what clients expect. The
+//! adapters write the geometry as [`wkt_string`], [`wkb_hex`] or [`geojson_geometry`] and read it
+//! back from that encoding:
+//!
+//! ```
+//! use geo::{point, Geometry};
+//! use serde::{Deserialize, Serialize};
+//! use serde_geozero::with;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Stop {
+//!     #[serde(with = "with::wkt_string")]
+//!     geometry: Geometry,
+//!     name: String,
+//! }
+//!
+//! let stop = Stop { geometry: point! { x: 1., y: 2. }.into(), name: "Zoo".to_string() };
+//! let json = serde_json::to_string(&stop).unwrap();
+//! assert_eq!(json, r#"{"geometry":"POINT(1 2)","name":"Zoo"}"#);
+//!
+//! let mut reader = geozero::geojson::GeoJsonReader(r#"{"type": "Feature",
+//!     "properties": {"name": "Zoo"}, "geometry": {"type": "Point", "coordinates": [1, 2]}}"#
+//!     .as_bytes());
+//! let stops: Vec<Stop> = serde_geozero::from_datasource(&mut reader).unwrap();
+//! assert_eq!(stops[0].geometry, stop.geometry);
+//! ```
+//!
+//! Fields keep working with datasources and the serialization path of this crate: the
+//! adapters also read the geometries handed out while deserializing features, and encoded
+//! `geometry` fields are decoded again when writing features.
+use geo::Geometry;
+use serde::{de::Error as _, Deserialize, Deserializer};
+
+use crate::{
+    encoded::{decode_json, GeometryEncoding},
+    value::Value,
+};
+
+/// Reads a geometry field, decoding strings with `encoding`.
+fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+    encoding: GeometryEncoding,
+) -> Result<Geometry, D::Error> {
+    let geometry = match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::String(text) => encoding.decode(&Value::String(text)).map(Some),
+        value => decode_json(value),
+    };
+    geometry
+        .map_err(D::Error::custom)?
+        .ok_or_else(|| D::Error::custom("expected a geometry"))
+}
+
+/// Well-known text, e.g. `"POINT(1 2)"`.
+pub mod wkt_string {
+    use geo::Geometry;
+    use serde::{ser::Error as _, Deserializer, Serializer};
+
+    use crate::encoded::{to_wkt, GeometryEncoding};
+
+    /// Writes the geometry as a WKT string.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of the serializer.
+    pub fn serialize<S: Serializer>(geometry: &Geometry, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&to_wkt(geometry).map_err(S::Error::custom)?)
+    }
+
+    /// Reads a WKT string or a geometry handed out by a datasource.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the value isn't a valid geometry.
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Geometry, D::Error> {
+        super::deserialize(deserializer, GeometryEncoding::Wkt)
+    }
+}
+
+/// Well-known binary as an upper case hex string, e.g. `"0101000000000000000000F03F..."`.
+pub mod wkb_hex {
+    use geo::Geometry;
+    use serde::{ser::Error as _, Deserializer, Serializer};
+
+    use crate::encoded::{to_wkb_hex, GeometryEncoding};
+
+    /// Writes the geometry as a hex encoded WKB string.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the geometry can't be encoded or the errors of the serializer.
+    pub fn serialize<S: Serializer>(geometry: &Geometry, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&to_wkb_hex(geometry).map_err(S::Error::custom)?)
+    }
+
+    /// Reads a hex encoded WKB or EWKB string or a geometry handed out by a datasource.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the value isn't a valid geometry.
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Geometry, D::Error> {
+        super::deserialize(deserializer, GeometryEncoding::Wkb)
+    }
+}
+
+/// A `GeoJSON` geometry object, e.g. `{"type": "Point", "coordinates": [1.0, 2.0]}`.
+pub mod geojson_geometry {
+    use geo::Geometry;
+    use serde::{Deserializer, Serialize, Serializer};
+
+    use crate::encoded::{to_geojson, GeometryEncoding};
+
+    /// Writes the geometry as a `GeoJSON` geometry object.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of the serializer.
+    pub fn serialize<S: Serializer>(geometry: &Geometry, serializer: S) -> Result<S::Ok, S::Error> {
+        to_geojson(geometry).serialize(serializer)
+    }
+
+    /// Reads a `GeoJSON` geometry object or string, or a geometry handed out by a datasource.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the value isn't a valid geometry.
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Geometry, D::Error> {
+        super::deserialize(deserializer, GeometryEncoding::GeoJson)
+    }
+}
+
+/// A `geos::Geometry` field, built and written with geozero's GEOS processors.
+///
+/// While deserializing features the field gets a geometry built by geozero's `GeosWriter` from
+/// the feature geometry, and while serializing the GEOS geometry is read by geozero's GEOS
+/// reader into the written feature, without encoding the geometry in between. Other
+/// deserializers may hand out a geometry like a `geo` geometry field or a WKT string, other
+/// serializers get a `geo` geometry. Requires the `geos` feature.
+///
+/// ```
+/// use serde::{Deserialize, Serialize};
+/// use serde_geozero::with;
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Parcel {
+///     #[serde(with = "with::geos_geometry")]
+///     geometry: geos::Geometry,
+///     owner: String,
+/// }
+/// ```
+#[cfg(feature = "geos")]
+pub mod geos_geometry {
+    use std::cell::{Cell, RefCell};
+
+    use geo::Geometry;
+    use geozero::{geo_types::GeoWriter, GeozeroGeometry, ToGeos};
+    use serde::{
+        de::{Error as _, Unexpected, Visitor},
+        ser::Error as _,
+        Deserializer, Serialize, Serializer,
+    };
+
+    use crate::encoded::GeometryEncoding;
+
+    /// Names the newtype struct the field is written as and read from, so the crate's own
+    /// serializer and deserializer can tell it from other fields.
+    pub(crate) const GEOS_NAME: &str = "serde_geozero::GeosGeometry";
+
+    thread_local! {
+        /// The geometry built by the feature's deserializer, see [`hand_over`].
+        static HANDED_OVER: RefCell<Option<geos::Geometry>> = const { RefCell::new(None) };
+        /// Whether the crate's serializer captures the geometry, see [`capture`].
+        static CAPTURING: Cell<bool> = const { Cell::new(false) };
+        static CAPTURED: RefCell<Option<Geometry>> = const { RefCell::new(None) };
+    }
+
+    /// Builds the GEOS geometry of `geometry` for the field being deserialized.
+    pub(crate) fn hand_over(geometry: &Geometry) -> geozero::error::Result<()> {
+        let geometry = geometry.to_geos()?;
+        HANDED_OVER.set(Some(geometry));
+        Ok(())
+    }
+
+    /// Runs `serialize` and returns the geometry of the field it wrote, read from GEOS instead
+    /// of being serialized.
+    pub(crate) fn capture<T>(serialize: impl FnOnce() -> T) -> (T, Option<Geometry>) {
+        CAPTURING.set(true);
+        let result = serialize();
+        CAPTURING.set(false);
+        (result, CAPTURED.take())
+    }
+
+    /// Writes the geometry, see the [module docs](self).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the geometry can't be read from GEOS or the errors of the serializer.
+    pub fn serialize<S: Serializer>(
+        geometry: &geos::Geometry,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct(GEOS_NAME, &Geos(geometry))
+    }
+
+    struct Geos<'a>(&'a geos::Geometry);
+
+    impl Serialize for Geos<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut writer = GeoWriter::new();
+            self.0.process_geom(&mut writer).map_err(S::Error::custom)?;
+            let geometry = writer
+                .take_geometry()
+                .ok_or_else(|| S::Error::custom("empty GEOS geometry"))?;
+            if CAPTURING.get() {
+                CAPTURED.set(Some(geometry));
+                return serializer.serialize_unit();
+            }
+            geometry.serialize(serializer)
+        }
+    }
+
+    /// Reads the geometry, see the [module docs](self).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the value isn't a valid geometry or GEOS fails to build it.
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<geos::Geometry, D::Error> {
+        deserializer.deserialize_newtype_struct(GEOS_NAME, GeosVisitor)
+    }
+
+    struct GeosVisitor;
+
+    impl<'de> Visitor<'de> for GeosVisitor {
+        type Value = geos::Geometry;
+
+        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            formatter.write_str("a geometry")
+        }
+
+        fn visit_unit<E: serde::de::Error>(self) -> Result<geos::Geometry, E> {
+            HANDED_OVER
+                .with_borrow_mut(Option::take)
+                .ok_or_else(|| E::invalid_type(Unexpected::Unit, &self))
+        }
+
+        fn visit_newtype_struct<D: Deserializer<'de>>(
+            self,
+            deserializer: D,
+        ) -> Result<geos::Geometry, D::Error> {
+            super::deserialize(deserializer, GeometryEncoding::Wkt)?
+                .to_geos()
+                .map_err(D::Error::custom)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use geo::{line_string, point, polygon, Geometry};
+    use serde::{Deserialize, Serialize};
+
+    use crate::{geojson::to_geojson_string, SerializeOptions};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Encoded {
+        #[serde(with = "super::wkt_string")]
+        wkt: Geometry,
+        #[serde(with = "super::wkb_hex")]
+        wkb: Geometry,
+        #[serde(with = "super::geojson_geometry")]
+        geometry: Geometry,
+    }
+
+    #[test]
+    fn json_round_trip() -> anyhow::Result<()> {
+        let encoded = Encoded {
+            wkt: line_string![(x: 0., y: 0.), (x: 1., y: 1.)].into(),
+            wkb: point! { x: 1., y: 2. }.into(),
+            geometry: polygon![(x: 0., y: 0.), (x: 1., y: 0.), (x: 1., y: 1.)].into(),
+        };
+        let json = serde_json::to_value(&encoded)?;
+        assert_eq!(json["wkt"], "LINESTRING(0 0,1 1)");
+        assert_eq!(json["wkb"], "0101000000000000000000F03F0000000000000040");
+        assert_eq!(json["geometry"]["type"], "Polygon");
+        assert_eq!(serde_json::from_value::<Encoded>(json)?, encoded);
+        Ok(())
+    }
+
+    #[test]
+    fn geozero_paths() -> anyhow::Result<()> {
+        let records = [Encoded {
+            wkt: point! { x: 3., y: 4. }.into(),
+            wkb: point! { x: 1., y: 2. }.into(),
+            geometry: point! { x: 5., y: 6. }.into(),
+        }];
+        let geojson = to_geojson_string(&records, &SerializeOptions::new())?;
+        assert!(geojson.contains(r#""wkt": "POINT(3 4)""#), "{geojson}");
+
+        let mut reader = geozero::geojson::GeoJsonReader(geojson.as_bytes());
+        let read: Vec<Encoded> = crate::from_datasource(&mut reader)?;
+        assert_eq!(read, records);
+        Ok(())
+    }
+
+    #[cfg(feature = "geos")]
+    #[test]
+    fn geos_fields() -> anyhow::Result<()> {
+        use geos::Geom;
+
+        #[derive(Serialize, Deserialize)]
+        struct Parcel {
+            #[serde(with = "super::geos_geometry")]
+            geometry: geos::Geometry,
+            owner: String,
+        }
+
+        let parcels = [Parcel {
+            geometry: geos::Geometry::new_from_wkt("POLYGON((0 0,2 0,2 1,0 0))")?,
+            owner: "city".to_string(),
+        }];
+        let geojson = to_geojson_string(&parcels, &SerializeOptions::new())?;
+        assert!(geojson.contains(r#""type": "Polygon""#), "{geojson}");
+
+        let mut reader = geozero::geojson::GeoJsonReader(geojson.as_bytes());
+        let read: Vec<Parcel> = crate::from_datasource(&mut reader)?;
+        assert!((read[0].geometry.area()? - 1.).abs() < 1e-9);
+        assert_eq!(read[0].owner, "city");
+        Ok(())
+    }
+
+    #[cfg(feature = "geos")]
+    #[test]
+    fn geos_datasource_round_trip() -> anyhow::Result<()> {
+        use geo::Area;
+        use geos::Geom;
+
+        use crate::{collector::RawCollector, de::Feature, to_geozero_datasource};
+
+        #[derive(Serialize, Deserialize)]
+        struct Country {
+            #[serde(with = "super::geos_geometry")]
+            geometry: geos::Geometry,
+            name: String,
+        }
+
+        let open = || -> anyhow::Result<_> {
+            let file = std::fs::File::open("test-data/countries.fgb")?;
+            Ok(flatgeobuf::FgbReader::open(file)?.select_all()?)
+        };
+        let features: Vec<Feature> = crate::from_datasource(&mut open()?)?;
+        let countries: Vec<Country> = crate::from_datasource(&mut open()?)?;
+        assert_eq!(countries.len(), features.len());
+        for (country, feature) in countries.iter().zip(&features) {
+            let area = feature.geometry.unsigned_area();
+            assert!((country.geometry.area()? - area).abs() <= 1e-9 * area.max(1.));
+        }
+
+        let mut collector = RawCollector::new();
+        to_geozero_datasource(&countries, &mut collector)?;
+        assert_eq!(collector.len(), features.len());
+        for (written, feature) in collector.features().iter().zip(&features) {
+            assert_eq!(written.geometry, feature.geometry);
+            assert_eq!(written.get_str("name"), feature.get_str("name"));
+        }
+        Ok(())
+    }
+}
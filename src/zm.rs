@@ -0,0 +1,238 @@
+//! Support for Z (elevation) and M (measure) coordinate values.
+//!
+//! geo-types geometries only store XY. When enabled via [`crate::DeserializeOptions::preserve_zm`]
+//! or [`crate::SerializeOptions::preserve_zm`], Z and M values are carried next to the geometry
+//! as companion fields named [`Z_COL`] and [`M_COL`], holding one value per coordinate in the
+//! order the coordinates appear in the geometry. Coordinates without a value are stored as NaN.
+//!
+//! ```
+//! use geo::Geometry;
+//! use serde::Deserialize;
+//! use serde_geozero::{from_datasource_with_options, DeserializeOptions};
+//!
+//! #[derive(Deserialize)]
+//! struct Summit {
+//!     geometry: Geometry,
+//!     geometry_z: Vec<f64>,
+//! }
+//!
+//! let geojson = r#"{"type": "Feature", "properties": {},
+//!     "geometry": {"type": "Point", "coordinates": [7.6, 45.9, 4478.0]}}"#;
+//!
+//! let mut reader = geozero::geojson::GeoJsonReader(geojson.as_bytes());
+//! let options = DeserializeOptions::new().preserve_zm(true);
+//! let summits: Vec<Summit> = from_datasource_with_options(&mut reader, &options).unwrap();
+//! assert_eq!(summits[0].geometry_z, vec![4478.0]);
+//! ```
+use geo::{Coord, Geometry, LineString, Polygon};
+use geozero::GeomProcessor;
+
+/// Name of the companion field holding Z values.
+pub const Z_COL: &str = "geometry_z";
+/// Name of the companion field holding M values.
+pub const M_COL: &str = "geometry_m";
+
+/// Collects Z and M values of all coordinates of a geometry.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ZmValues {
+    count: usize,
+    pub z: Option<Vec<f64>>,
+    pub m: Option<Vec<f64>>,
+}
+
+impl ZmValues {
+    pub fn push(&mut self, z: Option<f64>, m: Option<f64>) {
+        push_value(&mut self.z, z, self.count);
+        push_value(&mut self.m, m, self.count);
+        self.count += 1;
+    }
+}
+
+fn push_value(values: &mut Option<Vec<f64>>, value: Option<f64>, count: usize) {
+    match (values.as_mut(), value) {
+        (Some(values), value) => values.push(value.unwrap_or(f64::NAN)),
+        (None, Some(value)) => {
+            let mut new_values = vec![f64::NAN; count];
+            new_values.push(value);
+            *values = Some(new_values);
+        }
+        (None, None) => {}
+    }
+}
+
+/// Processes a geometry like [`geozero::geo_types::process_geom`], emitting the given Z and M
+/// values with the coordinates.
+pub(crate) fn process_geom_zm<P: GeomProcessor>(
+    geom: &Geometry,
+    z: &[f64],
+    m: &[f64],
+    processor: &mut P,
+) -> geozero::error::Result<()> {
+    let mut writer = ZmWriter {
+        z,
+        m,
+        next: 0,
+        processor,
+    };
+    writer.geometry(geom, 0)
+}
+
+struct ZmWriter<'a, P> {
+    z: &'a [f64],
+    m: &'a [f64],
+    next: usize,
+    processor: &'a mut P,
+}
+
+impl<P: GeomProcessor> ZmWriter<'_, P> {
+    fn geometry(&mut self, geom: &Geometry, idx: usize) -> geozero::error::Result<()> {
+        match geom {
+            Geometry::Point(point) => {
+                self.processor.point_begin(idx)?;
+                self.coord(point.0, 0)?;
+                self.processor.point_end(idx)
+            }
+            Geometry::Line(line) => {
+                self.processor.linestring_begin(true, 2, idx)?;
+                self.coord(line.start, 0)?;
+                self.coord(line.end, 1)?;
+                self.processor.linestring_end(true, idx)
+            }
+            Geometry::LineString(line_string) => self.line_string(line_string, true, idx),
+            Geometry::Polygon(polygon) => self.polygon(polygon, true, idx),
+            Geometry::MultiPoint(points) => {
+                self.processor.multipoint_begin(points.0.len(), idx)?;
+                for (i, point) in points.iter().enumerate() {
+                    self.coord(point.0, i)?;
+                }
+                self.processor.multipoint_end(idx)
+            }
+            Geometry::MultiLineString(line_strings) => {
+                self.processor
+                    .multilinestring_begin(line_strings.0.len(), idx)?;
+                for (i, line_string) in line_strings.iter().enumerate() {
+                    self.line_string(line_string, false, i)?;
+                }
+                self.processor.multilinestring_end(idx)
+            }
+            Geometry::MultiPolygon(polygons) => {
+                self.processor.multipolygon_begin(polygons.0.len(), idx)?;
+                for (i, polygon) in polygons.iter().enumerate() {
+                    self.polygon(polygon, false, i)?;
+                }
+                self.processor.multipolygon_end(idx)
+            }
+            Geometry::GeometryCollection(collection) => {
+                self.processor
+                    .geometrycollection_begin(collection.0.len(), idx)?;
+                for (i, geometry) in collection.iter().enumerate() {
+                    self.geometry(geometry, i)?;
+                }
+                self.processor.geometrycollection_end(idx)
+            }
+            Geometry::Rect(rect) => self.polygon(&rect.to_polygon(), true, idx),
+            Geometry::Triangle(triangle) => self.polygon(&triangle.to_polygon(), true, idx),
+        }
+    }
+
+    fn line_string(
+        &mut self,
+        line_string: &LineString,
+        tagged: bool,
+        idx: usize,
+    ) -> geozero::error::Result<()> {
+        self.processor
+            .linestring_begin(tagged, line_string.0.len(), idx)?;
+        for (i, coord) in line_string.0.iter().enumerate() {
+            self.coord(*coord, i)?;
+        }
+        self.processor.linestring_end(tagged, idx)
+    }
+
+    fn polygon(
+        &mut self,
+        polygon: &Polygon,
+        tagged: bool,
+        idx: usize,
+    ) -> geozero::error::Result<()> {
+        self.processor
+            .polygon_begin(tagged, polygon.interiors().len() + 1, idx)?;
+        self.line_string(polygon.exterior(), false, 0)?;
+        for (i, interior) in polygon.interiors().iter().enumerate() {
+            self.line_string(interior, false, i + 1)?;
+        }
+        self.processor.polygon_end(tagged, idx)
+    }
+
+    fn coord(&mut self, coord: Coord, idx: usize) -> geozero::error::Result<()> {
+        let value = |values: &[f64], i: usize| values.get(i).copied().filter(|v| !v.is_nan());
+        let (z, m) = (value(self.z, self.next), value(self.m, self.next));
+        self.next += 1;
+        // processors implementing only `xy` ignore `coordinate`, like in `process_geom`
+        if !self.processor.multi_dim() {
+            return self.processor.xy(coord.x, coord.y, idx);
+        }
+        self.processor
+            .coordinate(coord.x, coord.y, z, m, None, None, idx)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::from_utf8;
+
+    use geo::{line_string, Geometry};
+    use geozero::{geojson::GeoJsonWriter, CoordDimensions};
+    use serde::{Deserialize, Serialize};
+
+    use crate::{
+        from_datasource_with_options, to_geozero_datasource_with_options, DeserializeOptions,
+        SerializeOptions,
+    };
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Track {
+        geometry: Geometry,
+        geometry_z: Vec<f64>,
+    }
+
+    #[test]
+    fn z_values_round_trip() -> anyhow::Result<()> {
+        let geojson = r#"{"type": "Feature", "properties": {},
+            "geometry": {"type": "LineString", "coordinates": [[1, 2, 10], [3, 4, 20]]}}"#;
+
+        let mut reader = geozero::geojson::GeoJsonReader(geojson.as_bytes());
+        let options = DeserializeOptions::new().preserve_zm(true);
+        let tracks: Vec<Track> = from_datasource_with_options(&mut reader, &options)?;
+        assert_eq!(
+            tracks[0].geometry,
+            line_string![(x: 1., y: 2.), (x: 3., y: 4.)].into()
+        );
+        assert_eq!(tracks[0].geometry_z, vec![10., 20.]);
+
+        let mut out = Vec::new();
+        let mut writer = GeoJsonWriter::with_dims(&mut out, CoordDimensions::xyz());
+        let options = SerializeOptions::new().preserve_zm(true);
+        to_geozero_datasource_with_options(&tracks, &mut writer, &options)?;
+
+        let string = from_utf8(&out)?;
+        assert!(string.contains("[[1,2,10],[3,4,20]]"), "{string}");
+        assert!(!string.contains("geometry_z"), "{string}");
+        Ok(())
+    }
+
+    #[test]
+    fn xy_processors() -> anyhow::Result<()> {
+        use geozero::geo_types::GeoWriter;
+
+        let tracks = [Track {
+            geometry: line_string![(x: 1., y: 2.), (x: 3., y: 4.)].into(),
+            geometry_z: vec![10., 20.],
+        }];
+        let mut writer = GeoWriter::new();
+        let options = SerializeOptions::new().preserve_zm(true);
+        to_geozero_datasource_with_options(&tracks, &mut writer, &options)?;
+        assert_eq!(writer.take_geometry(), Some(tracks[0].geometry.clone()));
+        Ok(())
+    }
+}